@@ -0,0 +1,51 @@
+CREATE TABLE IF NOT EXISTS roles (
+    id uuid PRIMARY KEY DEFAULT uuid_generate_v4(),
+    name VARCHAR NOT NULL UNIQUE,
+    description VARCHAR NOT NULL DEFAULT '',
+    created_at TIMESTAMP NOT NULL DEFAULT NOW()
+);
+
+CREATE TABLE IF NOT EXISTS permissions (
+    id uuid PRIMARY KEY DEFAULT uuid_generate_v4(),
+    name VARCHAR NOT NULL UNIQUE,
+    description VARCHAR NOT NULL DEFAULT ''
+);
+
+CREATE TABLE IF NOT EXISTS role_permissions (
+    role_id uuid NOT NULL REFERENCES roles (id) ON DELETE CASCADE,
+    permission_id uuid NOT NULL REFERENCES permissions (id) ON DELETE CASCADE,
+    PRIMARY KEY (role_id, permission_id)
+);
+
+CREATE TABLE IF NOT EXISTS user_roles (
+    user_id uuid NOT NULL REFERENCES users (id) ON DELETE CASCADE,
+    role_id uuid NOT NULL REFERENCES roles (id) ON DELETE CASCADE,
+    assigned_at TIMESTAMP NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (user_id, role_id)
+);
+
+INSERT INTO roles (name, description) VALUES
+    ('admin', 'Manages users, roles and every resume'),
+    ('recruiter', 'Browses candidates and their resumes'),
+    ('candidate', 'Maintains their own resumes')
+ON CONFLICT (name) DO NOTHING;
+
+INSERT INTO permissions (name, description) VALUES
+    ('users:read', 'View user accounts'),
+    ('users:write', 'Create and update user accounts'),
+    ('users:delete', 'Delete user accounts'),
+    ('resumes:read', 'View resumes'),
+    ('resumes:write', 'Create and update resumes'),
+    ('resumes:delete', 'Delete resumes'),
+    ('roles:manage', 'Assign and revoke roles'),
+    ('lockouts:manage', 'View and clear login lockouts')
+ON CONFLICT (name) DO NOTHING;
+
+INSERT INTO role_permissions (role_id, permission_id)
+SELECT roles.id, permissions.id
+FROM roles
+JOIN permissions ON
+    roles.name = 'admin'
+    OR (roles.name = 'recruiter' AND permissions.name IN ('users:read', 'resumes:read'))
+    OR (roles.name = 'candidate' AND permissions.name IN ('resumes:read', 'resumes:write'))
+ON CONFLICT DO NOTHING;
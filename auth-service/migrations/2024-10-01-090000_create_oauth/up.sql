@@ -0,0 +1,37 @@
+CREATE TABLE IF NOT EXISTS oauth_clients (
+    id uuid PRIMARY KEY DEFAULT uuid_generate_v4(),
+    client_id VARCHAR NOT NULL UNIQUE,
+    client_secret_hash VARCHAR,
+    name VARCHAR NOT NULL,
+    redirect_uris TEXT[] NOT NULL,
+    allowed_scopes VARCHAR NOT NULL,
+    created_by uuid REFERENCES users (id) ON DELETE SET NULL,
+    created_at TIMESTAMP NOT NULL DEFAULT NOW()
+);
+
+CREATE TABLE IF NOT EXISTS oauth_authorization_codes (
+    id uuid PRIMARY KEY DEFAULT uuid_generate_v4(),
+    code_hash VARCHAR NOT NULL UNIQUE,
+    client_id uuid NOT NULL REFERENCES oauth_clients (id) ON DELETE CASCADE,
+    user_id uuid NOT NULL REFERENCES users (id) ON DELETE CASCADE,
+    redirect_uri VARCHAR NOT NULL,
+    scope VARCHAR NOT NULL,
+    code_challenge VARCHAR NOT NULL,
+    expires_at TIMESTAMP NOT NULL,
+    used_at TIMESTAMP,
+    created_at TIMESTAMP NOT NULL DEFAULT NOW()
+);
+
+ALTER TABLE refresh_tokens
+    ADD COLUMN IF NOT EXISTS client_id uuid REFERENCES oauth_clients (id) ON DELETE CASCADE,
+    ADD COLUMN IF NOT EXISTS scope VARCHAR;
+
+INSERT INTO permissions (name, description) VALUES
+    ('clients:manage', 'Register OAuth clients')
+ON CONFLICT (name) DO NOTHING;
+
+INSERT INTO role_permissions (role_id, permission_id)
+SELECT roles.id, permissions.id
+FROM roles, permissions
+WHERE roles.name = 'admin' AND permissions.name = 'clients:manage'
+ON CONFLICT DO NOTHING;
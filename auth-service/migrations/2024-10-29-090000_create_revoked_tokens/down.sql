@@ -0,0 +1,3 @@
+ALTER TABLE oauth_clients
+    DROP COLUMN IF EXISTS is_service;
+DROP TABLE IF EXISTS revoked_tokens;
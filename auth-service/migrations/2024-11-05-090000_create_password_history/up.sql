@@ -0,0 +1,10 @@
+-- Hashes of passwords a user has since replaced, so the password policy can
+-- refuse reusing them. Only the most recent few are kept.
+CREATE TABLE IF NOT EXISTS password_history (
+    id uuid PRIMARY KEY DEFAULT uuid_generate_v4(),
+    user_id uuid NOT NULL REFERENCES users (id) ON DELETE CASCADE,
+    password_hash VARCHAR NOT NULL,
+    created_at TIMESTAMP NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS password_history_user_id_idx ON password_history (user_id, created_at);
@@ -0,0 +1,15 @@
+-- Events written in the same transaction as the change they describe, and
+-- published to the event bus by the outbox relay afterwards.
+CREATE TABLE IF NOT EXISTS outbox (
+    id uuid PRIMARY KEY,
+    topic VARCHAR NOT NULL,
+    event_key VARCHAR NOT NULL,
+    payload JSONB NOT NULL,
+    created_at TIMESTAMP NOT NULL DEFAULT NOW(),
+    attempts INTEGER NOT NULL DEFAULT 0,
+    next_attempt_at TIMESTAMP NOT NULL DEFAULT NOW(),
+    last_error VARCHAR,
+    published_at TIMESTAMP
+);
+
+CREATE INDEX IF NOT EXISTS outbox_pending_idx ON outbox (next_attempt_at) WHERE published_at IS NULL;
@@ -0,0 +1,12 @@
+-- Used by user-service for subject-access exports and erasure requests.
+INSERT INTO permissions (name, description) VALUES
+    ('users:export', 'Export everything held about a user'),
+    ('users:erase', 'Erase user accounts and follow their erasure')
+ON CONFLICT (name) DO NOTHING;
+
+INSERT INTO role_permissions (role_id, permission_id)
+SELECT roles.id, permissions.id
+FROM roles
+JOIN permissions ON permissions.name IN ('users:export', 'users:erase')
+WHERE roles.name = 'admin'
+ON CONFLICT DO NOTHING;
@@ -0,0 +1,12 @@
+-- Every MFA challenge handed out at login, so that each completes at most one
+-- login and can only be guessed at a few times.
+CREATE TABLE IF NOT EXISTS mfa_challenges (
+    id uuid PRIMARY KEY,
+    user_id uuid NOT NULL REFERENCES users (id) ON DELETE CASCADE,
+    failed_attempts INTEGER NOT NULL DEFAULT 0,
+    expires_at TIMESTAMP NOT NULL,
+    closed_at TIMESTAMP,
+    created_at TIMESTAMP NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS mfa_challenges_user_id_created_at_idx ON mfa_challenges (user_id, created_at);
@@ -0,0 +1,205 @@
+use std::env;
+use std::fs;
+use std::path::Path;
+
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+use ed25519_dalek::pkcs8::{DecodePrivateKey, EncodePrivateKey};
+use jsonwebtoken::jwk::{
+    AlgorithmParameters, CommonParameters, EllipticCurve, Jwk, JwkSet, KeyAlgorithm,
+    OctetKeyPairParameters, OctetKeyPairType, PublicKeyUse, RSAKeyParameters, RSAKeyType,
+};
+use jsonwebtoken::errors::{Error as JwtError, ErrorKind};
+use jsonwebtoken::{decode, decode_header, encode, Algorithm, DecodingKey, EncodingKey, Header, Validation};
+use rsa::pkcs1::DecodeRsaPrivateKey;
+use rsa::traits::PublicKeyParts;
+use rsa::RsaPrivateKey;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use shared::auth::jwks::JwksCache;
+use shared::auth::validator::{audience, issuer};
+use shared::auth::TokenValidator;
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum KeyringError {
+    #[error("Failed to read key {0}: {1}")]
+    Io(String, std::io::Error),
+
+    #[error("Key {0} is not a PKCS#8 Ed25519 or RSA private key")]
+    UnsupportedKey(String),
+
+    #[error("Active key {0} not found in keyring")]
+    MissingActiveKey(String),
+}
+
+/// A private key the service can sign with, plus the public half published in the JWKS.
+pub struct SigningKey {
+    pub kid: String,
+    pub algorithm: Algorithm,
+    encoding_key: EncodingKey,
+    jwk: Jwk,
+}
+
+impl SigningKey {
+    pub fn from_pem(kid: &str, pem: &str) -> Result<SigningKey, KeyringError> {
+        if let Ok(key) = ed25519_dalek::SigningKey::from_pkcs8_pem(pem) {
+            return Self::from_ed25519(kid, &key);
+        }
+
+        let key = RsaPrivateKey::from_pkcs8_pem(pem)
+            .or_else(|_| RsaPrivateKey::from_pkcs1_pem(pem))
+            .map_err(|_| KeyringError::UnsupportedKey(kid.to_string()))?;
+        let encoding_key = EncodingKey::from_rsa_pem(pem.as_bytes())
+            .map_err(|_| KeyringError::UnsupportedKey(kid.to_string()))?;
+        let parameters = AlgorithmParameters::RSA(RSAKeyParameters {
+            key_type: RSAKeyType::RSA,
+            n: URL_SAFE_NO_PAD.encode(key.n().to_bytes_be()),
+            e: URL_SAFE_NO_PAD.encode(key.e().to_bytes_be()),
+        });
+
+        Ok(SigningKey {
+            kid: kid.to_string(),
+            algorithm: Algorithm::RS256,
+            encoding_key,
+            jwk: Self::jwk(kid, KeyAlgorithm::RS256, parameters),
+        })
+    }
+
+    pub fn from_ed25519(kid: &str, key: &ed25519_dalek::SigningKey) -> Result<SigningKey, KeyringError> {
+        let der = key.to_pkcs8_der().map_err(|_| KeyringError::UnsupportedKey(kid.to_string()))?;
+        let parameters = AlgorithmParameters::OctetKeyPair(OctetKeyPairParameters {
+            key_type: OctetKeyPairType::OctetKeyPair,
+            curve: EllipticCurve::Ed25519,
+            x: URL_SAFE_NO_PAD.encode(key.verifying_key().to_bytes()),
+        });
+
+        Ok(SigningKey {
+            kid: kid.to_string(),
+            algorithm: Algorithm::EdDSA,
+            encoding_key: EncodingKey::from_ed_der(der.as_bytes()),
+            jwk: Self::jwk(kid, KeyAlgorithm::EdDSA, parameters),
+        })
+    }
+
+    pub fn encoding_key(&self) -> &EncodingKey {
+        &self.encoding_key
+    }
+
+    pub fn decoding_key(&self) -> DecodingKey {
+        DecodingKey::from_jwk(&self.jwk).expect("Keyring JWKs are always valid")
+    }
+
+    fn jwk(kid: &str, algorithm: KeyAlgorithm, parameters: AlgorithmParameters) -> Jwk {
+        Jwk {
+            common: CommonParameters {
+                public_key_use: Some(PublicKeyUse::Signature),
+                key_algorithm: Some(algorithm),
+                key_id: Some(kid.to_string()),
+                ..Default::default()
+            },
+            algorithm: parameters,
+        }
+    }
+}
+
+/// Holds the key new tokens are signed with and the retired keys that still
+/// have to verify tokens issued before the last rotation.
+pub struct Keyring {
+    active_kid: String,
+    keys: Vec<SigningKey>,
+}
+
+impl Keyring {
+    pub fn new(active_kid: &str, keys: Vec<SigningKey>) -> Result<Keyring, KeyringError> {
+        if !keys.iter().any(|key| key.kid == active_kid) {
+            return Err(KeyringError::MissingActiveKey(active_kid.to_string()));
+        }
+
+        Ok(Keyring { active_kid: active_kid.to_string(), keys })
+    }
+
+    /// Loads every `<kid>.pem` file in `dir`. The key named `active_kid` signs
+    /// new tokens; the rest are retired and only published for verification.
+    pub fn load(dir: &Path, active_kid: &str) -> Result<Keyring, KeyringError> {
+        let entries = fs::read_dir(dir).map_err(|e| KeyringError::Io(dir.display().to_string(), e))?;
+        let mut keys = Vec::new();
+
+        for entry in entries.flatten() {
+            let path = entry.path();
+            if path.extension().and_then(|ext| ext.to_str()) != Some("pem") {
+                continue;
+            }
+
+            let kid = path.file_stem().and_then(|stem| stem.to_str()).unwrap_or_default().to_string();
+            let pem = fs::read_to_string(&path).map_err(|e| KeyringError::Io(path.display().to_string(), e))?;
+            keys.push(SigningKey::from_pem(&kid, &pem)?);
+        }
+
+        Self::new(active_kid, keys)
+    }
+
+    /// A single throwaway Ed25519 key, for local development and tests.
+    pub fn ephemeral() -> Keyring {
+        let key = ed25519_dalek::SigningKey::generate(&mut rand::rngs::OsRng);
+        let kid = uuid::Uuid::new_v4().to_string();
+        let signing_key = SigningKey::from_ed25519(&kid, &key).expect("Failed to encode ephemeral key");
+
+        Keyring { active_kid: kid, keys: vec![signing_key] }
+    }
+
+    pub fn active(&self) -> &SigningKey {
+        self.keys.iter()
+            .find(|key| key.kid == self.active_kid)
+            .expect("Active key is checked when the keyring is built")
+    }
+
+    pub fn find(&self, kid: &str) -> Option<&SigningKey> {
+        self.keys.iter().find(|key| key.kid == kid)
+    }
+
+    pub fn jwks(&self) -> JwkSet {
+        JwkSet { keys: self.keys.iter().map(|key| key.jwk.clone()).collect() }
+    }
+
+    /// Signs `claims` with the active key, setting `kid` in the header.
+    pub fn sign<T: Serialize>(&self, claims: &T) -> Result<String, JwtError> {
+        let signing_key = self.active();
+        let mut header = Header::new(signing_key.algorithm);
+        header.kid = Some(signing_key.kid.clone());
+
+        encode(&header, claims, signing_key.encoding_key())
+    }
+
+    /// Verifies a token this service signed for `audience`, e.g. a verification
+    /// link or an MFA challenge. Access tokens use `token_validator` instead.
+    pub fn verify<T: DeserializeOwned>(&self, token: &str, audience: &str) -> Result<T, JwtError> {
+        let signing_key = decode_header(token)?
+            .kid
+            .and_then(|kid| self.find(&kid))
+            .ok_or_else(|| JwtError::from(ErrorKind::InvalidKeyFormat))?;
+
+        let mut validation = Validation::new(signing_key.algorithm);
+        validation.set_issuer(&[issuer()]);
+        validation.set_audience(&[audience]);
+
+        decode::<T>(token, &signing_key.decoding_key(), &validation).map(|data| data.claims)
+    }
+
+    /// Validates our own tokens against this keyring, without going over HTTP.
+    pub fn token_validator(&self) -> TokenValidator {
+        TokenValidator::new(JwksCache::from_jwks(self.jwks()), &issuer(), &audience())
+    }
+}
+
+pub fn load_keyring() -> Keyring {
+    dotenv::dotenv().ok();
+    match (env::var("JWT_KEYS_DIR"), env::var("JWT_ACTIVE_KID")) {
+        (Ok(dir), Ok(active_kid)) => Keyring::load(Path::new(&dir), &active_kid)
+            .expect("Failed to load JWT signing keys"),
+        _ => {
+            log::warn!("JWT_KEYS_DIR or JWT_ACTIVE_KID not set, signing tokens with an ephemeral key");
+            Keyring::ephemeral()
+        }
+    }
+}
@@ -1,2 +1,5 @@
 pub mod database;
+pub mod keyring;
+pub mod oidc;
+pub mod webauthn;
 mod settings;
\ No newline at end of file
@@ -0,0 +1,112 @@
+use std::env;
+
+use shared::auth::jwks::JwksCache;
+use tokio::sync::OnceCell;
+
+use crate::models::oidc::ProviderMetadata;
+
+const DEFAULT_SCOPES: &str = "openid email profile";
+
+/// Settings for one external OpenID provider, read from `OIDC_<NAME>_*`.
+#[derive(Debug, Clone)]
+pub struct OidcProviderConfig {
+    /// Name used in the login URLs, e.g. `google` in `/auth/oidc/google/start`.
+    pub name: String,
+    /// Base URL the discovery document is fetched from. Points at a local mock
+    /// issuer in tests.
+    pub issuer_url: String,
+    pub client_id: String,
+    /// `None` for providers that accept public clients with PKCE alone.
+    pub client_secret: Option<String>,
+    /// Our callback URL, as registered with the provider.
+    pub redirect_uri: String,
+    pub scopes: String,
+}
+
+/// A configured provider and what was learned about it over HTTP. Discovery
+/// runs on first use and is then kept for the life of the process; signing
+/// keys are refetched when the provider rotates them.
+pub struct OidcProvider {
+    pub config: OidcProviderConfig,
+    metadata: OnceCell<ProviderMetadata>,
+    jwks: OnceCell<JwksCache>,
+}
+
+impl OidcProvider {
+    pub fn new(config: OidcProviderConfig) -> OidcProvider {
+        OidcProvider { config, metadata: OnceCell::new(), jwks: OnceCell::new() }
+    }
+
+    pub async fn metadata(&self) -> Result<&ProviderMetadata, String> {
+        self.metadata.get_or_try_init(|| self.discover()).await
+    }
+
+    pub async fn jwks(&self) -> Result<&JwksCache, String> {
+        let jwks_uri = self.metadata().await?.jwks_uri.clone();
+        Ok(self.jwks.get_or_init(|| async move { JwksCache::new(&jwks_uri) }).await)
+    }
+
+    /// The provider's issuer with any trailing slash removed.
+    pub fn issuer(&self) -> &str {
+        self.config.issuer_url.trim_end_matches('/')
+    }
+
+    async fn discover(&self) -> Result<ProviderMetadata, String> {
+        let url = format!("{}/.well-known/openid-configuration", self.issuer());
+        let metadata = reqwest::get(&url).await
+            .and_then(|response| response.error_for_status())
+            .map_err(|e| e.to_string())?
+            .json::<ProviderMetadata>().await
+            .map_err(|e| e.to_string())?;
+
+        // OpenID Connect Discovery 1.0 section 4.3: the document must name the
+        // issuer it was fetched from.
+        if metadata.issuer.trim_end_matches('/') != self.issuer() {
+            return Err(format!("discovery document names issuer {}", metadata.issuer));
+        }
+        Ok(metadata)
+    }
+}
+
+#[derive(Default)]
+pub struct OidcProviders {
+    providers: Vec<OidcProvider>,
+}
+
+impl OidcProviders {
+    pub fn new(configs: Vec<OidcProviderConfig>) -> OidcProviders {
+        OidcProviders { providers: configs.into_iter().map(OidcProvider::new).collect() }
+    }
+
+    pub fn find(&self, name: &str) -> Option<&OidcProvider> {
+        self.providers.iter().find(|provider| provider.config.name == name)
+    }
+}
+
+/// Reads the comma-separated provider names in `OIDC_PROVIDERS`, then
+/// `OIDC_<NAME>_ISSUER_URL`, `_CLIENT_ID`, `_CLIENT_SECRET` (optional),
+/// `_REDIRECT_URI` and `_SCOPES` (optional) for each.
+pub fn load_oidc_providers() -> OidcProviders {
+    dotenv::dotenv().ok();
+    let names = env::var("OIDC_PROVIDERS").unwrap_or_default();
+    let configs = names.split(',')
+        .map(str::trim)
+        .filter(|name| !name.is_empty())
+        .map(|name| {
+            let var = |suffix: &str| env::var(format!("OIDC_{}_{}", name.to_uppercase(), suffix)).ok();
+            let required = |suffix: &str| var(suffix)
+                .unwrap_or_else(|| panic!("OIDC_{}_{} must be set", name.to_uppercase(), suffix));
+
+            OidcProviderConfig {
+                name: name.to_lowercase(),
+                issuer_url: required("ISSUER_URL"),
+                client_id: required("CLIENT_ID"),
+                client_secret: var("CLIENT_SECRET").filter(|secret| !secret.is_empty()),
+                redirect_uri: required("REDIRECT_URI"),
+                scopes: var("SCOPES").unwrap_or_else(|| DEFAULT_SCOPES.to_string()),
+            }
+        })
+        .collect();
+
+    OidcProviders::new(configs)
+}
@@ -0,0 +1,65 @@
+use std::env;
+
+use serde::Serialize;
+
+/// What the authenticator is asked to prove about itself at registration.
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum AttestationConveyance {
+    /// No attestation is asked for; whatever comes back is not checked.
+    None,
+    Indirect,
+    /// Only attestation formats the service can verify are accepted.
+    Direct,
+}
+
+/// This service as a WebAuthn relying party, read from `WEBAUTHN_*`:
+///
+/// - `WEBAUTHN_RP_ID`: the domain passkeys are bound to
+/// - `WEBAUTHN_RP_NAME`: shown by the authenticator
+/// - `WEBAUTHN_ORIGINS`: comma-separated origins the ceremonies may run on
+/// - `WEBAUTHN_ATTESTATION`: `none`, `indirect` or `direct`
+/// - `WEBAUTHN_USER_VERIFICATION`: `required` or `preferred`
+#[derive(Debug, Clone)]
+pub struct RelyingParty {
+    pub id: String,
+    pub name: String,
+    pub origins: Vec<String>,
+    pub attestation: AttestationConveyance,
+    pub require_user_verification: bool,
+}
+
+impl RelyingParty {
+    pub fn from_env() -> RelyingParty {
+        let attestation = match env::var("WEBAUTHN_ATTESTATION").as_deref() {
+            Ok("direct") => AttestationConveyance::Direct,
+            Ok("indirect") => AttestationConveyance::Indirect,
+            _ => AttestationConveyance::None,
+        };
+
+        RelyingParty {
+            id: env::var("WEBAUTHN_RP_ID").unwrap_or_else(|_| "localhost".to_string()),
+            name: env::var("WEBAUTHN_RP_NAME").unwrap_or_else(|_| "Resume API".to_string()),
+            origins: env::var("WEBAUTHN_ORIGINS")
+                .unwrap_or_else(|_| "http://localhost:3000".to_string())
+                .split(',')
+                .map(|origin| origin.trim().trim_end_matches('/').to_string())
+                .filter(|origin| !origin.is_empty())
+                .collect(),
+            attestation,
+            require_user_verification: env::var("WEBAUTHN_USER_VERIFICATION").as_deref() != Ok("preferred"),
+        }
+    }
+
+    pub fn user_verification(&self) -> &'static str {
+        if self.require_user_verification {
+            "required"
+        } else {
+            "preferred"
+        }
+    }
+
+    pub fn allows_origin(&self, origin: &str) -> bool {
+        self.origins.iter().any(|allowed| allowed == origin)
+    }
+}
@@ -1,4 +1,5 @@
 use thiserror::Error;
+use validator::ValidationErrors;
 
 #[derive(Debug, Error)]
 pub enum AuthError {
@@ -11,6 +12,131 @@ pub enum AuthError {
     #[error("Invalid credentials")]
     InvalidCredentials,
 
+    #[error("Invalid input")]
+    ValidationError(#[from] ValidationErrors),
+
+    #[error("Email already registered")]
+    EmailAlreadyExists,
+
+    #[error("Invalid or expired reset token")]
+    InvalidResetToken,
+
+    #[error("Invalid or expired verification link")]
+    InvalidVerificationToken,
+
+    #[error("Email address not verified")]
+    EmailNotVerified,
+
+    #[error("Two-factor authentication is already enabled")]
+    MfaAlreadyEnabled,
+
+    #[error("Two-factor authentication is not enabled")]
+    MfaNotEnabled,
+
+    #[error("Invalid two-factor code")]
+    InvalidMfaCode,
+
+    #[error("Invalid or expired two-factor challenge")]
+    InvalidMfaChallenge,
+
+    #[error("Invalid or expired refresh token")]
+    InvalidRefreshToken,
+
+    #[error("Too many failed login attempts, try again in {0} seconds")]
+    TooManyLoginAttempts(i64),
+
+    #[error("No failed login attempts recorded for this email")]
+    LockoutNotFound,
+
+    #[error("Role not found")]
+    RoleNotFound,
+
+    #[error("Unknown identity provider")]
+    UnknownIdentityProvider,
+
+    #[error("Invalid or expired sign-in state")]
+    InvalidOidcState,
+
+    #[error("Sign-in was cancelled or refused by the identity provider")]
+    OidcLoginDenied,
+
+    #[error("Invalid ID token")]
+    InvalidIdToken,
+
+    #[error("Identity provider unavailable")]
+    IdentityProviderUnavailable,
+
+    #[error("This identity cannot be linked to an existing account automatically")]
+    IdentityNotLinkable,
+
+    #[error("Linked identity not found")]
+    IdentityNotFound,
+
+    #[error("Personal access token not found")]
+    PersonalAccessTokenNotFound,
+
+    #[error("Session not found")]
+    SessionNotFound,
+
+    #[error("Invalid or expired passkey ceremony")]
+    InvalidPasskeyCeremony,
+
+    #[error("Passkey could not be verified")]
+    PasskeyVerificationFailed,
+
+    #[error("Passkey attestation is not accepted")]
+    PasskeyAttestationRejected,
+
+    #[error("Passkey already registered")]
+    PasskeyAlreadyRegistered,
+
+    #[error("Passkey not found")]
+    PasskeyNotFound,
+
+    #[error("Forbidden")]
+    Forbidden,
+
     #[error("Internal server error")]
     InternalServerError,
 }
+
+impl AuthError {
+    /// Stable, machine-readable identifier sent as `code` in error responses.
+    /// Clients should branch on this rather than on the message.
+    pub fn code(&self) -> &'static str {
+        match self {
+            AuthError::DatabaseError(_) => "internal_error",
+            AuthError::UserNotFound => "user_not_found",
+            AuthError::InvalidCredentials => "invalid_credentials",
+            AuthError::ValidationError(_) => "validation_failed",
+            AuthError::EmailAlreadyExists => "email_already_exists",
+            AuthError::InvalidResetToken => "invalid_reset_token",
+            AuthError::InvalidVerificationToken => "invalid_verification_token",
+            AuthError::EmailNotVerified => "email_not_verified",
+            AuthError::MfaAlreadyEnabled => "mfa_already_enabled",
+            AuthError::MfaNotEnabled => "mfa_not_enabled",
+            AuthError::InvalidMfaCode => "invalid_mfa_code",
+            AuthError::InvalidMfaChallenge => "invalid_mfa_challenge",
+            AuthError::InvalidRefreshToken => "invalid_refresh_token",
+            AuthError::TooManyLoginAttempts(_) => "too_many_attempts",
+            AuthError::LockoutNotFound => "lockout_not_found",
+            AuthError::RoleNotFound => "role_not_found",
+            AuthError::UnknownIdentityProvider => "unknown_identity_provider",
+            AuthError::InvalidOidcState => "invalid_oidc_state",
+            AuthError::OidcLoginDenied => "oidc_login_denied",
+            AuthError::InvalidIdToken => "invalid_id_token",
+            AuthError::IdentityProviderUnavailable => "identity_provider_unavailable",
+            AuthError::IdentityNotLinkable => "identity_not_linkable",
+            AuthError::IdentityNotFound => "identity_not_found",
+            AuthError::PersonalAccessTokenNotFound => "personal_access_token_not_found",
+            AuthError::SessionNotFound => "session_not_found",
+            AuthError::InvalidPasskeyCeremony => "invalid_passkey_ceremony",
+            AuthError::PasskeyVerificationFailed => "passkey_verification_failed",
+            AuthError::PasskeyAttestationRejected => "passkey_attestation_rejected",
+            AuthError::PasskeyAlreadyRegistered => "passkey_already_registered",
+            AuthError::PasskeyNotFound => "passkey_not_found",
+            AuthError::Forbidden => "forbidden",
+            AuthError::InternalServerError => "internal_error",
+        }
+    }
+}
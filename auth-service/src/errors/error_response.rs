@@ -1,6 +1,5 @@
 use actix_web::{HttpResponse, ResponseError};
-use actix_web::http::StatusCode;
-use std::fmt;
+use actix_web::http::{header, StatusCode};
 use serde_json::json;
 use crate::errors::error::AuthError;
 
@@ -10,12 +9,52 @@ impl ResponseError for AuthError {
             AuthError::DatabaseError(_) => StatusCode::INTERNAL_SERVER_ERROR,
             AuthError::UserNotFound => StatusCode::NOT_FOUND,
             AuthError::InvalidCredentials => StatusCode::UNAUTHORIZED,
+            AuthError::ValidationError(_) => StatusCode::BAD_REQUEST,
+            AuthError::EmailAlreadyExists => StatusCode::CONFLICT,
+            AuthError::InvalidResetToken => StatusCode::BAD_REQUEST,
+            AuthError::InvalidVerificationToken => StatusCode::BAD_REQUEST,
+            AuthError::EmailNotVerified => StatusCode::FORBIDDEN,
+            AuthError::MfaAlreadyEnabled => StatusCode::CONFLICT,
+            AuthError::MfaNotEnabled => StatusCode::BAD_REQUEST,
+            AuthError::InvalidMfaCode => StatusCode::UNAUTHORIZED,
+            AuthError::InvalidMfaChallenge => StatusCode::UNAUTHORIZED,
+            AuthError::InvalidRefreshToken => StatusCode::UNAUTHORIZED,
+            AuthError::TooManyLoginAttempts(_) => StatusCode::TOO_MANY_REQUESTS,
+            AuthError::LockoutNotFound => StatusCode::NOT_FOUND,
+            AuthError::RoleNotFound => StatusCode::NOT_FOUND,
+            AuthError::UnknownIdentityProvider => StatusCode::NOT_FOUND,
+            AuthError::InvalidOidcState => StatusCode::BAD_REQUEST,
+            AuthError::OidcLoginDenied => StatusCode::UNAUTHORIZED,
+            AuthError::InvalidIdToken => StatusCode::UNAUTHORIZED,
+            AuthError::IdentityProviderUnavailable => StatusCode::BAD_GATEWAY,
+            AuthError::IdentityNotLinkable => StatusCode::CONFLICT,
+            AuthError::IdentityNotFound => StatusCode::NOT_FOUND,
+            AuthError::PersonalAccessTokenNotFound => StatusCode::NOT_FOUND,
+            AuthError::SessionNotFound => StatusCode::NOT_FOUND,
+            AuthError::InvalidPasskeyCeremony => StatusCode::BAD_REQUEST,
+            AuthError::PasskeyVerificationFailed => StatusCode::UNAUTHORIZED,
+            AuthError::PasskeyAttestationRejected => StatusCode::BAD_REQUEST,
+            AuthError::PasskeyAlreadyRegistered => StatusCode::CONFLICT,
+            AuthError::PasskeyNotFound => StatusCode::NOT_FOUND,
+            AuthError::Forbidden => StatusCode::FORBIDDEN,
             AuthError::InternalServerError => StatusCode::INTERNAL_SERVER_ERROR,
         }
     }
 
     fn error_response(&self) -> HttpResponse {
-        HttpResponse::build(self.status_code())
-            .json(json!({ "error": self.to_string() }))
+        let mut response = HttpResponse::build(self.status_code());
+        match self {
+            AuthError::ValidationError(errors) => response
+                .json(json!({ "error": self.to_string(), "code": self.code(), "fields": errors })),
+            AuthError::TooManyLoginAttempts(retry_after) => response
+                .insert_header((header::RETRY_AFTER, retry_after.to_string()))
+                .json(json!({ "error": self.to_string(), "code": self.code(), "retry_after": retry_after })),
+            AuthError::DatabaseError(e) => {
+                // The diesel message can include SQL details, so it only goes to the log.
+                log::error!("Database error: {}", e);
+                response.json(json!({ "error": AuthError::InternalServerError.to_string(), "code": self.code() }))
+            },
+            _ => response.json(json!({ "error": self.to_string(), "code": self.code() })),
+        }
     }
 }
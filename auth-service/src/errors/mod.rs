@@ -1,2 +1,3 @@
-mod error;
-pub mod error_response;
\ No newline at end of file
+pub mod error;
+pub mod error_response;
+pub mod oauth_error;
\ No newline at end of file
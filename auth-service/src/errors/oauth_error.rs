@@ -0,0 +1,89 @@
+use actix_web::http::header::{self, CacheControl, CacheDirective, HeaderValue};
+use actix_web::http::StatusCode;
+use actix_web::{HttpResponse, ResponseError};
+use serde_json::json;
+use thiserror::Error;
+use crate::errors::error::AuthError;
+
+/// Errors from the OAuth endpoints, answered in the RFC 6749 section 5.2 format
+/// (`error` and `error_description`) rather than like `AuthError`.
+#[derive(Debug, Error)]
+pub enum OAuthError {
+    #[error("{0}")]
+    InvalidRequest(String),
+
+    #[error("Client authentication failed")]
+    InvalidClient,
+
+    #[error("The grant is invalid, expired or already used")]
+    InvalidGrant,
+
+    #[error("The client is not allowed to use this grant")]
+    UnauthorizedClient,
+
+    #[error("Unsupported grant type")]
+    UnsupportedGrantType,
+
+    #[error("Unsupported response type")]
+    UnsupportedResponseType,
+
+    #[error("The requested scope is not allowed for this client")]
+    InvalidScope,
+
+    #[error("The user or the authorization server denied the request")]
+    AccessDenied,
+
+    #[error("Internal server error")]
+    ServerError,
+}
+
+impl OAuthError {
+    pub fn code(&self) -> &'static str {
+        match self {
+            OAuthError::InvalidRequest(_) => "invalid_request",
+            OAuthError::InvalidClient => "invalid_client",
+            OAuthError::InvalidGrant => "invalid_grant",
+            OAuthError::UnauthorizedClient => "unauthorized_client",
+            OAuthError::UnsupportedGrantType => "unsupported_grant_type",
+            OAuthError::UnsupportedResponseType => "unsupported_response_type",
+            OAuthError::InvalidScope => "invalid_scope",
+            OAuthError::AccessDenied => "access_denied",
+            OAuthError::ServerError => "server_error",
+        }
+    }
+}
+
+impl From<diesel::result::Error> for OAuthError {
+    fn from(e: diesel::result::Error) -> Self {
+        log::error!("Database error: {}", e);
+        OAuthError::ServerError
+    }
+}
+
+impl From<AuthError> for OAuthError {
+    fn from(e: AuthError) -> Self {
+        log::error!("OAuth request failed: {}", e);
+        OAuthError::ServerError
+    }
+}
+
+impl ResponseError for OAuthError {
+    fn status_code(&self) -> StatusCode {
+        match self {
+            OAuthError::InvalidClient => StatusCode::UNAUTHORIZED,
+            OAuthError::AccessDenied => StatusCode::FORBIDDEN,
+            OAuthError::ServerError => StatusCode::INTERNAL_SERVER_ERROR,
+            _ => StatusCode::BAD_REQUEST,
+        }
+    }
+
+    fn error_response(&self) -> HttpResponse {
+        let mut response = HttpResponse::build(self.status_code());
+        response.insert_header(CacheControl(vec![CacheDirective::NoStore]));
+        if let OAuthError::InvalidClient = self {
+            response.insert_header((header::WWW_AUTHENTICATE, HeaderValue::from_static("Basic")));
+        }
+
+        response.json(json!({ "error": self.code(), "error_description": self.to_string() }))
+    }
+}
@@ -0,0 +1,64 @@
+use actix_web::{HttpResponse, web};
+use shared::auth::AuthenticatedUser;
+use uuid::Uuid;
+use crate::{
+    config::database::DbPool,
+    errors::error::AuthError,
+    models::role::AssignRoleRequest,
+    services::login_attempt_service::LoginAttemptService,
+    services::role_service::RoleService,
+};
+
+pub async fn list_lockouts(pool: web::Data<DbPool>) -> Result<HttpResponse, AuthError> {
+    let attempts = LoginAttemptService::list(&pool).await?;
+    Ok(HttpResponse::Ok().json(attempts))
+}
+
+pub async fn get_lockout(pool: web::Data<DbPool>, email: web::Path<String>) -> Result<HttpResponse, AuthError> {
+    let attempt = LoginAttemptService::find(&pool, &email).await?;
+    Ok(HttpResponse::Ok().json(attempt))
+}
+
+pub async fn clear_lockout(
+    pool: web::Data<DbPool>,
+    user: AuthenticatedUser,
+    email: web::Path<String>,
+) -> Result<HttpResponse, AuthError> {
+    LoginAttemptService::clear(&pool, &email, user_id(&user)?).await?;
+    Ok(HttpResponse::NoContent().finish())
+}
+
+pub async fn list_roles(pool: web::Data<DbPool>) -> Result<HttpResponse, AuthError> {
+    let roles = RoleService::list_roles(&pool).await?;
+    Ok(HttpResponse::Ok().json(roles))
+}
+
+pub async fn get_user_roles(pool: web::Data<DbPool>, path: web::Path<Uuid>) -> Result<HttpResponse, AuthError> {
+    let roles = RoleService::user_roles(&pool, path.into_inner()).await?;
+    Ok(HttpResponse::Ok().json(roles))
+}
+
+pub async fn assign_role(
+    pool: web::Data<DbPool>,
+    user: AuthenticatedUser,
+    path: web::Path<Uuid>,
+    request: web::Json<AssignRoleRequest>,
+) -> Result<HttpResponse, AuthError> {
+    let target = path.into_inner();
+    RoleService::assign(&pool, target, &request.role, Some(user_id(&user)?)).await?;
+    Ok(HttpResponse::Ok().json(RoleService::user_roles(&pool, target).await?))
+}
+
+pub async fn revoke_role(
+    pool: web::Data<DbPool>,
+    user: AuthenticatedUser,
+    path: web::Path<(Uuid, String)>,
+) -> Result<HttpResponse, AuthError> {
+    let (target, role) = path.into_inner();
+    RoleService::revoke(&pool, target, &role, user_id(&user)?).await?;
+    Ok(HttpResponse::NoContent().finish())
+}
+
+fn user_id(user: &AuthenticatedUser) -> Result<Uuid, AuthError> {
+    Uuid::parse_str(&user.sub).map_err(|_e| AuthError::Forbidden)
+}
@@ -1,15 +1,69 @@
-use actix_web::{HttpResponse, Responder, web};
+use actix_web::{HttpRequest, HttpResponse, web};
+use shared::auth::AuthenticatedUser;
 use crate::{
     config::database::DbPool,
-    models::auth::{LoginRequest},
+    config::keyring::Keyring,
+    errors::error::AuthError,
+    handlers::guards::session_user,
+    mailer::Mailer,
+    models::auth::{LoginRequest, RefreshTokenRequest, RegisterRequest, UpdatePasswordRequest},
+    models::session::ClientInfo,
     services::auth_service::AuthService,
+    services::email_verification_service::EmailVerificationService,
 };
 
-pub async fn login(pool: web::Data<DbPool>, login_request: web::Json<LoginRequest>) -> impl Responder {
-    let login_response = AuthService::authenticate(&pool, &login_request).await;
+pub async fn login(
+    req: HttpRequest,
+    pool: web::Data<DbPool>,
+    keyring: web::Data<Keyring>,
+    login_request: web::Json<LoginRequest>,
+) -> Result<HttpResponse, AuthError> {
+    let login_response = AuthService::authenticate(&pool, &keyring, &login_request, &ClientInfo::from_request(&req)).await?;
+    Ok(HttpResponse::Ok().json(login_response))
+}
+
+pub async fn refresh(
+    pool: web::Data<DbPool>,
+    keyring: web::Data<Keyring>,
+    refresh_request: web::Json<RefreshTokenRequest>,
+) -> Result<HttpResponse, AuthError> {
+    let refresh_response = AuthService::refresh(&pool, &keyring, &refresh_request.refresh_token).await?;
+    Ok(HttpResponse::Ok().json(refresh_response))
+}
+
+pub async fn logout(pool: web::Data<DbPool>, logout_request: web::Json<RefreshTokenRequest>) -> Result<HttpResponse, AuthError> {
+    AuthService::logout(&pool, &logout_request.refresh_token).await?;
+    Ok(HttpResponse::NoContent().finish())
+}
+
+pub async fn register(
+    req: HttpRequest,
+    pool: web::Data<DbPool>,
+    keyring: web::Data<Keyring>,
+    mailer: web::Data<dyn Mailer>,
+    register_request: web::Json<RegisterRequest>,
+) -> Result<HttpResponse, AuthError> {
+    let register_response = AuthService::register(&pool, &keyring, register_request.into_inner(), &ClientInfo::from_request(&req)).await?;
+
+    let user_id = register_response.id;
+    let pool = pool.get_ref().clone();
+    let mailer = mailer.into_inner();
+    actix_web::rt::spawn(async move {
+        if let Err(e) = EmailVerificationService::send_verification(&pool, &keyring, mailer, user_id).await {
+            log::error!("Failed to send verification email: {}", e);
+        }
+    });
+
+    Ok(HttpResponse::Created().json(register_response))
+}
 
-    match login_response {
-        Ok(token) => HttpResponse::Ok().json(token),
-        Err(err) => HttpResponse::Unauthorized().body(err),
-    }
+pub async fn change_password(
+    req: HttpRequest,
+    pool: web::Data<DbPool>,
+    keyring: web::Data<Keyring>,
+    user: AuthenticatedUser,
+    update_password_request: web::Json<UpdatePasswordRequest>,
+) -> Result<HttpResponse, AuthError> {
+    let tokens = AuthService::change_password(&pool, &keyring, session_user(&user)?, update_password_request.into_inner(), &ClientInfo::from_request(&req)).await?;
+    Ok(HttpResponse::Ok().json(tokens))
 }
@@ -0,0 +1,38 @@
+use actix_web::{HttpResponse, web};
+use serde_json::json;
+use crate::{
+    config::database::DbPool,
+    config::keyring::Keyring,
+    errors::error::AuthError,
+    mailer::Mailer,
+    models::auth::{ResendVerificationRequest, VerifyEmailQuery},
+    services::email_verification_service::EmailVerificationService,
+};
+
+pub async fn verify_email(
+    pool: web::Data<DbPool>,
+    keyring: web::Data<Keyring>,
+    query: web::Query<VerifyEmailQuery>,
+) -> Result<HttpResponse, AuthError> {
+    EmailVerificationService::verify(&pool, &keyring, &query.token).await?;
+    Ok(HttpResponse::Ok().json(json!({ "message": "Email address verified" })))
+}
+
+pub async fn resend_verification(
+    pool: web::Data<DbPool>,
+    keyring: web::Data<Keyring>,
+    mailer: web::Data<dyn Mailer>,
+    resend_request: web::Json<ResendVerificationRequest>,
+) -> HttpResponse {
+    let pool = pool.get_ref().clone();
+    let mailer = mailer.into_inner();
+    let email = resend_request.into_inner().email;
+
+    actix_web::rt::spawn(async move {
+        EmailVerificationService::resend(&pool, &keyring, mailer, &email).await;
+    });
+
+    HttpResponse::Accepted().json(json!({
+        "message": "If an unverified account exists for that email, a new link has been sent"
+    }))
+}
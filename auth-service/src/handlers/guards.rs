@@ -0,0 +1,13 @@
+use shared::auth::AuthenticatedUser;
+use uuid::Uuid;
+use crate::errors::error::AuthError;
+
+/// The user behind a first-party session token. Personal access tokens and
+/// tokens issued to OAuth clients are refused, so a leaked token cannot
+/// manage the account or mint longer-lived or broader tokens.
+pub(crate) fn session_user(user: &AuthenticatedUser) -> Result<Uuid, AuthError> {
+    if user.claims.token_id.is_some() || user.claims.client_id.is_some() {
+        return Err(AuthError::Forbidden);
+    }
+    Uuid::parse_str(&user.sub).map_err(|_e| AuthError::InvalidCredentials)
+}
@@ -0,0 +1,44 @@
+use actix_web::http::header::{CacheControl, CacheDirective};
+use actix_web::{HttpRequest, HttpResponse, web};
+use shared::auth::TokenValidator;
+use crate::{
+    config::database::DbPool,
+    errors::oauth_error::OAuthError,
+    handlers::oauth::client_credentials,
+    models::introspection::TokenHintRequest,
+    services::introspection_service::IntrospectionService,
+    services::oauth_service::OAuthService,
+    services::token_revocation_service::TokenRevocationService,
+};
+
+/// RFC 7662 token introspection, for service clients.
+pub async fn introspect(
+    req: HttpRequest,
+    pool: web::Data<DbPool>,
+    validator: web::Data<TokenValidator>,
+    request: web::Form<TokenHintRequest>,
+) -> Result<HttpResponse, OAuthError> {
+    let request = request.into_inner();
+    let credentials = client_credentials(&req, request.client_id, request.client_secret)?;
+    let client = OAuthService::authenticate_client(&pool, &credentials).await?;
+
+    let response = IntrospectionService::introspect(&pool, &validator, &client, &request.token).await?;
+    Ok(HttpResponse::Ok()
+        .insert_header(CacheControl(vec![CacheDirective::NoStore]))
+        .json(response))
+}
+
+/// RFC 7009 token revocation. Answers 200 whether or not the token was known.
+pub async fn revoke(
+    req: HttpRequest,
+    pool: web::Data<DbPool>,
+    validator: web::Data<TokenValidator>,
+    request: web::Form<TokenHintRequest>,
+) -> Result<HttpResponse, OAuthError> {
+    let request = request.into_inner();
+    let credentials = client_credentials(&req, request.client_id, request.client_secret)?;
+    let client = OAuthService::authenticate_client(&pool, &credentials).await?;
+
+    TokenRevocationService::revoke(&pool, &validator, &client, &request.token).await?;
+    Ok(HttpResponse::Ok().finish())
+}
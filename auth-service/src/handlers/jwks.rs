@@ -0,0 +1,9 @@
+use actix_web::{HttpResponse, Responder, web};
+use actix_web::http::header::{CacheControl, CacheDirective};
+use crate::config::keyring::Keyring;
+
+pub async fn jwks(keyring: web::Data<Keyring>) -> impl Responder {
+    HttpResponse::Ok()
+        .insert_header(CacheControl(vec![CacheDirective::Public, CacheDirective::MaxAge(300)]))
+        .json(keyring.jwks())
+}
@@ -0,0 +1,45 @@
+use actix_web::{HttpRequest, HttpResponse, web};
+use shared::auth::AuthenticatedUser;
+use crate::{
+    config::database::DbPool,
+    config::keyring::Keyring,
+    errors::error::AuthError,
+    handlers::guards::session_user,
+    models::mfa::{MfaVerifyRequest, RecoveryCodesResponse, TotpCodeRequest},
+    models::session::ClientInfo,
+    services::auth_service::AuthService,
+    services::mfa_service::MfaService,
+};
+
+pub async fn enroll_totp(pool: web::Data<DbPool>, user: AuthenticatedUser) -> Result<HttpResponse, AuthError> {
+    let enrollment = MfaService::enroll(&pool, session_user(&user)?).await?;
+    Ok(HttpResponse::Ok().json(enrollment))
+}
+
+pub async fn confirm_totp(
+    pool: web::Data<DbPool>,
+    user: AuthenticatedUser,
+    request: web::Json<TotpCodeRequest>,
+) -> Result<HttpResponse, AuthError> {
+    let recovery_codes = MfaService::confirm(&pool, session_user(&user)?, &request.code).await?;
+    Ok(HttpResponse::Ok().json(RecoveryCodesResponse { recovery_codes }))
+}
+
+pub async fn disable_totp(
+    pool: web::Data<DbPool>,
+    user: AuthenticatedUser,
+    request: web::Json<TotpCodeRequest>,
+) -> Result<HttpResponse, AuthError> {
+    MfaService::disable(&pool, session_user(&user)?, &request.code).await?;
+    Ok(HttpResponse::NoContent().finish())
+}
+
+pub async fn verify_mfa(
+    req: HttpRequest,
+    pool: web::Data<DbPool>,
+    keyring: web::Data<Keyring>,
+    request: web::Json<MfaVerifyRequest>,
+) -> Result<HttpResponse, AuthError> {
+    let tokens = AuthService::complete_mfa_login(&pool, &keyring, &request, &ClientInfo::from_request(&req)).await?;
+    Ok(HttpResponse::Ok().json(tokens))
+}
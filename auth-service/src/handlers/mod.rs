@@ -1 +1,13 @@
-pub mod auth;
\ No newline at end of file
+pub mod admin;
+pub mod auth;
+pub mod email_verification;
+pub mod guards;
+pub mod introspection;
+pub mod jwks;
+pub mod mfa;
+pub mod oauth;
+pub mod oidc;
+pub mod passkey;
+pub mod password_reset;
+pub mod personal_access_token;
+pub mod session;
\ No newline at end of file
@@ -0,0 +1,95 @@
+use actix_web::http::header::{self, CacheControl, CacheDirective};
+use actix_web::{HttpRequest, HttpResponse, web};
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use shared::auth::AuthenticatedUser;
+use uuid::Uuid;
+use crate::{
+    config::database::DbPool,
+    config::keyring::Keyring,
+    errors::error::AuthError,
+    errors::oauth_error::OAuthError,
+    handlers::guards::session_user,
+    models::oauth::{AuthorizeQuery, ConsentDecision, RegisterClientRequest, TokenRequest},
+    services::oauth_service::{ClientCredentials, OAuthService},
+};
+
+pub async fn register_client(
+    pool: web::Data<DbPool>,
+    user: AuthenticatedUser,
+    request: web::Json<RegisterClientRequest>,
+) -> Result<HttpResponse, AuthError> {
+    let created_by = Uuid::parse_str(&user.sub).map_err(|_e| AuthError::Forbidden)?;
+    let client = OAuthService::register_client(&pool, created_by, request.into_inner()).await?;
+    Ok(HttpResponse::Created().json(client))
+}
+
+/// Shows the consent step for an authorization request. The caller is the
+/// signed-in user, authenticated with a first-party access token.
+pub async fn authorize(
+    pool: web::Data<DbPool>,
+    user: AuthenticatedUser,
+    query: web::Query<AuthorizeQuery>,
+) -> Result<HttpResponse, OAuthError> {
+    first_party_user(&user)?;
+    let prompt = OAuthService::consent_prompt(&pool, &query).await?;
+    Ok(HttpResponse::Ok().json(prompt))
+}
+
+pub async fn decide(
+    pool: web::Data<DbPool>,
+    user: AuthenticatedUser,
+    decision: web::Json<ConsentDecision>,
+) -> Result<HttpResponse, OAuthError> {
+    let user_id = first_party_user(&user)?;
+    let redirect = OAuthService::decide(&pool, user_id, decision.into_inner()).await?;
+    Ok(HttpResponse::Ok().json(redirect))
+}
+
+pub async fn token(
+    req: HttpRequest,
+    pool: web::Data<DbPool>,
+    keyring: web::Data<Keyring>,
+    request: web::Form<TokenRequest>,
+) -> Result<HttpResponse, OAuthError> {
+    let request = request.into_inner();
+    let credentials = client_credentials(&req, request.client_id.clone(), request.client_secret.clone())?;
+
+    let tokens = OAuthService::token(&pool, &keyring, request, credentials).await?;
+    Ok(HttpResponse::Ok()
+        .insert_header(CacheControl(vec![CacheDirective::NoStore]))
+        .json(tokens))
+}
+
+/// Only a first-party session may approve clients: a personal access token
+/// or a client's token would otherwise mint tokens beyond its own scope and
+/// lifetime.
+fn first_party_user(user: &AuthenticatedUser) -> Result<Uuid, OAuthError> {
+    session_user(user).map_err(|_e| OAuthError::AccessDenied)
+}
+
+/// HTTP Basic credentials, or else the ones posted in the form body.
+pub(crate) fn client_credentials(
+    req: &HttpRequest,
+    client_id: Option<String>,
+    client_secret: Option<String>,
+) -> Result<ClientCredentials, OAuthError> {
+    basic_credentials(req)
+        .or_else(|| client_id.map(|client_id| ClientCredentials { client_id, client_secret }))
+        .ok_or(OAuthError::InvalidClient)
+}
+
+fn basic_credentials(req: &HttpRequest) -> Option<ClientCredentials> {
+    let value = req.headers().get(header::AUTHORIZATION)?.to_str().ok()?;
+    let (scheme, encoded) = value.split_once(' ')?;
+    if !scheme.eq_ignore_ascii_case("Basic") {
+        return None;
+    }
+
+    let decoded = String::from_utf8(STANDARD.decode(encoded.trim()).ok()?).ok()?;
+    let (client_id, client_secret) = decoded.split_once(':')?;
+    Some(ClientCredentials {
+        client_id: client_id.to_string(),
+        client_secret: Some(client_secret.to_string()).filter(|secret| !secret.is_empty()),
+    })
+}
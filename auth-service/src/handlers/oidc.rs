@@ -0,0 +1,90 @@
+use actix_web::cookie::time::Duration;
+use actix_web::cookie::{Cookie, SameSite};
+use actix_web::http::header::{self, CacheControl, CacheDirective};
+use actix_web::{HttpRequest, HttpResponse, web};
+use shared::auth::AuthenticatedUser;
+use uuid::Uuid;
+use crate::{
+    config::database::DbPool,
+    config::keyring::Keyring,
+    config::oidc::{OidcProvider, OidcProviders},
+    errors::error::AuthError,
+    handlers::guards::session_user,
+    models::oidc::OidcCallbackQuery,
+    models::session::ClientInfo,
+    services::oidc_service::{OidcService, OIDC_STATE_TTL_SECONDS},
+};
+
+/// Carries the signed login state between `start` and `callback`.
+pub const OIDC_STATE_COOKIE: &str = "oidc_state";
+const OIDC_COOKIE_PATH: &str = "/auth/oidc";
+
+/// Sends the browser to the provider's sign-in page.
+pub async fn start(
+    providers: web::Data<OidcProviders>,
+    keyring: web::Data<Keyring>,
+    provider: web::Path<String>,
+) -> Result<HttpResponse, AuthError> {
+    let provider = find_provider(&providers, &provider)?;
+    let login = OidcService::start(provider, &keyring).await?;
+
+    let cookie = Cookie::build(OIDC_STATE_COOKIE, login.state_token)
+        .path(OIDC_COOKIE_PATH)
+        .http_only(true)
+        .secure(true)
+        .same_site(SameSite::Lax)
+        .max_age(Duration::seconds(OIDC_STATE_TTL_SECONDS))
+        .finish();
+
+    Ok(HttpResponse::Found()
+        .insert_header((header::LOCATION, login.authorization_url))
+        .cookie(cookie)
+        .finish())
+}
+
+/// Where the provider sends the browser back. Responds like `/auth/login`.
+pub async fn callback(
+    req: HttpRequest,
+    pool: web::Data<DbPool>,
+    providers: web::Data<OidcProviders>,
+    keyring: web::Data<Keyring>,
+    provider: web::Path<String>,
+    query: web::Query<OidcCallbackQuery>,
+) -> Result<HttpResponse, AuthError> {
+    let provider = find_provider(&providers, &provider)?;
+    let state_token = req.cookie(OIDC_STATE_COOKIE);
+    let outcome = OidcService::callback(
+        &pool,
+        &keyring,
+        provider,
+        query.into_inner(),
+        state_token.as_ref().map(Cookie::value),
+        &ClientInfo::from_request(&req),
+    ).await?;
+
+    let mut expired = Cookie::build(OIDC_STATE_COOKIE, "").path(OIDC_COOKIE_PATH).finish();
+    expired.make_removal();
+
+    Ok(HttpResponse::Ok()
+        .insert_header(CacheControl(vec![CacheDirective::NoStore]))
+        .cookie(expired)
+        .json(outcome))
+}
+
+pub async fn list_identities(pool: web::Data<DbPool>, user: AuthenticatedUser) -> Result<HttpResponse, AuthError> {
+    let identities = OidcService::identities(&pool, session_user(&user)?).await?;
+    Ok(HttpResponse::Ok().json(identities))
+}
+
+pub async fn unlink_identity(
+    pool: web::Data<DbPool>,
+    user: AuthenticatedUser,
+    identity_id: web::Path<Uuid>,
+) -> Result<HttpResponse, AuthError> {
+    OidcService::unlink(&pool, session_user(&user)?, identity_id.into_inner()).await?;
+    Ok(HttpResponse::NoContent().finish())
+}
+
+fn find_provider<'a>(providers: &'a OidcProviders, name: &str) -> Result<&'a OidcProvider, AuthError> {
+    providers.find(name).ok_or(AuthError::UnknownIdentityProvider)
+}
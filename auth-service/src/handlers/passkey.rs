@@ -0,0 +1,67 @@
+use actix_web::{HttpRequest, HttpResponse, web};
+use shared::auth::AuthenticatedUser;
+use uuid::Uuid;
+use crate::{
+    config::database::DbPool,
+    config::keyring::Keyring,
+    errors::error::AuthError,
+    handlers::guards::session_user,
+    models::passkey::{FinishPasskeyLoginRequest, FinishPasskeyRegistrationRequest, StartPasskeyLoginRequest},
+    models::session::ClientInfo,
+    services::auth_service::AuthService,
+    services::passkey_service::PasskeyService,
+};
+
+pub async fn start_passkey_registration(
+    pool: web::Data<DbPool>,
+    keyring: web::Data<Keyring>,
+    user: AuthenticatedUser,
+) -> Result<HttpResponse, AuthError> {
+    let ceremony = PasskeyService::start_registration(&pool, &keyring, session_user(&user)?).await?;
+    Ok(HttpResponse::Ok().json(ceremony))
+}
+
+pub async fn finish_passkey_registration(
+    pool: web::Data<DbPool>,
+    keyring: web::Data<Keyring>,
+    user: AuthenticatedUser,
+    request: web::Json<FinishPasskeyRegistrationRequest>,
+) -> Result<HttpResponse, AuthError> {
+    let passkey = PasskeyService::finish_registration(&pool, &keyring, session_user(&user)?, request.into_inner()).await?;
+    Ok(HttpResponse::Created().json(passkey))
+}
+
+pub async fn list_passkeys(pool: web::Data<DbPool>, user: AuthenticatedUser) -> Result<HttpResponse, AuthError> {
+    let passkeys = PasskeyService::list(&pool, session_user(&user)?).await?;
+    Ok(HttpResponse::Ok().json(passkeys))
+}
+
+pub async fn remove_passkey(
+    pool: web::Data<DbPool>,
+    user: AuthenticatedUser,
+    passkey_id: web::Path<Uuid>,
+) -> Result<HttpResponse, AuthError> {
+    PasskeyService::remove(&pool, session_user(&user)?, passkey_id.into_inner()).await?;
+    Ok(HttpResponse::NoContent().finish())
+}
+
+pub async fn start_passkey_login(
+    pool: web::Data<DbPool>,
+    keyring: web::Data<Keyring>,
+    request: web::Json<StartPasskeyLoginRequest>,
+) -> Result<HttpResponse, AuthError> {
+    let ceremony = PasskeyService::start_login(&pool, &keyring, &request).await?;
+    Ok(HttpResponse::Ok().json(ceremony))
+}
+
+/// Responds like `/auth/login`, minus the MFA challenge.
+pub async fn finish_passkey_login(
+    req: HttpRequest,
+    pool: web::Data<DbPool>,
+    keyring: web::Data<Keyring>,
+    request: web::Json<FinishPasskeyLoginRequest>,
+) -> Result<HttpResponse, AuthError> {
+    let login = PasskeyService::finish_login(&pool, &keyring, request.into_inner()).await?;
+    let login_response = AuthService::sign_in_with_passkey(&pool, &keyring, &login, &ClientInfo::from_request(&req)).await?;
+    Ok(HttpResponse::Ok().json(login_response))
+}
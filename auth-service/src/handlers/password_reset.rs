@@ -0,0 +1,38 @@
+use actix_web::{HttpResponse, web};
+use serde_json::json;
+use crate::{
+    config::database::DbPool,
+    errors::error::AuthError,
+    mailer::Mailer,
+    models::auth::{ForgotPasswordRequest, ResetPasswordRequest},
+    services::password_reset_service::PasswordResetService,
+};
+
+pub async fn forgot_password(
+    pool: web::Data<DbPool>,
+    mailer: web::Data<dyn Mailer>,
+    forgot_password_request: web::Json<ForgotPasswordRequest>,
+) -> HttpResponse {
+    let pool = pool.get_ref().clone();
+    let mailer = mailer.into_inner();
+    let email = forgot_password_request.into_inner().email;
+
+    // The lookup and the email happen after we answer, so neither the body nor
+    // the response time says whether the account exists.
+    actix_web::rt::spawn(async move {
+        PasswordResetService::request_reset(&pool, mailer, &email).await;
+    });
+
+    HttpResponse::Accepted().json(json!({
+        "message": "If an account exists for that email, a reset link has been sent"
+    }))
+}
+
+pub async fn reset_password(
+    pool: web::Data<DbPool>,
+    reset_password_request: web::Json<ResetPasswordRequest>,
+) -> Result<HttpResponse, AuthError> {
+    let request = reset_password_request.into_inner();
+    PasswordResetService::reset_password(&pool, &request.token, &request.new_password).await?;
+    Ok(HttpResponse::NoContent().finish())
+}
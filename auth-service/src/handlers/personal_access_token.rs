@@ -0,0 +1,43 @@
+use actix_web::http::header::{CacheControl, CacheDirective};
+use actix_web::{HttpResponse, web};
+use shared::auth::AuthenticatedUser;
+use uuid::Uuid;
+use crate::{
+    config::database::DbPool,
+    errors::error::AuthError,
+    handlers::guards::session_user,
+    models::personal_access_token::CreatePersonalAccessTokenRequest,
+    services::personal_access_token_service::PersonalAccessTokenService,
+};
+
+pub async fn create_token(
+    pool: web::Data<DbPool>,
+    user: AuthenticatedUser,
+    request: web::Json<CreatePersonalAccessTokenRequest>,
+) -> Result<HttpResponse, AuthError> {
+    let created = PersonalAccessTokenService::create(&pool, session_user(&user)?, request.into_inner()).await?;
+    Ok(HttpResponse::Created()
+        .insert_header(CacheControl(vec![CacheDirective::NoStore]))
+        .json(created))
+}
+
+pub async fn list_tokens(pool: web::Data<DbPool>, user: AuthenticatedUser) -> Result<HttpResponse, AuthError> {
+    let tokens = PersonalAccessTokenService::list(&pool, session_user(&user)?).await?;
+    Ok(HttpResponse::Ok().json(tokens))
+}
+
+pub async fn revoke_token(
+    pool: web::Data<DbPool>,
+    user: AuthenticatedUser,
+    token_id: web::Path<Uuid>,
+) -> Result<HttpResponse, AuthError> {
+    PersonalAccessTokenService::revoke(&pool, session_user(&user)?, token_id.into_inner()).await?;
+    Ok(HttpResponse::NoContent().finish())
+}
+
+/// The claims behind the presented bearer token, as this service sees them.
+pub async fn current_token(user: AuthenticatedUser) -> HttpResponse {
+    HttpResponse::Ok()
+        .insert_header(CacheControl(vec![CacheDirective::NoStore]))
+        .json(user.claims)
+}
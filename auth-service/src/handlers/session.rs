@@ -0,0 +1,32 @@
+use actix_web::{HttpResponse, web};
+use shared::auth::AuthenticatedUser;
+use uuid::Uuid;
+use crate::{
+    config::database::DbPool,
+    errors::error::AuthError,
+    handlers::guards::session_user,
+    services::session_service::SessionService,
+};
+
+/// The caller's active sessions, most recently used first.
+pub async fn list_sessions(pool: web::Data<DbPool>, user: AuthenticatedUser) -> Result<HttpResponse, AuthError> {
+    let user_id = session_user(&user)?;
+    let current = user.claims.sid.as_deref().and_then(|sid| Uuid::parse_str(sid).ok());
+    let sessions = SessionService::list(&pool, user_id, current).await?;
+    Ok(HttpResponse::Ok().json(sessions))
+}
+
+pub async fn revoke_session(
+    pool: web::Data<DbPool>,
+    user: AuthenticatedUser,
+    session_id: web::Path<Uuid>,
+) -> Result<HttpResponse, AuthError> {
+    SessionService::revoke(&pool, session_user(&user)?, session_id.into_inner()).await?;
+    Ok(HttpResponse::NoContent().finish())
+}
+
+/// Logs the caller out everywhere, including the session making the request.
+pub async fn revoke_all_sessions(pool: web::Data<DbPool>, user: AuthenticatedUser) -> Result<HttpResponse, AuthError> {
+    SessionService::revoke_all(&pool, session_user(&user)?).await?;
+    Ok(HttpResponse::NoContent().finish())
+}
@@ -1,10 +1,12 @@
 pub mod config;
 pub mod errors;
 pub mod handlers;
+pub mod mailer;
 pub mod models;
 pub mod repositories;
 pub mod services;
 pub mod utils;
-pub mod tests;
+#[cfg(test)]
+mod tests;
 pub mod routes;
 pub mod middleware;
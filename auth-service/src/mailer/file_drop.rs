@@ -0,0 +1,29 @@
+use std::path::PathBuf;
+
+use async_trait::async_trait;
+use uuid::Uuid;
+
+use crate::mailer::{Email, Mailer, MailerError};
+
+/// Writes each email as a JSON file instead of sending it. Handy for local
+/// development, where the reset link can be copied out of the file.
+pub struct FileDropMailer {
+    dir: PathBuf,
+}
+
+impl FileDropMailer {
+    pub fn new(dir: impl Into<PathBuf>) -> FileDropMailer {
+        FileDropMailer { dir: dir.into() }
+    }
+}
+
+#[async_trait]
+impl Mailer for FileDropMailer {
+    async fn send(&self, email: Email) -> Result<(), MailerError> {
+        let payload = serde_json::to_vec_pretty(&email).map_err(|e| MailerError::Delivery(e.to_string()))?;
+        let path = self.dir.join(format!("{}.json", Uuid::new_v4()));
+
+        tokio::fs::create_dir_all(&self.dir).await.map_err(|e| MailerError::Delivery(e.to_string()))?;
+        tokio::fs::write(&path, payload).await.map_err(|e| MailerError::Delivery(e.to_string()))
+    }
+}
@@ -0,0 +1,25 @@
+use std::sync::Mutex;
+
+use async_trait::async_trait;
+
+use crate::mailer::{Email, Mailer, MailerError};
+
+/// Keeps sent emails in memory so tests can assert on them.
+#[derive(Default)]
+pub struct InMemoryMailer {
+    sent: Mutex<Vec<Email>>,
+}
+
+impl InMemoryMailer {
+    pub fn sent(&self) -> Vec<Email> {
+        self.sent.lock().unwrap().clone()
+    }
+}
+
+#[async_trait]
+impl Mailer for InMemoryMailer {
+    async fn send(&self, email: Email) -> Result<(), MailerError> {
+        self.sent.lock().unwrap().push(email);
+        Ok(())
+    }
+}
@@ -0,0 +1,55 @@
+use std::env;
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use serde::Serialize;
+use thiserror::Error;
+
+pub mod file_drop;
+pub mod in_memory;
+pub mod smtp;
+
+pub use file_drop::FileDropMailer;
+pub use in_memory::InMemoryMailer;
+pub use smtp::SmtpMailer;
+
+#[derive(Debug, Clone, Serialize, PartialEq, Eq)]
+pub struct Email {
+    pub to: String,
+    pub subject: String,
+    pub body: String,
+}
+
+#[derive(Debug, Error)]
+pub enum MailerError {
+    #[error("Invalid address: {0}")]
+    InvalidAddress(String),
+
+    #[error("Failed to deliver email: {0}")]
+    Delivery(String),
+}
+
+#[async_trait]
+pub trait Mailer: Send + Sync {
+    async fn send(&self, email: Email) -> Result<(), MailerError>;
+}
+
+/// Picks the transport from `MAILER`: `smtp` (needs `SMTP_URL` and
+/// `MAIL_FROM`), `file` (writes to `MAIL_DROP_DIR`) or `memory`. Panics on
+/// anything else, so a typo cannot quietly drop every email into a directory.
+pub fn mailer_from_env() -> Arc<dyn Mailer> {
+    let mailer = env::var("MAILER").expect("MAILER must be set to smtp, file or memory");
+    match mailer.as_str() {
+        "smtp" => {
+            let url = env::var("SMTP_URL").expect("SMTP_URL must be set");
+            let from = env::var("MAIL_FROM").expect("MAIL_FROM must be set");
+            Arc::new(SmtpMailer::new(&url, &from).expect("Failed to configure SMTP mailer"))
+        }
+        "file" => {
+            let dir = env::var("MAIL_DROP_DIR").unwrap_or_else(|_| "mail".to_string());
+            Arc::new(FileDropMailer::new(dir))
+        }
+        "memory" => Arc::new(InMemoryMailer::default()),
+        other => panic!("MAILER must be smtp, file or memory, not {:?}", other),
+    }
+}
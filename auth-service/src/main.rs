@@ -1,12 +1,24 @@
 use std::io;
+use std::sync::Arc;
 
+use actix_governor::Governor;
 use actix_web::{App, HttpServer, middleware, web};
 use diesel_migrations::{embed_migrations, EmbeddedMigrations, MigrationHarness};
 use env_logger::Env;
 
 use auth_service::config::database::establish_connection;
-use auth_service::middleware::rate_limiter::configure_rate_limiter;
-use auth_service::routes::auth_routes;
+use auth_service::config::keyring::load_keyring;
+use auth_service::config::oidc::load_oidc_providers;
+use auth_service::mailer::{mailer_from_env, Mailer};
+use auth_service::middleware::rate_limiter::{configure_login_rate_limiter, configure_rate_limiter};
+use auth_service::services::erasure_service::ErasureService;
+use auth_service::services::personal_access_token_service::DatabasePersonalAccessTokens;
+use auth_service::services::role_service::RoleService;
+use auth_service::services::token_revocation_service::DatabaseRevocations;
+use auth_service::routes::{admin_routes, auth_routes, oauth_routes, well_known_routes};
+use shared::erasure::ERASURE_REQUESTED_TOPIC;
+use shared::events::{event_bus_from_env, event_consumer_from_env};
+use shared::outbox::OutboxRelay;
 
 pub const MIGRATIONS: EmbeddedMigrations = embed_migrations!("./migrations");
 
@@ -15,17 +27,41 @@ async fn main() -> io::Result<()> {
     dotenv::dotenv().ok();
     env_logger::init_from_env(Env::default().default_filter_or("info"));
     let pool = establish_connection();
+    let keyring = web::Data::new(load_keyring());
+    let token_validator = web::Data::new(keyring.token_validator()
+        .with_personal_access_tokens(Arc::new(DatabasePersonalAccessTokens::new(pool.clone())))
+        .with_revocation_check(Arc::new(DatabaseRevocations::from_env(pool.clone()))));
+    let mailer: web::Data<dyn Mailer> = web::Data::from(mailer_from_env());
+    let oidc_providers = web::Data::new(load_oidc_providers());
 
     // Executa migrações
     let mut conn = pool.get().expect("Failed to get DB connection from pool");
     conn.run_pending_migrations(MIGRATIONS).expect("Failed to run migrations");
+    RoleService::bootstrap_admins(&pool).await;
+    tokio::spawn(OutboxRelay::new(pool.clone(), event_bus_from_env()).run());
+    if let Some(consumer) = event_consumer_from_env("auth-service", &[ERASURE_REQUESTED_TOPIC]) {
+        let pool = pool.clone();
+        tokio::spawn(consumer.run(move |_topic, payload| {
+            let pool = pool.clone();
+            async move { ErasureService::handle(&pool, &payload).await }
+        }));
+    }
 
+    let rate_limit = configure_rate_limiter();
+    let login_rate_limit = configure_login_rate_limiter();
     HttpServer::new(move || {
         App::new()
             .app_data(web::Data::new(pool.clone()))
+            .app_data(keyring.clone())
+            .app_data(token_validator.clone())
+            .app_data(mailer.clone())
+            .app_data(oidc_providers.clone())
             .wrap(middleware::Logger::default())
-            .wrap(configure_rate_limiter())
-            .service(auth_routes())
+            .wrap(Governor::new(&rate_limit))
+            .service(auth_routes(&login_rate_limit))
+            .service(admin_routes())
+            .service(oauth_routes())
+            .service(well_known_routes())
     })
         .bind("127.0.0.1:8081")?
         .run()
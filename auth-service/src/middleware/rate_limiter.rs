@@ -1,27 +1,57 @@
-use actix_governor::{Governor, GovernorConfigBuilder, KeyExtractor};
+use std::env;
+use actix_governor::{GovernorConfig, GovernorConfigBuilder, PeerIpKeyExtractor};
 use actix_governor::governor::middleware::NoOpMiddleware;
-use actix_web::dev::ServiceRequest;
 
-#[derive(Clone)]
-pub struct FixedKeyExtractor;
+const DEFAULT_LOGIN_BURST: u32 = 5;
+const DEFAULT_LOGIN_SECONDS: u64 = 12;
 
-impl KeyExtractor for FixedKeyExtractor {
-    type Key = String;
-    type KeyExtractionError = std::convert::Infallible; // Ajuste aqui para um erro infalível
+/// A per-IP limit. Build it once and wrap each worker's app in
+/// `Governor::new(&config)`: the workers then share its buckets, where a
+/// governor built per worker would multiply the limit by the worker count.
+pub type RateLimit = GovernorConfig<PeerIpKeyExtractor, NoOpMiddleware>;
 
-    fn extract(&self, _req: &ServiceRequest) -> Result<Self::Key, Self::KeyExtractionError> {
-        Ok("fixed_key".to_string())
-    }
+/// Rate limiter for the whole service. Every client IP gets its own bucket, so
+/// one noisy client cannot use up the budget for everyone else.
+pub fn configure_rate_limiter() -> RateLimit {
+    GovernorConfigBuilder::default()
+        .per_second(10)
+        .burst_size(10)
+        .key_extractor(PeerIpKeyExtractor)
+        .finish()
+        .unwrap()
 }
 
-pub fn configure_rate_limiter() -> Governor<FixedKeyExtractor, NoOpMiddleware> {
-    // Configuração do rate limiter
-    let governor_conf = GovernorConfigBuilder::default()
-        .per_second(10) // 10 requisições por segundo
-        .burst_size(10) // Permitir "burst" de até 10 requisições
-        .key_extractor(FixedKeyExtractor)
+/// Stricter per-IP limit for `/auth/login`: a burst of `LOGIN_RATE_LIMIT_BURST`
+/// attempts, then one every `LOGIN_RATE_LIMIT_SECONDS`. Guessing against many
+/// accounts from one address is slowed down here; guessing one account from
+/// many addresses is handled by the per-email lockout.
+pub fn configure_login_rate_limiter() -> RateLimit {
+    let burst = positive_env("LOGIN_RATE_LIMIT_BURST", DEFAULT_LOGIN_BURST);
+    let seconds = positive_env("LOGIN_RATE_LIMIT_SECONDS", DEFAULT_LOGIN_SECONDS);
+
+    GovernorConfigBuilder::default()
+        .per_second(seconds)
+        .burst_size(burst)
+        .key_extractor(PeerIpKeyExtractor)
         .finish()
-        .unwrap();
+        .unwrap()
+}
+
+/// `name` parsed as a number above zero, or `default` with a warning when it is
+/// set to anything else. The builder cannot make a limiter from a zero.
+fn positive_env<T>(name: &str, default: T) -> T
+where
+    T: std::str::FromStr + PartialEq + Default + std::fmt::Display,
+{
+    let Ok(value) = env::var(name) else {
+        return default;
+    };
 
-    Governor::new(&governor_conf)
+    match value.parse::<T>() {
+        Ok(parsed) if parsed != T::default() => parsed,
+        _ => {
+            log::warn!("{} must be a number above zero, not {:?}; using {}", name, value, default);
+            default
+        }
+    }
 }
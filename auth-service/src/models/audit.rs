@@ -0,0 +1,41 @@
+use chrono::NaiveDateTime;
+use serde::Serialize;
+use serde_json::Value;
+use uuid::Uuid;
+
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum AuditEventType {
+    LoginSucceeded,
+    LoginFailed,
+    PasswordChanged,
+    PasswordResetRequested,
+    PasswordReset,
+    MfaEnabled,
+    MfaDisabled,
+    RecoveryCodeUsed,
+    AccountLocked,
+    LockoutCleared,
+    RoleAssigned,
+    RoleRevoked,
+    IdentityLinked,
+    IdentityUnlinked,
+    PersonalAccessTokenCreated,
+    PersonalAccessTokenRevoked,
+    PasskeyRegistered,
+    PasskeyRemoved,
+    SessionRevoked,
+    AllSessionsRevoked,
+    TokenRevoked,
+    AccountErased,
+}
+
+#[derive(Serialize, Debug)]
+pub struct AuditEvent {
+    /// Also the outbox id; consumers can use it to drop redeliveries.
+    pub id: Uuid,
+    pub event_type: AuditEventType,
+    pub user_id: Option<Uuid>,
+    pub occurred_at: NaiveDateTime,
+    pub metadata: Value,
+}
@@ -1,5 +1,6 @@
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
+use crate::models::mfa::MfaChallengeResponse;
 
 #[derive(Deserialize, Debug)]
 pub struct LoginRequest {
@@ -10,6 +11,23 @@ pub struct LoginRequest {
 #[derive(Serialize, Debug)]
 pub struct LoginResponse {
     pub token: String,
+    pub refresh_token: String,
+    pub token_type: String,
+    pub expires_in: i64,
+}
+
+/// What `/auth/login` answers with: tokens, or a challenge when the account
+/// has a second factor.
+#[derive(Serialize, Debug)]
+#[serde(untagged)]
+pub enum LoginOutcome {
+    Tokens(LoginResponse),
+    MfaRequired(MfaChallengeResponse),
+}
+
+#[derive(Deserialize, Debug)]
+pub struct RefreshTokenRequest {
+    pub refresh_token: String,
 }
 
 #[derive(Deserialize)]
@@ -24,12 +42,29 @@ pub struct RegisterResponse {
     pub id: Uuid,
     pub username: String,
     pub email: String,
+    pub email_verified: bool,
+    #[serde(flatten)]
+    pub tokens: Option<LoginResponse>,
 }
 
 #[derive(Serialize)]
 pub struct Claims {
     pub sub: String,
+    pub iat: usize,
     pub exp: usize,
+    pub iss: String,
+    pub aud: String,
+    pub email_verified: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub scope: Option<String>,
+    pub roles: Vec<String>,
+    pub permissions: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub client_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sid: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub jti: Option<String>,
 }
 
 #[derive(Deserialize)]
@@ -39,6 +74,27 @@ pub struct UpdatePasswordRequest {
     pub new_password: String,
 }
 
+#[derive(Deserialize)]
+pub struct ForgotPasswordRequest {
+    pub email: String,
+}
+
+#[derive(Deserialize)]
+pub struct ResetPasswordRequest {
+    pub token: String,
+    pub new_password: String,
+}
+
+#[derive(Deserialize)]
+pub struct VerifyEmailQuery {
+    pub token: String,
+}
+
+#[derive(Deserialize)]
+pub struct ResendVerificationRequest {
+    pub email: String,
+}
+
 #[derive(Serialize)]
 pub struct AuthToken {
     pub sub: String,
@@ -0,0 +1,33 @@
+use serde::{Deserialize, Serialize};
+use shared::auth::Claims;
+
+/// Form body of `/auth/introspect` (RFC 7662 section 2.1) and `/auth/revoke`
+/// (RFC 7009 section 2.1). Client credentials may come in the body instead
+/// of HTTP Basic.
+#[derive(Deserialize)]
+pub struct TokenHintRequest {
+    pub token: String,
+    pub token_type_hint: Option<String>,
+    pub client_id: Option<String>,
+    pub client_secret: Option<String>,
+}
+
+/// RFC 7662 section 2.2: `active`, and for active tokens their claims.
+#[derive(Serialize, Debug)]
+pub struct IntrospectionResponse {
+    pub active: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub token_type: Option<String>,
+    #[serde(flatten, skip_serializing_if = "Option::is_none")]
+    pub claims: Option<Claims>,
+}
+
+impl IntrospectionResponse {
+    pub fn inactive() -> IntrospectionResponse {
+        IntrospectionResponse { active: false, token_type: None, claims: None }
+    }
+
+    pub fn active(token_type: &str, claims: Claims) -> IntrospectionResponse {
+        IntrospectionResponse { active: true, token_type: Some(token_type.to_string()), claims: Some(claims) }
+    }
+}
@@ -0,0 +1,15 @@
+use chrono::NaiveDateTime;
+use diesel::{AsChangeset, Insertable, Queryable};
+use serde::Serialize;
+use crate::models::schema::login_attempts;
+
+/// Consecutive failed logins for one email address, keyed by the lowercased email.
+#[derive(Queryable, Insertable, AsChangeset, Serialize, Debug, Clone)]
+#[diesel(table_name = login_attempts, primary_key(email), treat_none_as_null = true)]
+pub struct LoginAttempt {
+    pub email: String,
+    pub failed_attempts: i32,
+    pub last_failed_at: NaiveDateTime,
+    pub last_failed_ip: Option<String>,
+    pub locked_until: Option<NaiveDateTime>,
+}
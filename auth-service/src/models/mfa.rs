@@ -0,0 +1,81 @@
+use chrono::NaiveDateTime;
+use diesel::{Insertable, Queryable};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+use crate::models::schema::{mfa_challenges, recovery_codes, user_totp};
+
+#[derive(Queryable, Debug)]
+pub struct UserTotp {
+    pub user_id: Uuid,
+    pub secret_ciphertext: String,
+    pub confirmed_at: Option<NaiveDateTime>,
+    pub last_used_step: Option<i64>,
+    pub created_at: NaiveDateTime,
+}
+
+#[derive(Insertable)]
+#[diesel(table_name = user_totp)]
+pub struct NewUserTotp {
+    pub user_id: Uuid,
+    pub secret_ciphertext: String,
+}
+
+#[derive(Insertable)]
+#[diesel(table_name = recovery_codes)]
+pub struct NewRecoveryCode {
+    pub user_id: Uuid,
+    pub code_hash: String,
+}
+
+/// A challenge handed out at login. It is closed once it completes a login,
+/// or once it has been failed too often.
+#[derive(Queryable, Debug)]
+pub struct MfaChallenge {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    pub failed_attempts: i32,
+    pub expires_at: NaiveDateTime,
+    pub closed_at: Option<NaiveDateTime>,
+    pub created_at: NaiveDateTime,
+}
+
+#[derive(Insertable)]
+#[diesel(table_name = mfa_challenges)]
+pub struct NewMfaChallenge {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    pub expires_at: NaiveDateTime,
+}
+
+#[derive(Serialize)]
+pub struct TotpEnrollmentResponse {
+    pub secret: String,
+    pub otpauth_uri: String,
+    /// Base64-encoded PNG of the `otpauth_uri` QR code.
+    pub qr_code_png: String,
+}
+
+#[derive(Deserialize)]
+pub struct TotpCodeRequest {
+    pub code: String,
+}
+
+#[derive(Serialize)]
+pub struct RecoveryCodesResponse {
+    pub recovery_codes: Vec<String>,
+}
+
+#[derive(Serialize, Debug)]
+pub struct MfaChallengeResponse {
+    pub mfa_required: bool,
+    pub mfa_token: String,
+    pub expires_in: i64,
+}
+
+/// Second login step: the challenge token plus either a TOTP code or a recovery code.
+#[derive(Deserialize)]
+pub struct MfaVerifyRequest {
+    pub mfa_token: String,
+    pub code: Option<String>,
+    pub recovery_code: Option<String>,
+}
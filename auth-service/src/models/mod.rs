@@ -1,3 +1,17 @@
+pub mod audit;
 pub mod auth;
+pub mod introspection;
+pub mod login_attempt;
+pub mod mfa;
+pub mod oauth;
+pub mod oidc;
+pub mod passkey;
+pub mod password_history;
+pub mod password_reset_token;
+pub mod personal_access_token;
+pub mod refresh_token;
+pub mod revoked_token;
+pub mod role;
+pub mod session;
 pub mod user;
 pub mod schema;
@@ -0,0 +1,150 @@
+use chrono::NaiveDateTime;
+use diesel::{Insertable, Queryable};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+use validator::Validate;
+use crate::models::schema::{oauth_authorization_codes, oauth_clients};
+
+#[derive(Queryable, Debug, Clone)]
+pub struct OAuthClient {
+    pub id: Uuid,
+    pub client_id: String,
+    /// `None` for public clients, which authenticate with PKCE alone.
+    pub client_secret_hash: Option<String>,
+    pub name: String,
+    pub redirect_uris: Vec<String>,
+    /// Space-separated scopes the client may ask for.
+    pub allowed_scopes: String,
+    pub created_by: Option<Uuid>,
+    pub created_at: NaiveDateTime,
+    /// Another backend, allowed to introspect and revoke any token.
+    pub is_service: bool,
+}
+
+#[derive(Insertable)]
+#[diesel(table_name = oauth_clients)]
+pub struct NewOAuthClient {
+    pub client_id: String,
+    pub client_secret_hash: Option<String>,
+    pub name: String,
+    pub redirect_uris: Vec<String>,
+    pub allowed_scopes: String,
+    pub created_by: Option<Uuid>,
+    pub is_service: bool,
+}
+
+#[derive(Queryable, Debug)]
+pub struct AuthorizationCode {
+    pub id: Uuid,
+    pub code_hash: String,
+    pub client_id: Uuid,
+    pub user_id: Uuid,
+    pub redirect_uri: String,
+    pub scope: String,
+    pub code_challenge: String,
+    pub expires_at: NaiveDateTime,
+    pub used_at: Option<NaiveDateTime>,
+    pub created_at: NaiveDateTime,
+}
+
+#[derive(Insertable)]
+#[diesel(table_name = oauth_authorization_codes)]
+pub struct NewAuthorizationCode {
+    pub code_hash: String,
+    pub client_id: Uuid,
+    pub user_id: Uuid,
+    pub redirect_uri: String,
+    pub scope: String,
+    pub code_challenge: String,
+    pub expires_at: NaiveDateTime,
+}
+
+#[derive(Deserialize, Validate)]
+pub struct RegisterClientRequest {
+    #[validate(length(min = 1, max = 100))]
+    pub name: String,
+    /// Required, except for service clients.
+    #[serde(default)]
+    pub redirect_uris: Vec<String>,
+    /// Required, except for service clients.
+    #[serde(default)]
+    pub scopes: Vec<String>,
+    /// Confidential clients get a secret; public ones (SPAs, mobile apps) do not.
+    #[serde(default)]
+    pub confidential: bool,
+    /// Another backend that introspects and revokes tokens rather than
+    /// acting for users. Must be confidential.
+    #[serde(default)]
+    pub service: bool,
+}
+
+#[derive(Serialize)]
+pub struct RegisterClientResponse {
+    pub client_id: String,
+    /// Only returned once, at registration.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub client_secret: Option<String>,
+    pub name: String,
+    pub redirect_uris: Vec<String>,
+    pub scopes: Vec<String>,
+    pub service: bool,
+}
+
+/// Query string of `/oauth/authorize` (RFC 6749 section 4.1.1, RFC 7636 section 4.3).
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct AuthorizeQuery {
+    pub response_type: String,
+    pub client_id: String,
+    pub redirect_uri: String,
+    pub scope: Option<String>,
+    pub state: Option<String>,
+    pub code_challenge: Option<String>,
+    pub code_challenge_method: Option<String>,
+}
+
+/// What the user is asked to approve before a code is issued.
+#[derive(Serialize, Debug)]
+pub struct ConsentPrompt {
+    pub client_id: String,
+    pub client_name: String,
+    pub redirect_uri: String,
+    pub scopes: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub state: Option<String>,
+}
+
+/// The user's answer to a `ConsentPrompt`, posted with the original request.
+#[derive(Deserialize)]
+pub struct ConsentDecision {
+    #[serde(flatten)]
+    pub request: AuthorizeQuery,
+    pub approve: bool,
+}
+
+/// Form body of `/oauth/token` for both supported grants.
+#[derive(Deserialize)]
+pub struct TokenRequest {
+    pub grant_type: String,
+    pub code: Option<String>,
+    pub redirect_uri: Option<String>,
+    pub code_verifier: Option<String>,
+    pub refresh_token: Option<String>,
+    pub scope: Option<String>,
+    pub client_id: Option<String>,
+    pub client_secret: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct TokenResponse {
+    pub access_token: String,
+    pub token_type: String,
+    pub expires_in: i64,
+    pub refresh_token: String,
+    pub scope: String,
+}
+
+/// Where to send the browser once the user has decided.
+#[derive(Serialize, Deserialize, Debug)]
+pub struct AuthorizeRedirect {
+    pub redirect_to: String,
+}
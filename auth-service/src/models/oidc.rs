@@ -0,0 +1,65 @@
+use chrono::NaiveDateTime;
+use diesel::{Insertable, Queryable};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+use crate::models::schema::user_identities;
+
+/// An account at an external OpenID provider, linked to one of our users.
+#[derive(Queryable, Serialize, Debug)]
+pub struct UserIdentity {
+    pub id: Uuid,
+    #[serde(skip_serializing)]
+    pub user_id: Uuid,
+    pub provider: String,
+    /// The provider's `sub` claim, stable for the lifetime of that account.
+    pub subject: String,
+    pub email: Option<String>,
+    pub created_at: NaiveDateTime,
+    pub last_login_at: Option<NaiveDateTime>,
+}
+
+#[derive(Insertable)]
+#[diesel(table_name = user_identities)]
+pub struct NewUserIdentity {
+    pub user_id: Uuid,
+    pub provider: String,
+    pub subject: String,
+    pub email: Option<String>,
+    pub last_login_at: Option<NaiveDateTime>,
+}
+
+/// The parts of `/.well-known/openid-configuration` the login flow uses.
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct ProviderMetadata {
+    pub issuer: String,
+    pub authorization_endpoint: String,
+    pub token_endpoint: String,
+    pub jwks_uri: String,
+}
+
+/// Query string the provider redirects back with, on success or failure.
+#[derive(Deserialize)]
+pub struct OidcCallbackQuery {
+    pub code: Option<String>,
+    pub state: Option<String>,
+    pub error: Option<String>,
+}
+
+/// Claims read from a validated ID token. `iss`, `aud` and `exp` are checked
+/// during decoding and not kept.
+#[derive(Deserialize, Serialize, Debug)]
+pub struct IdTokenClaims {
+    pub sub: String,
+    pub nonce: Option<String>,
+    pub email: Option<String>,
+    #[serde(default)]
+    pub email_verified: bool,
+    pub name: Option<String>,
+    pub preferred_username: Option<String>,
+}
+
+/// Successful response of the provider's token endpoint; only the ID token is used.
+#[derive(Deserialize)]
+pub struct ProviderTokenResponse {
+    pub id_token: String,
+}
@@ -0,0 +1,211 @@
+use chrono::NaiveDateTime;
+use diesel::{Insertable, Queryable};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+use validator::Validate;
+use crate::config::webauthn::AttestationConveyance;
+use crate::models::schema::passkeys;
+
+#[derive(Queryable, Debug)]
+pub struct Passkey {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    /// Base64url, as the browser sends it back in assertions.
+    pub credential_id: String,
+    /// CBOR-encoded COSE_Key.
+    pub public_key: Vec<u8>,
+    /// COSE algorithm identifier, e.g. -7 for ES256.
+    pub algorithm: i32,
+    /// The highest signature counter seen. Stays 0 for authenticators that
+    /// do not count, which is most synced passkeys.
+    pub sign_count: i64,
+    /// Identifies the authenticator model; all zeros unless attested.
+    pub aaguid: Uuid,
+    pub attestation_format: String,
+    pub transports: Vec<String>,
+    pub backup_eligible: bool,
+    pub backed_up: bool,
+    pub name: String,
+    pub created_at: NaiveDateTime,
+    pub last_used_at: Option<NaiveDateTime>,
+}
+
+#[derive(Insertable)]
+#[diesel(table_name = passkeys)]
+pub struct NewPasskey {
+    pub user_id: Uuid,
+    pub credential_id: String,
+    pub public_key: Vec<u8>,
+    pub algorithm: i32,
+    pub sign_count: i64,
+    pub aaguid: Uuid,
+    pub attestation_format: String,
+    pub transports: Vec<String>,
+    pub backup_eligible: bool,
+    pub backed_up: bool,
+    pub name: String,
+}
+
+#[derive(Serialize, Debug)]
+pub struct PasskeyResponse {
+    pub id: Uuid,
+    pub name: String,
+    pub aaguid: Uuid,
+    pub transports: Vec<String>,
+    /// Whether the passkey is synced to other devices.
+    pub backed_up: bool,
+    pub created_at: NaiveDateTime,
+    pub last_used_at: Option<NaiveDateTime>,
+}
+
+impl From<Passkey> for PasskeyResponse {
+    fn from(passkey: Passkey) -> Self {
+        PasskeyResponse {
+            id: passkey.id,
+            name: passkey.name,
+            aaguid: passkey.aaguid,
+            transports: passkey.transports,
+            backed_up: passkey.backed_up,
+            created_at: passkey.created_at,
+            last_used_at: passkey.last_used_at,
+        }
+    }
+}
+
+/// Returned by both `start` endpoints. `public_key` is passed as is to
+/// `navigator.credentials.create()` or `.get()`, once the challenge and ids
+/// are decoded from base64url; `ceremony_token` goes back with the result.
+#[derive(Serialize, Debug)]
+pub struct PasskeyCeremony<T> {
+    pub ceremony_token: String,
+    pub public_key: T,
+}
+
+#[derive(Serialize, Debug)]
+pub struct RelyingPartyEntity {
+    pub id: String,
+    pub name: String,
+}
+
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct UserEntity {
+    /// The user id bytes, base64url-encoded; returned as `userHandle`.
+    pub id: String,
+    pub name: String,
+    pub display_name: String,
+}
+
+#[derive(Serialize, Debug)]
+pub struct CredentialParameters {
+    #[serde(rename = "type")]
+    pub kind: &'static str,
+    pub alg: i64,
+}
+
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct AuthenticatorSelection {
+    pub resident_key: &'static str,
+    pub require_resident_key: bool,
+    pub user_verification: &'static str,
+}
+
+#[derive(Serialize, Debug)]
+pub struct CredentialDescriptor {
+    #[serde(rename = "type")]
+    pub kind: &'static str,
+    pub id: String,
+    pub transports: Vec<String>,
+}
+
+/// `PublicKeyCredentialCreationOptions`.
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct CredentialCreationOptions {
+    pub rp: RelyingPartyEntity,
+    pub user: UserEntity,
+    pub challenge: String,
+    pub pub_key_cred_params: Vec<CredentialParameters>,
+    /// Milliseconds.
+    pub timeout: i64,
+    pub attestation: AttestationConveyance,
+    pub authenticator_selection: AuthenticatorSelection,
+    /// The user's existing passkeys, so an authenticator is not registered twice.
+    pub exclude_credentials: Vec<CredentialDescriptor>,
+}
+
+/// `PublicKeyCredentialRequestOptions`.
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct CredentialRequestOptions {
+    pub challenge: String,
+    pub rp_id: String,
+    /// Milliseconds.
+    pub timeout: i64,
+    pub user_verification: &'static str,
+    /// Empty for discoverable sign-in, where the authenticator offers its
+    /// passkeys for this site and the user picks one.
+    pub allow_credentials: Vec<CredentialDescriptor>,
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct AttestationResponse {
+    #[serde(rename = "clientDataJSON")]
+    pub client_data_json: String,
+    pub attestation_object: String,
+    #[serde(default)]
+    pub transports: Vec<String>,
+}
+
+/// The `PublicKeyCredential` from `navigator.credentials.create()`, with
+/// binary fields base64url-encoded.
+#[derive(Deserialize, Debug)]
+pub struct RegistrationCredential {
+    pub id: String,
+    #[serde(rename = "type")]
+    pub kind: String,
+    pub response: AttestationResponse,
+}
+
+#[derive(Deserialize, Validate, Debug)]
+pub struct FinishPasskeyRegistrationRequest {
+    pub ceremony_token: String,
+    /// Defaults to "Passkey".
+    #[validate(length(min = 1, max = 100))]
+    pub name: Option<String>,
+    pub credential: RegistrationCredential,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct StartPasskeyLoginRequest {
+    /// Restricts the sign-in to this account's passkeys. Without it, any
+    /// discoverable passkey for the site can be used.
+    pub email: Option<String>,
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct AssertionResponse {
+    #[serde(rename = "clientDataJSON")]
+    pub client_data_json: String,
+    pub authenticator_data: String,
+    pub signature: String,
+    pub user_handle: Option<String>,
+}
+
+/// The `PublicKeyCredential` from `navigator.credentials.get()`.
+#[derive(Deserialize, Debug)]
+pub struct AuthenticationCredential {
+    pub id: String,
+    #[serde(rename = "type")]
+    pub kind: String,
+    pub response: AssertionResponse,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct FinishPasskeyLoginRequest {
+    pub ceremony_token: String,
+    pub credential: AuthenticationCredential,
+}
@@ -0,0 +1,20 @@
+use chrono::NaiveDateTime;
+use diesel::{Insertable, Queryable};
+use uuid::Uuid;
+use crate::models::schema::password_history;
+
+#[derive(Queryable, Debug)]
+pub struct PasswordHistory {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    /// The hash the user had before changing their password.
+    pub password_hash: String,
+    pub created_at: NaiveDateTime,
+}
+
+#[derive(Insertable)]
+#[diesel(table_name = password_history)]
+pub struct NewPasswordHistory {
+    pub user_id: Uuid,
+    pub password_hash: String,
+}
@@ -0,0 +1,54 @@
+use chrono::NaiveDateTime;
+use diesel::{Insertable, Queryable};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+use validator::Validate;
+use crate::models::schema::personal_access_tokens;
+
+#[derive(Queryable, Serialize, Debug)]
+pub struct PersonalAccessToken {
+    pub id: Uuid,
+    #[serde(skip_serializing)]
+    pub user_id: Uuid,
+    pub name: String,
+    #[serde(skip_serializing)]
+    pub token_hash: String,
+    /// The last few characters, so users can tell their tokens apart.
+    pub token_hint: String,
+    /// Space-separated permissions the token is limited to.
+    pub scopes: String,
+    pub expires_at: NaiveDateTime,
+    pub last_used_at: Option<NaiveDateTime>,
+    pub revoked_at: Option<NaiveDateTime>,
+    pub created_at: NaiveDateTime,
+}
+
+#[derive(Insertable)]
+#[diesel(table_name = personal_access_tokens)]
+pub struct NewPersonalAccessToken {
+    pub user_id: Uuid,
+    pub name: String,
+    pub token_hash: String,
+    pub token_hint: String,
+    pub scopes: String,
+    pub expires_at: NaiveDateTime,
+}
+
+#[derive(Deserialize, Validate)]
+pub struct CreatePersonalAccessTokenRequest {
+    #[validate(length(min = 1, max = 100))]
+    pub name: String,
+    #[validate(length(min = 1))]
+    pub scopes: Vec<String>,
+    /// Defaults to 30 days.
+    #[validate(range(min = 1, max = 365))]
+    pub expires_in_days: Option<i64>,
+}
+
+#[derive(Serialize)]
+pub struct CreatedPersonalAccessToken {
+    /// The plaintext token. Only returned once, at creation.
+    pub token: String,
+    #[serde(flatten)]
+    pub details: PersonalAccessToken,
+}
@@ -0,0 +1,18 @@
+use chrono::NaiveDateTime;
+use diesel::{Insertable, Queryable};
+use crate::models::schema::revoked_tokens;
+
+#[derive(Queryable, Debug)]
+pub struct RevokedToken {
+    pub jti: String,
+    /// When the token would have expired; the row can go after that.
+    pub expires_at: NaiveDateTime,
+    pub revoked_at: NaiveDateTime,
+}
+
+#[derive(Insertable)]
+#[diesel(table_name = revoked_tokens)]
+pub struct NewRevokedToken {
+    pub jti: String,
+    pub expires_at: NaiveDateTime,
+}
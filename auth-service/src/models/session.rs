@@ -0,0 +1,58 @@
+use actix_web::http::header;
+use actix_web::HttpRequest;
+use chrono::NaiveDateTime;
+use diesel::{Insertable, Queryable};
+use serde::Serialize;
+use uuid::Uuid;
+use crate::models::schema::sessions;
+
+/// Longer user agents are cut to this many characters before they are stored.
+const MAX_USER_AGENT_LENGTH: usize = 512;
+
+#[derive(Queryable, Debug)]
+pub struct Session {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    pub user_agent: Option<String>,
+    pub ip_address: Option<String>,
+    pub created_at: NaiveDateTime,
+    pub last_seen_at: NaiveDateTime,
+    pub revoked_at: Option<NaiveDateTime>,
+}
+
+#[derive(Insertable)]
+#[diesel(table_name = sessions)]
+pub struct NewSession {
+    pub user_id: Uuid,
+    pub user_agent: Option<String>,
+    pub ip_address: Option<String>,
+}
+
+#[derive(Serialize, Debug)]
+pub struct SessionResponse {
+    pub id: Uuid,
+    pub user_agent: Option<String>,
+    pub ip_address: Option<String>,
+    pub created_at: NaiveDateTime,
+    pub last_seen_at: NaiveDateTime,
+    /// Whether this is the session the request was made from.
+    pub current: bool,
+}
+
+/// Where a login came from, recorded on the session it starts.
+#[derive(Debug, Clone, Default)]
+pub struct ClientInfo {
+    pub user_agent: Option<String>,
+    pub ip_address: Option<String>,
+}
+
+impl ClientInfo {
+    pub fn from_request(req: &HttpRequest) -> ClientInfo {
+        ClientInfo {
+            user_agent: req.headers().get(header::USER_AGENT)
+                .and_then(|value| value.to_str().ok())
+                .map(|value| value.chars().take(MAX_USER_AGENT_LENGTH).collect()),
+            ip_address: req.peer_addr().map(|addr| addr.ip().to_string()),
+        }
+    }
+}
@@ -1,7 +1,9 @@
+use chrono::NaiveDateTime;
 use diesel::{AsChangeset, Insertable, Queryable};
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
-use validator::{Validate, ValidationError};
+use validator::Validate;
+use crate::models::auth::RegisterRequest;
 use crate::models::schema::users;
 
 #[derive(Queryable, Serialize, Deserialize)]
@@ -10,6 +12,14 @@ pub struct User {
     pub username: String,
     pub email: String,
     pub password: String,
+    pub email_verified_at: Option<NaiveDateTime>,
+    pub email_verification_sent_at: Option<NaiveDateTime>,
+}
+
+impl User {
+    pub fn is_email_verified(&self) -> bool {
+        self.email_verified_at.is_some()
+    }
 }
 
 #[derive(Insertable, Serialize, Deserialize, Validate)]
@@ -19,11 +29,26 @@ pub struct NewUser {
     pub username: String,
     #[validate(email)]
     pub email: String,
-    #[validate(length(min = 8))]
-    #[validate(custom(function = "validate_password_strength"))]
+    /// Checked against the password policy by `PasswordPolicyService`.
     pub password: String,
 }
 
+impl From<RegisterRequest> for NewUser {
+    fn from(request: RegisterRequest) -> Self {
+        NewUser {
+            username: request.username,
+            email: normalize_email(&request.email),
+            password: request.password,
+        }
+    }
+}
+
+/// Emails are stored and looked up trimmed and lowercased, so `Ada@x.com`
+/// and `ada@x.com` are the same account.
+pub fn normalize_email(email: &str) -> String {
+    email.trim().to_lowercase()
+}
+
 #[derive(AsChangeset, Serialize, Deserialize, Validate)]
 #[diesel(table_name = users)]
 pub struct UpdateUser {
@@ -31,20 +56,5 @@ pub struct UpdateUser {
     pub username: Option<String>,
     #[validate(email)]
     pub email: Option<String>,
-    #[validate(length(min = 8))]
-    #[validate(custom(function = "validate_password_strength"))]
     pub password: Option<String>,
 }
-
-fn validate_password_strength(password: &str) -> Result<(), validator::ValidationError> {
-    let has_upper = password.chars().any(|c| c.is_uppercase());
-    let has_lower = password.chars().any(|c| c.is_lowercase());
-    let has_digit = password.chars().any(|c| c.is_digit(10));
-    let has_special = password.chars().any(|c| !c.is_alphanumeric());
-
-    if has_upper && has_lower && has_digit && has_special {
-        Ok(())
-    } else {
-        Err(ValidationError::new("password_strength"))
-    }
-}
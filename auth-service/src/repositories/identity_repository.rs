@@ -0,0 +1,56 @@
+use chrono::Utc;
+use diesel::prelude::*;
+use uuid::Uuid;
+use crate::config::database::DbPool;
+use crate::models::oidc::{NewUserIdentity, UserIdentity};
+use crate::models::schema::user_identities;
+
+pub struct IdentityRepository;
+
+impl IdentityRepository {
+    pub async fn find(pool: &DbPool, provider: &str, subject: &str) -> Result<Option<UserIdentity>, diesel::result::Error> {
+        let conn = &mut pool.get().expect("Failed to get DB connection from pool");
+        user_identities::table
+            .filter(user_identities::provider.eq(provider))
+            .filter(user_identities::subject.eq(subject))
+            .first::<UserIdentity>(conn)
+            .optional()
+    }
+
+    pub async fn create(pool: &DbPool, new_identity: NewUserIdentity) -> Result<UserIdentity, diesel::result::Error> {
+        let conn = &mut pool.get().expect("Failed to get DB connection from pool");
+        diesel::insert_into(user_identities::table)
+            .values(&new_identity)
+            .get_result(conn)
+    }
+
+    /// Stamps a login through the identity and refreshes the email the
+    /// provider reported for it.
+    pub async fn touch(pool: &DbPool, id: Uuid, email: Option<&str>) -> Result<UserIdentity, diesel::result::Error> {
+        let conn = &mut pool.get().expect("Failed to get DB connection from pool");
+        diesel::update(user_identities::table.find(id))
+            .set((
+                user_identities::last_login_at.eq(Utc::now().naive_utc()),
+                user_identities::email.eq(email),
+            ))
+            .get_result(conn)
+    }
+
+    pub async fn list_for_user(pool: &DbPool, user_id: Uuid) -> Result<Vec<UserIdentity>, diesel::result::Error> {
+        let conn = &mut pool.get().expect("Failed to get DB connection from pool");
+        user_identities::table
+            .filter(user_identities::user_id.eq(user_id))
+            .order(user_identities::created_at.asc())
+            .load::<UserIdentity>(conn)
+    }
+
+    pub async fn delete(pool: &DbPool, user_id: Uuid, id: Uuid) -> Result<usize, diesel::result::Error> {
+        let conn = &mut pool.get().expect("Failed to get DB connection from pool");
+        diesel::delete(
+            user_identities::table
+                .filter(user_identities::id.eq(id))
+                .filter(user_identities::user_id.eq(user_id)),
+        )
+            .execute(conn)
+    }
+}
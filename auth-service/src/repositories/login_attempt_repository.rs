@@ -0,0 +1,56 @@
+use diesel::pg::PgConnection;
+use diesel::prelude::*;
+use diesel::sql_types::Text;
+use crate::config::database::DbPool;
+use crate::models::login_attempt::LoginAttempt;
+use crate::models::schema::login_attempts::dsl::*;
+
+pub struct LoginAttemptRepository;
+
+impl LoginAttemptRepository {
+    pub async fn find(pool: &DbPool, attempt_email: &str) -> Result<Option<LoginAttempt>, diesel::result::Error> {
+        let conn = &mut pool.get().expect("Failed to get DB connection from pool");
+        login_attempts.find(attempt_email).first::<LoginAttempt>(conn).optional()
+    }
+
+    /// Replaces the record for `attempt_email` with `next(current)`, or leaves
+    /// it alone when `next` fails. The email stays locked until the caller's
+    /// transaction ends, even before it has a row to lock, so concurrent
+    /// attempts are counted one after the other.
+    pub fn record_failure<F, E>(conn: &mut PgConnection, attempt_email: &str, next: F) -> Result<LoginAttempt, E>
+    where
+        F: FnOnce(Option<LoginAttempt>) -> Result<LoginAttempt, E>,
+        E: From<diesel::result::Error>,
+    {
+        diesel::sql_query("SELECT pg_advisory_xact_lock(hashtext($1))")
+            .bind::<Text, _>(attempt_email)
+            .execute(conn)?;
+        let current = login_attempts.find(attempt_email)
+            .for_update()
+            .first::<LoginAttempt>(conn)
+            .optional()?;
+        let attempt = next(current)?;
+
+        Ok(diesel::insert_into(login_attempts)
+            .values(&attempt)
+            .on_conflict(email)
+            .do_update()
+            .set(&attempt)
+            .get_result(conn)?)
+    }
+
+    pub async fn delete(pool: &DbPool, attempt_email: &str) -> Result<usize, diesel::result::Error> {
+        let conn = &mut pool.get().expect("Failed to get DB connection from pool");
+        Self::clear(conn, attempt_email)
+    }
+
+    /// `delete` inside the caller's transaction.
+    pub fn clear(conn: &mut PgConnection, attempt_email: &str) -> Result<usize, diesel::result::Error> {
+        diesel::delete(login_attempts.find(attempt_email)).execute(conn)
+    }
+
+    pub async fn list(pool: &DbPool) -> Result<Vec<LoginAttempt>, diesel::result::Error> {
+        let conn = &mut pool.get().expect("Failed to get DB connection from pool");
+        login_attempts.order(last_failed_at.desc()).load::<LoginAttempt>(conn)
+    }
+}
@@ -0,0 +1,151 @@
+use chrono::{NaiveDateTime, Utc};
+use diesel::dsl::sum;
+use diesel::prelude::*;
+use uuid::Uuid;
+use crate::config::database::DbPool;
+use crate::models::mfa::{MfaChallenge, NewMfaChallenge, NewRecoveryCode, NewUserTotp, UserTotp};
+use crate::models::schema::{mfa_challenges, recovery_codes, user_totp};
+
+pub struct MfaRepository;
+
+impl MfaRepository {
+    pub async fn find_totp(pool: &DbPool, user: Uuid) -> Result<Option<UserTotp>, diesel::result::Error> {
+        let conn = &mut pool.get().expect("Failed to get DB connection from pool");
+        user_totp::table.find(user).first::<UserTotp>(conn).optional()
+    }
+
+    /// Stores a new, unconfirmed secret, replacing any earlier enrollment that
+    /// was never confirmed.
+    pub async fn upsert_pending_totp(pool: &DbPool, new_totp: NewUserTotp) -> Result<UserTotp, diesel::result::Error> {
+        let conn = &mut pool.get().expect("Failed to get DB connection from pool");
+        diesel::insert_into(user_totp::table)
+            .values(&new_totp)
+            .on_conflict(user_totp::user_id)
+            .do_update()
+            .set((
+                user_totp::secret_ciphertext.eq(&new_totp.secret_ciphertext),
+                user_totp::confirmed_at.eq(None::<chrono::NaiveDateTime>),
+                user_totp::last_used_step.eq(None::<i64>),
+            ))
+            .get_result(conn)
+    }
+
+    /// Confirms the enrollment and replaces the user's recovery codes in one transaction.
+    pub async fn confirm_totp(pool: &DbPool, user: Uuid, step: i64, codes: Vec<NewRecoveryCode>) -> Result<(), diesel::result::Error> {
+        let conn = &mut pool.get().expect("Failed to get DB connection from pool");
+        conn.transaction(|conn| {
+            diesel::update(user_totp::table.find(user))
+                .set((
+                    user_totp::confirmed_at.eq(Utc::now().naive_utc()),
+                    user_totp::last_used_step.eq(step),
+                ))
+                .execute(conn)?;
+            diesel::delete(recovery_codes::table.filter(recovery_codes::user_id.eq(user))).execute(conn)?;
+            diesel::insert_into(recovery_codes::table).values(&codes).execute(conn)?;
+            Ok(())
+        })
+    }
+
+    /// Records `step` as used. Returns false if that step, or a later one, was
+    /// already used, so a code cannot be replayed.
+    pub async fn record_totp_step(pool: &DbPool, user: Uuid, step: i64) -> Result<bool, diesel::result::Error> {
+        let conn = &mut pool.get().expect("Failed to get DB connection from pool");
+        diesel::update(
+            user_totp::table
+                .find(user)
+                .filter(user_totp::last_used_step.is_null().or(user_totp::last_used_step.lt(step))),
+        )
+            .set(user_totp::last_used_step.eq(step))
+            .execute(conn)
+            .map(|updated| updated == 1)
+    }
+
+    /// Marks an unused recovery code as used. Returns false if none matched.
+    pub async fn consume_recovery_code(pool: &DbPool, user: Uuid, hash: &str) -> Result<bool, diesel::result::Error> {
+        let conn = &mut pool.get().expect("Failed to get DB connection from pool");
+        diesel::update(
+            recovery_codes::table
+                .filter(recovery_codes::user_id.eq(user))
+                .filter(recovery_codes::code_hash.eq(hash))
+                .filter(recovery_codes::used_at.is_null()),
+        )
+            .set(recovery_codes::used_at.eq(Utc::now().naive_utc()))
+            .execute(conn)
+            .map(|updated| updated == 1)
+    }
+
+    pub async fn delete_all_for_user(pool: &DbPool, user: Uuid) -> Result<(), diesel::result::Error> {
+        let conn = &mut pool.get().expect("Failed to get DB connection from pool");
+        conn.transaction(|conn| {
+            diesel::delete(recovery_codes::table.filter(recovery_codes::user_id.eq(user))).execute(conn)?;
+            diesel::delete(user_totp::table.find(user)).execute(conn)?;
+            Ok(())
+        })
+    }
+
+    /// Stores a new challenge, and forgets the user's challenges created
+    /// before `forget_before`.
+    pub async fn create_challenge(pool: &DbPool, challenge: NewMfaChallenge, forget_before: NaiveDateTime) -> Result<(), diesel::result::Error> {
+        let conn = &mut pool.get().expect("Failed to get DB connection from pool");
+        conn.transaction(|conn| {
+            diesel::delete(
+                mfa_challenges::table
+                    .filter(mfa_challenges::user_id.eq(challenge.user_id))
+                    .filter(mfa_challenges::created_at.lt(forget_before)),
+            )
+                .execute(conn)?;
+            diesel::insert_into(mfa_challenges::table).values(&challenge).execute(conn)?;
+            Ok(())
+        })
+    }
+
+    /// The challenge, if it belongs to `user` and is neither closed nor expired.
+    pub async fn find_open_challenge(pool: &DbPool, id: Uuid, user: Uuid) -> Result<Option<MfaChallenge>, diesel::result::Error> {
+        let conn = &mut pool.get().expect("Failed to get DB connection from pool");
+        mfa_challenges::table
+            .find(id)
+            .filter(mfa_challenges::user_id.eq(user))
+            .filter(mfa_challenges::closed_at.is_null())
+            .filter(mfa_challenges::expires_at.gt(Utc::now().naive_utc()))
+            .first::<MfaChallenge>(conn)
+            .optional()
+    }
+
+    /// Counts a wrong code against the challenge, closing it once it has
+    /// been failed `max_attempts` times.
+    pub async fn record_challenge_failure(pool: &DbPool, id: Uuid, max_attempts: i32) -> Result<MfaChallenge, diesel::result::Error> {
+        let conn = &mut pool.get().expect("Failed to get DB connection from pool");
+        conn.transaction(|conn| {
+            let challenge = diesel::update(mfa_challenges::table.find(id))
+                .set(mfa_challenges::failed_attempts.eq(mfa_challenges::failed_attempts + 1))
+                .get_result::<MfaChallenge>(conn)?;
+            if challenge.failed_attempts < max_attempts || challenge.closed_at.is_some() {
+                return Ok(challenge);
+            }
+            diesel::update(mfa_challenges::table.find(id))
+                .set(mfa_challenges::closed_at.eq(Utc::now().naive_utc()))
+                .get_result(conn)
+        })
+    }
+
+    /// Closes a challenge that completed a login. Returns false if it was
+    /// already closed, so it cannot complete another.
+    pub async fn close_challenge(pool: &DbPool, id: Uuid) -> Result<bool, diesel::result::Error> {
+        let conn = &mut pool.get().expect("Failed to get DB connection from pool");
+        diesel::update(mfa_challenges::table.find(id).filter(mfa_challenges::closed_at.is_null()))
+            .set(mfa_challenges::closed_at.eq(Utc::now().naive_utc()))
+            .execute(conn)
+            .map(|updated| updated == 1)
+    }
+
+    /// Wrong codes entered against the user's challenges created since `since`.
+    pub async fn challenge_failures_since(pool: &DbPool, user: Uuid, since: NaiveDateTime) -> Result<i64, diesel::result::Error> {
+        let conn = &mut pool.get().expect("Failed to get DB connection from pool");
+        mfa_challenges::table
+            .filter(mfa_challenges::user_id.eq(user))
+            .filter(mfa_challenges::created_at.ge(since))
+            .select(sum(mfa_challenges::failed_attempts))
+            .first::<Option<i64>>(conn)
+            .map(Option::unwrap_or_default)
+    }
+}
@@ -1 +1,13 @@
+pub mod identity_repository;
+pub mod login_attempt_repository;
+pub mod mfa_repository;
+pub mod oauth_repository;
+pub mod passkey_repository;
+pub mod password_history_repository;
+pub mod password_reset_repository;
+pub mod personal_access_token_repository;
+pub mod refresh_token_repository;
+pub mod revoked_token_repository;
+pub mod role_repository;
+pub mod session_repository;
 pub mod user_repository;
\ No newline at end of file
@@ -0,0 +1,59 @@
+use chrono::Utc;
+use diesel::prelude::*;
+use uuid::Uuid;
+use crate::config::database::DbPool;
+use crate::models::oauth::{AuthorizationCode, NewAuthorizationCode, NewOAuthClient, OAuthClient};
+use crate::models::schema::{oauth_authorization_codes, oauth_clients, permissions};
+
+pub struct OAuthRepository;
+
+impl OAuthRepository {
+    pub async fn create_client(pool: &DbPool, new_client: NewOAuthClient) -> Result<OAuthClient, diesel::result::Error> {
+        let conn = &mut pool.get().expect("Failed to get DB connection from pool");
+        diesel::insert_into(oauth_clients::table)
+            .values(&new_client)
+            .get_result(conn)
+    }
+
+    pub async fn find_client(pool: &DbPool, public_id: &str) -> Result<Option<OAuthClient>, diesel::result::Error> {
+        let conn = &mut pool.get().expect("Failed to get DB connection from pool");
+        oauth_clients::table
+            .filter(oauth_clients::client_id.eq(public_id))
+            .first::<OAuthClient>(conn)
+            .optional()
+    }
+
+    pub async fn find_client_by_id(pool: &DbPool, id: Uuid) -> Result<OAuthClient, diesel::result::Error> {
+        let conn = &mut pool.get().expect("Failed to get DB connection from pool");
+        oauth_clients::table.find(id).first::<OAuthClient>(conn)
+    }
+
+    pub async fn create_code(pool: &DbPool, new_code: NewAuthorizationCode) -> Result<AuthorizationCode, diesel::result::Error> {
+        let conn = &mut pool.get().expect("Failed to get DB connection from pool");
+        diesel::insert_into(oauth_authorization_codes::table)
+            .values(&new_code)
+            .get_result(conn)
+    }
+
+    /// Marks an unused, unexpired code as used and returns it, in one statement
+    /// so a code can only ever be exchanged once.
+    pub async fn consume_code(pool: &DbPool, hash: &str) -> Result<Option<AuthorizationCode>, diesel::result::Error> {
+        let conn = &mut pool.get().expect("Failed to get DB connection from pool");
+        let now = Utc::now().naive_utc();
+        diesel::update(
+            oauth_authorization_codes::table
+                .filter(oauth_authorization_codes::code_hash.eq(hash))
+                .filter(oauth_authorization_codes::used_at.is_null())
+                .filter(oauth_authorization_codes::expires_at.gt(now)),
+        )
+            .set(oauth_authorization_codes::used_at.eq(now))
+            .get_result(conn)
+            .optional()
+    }
+
+    /// Scopes are permission names, so these are all the scopes a client can register.
+    pub async fn known_scopes(pool: &DbPool) -> Result<Vec<String>, diesel::result::Error> {
+        let conn = &mut pool.get().expect("Failed to get DB connection from pool");
+        permissions::table.select(permissions::name).load(conn)
+    }
+}
@@ -0,0 +1,54 @@
+use chrono::Utc;
+use diesel::pg::PgConnection;
+use diesel::prelude::*;
+use uuid::Uuid;
+use crate::config::database::DbPool;
+use crate::models::passkey::{NewPasskey, Passkey};
+use crate::models::schema::passkeys;
+
+pub struct PasskeyRepository;
+
+impl PasskeyRepository {
+    pub fn create(conn: &mut PgConnection, new_passkey: NewPasskey) -> Result<Passkey, diesel::result::Error> {
+        diesel::insert_into(passkeys::table)
+            .values(&new_passkey)
+            .get_result(conn)
+    }
+
+    pub async fn find_by_credential_id(pool: &DbPool, credential_id: &str) -> Result<Option<Passkey>, diesel::result::Error> {
+        let conn = &mut pool.get().expect("Failed to get DB connection from pool");
+        passkeys::table
+            .filter(passkeys::credential_id.eq(credential_id))
+            .first::<Passkey>(conn)
+            .optional()
+    }
+
+    pub async fn list_for_user(pool: &DbPool, user_id: Uuid) -> Result<Vec<Passkey>, diesel::result::Error> {
+        let conn = &mut pool.get().expect("Failed to get DB connection from pool");
+        passkeys::table
+            .filter(passkeys::user_id.eq(user_id))
+            .order(passkeys::created_at.desc())
+            .load::<Passkey>(conn)
+    }
+
+    /// Stores the counter from a successful assertion. Nothing is updated
+    /// unless it went up, or both it and the stored one are 0 for an
+    /// authenticator that does not count, so a cloned authenticator replaying
+    /// an old counter matches no row.
+    pub fn record_use(conn: &mut PgConnection, id: Uuid, sign_count: i64, backed_up: bool) -> Result<usize, diesel::result::Error> {
+        let counter_ok = passkeys::sign_count.lt(sign_count)
+            .or(passkeys::sign_count.eq(0).and(passkeys::sign_count.eq(sign_count)));
+        diesel::update(passkeys::table.find(id).filter(counter_ok))
+            .set((
+                passkeys::sign_count.eq(sign_count),
+                passkeys::backed_up.eq(backed_up),
+                passkeys::last_used_at.eq(Utc::now().naive_utc()),
+            ))
+            .execute(conn)
+    }
+
+    pub fn delete(conn: &mut PgConnection, user_id: Uuid, id: Uuid) -> Result<usize, diesel::result::Error> {
+        diesel::delete(passkeys::table.find(id).filter(passkeys::user_id.eq(user_id)))
+            .execute(conn)
+    }
+}
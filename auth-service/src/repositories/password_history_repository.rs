@@ -0,0 +1,43 @@
+use diesel::pg::PgConnection;
+use diesel::prelude::*;
+use uuid::Uuid;
+use crate::config::database::DbPool;
+use crate::models::password_history::NewPasswordHistory;
+use crate::models::schema::password_history;
+
+pub struct PasswordHistoryRepository;
+
+impl PasswordHistoryRepository {
+    pub fn create(conn: &mut PgConnection, entry: NewPasswordHistory) -> Result<usize, diesel::result::Error> {
+        diesel::insert_into(password_history::table)
+            .values(&entry)
+            .execute(conn)
+    }
+
+    /// The user's previous password hashes, newest first.
+    pub async fn recent(pool: &DbPool, user: Uuid, limit: usize) -> Result<Vec<String>, diesel::result::Error> {
+        let conn = &mut pool.get().expect("Failed to get DB connection from pool");
+        password_history::table
+            .filter(password_history::user_id.eq(user))
+            .order(password_history::created_at.desc())
+            .limit(limit as i64)
+            .select(password_history::password_hash)
+            .load(conn)
+    }
+
+    /// Deletes all but the `keep` newest entries of the user.
+    pub fn prune(conn: &mut PgConnection, user: Uuid, keep: usize) -> Result<usize, diesel::result::Error> {
+        let kept = password_history::table
+            .filter(password_history::user_id.eq(user))
+            .order(password_history::created_at.desc())
+            .limit(keep as i64)
+            .select(password_history::id)
+            .load::<Uuid>(conn)?;
+        diesel::delete(
+            password_history::table
+                .filter(password_history::user_id.eq(user))
+                .filter(password_history::id.ne_all(kept)),
+        )
+            .execute(conn)
+    }
+}
@@ -0,0 +1,48 @@
+use chrono::Utc;
+use diesel::pg::PgConnection;
+use diesel::prelude::*;
+use uuid::Uuid;
+use crate::config::database::DbPool;
+use crate::models::password_reset_token::{NewPasswordResetToken, PasswordResetToken};
+use crate::models::schema::password_reset_tokens::dsl::*;
+
+pub struct PasswordResetRepository;
+
+impl PasswordResetRepository {
+    pub async fn create(pool: &DbPool, new_token: NewPasswordResetToken) -> Result<PasswordResetToken, diesel::result::Error> {
+        let conn = &mut pool.get().expect("Failed to get DB connection from pool");
+        diesel::insert_into(password_reset_tokens)
+            .values(&new_token)
+            .get_result(conn)
+    }
+
+    /// An unused, unexpired token, without using it up.
+    pub async fn find_valid(pool: &DbPool, hash: &str) -> Result<PasswordResetToken, diesel::result::Error> {
+        let conn = &mut pool.get().expect("Failed to get DB connection from pool");
+        password_reset_tokens
+            .filter(token_hash.eq(hash))
+            .filter(used_at.is_null())
+            .filter(expires_at.gt(Utc::now().naive_utc()))
+            .first(conn)
+    }
+
+    /// Marks an unused, unexpired token as used and returns it. The update is a
+    /// single statement, so two concurrent resets cannot both succeed.
+    pub fn consume(conn: &mut PgConnection, hash: &str) -> Result<PasswordResetToken, diesel::result::Error> {
+        let now = Utc::now().naive_utc();
+        diesel::update(
+            password_reset_tokens
+                .filter(token_hash.eq(hash))
+                .filter(used_at.is_null())
+                .filter(expires_at.gt(now)),
+        )
+            .set(used_at.eq(now))
+            .get_result(conn)
+    }
+
+    pub fn invalidate_all_for_user(conn: &mut PgConnection, user: Uuid) -> Result<usize, diesel::result::Error> {
+        diesel::update(password_reset_tokens.filter(user_id.eq(user)).filter(used_at.is_null()))
+            .set(used_at.eq(Utc::now().naive_utc()))
+            .execute(conn)
+    }
+}
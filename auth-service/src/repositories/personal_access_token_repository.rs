@@ -0,0 +1,59 @@
+use chrono::{Duration, Utc};
+use diesel::pg::PgConnection;
+use diesel::prelude::*;
+use uuid::Uuid;
+use crate::config::database::DbPool;
+use crate::models::personal_access_token::{NewPersonalAccessToken, PersonalAccessToken};
+use crate::models::schema::personal_access_tokens;
+
+pub struct PersonalAccessTokenRepository;
+
+impl PersonalAccessTokenRepository {
+    pub async fn create(pool: &DbPool, new_token: NewPersonalAccessToken) -> Result<PersonalAccessToken, diesel::result::Error> {
+        let conn = &mut pool.get().expect("Failed to get DB connection from pool");
+        diesel::insert_into(personal_access_tokens::table)
+            .values(&new_token)
+            .get_result(conn)
+    }
+
+    pub async fn find_by_hash(pool: &DbPool, hash: &str) -> Result<Option<PersonalAccessToken>, diesel::result::Error> {
+        let conn = &mut pool.get().expect("Failed to get DB connection from pool");
+        personal_access_tokens::table
+            .filter(personal_access_tokens::token_hash.eq(hash))
+            .first::<PersonalAccessToken>(conn)
+            .optional()
+    }
+
+    pub async fn list_for_user(pool: &DbPool, user_id: Uuid) -> Result<Vec<PersonalAccessToken>, diesel::result::Error> {
+        let conn = &mut pool.get().expect("Failed to get DB connection from pool");
+        personal_access_tokens::table
+            .filter(personal_access_tokens::user_id.eq(user_id))
+            .order(personal_access_tokens::created_at.desc())
+            .load::<PersonalAccessToken>(conn)
+    }
+
+    /// Stamps `last_used_at`, at most once per `interval` so a busy script does
+    /// not turn every request into a write.
+    pub async fn touch(pool: &DbPool, id: Uuid, interval: Duration) -> Result<usize, diesel::result::Error> {
+        let conn = &mut pool.get().expect("Failed to get DB connection from pool");
+        let now = Utc::now().naive_utc();
+        diesel::update(
+            personal_access_tokens::table
+                .find(id)
+                .filter(personal_access_tokens::last_used_at.is_null().or(personal_access_tokens::last_used_at.lt(now - interval))),
+        )
+            .set(personal_access_tokens::last_used_at.eq(now))
+            .execute(conn)
+    }
+
+    pub fn revoke(conn: &mut PgConnection, user_id: Uuid, id: Uuid) -> Result<usize, diesel::result::Error> {
+        diesel::update(
+            personal_access_tokens::table
+                .find(id)
+                .filter(personal_access_tokens::user_id.eq(user_id))
+                .filter(personal_access_tokens::revoked_at.is_null()),
+        )
+            .set(personal_access_tokens::revoked_at.eq(Utc::now().naive_utc()))
+            .execute(conn)
+    }
+}
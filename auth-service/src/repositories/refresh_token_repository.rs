@@ -0,0 +1,71 @@
+use chrono::Utc;
+use diesel::pg::PgConnection;
+use diesel::prelude::*;
+use uuid::Uuid;
+use crate::config::database::DbPool;
+use crate::models::refresh_token::{NewRefreshToken, RefreshToken};
+use crate::models::schema::refresh_tokens::dsl::*;
+
+pub struct RefreshTokenRepository;
+
+impl RefreshTokenRepository {
+    pub async fn create(pool: &DbPool, new_token: NewRefreshToken) -> Result<RefreshToken, diesel::result::Error> {
+        let conn = &mut pool.get().expect("Failed to get DB connection from pool");
+        diesel::insert_into(refresh_tokens)
+            .values(&new_token)
+            .get_result(conn)
+    }
+
+    pub async fn find_by_hash(pool: &DbPool, hash: &str) -> Result<RefreshToken, diesel::result::Error> {
+        let conn = &mut pool.get().expect("Failed to get DB connection from pool");
+        refresh_tokens.filter(token_hash.eq(hash)).first::<RefreshToken>(conn)
+    }
+
+    /// Revokes `current` and stores `next` in its place. Returns `Ok(None)` when
+    /// `current` had already been revoked, which means the token is being reused.
+    pub async fn rotate(
+        pool: &DbPool,
+        current: &RefreshToken,
+        next: NewRefreshToken,
+    ) -> Result<Option<RefreshToken>, diesel::result::Error> {
+        let conn = &mut pool.get().expect("Failed to get DB connection from pool");
+        conn.transaction(|conn| {
+            let revoked = diesel::update(refresh_tokens.filter(id.eq(current.id)).filter(revoked_at.is_null()))
+                .set(revoked_at.eq(Utc::now().naive_utc()))
+                .execute(conn)?;
+
+            if revoked == 0 {
+                return Ok(None);
+            }
+
+            diesel::insert_into(refresh_tokens)
+                .values(&next)
+                .get_result(conn)
+                .map(Some)
+        })
+    }
+
+    pub fn revoke_family(conn: &mut PgConnection, family: Uuid) -> Result<usize, diesel::result::Error> {
+        diesel::update(refresh_tokens.filter(family_id.eq(family)).filter(revoked_at.is_null()))
+            .set(revoked_at.eq(Utc::now().naive_utc()))
+            .execute(conn)
+    }
+
+    pub fn revoke_all_for_user(conn: &mut PgConnection, user: Uuid) -> Result<usize, diesel::result::Error> {
+        diesel::update(refresh_tokens.filter(user_id.eq(user)).filter(revoked_at.is_null()))
+            .set(revoked_at.eq(Utc::now().naive_utc()))
+            .execute(conn)
+    }
+
+    /// Revokes the user's login sessions but leaves tokens granted to OAuth clients alone.
+    pub fn revoke_first_party_for_user(conn: &mut PgConnection, user: Uuid) -> Result<usize, diesel::result::Error> {
+        diesel::update(
+            refresh_tokens
+                .filter(user_id.eq(user))
+                .filter(client_id.is_null())
+                .filter(revoked_at.is_null()),
+        )
+            .set(revoked_at.eq(Utc::now().naive_utc()))
+            .execute(conn)
+    }
+}
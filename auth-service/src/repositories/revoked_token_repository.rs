@@ -0,0 +1,31 @@
+use chrono::Utc;
+use diesel::dsl::exists;
+use diesel::pg::PgConnection;
+use diesel::prelude::*;
+use crate::config::database::DbPool;
+use crate::models::revoked_token::NewRevokedToken;
+use crate::models::schema::revoked_tokens;
+
+pub struct RevokedTokenRepository;
+
+impl RevokedTokenRepository {
+    pub fn create(conn: &mut PgConnection, new_token: NewRevokedToken) -> Result<usize, diesel::result::Error> {
+        diesel::insert_into(revoked_tokens::table)
+            .values(&new_token)
+            .on_conflict_do_nothing()
+            .execute(conn)
+    }
+
+    pub async fn is_revoked(pool: &DbPool, jti: &str) -> Result<bool, diesel::result::Error> {
+        let conn = &mut pool.get().expect("Failed to get DB connection from pool");
+        diesel::select(exists(revoked_tokens::table.find(jti))).get_result(conn)
+    }
+
+    /// Drops rows of tokens that have expired since, which no longer need
+    /// to be remembered.
+    pub async fn purge_expired(pool: &DbPool) -> Result<usize, diesel::result::Error> {
+        let conn = &mut pool.get().expect("Failed to get DB connection from pool");
+        diesel::delete(revoked_tokens::table.filter(revoked_tokens::expires_at.lt(Utc::now().naive_utc())))
+            .execute(conn)
+    }
+}
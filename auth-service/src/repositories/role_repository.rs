@@ -0,0 +1,79 @@
+use diesel::pg::PgConnection;
+use diesel::prelude::*;
+use uuid::Uuid;
+use crate::config::database::DbPool;
+use crate::models::role::{NewUserRole, Role};
+use crate::models::schema::{permissions, role_permissions, roles, user_roles};
+
+pub struct RoleRepository;
+
+impl RoleRepository {
+    pub fn find_by_name(conn: &mut PgConnection, role_name: &str) -> Result<Role, diesel::result::Error> {
+        roles::table.filter(roles::name.eq(role_name)).first::<Role>(conn)
+    }
+
+    /// Every role with the names of the permissions it grants.
+    pub async fn list_with_permissions(pool: &DbPool) -> Result<Vec<(Role, Vec<String>)>, diesel::result::Error> {
+        let conn = &mut pool.get().expect("Failed to get DB connection from pool");
+        let all_roles = roles::table.order(roles::name).load::<Role>(conn)?;
+        let grants = role_permissions::table
+            .inner_join(permissions::table)
+            .select((role_permissions::role_id, permissions::name))
+            .order(permissions::name)
+            .load::<(Uuid, String)>(conn)?;
+
+        Ok(all_roles.into_iter()
+            .map(|role| {
+                let granted = grants.iter()
+                    .filter(|(role_id, _)| *role_id == role.id)
+                    .map(|(_, name)| name.clone())
+                    .collect();
+                (role, granted)
+            })
+            .collect())
+    }
+
+    pub async fn role_names_for_user(pool: &DbPool, user: Uuid) -> Result<Vec<String>, diesel::result::Error> {
+        let conn = &mut pool.get().expect("Failed to get DB connection from pool");
+        user_roles::table
+            .inner_join(roles::table)
+            .filter(user_roles::user_id.eq(user))
+            .select(roles::name)
+            .order(roles::name)
+            .load(conn)
+    }
+
+    pub async fn permission_names_for_user(pool: &DbPool, user: Uuid) -> Result<Vec<String>, diesel::result::Error> {
+        let conn = &mut pool.get().expect("Failed to get DB connection from pool");
+        let user_role_ids = user_roles::table
+            .filter(user_roles::user_id.eq(user))
+            .select(user_roles::role_id);
+
+        role_permissions::table
+            .inner_join(permissions::table)
+            .filter(role_permissions::role_id.eq_any(user_role_ids))
+            .select(permissions::name)
+            .distinct()
+            .order(permissions::name)
+            .load(conn)
+    }
+
+    /// Returns false if the user already had the role.
+    pub fn assign(conn: &mut PgConnection, user_role: NewUserRole) -> Result<bool, diesel::result::Error> {
+        diesel::insert_into(user_roles::table)
+            .values(&user_role)
+            .on_conflict_do_nothing()
+            .execute(conn)
+            .map(|inserted| inserted == 1)
+    }
+
+    pub async fn revoke(pool: &DbPool, user: Uuid, role: Uuid) -> Result<usize, diesel::result::Error> {
+        let conn = &mut pool.get().expect("Failed to get DB connection from pool");
+        diesel::delete(
+            user_roles::table
+                .filter(user_roles::user_id.eq(user))
+                .filter(user_roles::role_id.eq(role)),
+        )
+            .execute(conn)
+    }
+}
@@ -0,0 +1,64 @@
+use chrono::{Duration, Utc};
+use diesel::pg::PgConnection;
+use diesel::prelude::*;
+use uuid::Uuid;
+use crate::config::database::DbPool;
+use crate::models::schema::sessions;
+use crate::models::session::{NewSession, Session};
+
+pub struct SessionRepository;
+
+impl SessionRepository {
+    pub fn create(conn: &mut PgConnection, new_session: NewSession) -> Result<Session, diesel::result::Error> {
+        diesel::insert_into(sessions::table)
+            .values(&new_session)
+            .get_result(conn)
+    }
+
+    pub async fn find(pool: &DbPool, id: Uuid) -> Result<Option<Session>, diesel::result::Error> {
+        let conn = &mut pool.get().expect("Failed to get DB connection from pool");
+        sessions::table.find(id).first::<Session>(conn).optional()
+    }
+
+    pub async fn list_active(pool: &DbPool, user_id: Uuid) -> Result<Vec<Session>, diesel::result::Error> {
+        let conn = &mut pool.get().expect("Failed to get DB connection from pool");
+        sessions::table
+            .filter(sessions::user_id.eq(user_id))
+            .filter(sessions::revoked_at.is_null())
+            .order(sessions::last_seen_at.desc())
+            .load::<Session>(conn)
+    }
+
+    /// Stamps `last_seen_at`, at most once per `interval`.
+    pub async fn touch(pool: &DbPool, id: Uuid, interval: Duration) -> Result<usize, diesel::result::Error> {
+        let conn = &mut pool.get().expect("Failed to get DB connection from pool");
+        let now = Utc::now().naive_utc();
+        diesel::update(sessions::table.find(id).filter(sessions::last_seen_at.lt(now - interval)))
+            .set(sessions::last_seen_at.eq(now))
+            .execute(conn)
+    }
+
+    pub fn revoke(conn: &mut PgConnection, id: Uuid) -> Result<usize, diesel::result::Error> {
+        diesel::update(sessions::table.find(id).filter(sessions::revoked_at.is_null()))
+            .set(sessions::revoked_at.eq(Utc::now().naive_utc()))
+            .execute(conn)
+    }
+
+    /// Like `revoke`, but only matches sessions that belong to `user_id`.
+    pub fn revoke_for_user(conn: &mut PgConnection, user_id: Uuid, id: Uuid) -> Result<usize, diesel::result::Error> {
+        diesel::update(
+            sessions::table
+                .find(id)
+                .filter(sessions::user_id.eq(user_id))
+                .filter(sessions::revoked_at.is_null()),
+        )
+            .set(sessions::revoked_at.eq(Utc::now().naive_utc()))
+            .execute(conn)
+    }
+
+    pub fn revoke_all_for_user(conn: &mut PgConnection, user_id: Uuid) -> Result<usize, diesel::result::Error> {
+        diesel::update(sessions::table.filter(sessions::user_id.eq(user_id)).filter(sessions::revoked_at.is_null()))
+            .set(sessions::revoked_at.eq(Utc::now().naive_utc()))
+            .execute(conn)
+    }
+}
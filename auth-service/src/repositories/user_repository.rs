@@ -1,13 +1,81 @@
+use chrono::{Duration, Utc};
+use diesel::pg::PgConnection;
 use diesel::prelude::*;
-use crate::models::user::{User};
+use uuid::Uuid;
+use crate::models::user::{normalize_email, NewUser, User};
 use crate::models::schema::users::dsl::*;
 use crate::config::database::DbPool;
 
 pub struct UserRepository;
 
 impl UserRepository {
+    /// Looks up the email the way `NewUser` stores it, so case and
+    /// surrounding spaces do not matter.
     pub async fn find_by_email(pool: &DbPool, user_email: &str) -> Result<User, diesel::result::Error> {
         let conn = &mut pool.get().expect("Failed to get DB connection from pool");
-        users.filter(email.eq(user_email)).first::<User>(conn)
+        users.filter(email.eq(normalize_email(user_email))).first::<User>(conn)
+    }
+
+    /// `find_by_email` inside the caller's transaction, locking the row.
+    pub fn lock_by_email(conn: &mut PgConnection, user_email: &str) -> Result<Option<User>, diesel::result::Error> {
+        users.filter(email.eq(normalize_email(user_email))).for_update().first::<User>(conn).optional()
+    }
+
+    pub async fn find_by_id(pool: &DbPool, user_id: Uuid) -> Result<User, diesel::result::Error> {
+        let conn = &mut pool.get().expect("Failed to get DB connection from pool");
+        users.find(user_id).first::<User>(conn)
+    }
+
+    pub fn create(conn: &mut PgConnection, new_user: NewUser) -> Result<User, diesel::result::Error> {
+        diesel::insert_into(users)
+            .values(&new_user)
+            .get_result(conn)
+    }
+
+    pub async fn update_password(pool: &DbPool, user_id: Uuid, password_hash: &str) -> Result<User, diesel::result::Error> {
+        let conn = &mut pool.get().expect("Failed to get DB connection from pool");
+        Self::set_password(conn, user_id, password_hash)
+    }
+
+    /// `update_password` inside the caller's transaction.
+    pub fn set_password(conn: &mut PgConnection, user_id: Uuid, password_hash: &str) -> Result<User, diesel::result::Error> {
+        diesel::update(users.find(user_id))
+            .set(password.eq(password_hash))
+            .get_result(conn)
+    }
+
+    /// Records that the address `user_email` was confirmed for this user. A link
+    /// issued for an address the user has since changed matches no row.
+    pub async fn mark_email_verified(pool: &DbPool, user_id: Uuid, user_email: &str) -> Result<User, diesel::result::Error> {
+        let conn = &mut pool.get().expect("Failed to get DB connection from pool");
+        Self::set_email_verified(conn, user_id, user_email)
+    }
+
+    /// `mark_email_verified` inside the caller's transaction.
+    pub fn set_email_verified(conn: &mut PgConnection, user_id: Uuid, user_email: &str) -> Result<User, diesel::result::Error> {
+        diesel::update(users.find(user_id).filter(email.eq(user_email)))
+            .set(email_verified_at.eq(Utc::now().naive_utc()))
+            .get_result(conn)
+    }
+
+    /// Stamps `email_verification_sent_at` unless a link went out less than
+    /// `interval` ago. Returns `Ok(None)` when the send should be throttled.
+    pub async fn claim_verification_send(pool: &DbPool, user_id: Uuid, interval: Duration) -> Result<Option<User>, diesel::result::Error> {
+        let conn = &mut pool.get().expect("Failed to get DB connection from pool");
+        let now = Utc::now().naive_utc();
+        diesel::update(
+            users
+                .find(user_id)
+                .filter(email_verification_sent_at.is_null().or(email_verification_sent_at.lt(now - interval))),
+        )
+            .set(email_verification_sent_at.eq(now))
+            .get_result(conn)
+            .optional()
+    }
+
+    /// Deletes the user and, by cascade, everything keyed to them: sessions,
+    /// tokens, roles, MFA, passkeys, identities and password history.
+    pub fn delete(conn: &mut PgConnection, user_id: Uuid) -> Result<usize, diesel::result::Error> {
+        diesel::delete(users.find(user_id)).execute(conn)
     }
 }
@@ -1,7 +1,102 @@
+use actix_governor::Governor;
 use actix_web::{web, Scope};
-use crate::handlers::auth::{login};
+use shared::auth::require_permission;
+use crate::handlers::admin::{
+    assign_role, clear_lockout, get_lockout, get_user_roles, list_lockouts, list_roles, revoke_role,
+};
+use crate::handlers::auth::{change_password, login, logout, refresh, register};
+use crate::handlers::email_verification::{resend_verification, verify_email};
+use crate::handlers::introspection::{introspect, revoke};
+use crate::handlers::jwks::jwks;
+use crate::handlers::mfa::{confirm_totp, disable_totp, enroll_totp, verify_mfa};
+use crate::handlers::oauth::{authorize, decide, register_client, token};
+use crate::handlers::oidc::{callback, list_identities, start, unlink_identity};
+use crate::handlers::passkey::{
+    finish_passkey_login, finish_passkey_registration, list_passkeys, remove_passkey, start_passkey_login,
+    start_passkey_registration,
+};
+use crate::handlers::password_reset::{forgot_password, reset_password};
+use crate::handlers::personal_access_token::{create_token, current_token, list_tokens, revoke_token};
+use crate::handlers::session::{list_sessions, revoke_all_sessions, revoke_session};
+use crate::middleware::rate_limiter::RateLimit;
 
-pub fn auth_routes() -> Scope {
+/// `login_rate_limit` is shared by every worker; see `RateLimit`.
+pub fn auth_routes(login_rate_limit: &RateLimit) -> Scope {
     web::scope("/auth")
-        .route("/login", web::post().to(login))
+        .route("/register", web::post().to(register))
+        .service(
+            web::resource("/login")
+                .wrap(Governor::new(login_rate_limit))
+                .route(web::post().to(login)),
+        )
+        .route("/refresh", web::post().to(refresh))
+        .route("/logout", web::post().to(logout))
+        .route("/password/change", web::post().to(change_password))
+        .route("/password/forgot", web::post().to(forgot_password))
+        .route("/password/reset", web::post().to(reset_password))
+        .route("/email/verify", web::get().to(verify_email))
+        .route("/email/verify/resend", web::post().to(resend_verification))
+        .route("/mfa/totp/enroll", web::post().to(enroll_totp))
+        .route("/mfa/totp/confirm", web::post().to(confirm_totp))
+        .route("/mfa/totp/disable", web::post().to(disable_totp))
+        .route("/mfa/verify", web::post().to(verify_mfa))
+        .route("/oidc/{provider}/start", web::get().to(start))
+        .route("/oidc/{provider}/callback", web::get().to(callback))
+        .route("/identities", web::get().to(list_identities))
+        .route("/identities/{id}", web::delete().to(unlink_identity))
+        .route("/passkeys", web::get().to(list_passkeys))
+        .route("/passkeys/register/start", web::post().to(start_passkey_registration))
+        .route("/passkeys/register/finish", web::post().to(finish_passkey_registration))
+        .route("/passkeys/login/start", web::post().to(start_passkey_login))
+        .route("/passkeys/login/finish", web::post().to(finish_passkey_login))
+        .route("/passkeys/{id}", web::delete().to(remove_passkey))
+        .route("/tokens", web::post().to(create_token))
+        .route("/tokens", web::get().to(list_tokens))
+        .route("/tokens/current", web::get().to(current_token))
+        .route("/tokens/{id}", web::delete().to(revoke_token))
+        .route("/sessions", web::get().to(list_sessions))
+        .route("/sessions", web::delete().to(revoke_all_sessions))
+        .route("/sessions/{id}", web::delete().to(revoke_session))
+        .route("/introspect", web::post().to(introspect))
+        .route("/revoke", web::post().to(revoke))
+}
+
+pub fn admin_routes() -> Scope {
+    web::scope("/admin")
+        .service(
+            web::scope("/lockouts")
+                .wrap(require_permission("lockouts:manage"))
+                .route("", web::get().to(list_lockouts))
+                .route("/{email}", web::get().to(get_lockout))
+                .route("/{email}", web::delete().to(clear_lockout)),
+        )
+        .service(
+            web::scope("/roles")
+                .wrap(require_permission("roles:manage"))
+                .route("", web::get().to(list_roles)),
+        )
+        .service(
+            web::scope("/users/{id}/roles")
+                .wrap(require_permission("roles:manage"))
+                .route("", web::get().to(get_user_roles))
+                .route("", web::post().to(assign_role))
+                .route("/{role}", web::delete().to(revoke_role)),
+        )
+}
+
+pub fn oauth_routes() -> Scope {
+    web::scope("/oauth")
+        .service(
+            web::resource("/clients")
+                .wrap(require_permission("clients:manage"))
+                .route(web::post().to(register_client)),
+        )
+        .route("/authorize", web::get().to(authorize))
+        .route("/authorize", web::post().to(decide))
+        .route("/token", web::post().to(token))
+}
+
+pub fn well_known_routes() -> Scope {
+    web::scope("/.well-known")
+        .route("/jwks.json", web::get().to(jwks))
 }
@@ -0,0 +1,90 @@
+use std::env;
+use chrono::Utc;
+use diesel::pg::PgConnection;
+use diesel::Connection;
+use serde_json::Value;
+use shared::outbox::{NewOutboxMessage, OutboxRepository};
+use uuid::Uuid;
+use crate::config::database::DbPool;
+use crate::models::audit::{AuditEvent, AuditEventType};
+
+const DEFAULT_AUDIT_TOPIC: &str = "auth.audit";
+
+/// Events recorded while a change is being made, stored in the outbox when
+/// the change commits. See `AuditService::transaction`.
+#[derive(Default)]
+pub struct AuditTrail {
+    events: Vec<AuditEvent>,
+}
+
+impl AuditTrail {
+    pub fn record(&mut self, event_type: AuditEventType, user_id: Option<Uuid>, metadata: Value) {
+        self.events.push(AuditEvent {
+            id: Uuid::new_v4(),
+            event_type,
+            user_id,
+            occurred_at: Utc::now().naive_utc(),
+            metadata,
+        });
+    }
+
+    fn into_messages(self) -> Result<Vec<NewOutboxMessage>, serde_json::Error> {
+        let topic = env::var("AUDIT_TOPIC").unwrap_or_else(|_| DEFAULT_AUDIT_TOPIC.to_string());
+        self.events.into_iter()
+            .map(|event| Ok(NewOutboxMessage {
+                id: event.id,
+                topic: topic.clone(),
+                event_key: event_key(&event),
+                payload: serde_json::to_value(&event)?,
+            }))
+            .collect()
+    }
+}
+
+/// Audit events go to the `outbox` table, and from there to the event bus by
+/// way of `OutboxRelay`, so an event is published if and only if the change
+/// it describes was committed.
+pub struct AuditService;
+
+impl AuditService {
+    /// Runs `change` in a transaction and stores the events it records in
+    /// that same transaction. Writes made inside take the connection.
+    pub async fn transaction<T, E, F>(pool: &DbPool, change: F) -> Result<T, E>
+    where
+        F: FnOnce(&mut PgConnection, &mut AuditTrail) -> Result<T, E>,
+        E: From<diesel::result::Error>,
+    {
+        let conn = &mut pool.get().expect("Failed to get DB connection from pool");
+        conn.transaction(|conn| {
+            let mut trail = AuditTrail::default();
+            let value = change(conn, &mut trail)?;
+            let messages = trail.into_messages()
+                .map_err(|e| diesel::result::Error::SerializationError(Box::new(e)))?;
+            OutboxRepository::create(conn, &messages)?;
+            Ok(value)
+        })
+    }
+
+    /// Records an event for a change that has already been committed. A
+    /// failure is logged, not returned: the change stands either way.
+    pub async fn record(pool: &DbPool, event_type: AuditEventType, user_id: Option<Uuid>, metadata: Value) {
+        let recorded = Self::transaction(pool, |_conn, trail| {
+            trail.record(event_type, user_id, metadata);
+            Ok::<_, diesel::result::Error>(())
+        }).await;
+
+        if let Err(e) = recorded {
+            log::error!("Failed to record {:?} audit event: {}", event_type, e);
+        }
+    }
+}
+
+/// Keys events by user, so each user's events stay in order. Events before
+/// a user is known, such as failed logins, are keyed by email.
+fn event_key(event: &AuditEvent) -> String {
+    match (event.user_id, event.metadata.get("email").and_then(Value::as_str)) {
+        (Some(user_id), _) => user_id.to_string(),
+        (None, Some(email)) => email.to_string(),
+        (None, None) => event.id.to_string(),
+    }
+}
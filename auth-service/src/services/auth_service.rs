@@ -1,47 +1,358 @@
-use std::env;
-use jsonwebtoken::{encode, Header, EncodingKey};
-use crate::models::auth::{Claims, LoginRequest, LoginResponse};
+use chrono::{Duration, Utc};
+use shared::auth::validator::{audience, issuer};
+use diesel::result::DatabaseErrorKind;
+use uuid::Uuid;
+use crate::errors::error::AuthError;
+use serde_json::json;
+use crate::models::audit::AuditEventType;
+use crate::models::auth::{Claims, LoginOutcome, LoginRequest, LoginResponse, RegisterRequest, RegisterResponse, UpdatePasswordRequest};
+use crate::models::login_attempt::LoginAttempt;
+use crate::models::mfa::MfaVerifyRequest;
+use crate::models::refresh_token::{NewRefreshToken, RefreshToken};
+use crate::models::session::ClientInfo;
+use crate::models::user::{NewUser, User};
 use crate::config::database::DbPool;
+use crate::config::keyring::Keyring;
+use crate::services::audit_service::AuditService;
+use crate::services::email_verification_service::EmailVerificationPolicy;
+use crate::services::login_attempt_service::LoginAttemptService;
+use crate::services::mfa_service::MfaService;
+use crate::services::passkey_service::PasskeyLogin;
+use crate::services::password_policy_service::PasswordPolicyService;
+use crate::services::role_service::{RoleService, DEFAULT_ROLE};
+use crate::services::session_service::SessionService;
+use crate::repositories::refresh_token_repository::RefreshTokenRepository;
+use crate::repositories::session_repository::SessionRepository;
 use crate::repositories::user_repository::UserRepository;
-use crate::utils::verify_password::verify_password;
+use crate::utils::hash_password::hash_password;
+use crate::utils::opaque_token::{generate_opaque_token, hash_opaque_token};
+use crate::utils::verify_password::{dummy_verify_password, needs_rehash, verify_password};
+
+pub const ACCESS_TOKEN_TTL_SECONDS: i64 = 15 * 60;
+pub const REFRESH_TOKEN_TTL_DAYS: i64 = 30;
 
 pub struct AuthService;
 
 impl AuthService {
+    /// Checks the credentials, counting the attempt against its email before
+    /// the password is checked so repeated guesses are slowed down and eventually locked out. Unknown emails and
+    /// wrong passwords fail the same way and take about as long, so the
+    /// response does not reveal which accounts exist.
     pub async fn authenticate(
         pool: &DbPool,
+        keyring: &Keyring,
         login_request: &LoginRequest,
-    ) -> Result<LoginResponse, &'static str> {
-        let user = UserRepository::find_by_email(pool, &login_request.email).await;
-
-        match user {
-            Ok(user) => {
-                if verify_password(&user.password, &login_request.password) {
-                    let token = Self::generate_token(&user.id.to_string()).await;
-                    match token {
-                        Ok(token) => Ok(LoginResponse { token }),
-                        Err(_e) => Err("Token not created!"),
-                    }
-                } else {
-                    Err("Invalid Credentials")
-                }
+        client: &ClientInfo,
+    ) -> Result<LoginOutcome, AuthError> {
+        let attempt = LoginAttemptService::begin(pool, &login_request.email, client.ip_address.as_deref()).await?;
+        let user = match UserRepository::find_by_email(pool, &login_request.email).await {
+            Ok(user) => Some(user),
+            Err(diesel::result::Error::NotFound) => None,
+            Err(e) => return Err(AuthError::DatabaseError(e)),
+        };
+
+        let user = match user {
+            Some(user) if verify_password(&user.password, &login_request.password) => user,
+            Some(_) => return Self::failed_login(pool, &attempt).await,
+            None => {
+                dummy_verify_password(&login_request.password);
+                return Self::failed_login(pool, &attempt).await;
+            }
+        };
+
+        LoginAttemptService::record_success(pool, &login_request.email).await?;
+        Self::upgrade_password_hash(pool, &user, &login_request.password).await;
+        Self::sign_in(pool, keyring, &user, client).await
+    }
+
+    /// Finishes a login once the first factor has been checked, by password or
+    /// by an external identity provider: a new session, or an MFA challenge
+    /// for accounts with TOTP enabled.
+    pub async fn sign_in(pool: &DbPool, keyring: &Keyring, user: &User, client: &ClientInfo) -> Result<LoginOutcome, AuthError> {
+        if Self::login_blocked(user) {
+            return Err(AuthError::EmailNotVerified);
+        }
+        if MfaService::is_enabled(pool, user.id).await? {
+            return MfaService::create_challenge(pool, keyring, user.id).await.map(LoginOutcome::MfaRequired);
+        }
+
+        Self::start_session(pool, keyring, user, client).await.map(LoginOutcome::Tokens)
+    }
+
+    /// Second half of a login for accounts with TOTP enabled: exchanges the
+    /// challenge from `authenticate` plus a code for a token pair.
+    pub async fn complete_mfa_login(
+        pool: &DbPool,
+        keyring: &Keyring,
+        request: &MfaVerifyRequest,
+        client: &ClientInfo,
+    ) -> Result<LoginResponse, AuthError> {
+        let user_id = MfaService::verify_challenge(pool, keyring, request).await?;
+        let user = UserRepository::find_by_id(pool, user_id).await?;
+        if Self::login_blocked(&user) {
+            return Err(AuthError::EmailNotVerified);
+        }
+
+        Self::start_session(pool, keyring, &user, client).await
+    }
+
+    /// Finishes a passkey login. User verification on the authenticator
+    /// already counts as a second factor, so there is no TOTP challenge;
+    /// without it the passkey is only the first factor, and the login goes
+    /// on like a password login.
+    pub async fn sign_in_with_passkey(pool: &DbPool, keyring: &Keyring, login: &PasskeyLogin, client: &ClientInfo) -> Result<LoginOutcome, AuthError> {
+        if !login.user_verified {
+            return Self::sign_in(pool, keyring, &login.user, client).await;
+        }
+        if Self::login_blocked(&login.user) {
+            return Err(AuthError::EmailNotVerified);
+        }
+
+        Self::start_session(pool, keyring, &login.user, client).await.map(LoginOutcome::Tokens)
+    }
+
+    /// Creates an account with the rules of `NewUser` and the password policy,
+    /// together with its default role, and signs the new user in straight away, unless the verification policy blocks login.
+    pub async fn register(
+        pool: &DbPool,
+        keyring: &Keyring,
+        register_request: RegisterRequest,
+        client: &ClientInfo,
+    ) -> Result<RegisterResponse, AuthError> {
+        let new_user = NewUser::from(register_request);
+        PasswordPolicyService::validate_new_user(&new_user)?;
+
+        let password = hash_password(&new_user.password).map_err(|_e| AuthError::InternalServerError)?;
+        let user = AuditService::transaction(pool, |conn, trail| {
+            let user = UserRepository::create(conn, NewUser { password, ..new_user })
+                .map_err(|e| match e {
+                    diesel::result::Error::DatabaseError(DatabaseErrorKind::UniqueViolation, _) => AuthError::EmailAlreadyExists,
+                    e => AuthError::DatabaseError(e),
+                })?;
+            RoleService::grant(conn, trail, user.id, DEFAULT_ROLE, None)?;
+            Ok::<_, AuthError>(user)
+        }).await?;
+
+        let tokens = if Self::login_blocked(&user) {
+            None
+        } else {
+            let tokens = Self::start_session(pool, keyring, &user, client).await?;
+            Some(tokens)
+        };
+
+        Ok(RegisterResponse {
+            id: user.id,
+            email_verified: user.is_email_verified(),
+            username: user.username,
+            email: user.email,
+            tokens,
+        })
+    }
+
+    /// Changes the caller's password after re-checking the current one. Every
+    /// session and refresh token the user holds is revoked and the caller gets
+    /// a new session, so all other devices are logged out.
+    pub async fn change_password(
+        pool: &DbPool,
+        keyring: &Keyring,
+        user_id: Uuid,
+        request: UpdatePasswordRequest,
+        client: &ClientInfo,
+    ) -> Result<LoginResponse, AuthError> {
+        let user = match UserRepository::find_by_email(pool, &request.email).await {
+            Ok(user) if user.id == user_id => user,
+            Ok(_) | Err(diesel::result::Error::NotFound) => return Err(AuthError::InvalidCredentials),
+            Err(e) => return Err(AuthError::DatabaseError(e)),
+        };
+
+        if !verify_password(&user.password, &request.current_password) {
+            return Err(AuthError::InvalidCredentials);
+        }
+
+        PasswordPolicyService::validate_new_password(pool, &user, &request.new_password).await?;
+
+        let password_hash = hash_password(&request.new_password).map_err(|_e| AuthError::InternalServerError)?;
+        AuditService::transaction(pool, |conn, trail| {
+            UserRepository::set_password(conn, user.id, &password_hash)?;
+            PasswordPolicyService::remember_previous(conn, &user)?;
+            let revoked = RefreshTokenRepository::revoke_all_for_user(conn, user.id)?;
+            SessionRepository::revoke_all_for_user(conn, user.id)?;
+
+            trail.record(AuditEventType::PasswordChanged, Some(user.id), json!({ "revoked_refresh_tokens": revoked }));
+            Ok::<_, AuthError>(())
+        }).await?;
+
+        Self::start_session(pool, keyring, &user, client).await
+    }
+
+    /// Exchanges a refresh token for a new token pair. The presented token is
+    /// revoked; presenting it again revokes every token in its family.
+    pub async fn refresh(pool: &DbPool, keyring: &Keyring, refresh_token: &str) -> Result<LoginResponse, AuthError> {
+        let current = match RefreshTokenRepository::find_by_hash(pool, &hash_opaque_token(refresh_token)).await {
+            Ok(current) => current,
+            Err(diesel::result::Error::NotFound) => return Err(AuthError::InvalidRefreshToken),
+            Err(e) => return Err(AuthError::DatabaseError(e)),
+        };
+
+        // Tokens issued to OAuth clients are only refreshed at /oauth/token.
+        if current.client_id.is_some() {
+            return Err(AuthError::InvalidRefreshToken);
+        }
+
+        if current.revoked_at.is_some() {
+            return Self::revoke_reused_family(pool, &current).await;
+        }
+
+        if current.expires_at <= Utc::now().naive_utc() {
+            return Err(AuthError::InvalidRefreshToken);
+        }
+
+        // A first-party token family is a session; tokens from before sessions
+        // were recorded have none and need a fresh login.
+        let session = SessionRepository::find(pool, current.family_id).await?;
+        let active = session.is_some_and(|session| session.revoked_at.is_none());
+        if !active {
+            return Err(AuthError::InvalidRefreshToken);
+        }
+
+        let user = UserRepository::find_by_id(pool, current.user_id).await
+            .map_err(|_e| AuthError::InvalidRefreshToken)?;
+        if Self::login_blocked(&user) {
+            return Err(AuthError::EmailNotVerified);
+        }
+
+        let token = Self::generate_token(pool, keyring, &user, current.family_id).await?;
+        let (refresh_token, next) = Self::new_refresh_token(current.user_id, current.family_id);
+
+        match RefreshTokenRepository::rotate(pool, &current, next).await? {
+            Some(_) => {
+                SessionRepository::touch(pool, current.family_id, Duration::zero()).await?;
+                Ok(Self::login_response(token, refresh_token))
             },
-            Err(diesel::result::Error::NotFound) => Err("User not found"),
-            Err(_e) => Err("Error occurred"),
+            None => Self::revoke_reused_family(pool, &current).await,
         }
     }
 
-    async fn generate_token(user_id: &str) -> Result<String, String> {
-        let expiration = 3600 * 60;
-        let claims = Claims {
-            sub: user_id.to_owned(),
-            exp: expiration as usize,
+    /// Ends the session the refresh token belongs to. Unknown tokens are ignored
+    /// so that logging out twice is not an error.
+    pub async fn logout(pool: &DbPool, refresh_token: &str) -> Result<(), AuthError> {
+        match RefreshTokenRepository::find_by_hash(pool, &hash_opaque_token(refresh_token)).await {
+            Ok(current) => AuditService::transaction(pool, |conn, trail| {
+                RefreshTokenRepository::revoke_family(conn, current.family_id)?;
+                if SessionRepository::revoke(conn, current.family_id)? > 0 {
+                    trail.record(AuditEventType::SessionRevoked, Some(current.user_id), json!({ "session_id": current.family_id }));
+                }
+                Ok(())
+            }).await,
+            Err(diesel::result::Error::NotFound) => Ok(()),
+            Err(e) => Err(AuthError::DatabaseError(e)),
+        }
+    }
+
+    async fn failed_login(pool: &DbPool, attempt: &LoginAttempt) -> Result<LoginOutcome, AuthError> {
+        if let Err(e) = LoginAttemptService::record_failure(pool, attempt).await {
+            log::error!("Failed to record failed login: {}", e);
+        }
+        Err(AuthError::InvalidCredentials)
+    }
+
+    /// Replaces a legacy or outdated hash while the plaintext is at hand,
+    /// right after it verified. A failure only means trying again next login.
+    async fn upgrade_password_hash(pool: &DbPool, user: &User, password: &str) {
+        if !needs_rehash(&user.password) {
+            return;
+        }
+
+        let upgraded = match hash_password(password) {
+            Ok(password_hash) => UserRepository::update_password(pool, user.id, &password_hash).await.map_err(|e| e.to_string()),
+            Err(e) => Err(e.to_string()),
+        };
+        if let Err(e) = upgraded {
+            log::error!("Failed to upgrade password hash for user {}: {}", user.id, e);
+        }
+    }
+
+    fn login_blocked(user: &User) -> bool {
+        EmailVerificationPolicy::from_env() == EmailVerificationPolicy::BlockLogin && !user.is_email_verified()
+    }
+
+    /// Records a new session and issues its first token pair. The session id
+    /// is the refresh token family and the access token's `sid`.
+    async fn start_session(pool: &DbPool, keyring: &Keyring, user: &User, client: &ClientInfo) -> Result<LoginResponse, AuthError> {
+        let session = SessionService::start(pool, user, client).await?;
+        Self::issue_tokens(pool, keyring, user, session.id).await
+    }
+
+    async fn issue_tokens(pool: &DbPool, keyring: &Keyring, user: &User, family_id: Uuid) -> Result<LoginResponse, AuthError> {
+        let token = Self::generate_token(pool, keyring, user, family_id).await?;
+        let (refresh_token, new_refresh_token) = Self::new_refresh_token(user.id, family_id);
+
+        RefreshTokenRepository::create(pool, new_refresh_token).await?;
+
+        Ok(Self::login_response(token, refresh_token))
+    }
+
+    fn new_refresh_token(user_id: Uuid, family_id: Uuid) -> (String, NewRefreshToken) {
+        let refresh_token = generate_opaque_token();
+        let new_refresh_token = NewRefreshToken {
+            user_id,
+            family_id,
+            token_hash: hash_opaque_token(&refresh_token),
+            expires_at: (Utc::now() + Duration::days(REFRESH_TOKEN_TTL_DAYS)).naive_utc(),
+            client_id: None,
+            scope: None,
         };
 
-        let secret = env::var("SECRET").map_err(|_| "SECRET not set".to_string())?;
-        let token = encode(&Header::default(), &claims, &EncodingKey::from_secret(secret.as_ref()))
-            .map_err(|e| e.to_string())?;
+        (refresh_token, new_refresh_token)
+    }
+
+    async fn revoke_reused_family(pool: &DbPool, current: &RefreshToken) -> Result<LoginResponse, AuthError> {
+        log::warn!("Refresh token reuse detected, revoking token family {}", current.family_id);
+        AuditService::transaction(pool, |conn, trail| {
+            RefreshTokenRepository::revoke_family(conn, current.family_id)?;
+            SessionRepository::revoke(conn, current.family_id)?;
+
+            trail.record(AuditEventType::TokenRevoked, Some(current.user_id), json!({
+                "token_type": "refresh_token",
+                "reason": "reuse_detected",
+                "session_id": current.family_id,
+            }));
+            Ok::<_, AuthError>(())
+        }).await?;
+        Err(AuthError::InvalidRefreshToken)
+    }
+
+    fn login_response(token: String, refresh_token: String) -> LoginResponse {
+        LoginResponse {
+            token,
+            refresh_token,
+            token_type: "Bearer".to_string(),
+            expires_in: ACCESS_TOKEN_TTL_SECONDS,
+        }
+    }
+
+    async fn generate_token(pool: &DbPool, keyring: &Keyring, user: &User, session_id: Uuid) -> Result<String, AuthError> {
+        let now = Utc::now();
+        let (roles, permissions) = RoleService::grants_for(pool, user.id).await?;
+        let email_verified = user.is_email_verified();
+        let claims = Claims {
+            sub: user.id.to_string(),
+            iat: now.timestamp() as usize,
+            exp: (now + Duration::seconds(ACCESS_TOKEN_TTL_SECONDS)).timestamp() as usize,
+            iss: issuer(),
+            aud: audience(),
+            email_verified,
+            scope: EmailVerificationPolicy::from_env().scope_for(email_verified),
+            roles,
+            permissions,
+            client_id: None,
+            sid: Some(session_id.to_string()),
+            jti: Some(Uuid::new_v4().to_string()),
+        };
 
-        Ok(token.to_string())
+        keyring.sign(&claims).map_err(|e| {
+            log::error!("Failed to sign access token: {}", e);
+            AuthError::InternalServerError
+        })
     }
 }
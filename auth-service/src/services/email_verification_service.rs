@@ -0,0 +1,137 @@
+use std::env;
+use std::sync::Arc;
+use chrono::{Duration, Utc};
+use serde::{Deserialize, Serialize};
+use shared::auth::validator::issuer;
+use uuid::Uuid;
+use crate::config::database::DbPool;
+use crate::config::keyring::Keyring;
+use crate::errors::error::AuthError;
+use crate::mailer::{Email, Mailer};
+use crate::repositories::user_repository::UserRepository;
+
+pub const VERIFICATION_LINK_TTL_HOURS: i64 = 24;
+pub const RESEND_INTERVAL_SECONDS: i64 = 60;
+/// Verification links are JWTs for this audience, so they are never accepted
+/// as access tokens.
+const VERIFICATION_AUDIENCE: &str = "email-verification";
+const DEFAULT_VERIFICATION_URL: &str = "http://localhost:8081/auth/email/verify";
+const DEFAULT_UNVERIFIED_SCOPES: &str = "profile:read";
+
+/// What an account may do before its email is verified, from `EMAIL_VERIFICATION_POLICY`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EmailVerificationPolicy {
+    /// `optional`: unverified accounts are treated like verified ones.
+    Optional,
+    /// `block_login`: no tokens are issued until the email is verified.
+    BlockLogin,
+    /// `restrict_scopes`: tokens are limited to `UNVERIFIED_SCOPES`.
+    RestrictScopes,
+}
+
+impl EmailVerificationPolicy {
+    pub fn from_env() -> EmailVerificationPolicy {
+        match env::var("EMAIL_VERIFICATION_POLICY").unwrap_or_default().as_str() {
+            "block_login" => EmailVerificationPolicy::BlockLogin,
+            "restrict_scopes" => EmailVerificationPolicy::RestrictScopes,
+            _ => EmailVerificationPolicy::Optional,
+        }
+    }
+
+    /// The `scope` claim for an access token, or `None` for unrestricted access.
+    pub fn scope_for(&self, email_verified: bool) -> Option<String> {
+        match self {
+            EmailVerificationPolicy::RestrictScopes if !email_verified => Some(
+                env::var("UNVERIFIED_SCOPES").unwrap_or_else(|_| DEFAULT_UNVERIFIED_SCOPES.to_string()),
+            ),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct VerificationClaims {
+    sub: String,
+    email: String,
+    iat: usize,
+    exp: usize,
+    iss: String,
+    aud: String,
+}
+
+pub struct EmailVerificationService;
+
+impl EmailVerificationService {
+    /// Emails a signed verification link, unless one was sent in the last
+    /// `RESEND_INTERVAL_SECONDS` or the address is already verified.
+    pub async fn send_verification(pool: &DbPool, keyring: &Keyring, mailer: Arc<dyn Mailer>, user_id: Uuid) -> Result<(), AuthError> {
+        let user = UserRepository::find_by_id(pool, user_id).await?;
+        if user.is_email_verified() {
+            return Ok(());
+        }
+
+        let user = match UserRepository::claim_verification_send(pool, user.id, Duration::seconds(RESEND_INTERVAL_SECONDS)).await? {
+            Some(user) => user,
+            None => {
+                log::info!("Throttled verification email for user {}", user.id);
+                return Ok(());
+            }
+        };
+
+        let token = Self::generate_token(keyring, &user.id, &user.email)?;
+        let verification_url = env::var("EMAIL_VERIFICATION_URL").unwrap_or_else(|_| DEFAULT_VERIFICATION_URL.to_string());
+        let email = Email {
+            to: user.email,
+            subject: "Confirm your email address".to_string(),
+            body: format!(
+                "Hi {},\n\nPlease confirm your email address by opening the link below. It expires in {} hours.\n\n{}?token={}\n",
+                user.username, VERIFICATION_LINK_TTL_HOURS, verification_url, token,
+            ),
+        };
+
+        mailer.send(email).await.map_err(|e| {
+            log::error!("Failed to send verification email: {}", e);
+            AuthError::InternalServerError
+        })
+    }
+
+    /// Resends the link for `email`. Unknown and already verified addresses are
+    /// ignored so the caller cannot tell them apart.
+    pub async fn resend(pool: &DbPool, keyring: &Keyring, mailer: Arc<dyn Mailer>, email: &str) {
+        let result = match UserRepository::find_by_email(pool, email).await {
+            Ok(user) => Self::send_verification(pool, keyring, mailer, user.id).await,
+            Err(diesel::result::Error::NotFound) => Ok(()),
+            Err(e) => Err(AuthError::DatabaseError(e)),
+        };
+
+        if let Err(e) = result {
+            log::error!("Failed to resend verification email: {}", e);
+        }
+    }
+
+    pub async fn verify(pool: &DbPool, keyring: &Keyring, token: &str) -> Result<(), AuthError> {
+        let claims = keyring.verify::<VerificationClaims>(token, VERIFICATION_AUDIENCE)
+            .map_err(|_e| AuthError::InvalidVerificationToken)?;
+        let user_id = Uuid::parse_str(&claims.sub).map_err(|_e| AuthError::InvalidVerificationToken)?;
+
+        match UserRepository::mark_email_verified(pool, user_id, &claims.email).await {
+            Ok(_) => Ok(()),
+            Err(diesel::result::Error::NotFound) => Err(AuthError::InvalidVerificationToken),
+            Err(e) => Err(AuthError::DatabaseError(e)),
+        }
+    }
+
+    fn generate_token(keyring: &Keyring, user_id: &Uuid, email: &str) -> Result<String, AuthError> {
+        let now = Utc::now();
+        let claims = VerificationClaims {
+            sub: user_id.to_string(),
+            email: email.to_string(),
+            iat: now.timestamp() as usize,
+            exp: (now + Duration::hours(VERIFICATION_LINK_TTL_HOURS)).timestamp() as usize,
+            iss: issuer(),
+            aud: VERIFICATION_AUDIENCE.to_string(),
+        };
+
+        keyring.sign(&claims).map_err(|_e| AuthError::InternalServerError)
+    }
+}
@@ -0,0 +1,62 @@
+use chrono::Utc;
+use serde_json::json;
+use shared::erasure::{ErasureAcknowledged, ErasureRequested, ERASURE_ACKNOWLEDGED_TOPIC};
+use shared::outbox::{NewOutboxMessage, OutboxRepository};
+use uuid::Uuid;
+use crate::config::database::DbPool;
+use crate::errors::error::AuthError;
+use crate::models::audit::AuditEventType;
+use crate::repositories::login_attempt_repository::LoginAttemptRepository;
+use crate::repositories::user_repository::UserRepository;
+use crate::services::audit_service::AuditService;
+
+/// The name this service acknowledges erasures under.
+pub const SERVICE_NAME: &str = "auth-service";
+
+/// Answers user-service's erasure requests. See `shared::erasure`.
+pub struct ErasureService;
+
+impl ErasureService {
+    /// Handles a message from `ERASURE_REQUESTED_TOPIC`. A payload that is
+    /// not a request is logged and skipped: handling it again cannot help.
+    pub async fn handle(pool: &DbPool, payload: &str) -> Result<(), AuthError> {
+        match serde_json::from_str::<ErasureRequested>(payload) {
+            Ok(request) => Self::erase(pool, &request).await,
+            Err(e) => {
+                log::warn!("Skipping malformed erasure request: {}", e);
+                Ok(())
+            }
+        }
+    }
+
+    /// Deletes the account registered with the request's email, everything
+    /// keyed to it and its failed login attempts, and queues the
+    /// acknowledgement in the same transaction. When there is no such
+    /// account, because it never existed or a redelivered request already
+    /// erased it, the request is acknowledged all the same.
+    pub async fn erase(pool: &DbPool, request: &ErasureRequested) -> Result<(), AuthError> {
+        AuditService::transaction(pool, |conn, trail| {
+            if let Some(user) = UserRepository::lock_by_email(conn, &request.email)? {
+                UserRepository::delete(conn, user.id)?;
+                trail.record(AuditEventType::AccountErased, Some(user.id), json!({ "erasure_request_id": request.request_id }));
+            }
+            LoginAttemptRepository::clear(conn, &request.email)?;
+
+            let acknowledgement = ErasureAcknowledged {
+                request_id: request.request_id,
+                user_id: request.user_id,
+                service: SERVICE_NAME.to_string(),
+                erased_at: Utc::now().naive_utc(),
+            };
+            let message = NewOutboxMessage {
+                id: Uuid::new_v4(),
+                topic: ERASURE_ACKNOWLEDGED_TOPIC.to_string(),
+                event_key: request.user_id.to_string(),
+                payload: serde_json::to_value(&acknowledgement)
+                    .map_err(|e| diesel::result::Error::SerializationError(Box::new(e)))?,
+            };
+            OutboxRepository::create(conn, &[message])?;
+            Ok(())
+        }).await
+    }
+}
@@ -0,0 +1,96 @@
+use chrono::Utc;
+use shared::auth::personal_access_token::is_personal_access_token;
+use shared::auth::validator::{audience, issuer};
+use shared::auth::{Claims, TokenValidator};
+use crate::config::database::DbPool;
+use crate::errors::oauth_error::OAuthError;
+use crate::models::introspection::IntrospectionResponse;
+use crate::models::oauth::OAuthClient;
+use crate::models::refresh_token::RefreshToken;
+use crate::repositories::oauth_repository::OAuthRepository;
+use crate::repositories::refresh_token_repository::RefreshTokenRepository;
+use crate::repositories::session_repository::SessionRepository;
+use crate::repositories::user_repository::UserRepository;
+use crate::utils::opaque_token::hash_opaque_token;
+
+/// Access tokens are JWTs; refresh tokens and personal access tokens are opaque.
+pub fn looks_like_jwt(token: &str) -> bool {
+    token.split('.').count() == 3
+}
+
+pub struct IntrospectionService;
+
+impl IntrospectionService {
+    /// RFC 7662: whether `token` is active, and its claims if so. Access
+    /// tokens go through the same `TokenValidator` as every request here, so
+    /// revocation and ended sessions are taken into account. Only service
+    /// clients may ask.
+    pub async fn introspect(
+        pool: &DbPool,
+        validator: &TokenValidator,
+        client: &OAuthClient,
+        token: &str,
+    ) -> Result<IntrospectionResponse, OAuthError> {
+        if !client.is_service {
+            return Err(OAuthError::AccessDenied);
+        }
+
+        if is_personal_access_token(token) || looks_like_jwt(token) {
+            return Ok(match validator.validate(token).await {
+                Ok(claims) => IntrospectionResponse::active("Bearer", claims),
+                Err(_) => IntrospectionResponse::inactive(),
+            });
+        }
+        Self::introspect_refresh_token(pool, token).await
+    }
+
+    async fn introspect_refresh_token(pool: &DbPool, token: &str) -> Result<IntrospectionResponse, OAuthError> {
+        let current = match RefreshTokenRepository::find_by_hash(pool, &hash_opaque_token(token)).await {
+            Ok(current) => current,
+            Err(diesel::result::Error::NotFound) => return Ok(IntrospectionResponse::inactive()),
+            Err(e) => return Err(e.into()),
+        };
+        if current.revoked_at.is_some() || current.expires_at <= Utc::now().naive_utc() {
+            return Ok(IntrospectionResponse::inactive());
+        }
+
+        let (client_id, sid) = match current.client_id {
+            Some(id) => (Some(OAuthRepository::find_client_by_id(pool, id).await?.client_id), None),
+            None => {
+                let session = SessionRepository::find(pool, current.family_id).await?;
+                let active = session.is_some_and(|session| session.revoked_at.is_none());
+                if !active {
+                    return Ok(IntrospectionResponse::inactive());
+                }
+                (None, Some(current.family_id.to_string()))
+            }
+        };
+
+        let claims = Self::refresh_token_claims(pool, &current, client_id, sid).await?;
+        Ok(IntrospectionResponse::active("refresh_token", claims))
+    }
+
+    async fn refresh_token_claims(
+        pool: &DbPool,
+        current: &RefreshToken,
+        client_id: Option<String>,
+        sid: Option<String>,
+    ) -> Result<Claims, OAuthError> {
+        let user = UserRepository::find_by_id(pool, current.user_id).await?;
+        Ok(Claims {
+            sub: user.id.to_string(),
+            iat: current.created_at.and_utc().timestamp() as usize,
+            exp: current.expires_at.and_utc().timestamp() as usize,
+            iss: issuer(),
+            aud: audience(),
+            email_verified: user.is_email_verified(),
+            scope: current.scope.clone(),
+            roles: Vec::new(),
+            permissions: Vec::new(),
+            client_id,
+            token_id: None,
+            sid,
+            jti: None,
+        })
+    }
+}
@@ -0,0 +1,172 @@
+use std::env;
+use chrono::{Duration, NaiveDateTime, Utc};
+use diesel::Connection;
+use serde_json::json;
+use uuid::Uuid;
+use crate::config::database::DbPool;
+use crate::errors::error::AuthError;
+use crate::models::audit::AuditEventType;
+use crate::models::login_attempt::LoginAttempt;
+use crate::models::user::normalize_email;
+use crate::repositories::login_attempt_repository::LoginAttemptRepository;
+use crate::services::audit_service::AuditService;
+
+const DEFAULT_FREE_ATTEMPTS: i32 = 3;
+const DEFAULT_MAX_ATTEMPTS: i32 = 10;
+const DEFAULT_MAX_DELAY_SECONDS: i64 = 60;
+const DEFAULT_LOCKOUT_MINUTES: i64 = 15;
+
+/// How failed logins for one email are slowed down and, eventually, locked out.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LockoutPolicy {
+    /// Failures allowed before any delay applies (`LOGIN_FREE_ATTEMPTS`).
+    pub free_attempts: i32,
+    /// Failures that lock the account (`LOGIN_MAX_ATTEMPTS`).
+    pub max_attempts: i32,
+    /// Upper bound for the delay between attempts (`LOGIN_MAX_DELAY_SECONDS`).
+    pub max_delay_seconds: i64,
+    /// How long a lockout lasts, and how long failures are remembered (`LOGIN_LOCKOUT_MINUTES`).
+    pub lockout_minutes: i64,
+}
+
+impl Default for LockoutPolicy {
+    fn default() -> Self {
+        LockoutPolicy {
+            free_attempts: DEFAULT_FREE_ATTEMPTS,
+            max_attempts: DEFAULT_MAX_ATTEMPTS,
+            max_delay_seconds: DEFAULT_MAX_DELAY_SECONDS,
+            lockout_minutes: DEFAULT_LOCKOUT_MINUTES,
+        }
+    }
+}
+
+impl LockoutPolicy {
+    pub fn from_env() -> LockoutPolicy {
+        let defaults = LockoutPolicy::default();
+        LockoutPolicy {
+            free_attempts: env_or("LOGIN_FREE_ATTEMPTS", defaults.free_attempts),
+            max_attempts: env_or("LOGIN_MAX_ATTEMPTS", defaults.max_attempts),
+            max_delay_seconds: env_or("LOGIN_MAX_DELAY_SECONDS", defaults.max_delay_seconds),
+            lockout_minutes: env_or("LOGIN_LOCKOUT_MINUTES", defaults.lockout_minutes),
+        }
+    }
+
+    /// Seconds to wait after `failed_attempts` consecutive failures. Doubles
+    /// with every failure past the free ones, up to `max_delay_seconds`.
+    pub fn delay_after(&self, failed_attempts: i32) -> i64 {
+        if failed_attempts <= self.free_attempts {
+            return 0;
+        }
+
+        let exponent = (failed_attempts - self.free_attempts - 1).min(31) as u32;
+        2i64.saturating_pow(exponent).min(self.max_delay_seconds)
+    }
+
+    /// Seconds until `attempt`'s email may try again, or `None` if it may now.
+    pub fn retry_after(&self, attempt: &LoginAttempt, now: NaiveDateTime) -> Option<i64> {
+        let ready_at = match attempt.locked_until {
+            Some(locked_until) => locked_until,
+            None => attempt.last_failed_at + Duration::seconds(self.delay_after(attempt.failed_attempts)),
+        };
+
+        (ready_at > now).then(|| (ready_at - now).num_seconds().max(1))
+    }
+
+    /// The record after one more failure. Counting starts over once a lockout
+    /// has run out, or when the last failure is older than a lockout period.
+    pub fn next_failure(&self, previous: Option<LoginAttempt>, email: &str, ip: Option<&str>, now: NaiveDateTime) -> LoginAttempt {
+        let lockout = Duration::minutes(self.lockout_minutes);
+        let failed_attempts = match previous {
+            Some(previous) if previous.locked_until.is_none() && previous.last_failed_at + lockout > now => previous.failed_attempts + 1,
+            _ => 1,
+        };
+
+        LoginAttempt {
+            email: email.to_string(),
+            failed_attempts,
+            last_failed_at: now,
+            last_failed_ip: ip.map(str::to_string),
+            locked_until: (failed_attempts >= self.max_attempts).then(|| now + lockout),
+        }
+    }
+}
+
+pub struct LoginAttemptService;
+
+impl LoginAttemptService {
+    /// Counts a login attempt as a failure before its password is checked,
+    /// refusing it instead while the email is locked or still inside its
+    /// delay. The check and the count happen under one lock, so a burst of
+    /// concurrent guesses cannot all pass the check before any of them has
+    /// failed. `record_success` clears the count once a password is right.
+    pub async fn begin(pool: &DbPool, email: &str, ip: Option<&str>) -> Result<LoginAttempt, AuthError> {
+        let email = normalize_email(email);
+        let policy = LockoutPolicy::from_env();
+        let now = Utc::now().naive_utc();
+
+        let conn = &mut pool.get().expect("Failed to get DB connection from pool");
+        conn.transaction(|conn| {
+            LoginAttemptRepository::record_failure(conn, &email, |previous| {
+                if let Some(retry_after) = previous.as_ref().and_then(|previous| policy.retry_after(previous, now)) {
+                    return Err(AuthError::TooManyLoginAttempts(retry_after));
+                }
+                Ok(policy.next_failure(previous, &email, ip, now))
+            })
+        })
+    }
+
+    /// Records the audit events for an attempt `begin` counted whose
+    /// password turned out to be wrong.
+    pub async fn record_failure(pool: &DbPool, attempt: &LoginAttempt) -> Result<(), AuthError> {
+        let policy = LockoutPolicy::from_env();
+
+        AuditService::transaction(pool, |_conn, trail| {
+            trail.record(AuditEventType::LoginFailed, None, json!({
+                "email": attempt.email,
+                "ip": attempt.last_failed_ip,
+                "failed_attempts": attempt.failed_attempts,
+            }));
+            if attempt.failed_attempts == policy.max_attempts {
+                log::warn!("Locking out {} after {} failed logins", attempt.email, attempt.failed_attempts);
+                trail.record(AuditEventType::AccountLocked, None, json!({
+                    "email": attempt.email,
+                    "ip": attempt.last_failed_ip,
+                    "locked_until": attempt.locked_until,
+                }));
+            }
+            Ok::<_, AuthError>(())
+        }).await
+    }
+
+    pub async fn record_success(pool: &DbPool, email: &str) -> Result<(), AuthError> {
+        LoginAttemptRepository::delete(pool, &normalize_email(email)).await?;
+        Ok(())
+    }
+
+    pub async fn list(pool: &DbPool) -> Result<Vec<LoginAttempt>, AuthError> {
+        Ok(LoginAttemptRepository::list(pool).await?)
+    }
+
+    pub async fn find(pool: &DbPool, email: &str) -> Result<LoginAttempt, AuthError> {
+        LoginAttemptRepository::find(pool, &normalize_email(email)).await?
+            .ok_or(AuthError::LockoutNotFound)
+    }
+
+    /// Lets an admin unlock an email straight away.
+    pub async fn clear(pool: &DbPool, email: &str, cleared_by: Uuid) -> Result<(), AuthError> {
+        let email = normalize_email(email);
+        if LoginAttemptRepository::delete(pool, &email).await? == 0 {
+            return Err(AuthError::LockoutNotFound);
+        }
+
+        AuditService::record(pool, AuditEventType::LockoutCleared, None, json!({
+            "email": email,
+            "cleared_by": cleared_by,
+        })).await;
+        Ok(())
+    }
+}
+
+fn env_or<T: std::str::FromStr>(name: &str, default: T) -> T {
+    env::var(name).ok().and_then(|value| value.parse().ok()).unwrap_or(default)
+}
@@ -0,0 +1,264 @@
+use std::env;
+use std::io::Cursor;
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use chrono::{Duration, Utc};
+use image::{ImageFormat, Luma};
+use qrcode::QrCode;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use shared::auth::validator::issuer;
+use totp_rs::{Algorithm, Secret, TOTP};
+use uuid::Uuid;
+use crate::config::database::DbPool;
+use crate::config::keyring::Keyring;
+use crate::errors::error::AuthError;
+use crate::models::audit::AuditEventType;
+use crate::models::mfa::{MfaChallengeResponse, MfaVerifyRequest, NewMfaChallenge, NewRecoveryCode, NewUserTotp, TotpEnrollmentResponse};
+use crate::repositories::mfa_repository::MfaRepository;
+use crate::repositories::user_repository::UserRepository;
+use crate::services::audit_service::AuditService;
+use crate::services::login_attempt_service::LockoutPolicy;
+use crate::utils::opaque_token::hash_opaque_token;
+use crate::utils::secret_box::{decrypt_secret, encrypt_secret};
+
+pub const MFA_CHALLENGE_TTL_SECONDS: i64 = 5 * 60;
+/// Wrong codes after which a challenge is closed and the login must start over.
+pub const MFA_CHALLENGE_MAX_ATTEMPTS: i32 = 5;
+pub const RECOVERY_CODE_COUNT: usize = 10;
+const TOTP_STEP_SECONDS: u64 = 30;
+const TOTP_DIGITS: usize = 6;
+/// Codes from the previous and next time step are accepted to absorb clock drift.
+const TOTP_SKEW_STEPS: i64 = 1;
+const MFA_CHALLENGE_AUDIENCE: &str = "mfa-challenge";
+const DEFAULT_TOTP_ISSUER: &str = "Resume API";
+const RECOVERY_CODE_ALPHABET: &[u8] = b"abcdefghjkmnpqrstuvwxyz23456789";
+
+#[derive(Serialize, Deserialize)]
+struct MfaChallengeClaims {
+    sub: String,
+    iat: usize,
+    exp: usize,
+    iss: String,
+    aud: String,
+    /// The `mfa_challenges` row tracking the challenge.
+    jti: String,
+}
+
+pub struct MfaService;
+
+impl MfaService {
+    pub async fn is_enabled(pool: &DbPool, user_id: Uuid) -> Result<bool, AuthError> {
+        let totp = MfaRepository::find_totp(pool, user_id).await?;
+        Ok(totp.is_some_and(|totp| totp.confirmed_at.is_some()))
+    }
+
+    /// Starts enrollment with a fresh secret. Nothing changes at login until
+    /// the user proves they can generate codes with `confirm`.
+    pub async fn enroll(pool: &DbPool, user_id: Uuid) -> Result<TotpEnrollmentResponse, AuthError> {
+        if Self::is_enabled(pool, user_id).await? {
+            return Err(AuthError::MfaAlreadyEnabled);
+        }
+
+        let user = UserRepository::find_by_id(pool, user_id).await?;
+        let secret = Secret::generate_secret().to_bytes().map_err(|_e| AuthError::InternalServerError)?;
+        let totp = Self::totp(secret.clone(), &user.email)?;
+
+        let secret_ciphertext = encrypt_secret(&secret, user_id.as_bytes()).map_err(|e| {
+            log::error!("Failed to encrypt TOTP secret: {}", e);
+            AuthError::InternalServerError
+        })?;
+        MfaRepository::upsert_pending_totp(pool, NewUserTotp { user_id, secret_ciphertext }).await?;
+
+        let otpauth_uri = totp.get_url();
+        Ok(TotpEnrollmentResponse {
+            secret: totp.get_secret_base32(),
+            qr_code_png: Self::qr_code_png(&otpauth_uri)?,
+            otpauth_uri,
+        })
+    }
+
+    /// Activates TOTP once the user submits a valid code and returns the
+    /// recovery codes. They are only ever shown here.
+    pub async fn confirm(pool: &DbPool, user_id: Uuid, code: &str) -> Result<Vec<String>, AuthError> {
+        let pending = match MfaRepository::find_totp(pool, user_id).await? {
+            Some(totp) if totp.confirmed_at.is_none() => totp,
+            Some(_) => return Err(AuthError::MfaAlreadyEnabled),
+            None => return Err(AuthError::MfaNotEnabled),
+        };
+
+        let step = Self::matching_step(pool, user_id, &pending.secret_ciphertext, code).await?
+            .ok_or(AuthError::InvalidMfaCode)?;
+
+        let recovery_codes: Vec<String> = (0..RECOVERY_CODE_COUNT).map(|_| Self::generate_recovery_code()).collect();
+        let new_codes = recovery_codes.iter()
+            .map(|code| NewRecoveryCode { user_id, code_hash: hash_opaque_token(code) })
+            .collect();
+        MfaRepository::confirm_totp(pool, user_id, step, new_codes).await?;
+
+        AuditService::record(pool, AuditEventType::MfaEnabled, Some(user_id), json!({})).await;
+        Ok(recovery_codes)
+    }
+
+    pub async fn disable(pool: &DbPool, user_id: Uuid, code: &str) -> Result<(), AuthError> {
+        if !Self::is_enabled(pool, user_id).await? {
+            return Err(AuthError::MfaNotEnabled);
+        }
+
+        Self::verify_code(pool, user_id, code).await?;
+        MfaRepository::delete_all_for_user(pool, user_id).await?;
+
+        AuditService::record(pool, AuditEventType::MfaDisabled, Some(user_id), json!({})).await;
+        Ok(())
+    }
+
+    /// Issued by `/auth/login` instead of tokens when the user has TOTP on.
+    pub async fn create_challenge(pool: &DbPool, keyring: &Keyring, user_id: Uuid) -> Result<MfaChallengeResponse, AuthError> {
+        let now = Utc::now();
+        let challenge = NewMfaChallenge {
+            id: Uuid::new_v4(),
+            user_id,
+            expires_at: (now + Duration::seconds(MFA_CHALLENGE_TTL_SECONDS)).naive_utc(),
+        };
+        let claims = MfaChallengeClaims {
+            sub: user_id.to_string(),
+            iat: now.timestamp() as usize,
+            exp: challenge.expires_at.and_utc().timestamp() as usize,
+            iss: issuer(),
+            aud: MFA_CHALLENGE_AUDIENCE.to_string(),
+            jti: challenge.id.to_string(),
+        };
+        let forget_before = (now - Duration::minutes(LockoutPolicy::from_env().lockout_minutes)).naive_utc();
+        MfaRepository::create_challenge(pool, challenge, forget_before).await?;
+
+        let mfa_token = keyring.sign(&claims).map_err(|_e| AuthError::InternalServerError)?;
+        Ok(MfaChallengeResponse { mfa_required: true, mfa_token, expires_in: MFA_CHALLENGE_TTL_SECONDS })
+    }
+
+    /// Checks the challenge token and the second factor, returning the user
+    /// that completed the login.
+    ///
+    /// A challenge completes one login at most, and is closed after
+    /// `MFA_CHALLENGE_MAX_ATTEMPTS` wrong codes. Since a new challenge only
+    /// takes the password, wrong codes are also counted across all of the
+    /// user's challenges: past `LOGIN_MAX_ATTEMPTS` of them within
+    /// `LOGIN_LOCKOUT_MINUTES`, no challenge is accepted.
+    pub async fn verify_challenge(pool: &DbPool, keyring: &Keyring, request: &MfaVerifyRequest) -> Result<Uuid, AuthError> {
+        let claims = keyring.verify::<MfaChallengeClaims>(&request.mfa_token, MFA_CHALLENGE_AUDIENCE)
+            .map_err(|_e| AuthError::InvalidMfaChallenge)?;
+        let user_id = Uuid::parse_str(&claims.sub).map_err(|_e| AuthError::InvalidMfaChallenge)?;
+        let challenge_id = Uuid::parse_str(&claims.jti).map_err(|_e| AuthError::InvalidMfaChallenge)?;
+
+        let policy = LockoutPolicy::from_env();
+        let since = (Utc::now() - Duration::minutes(policy.lockout_minutes)).naive_utc();
+        if MfaRepository::challenge_failures_since(pool, user_id, since).await? >= i64::from(policy.max_attempts) {
+            return Err(AuthError::TooManyLoginAttempts(policy.lockout_minutes * 60));
+        }
+        MfaRepository::find_open_challenge(pool, challenge_id, user_id).await?
+            .ok_or(AuthError::InvalidMfaChallenge)?;
+
+        match Self::verify_second_factor(pool, user_id, request).await {
+            Ok(()) => {}
+            Err(AuthError::InvalidMfaCode) => {
+                let challenge = MfaRepository::record_challenge_failure(pool, challenge_id, MFA_CHALLENGE_MAX_ATTEMPTS).await?;
+                if challenge.closed_at.is_some() {
+                    log::warn!("Closed MFA challenge for user {} after {} wrong codes", user_id, challenge.failed_attempts);
+                }
+                return Err(AuthError::InvalidMfaCode);
+            }
+            Err(e) => return Err(e),
+        }
+
+        if !MfaRepository::close_challenge(pool, challenge_id).await? {
+            return Err(AuthError::InvalidMfaChallenge);
+        }
+        Ok(user_id)
+    }
+
+    async fn verify_second_factor(pool: &DbPool, user_id: Uuid, request: &MfaVerifyRequest) -> Result<(), AuthError> {
+        match (&request.code, &request.recovery_code) {
+            (Some(code), _) => Self::verify_code(pool, user_id, code).await,
+            (None, Some(recovery_code)) => {
+                let hash = hash_opaque_token(&Self::normalize_recovery_code(recovery_code));
+                if !MfaRepository::consume_recovery_code(pool, user_id, &hash).await? {
+                    return Err(AuthError::InvalidMfaCode);
+                }
+                AuditService::record(pool, AuditEventType::RecoveryCodeUsed, Some(user_id), json!({})).await;
+                Ok(())
+            }
+            (None, None) => Err(AuthError::InvalidMfaCode),
+        }
+    }
+
+    async fn verify_code(pool: &DbPool, user_id: Uuid, code: &str) -> Result<(), AuthError> {
+        let totp = MfaRepository::find_totp(pool, user_id).await?
+            .filter(|totp| totp.confirmed_at.is_some())
+            .ok_or(AuthError::MfaNotEnabled)?;
+
+        let step = Self::matching_step(pool, user_id, &totp.secret_ciphertext, code).await?
+            .ok_or(AuthError::InvalidMfaCode)?;
+
+        if MfaRepository::record_totp_step(pool, user_id, step).await? {
+            Ok(())
+        } else {
+            Err(AuthError::InvalidMfaCode)
+        }
+    }
+
+    /// The time step `code` was generated for, if it is valid within the skew window.
+    async fn matching_step(pool: &DbPool, user_id: Uuid, secret_ciphertext: &str, code: &str) -> Result<Option<i64>, AuthError> {
+        let secret = decrypt_secret(secret_ciphertext, user_id.as_bytes()).map_err(|e| {
+            log::error!("Failed to decrypt TOTP secret: {}", e);
+            AuthError::InternalServerError
+        })?;
+        let user = UserRepository::find_by_id(pool, user_id).await?;
+        let totp = Self::totp(secret, &user.email)?;
+
+        let now = Utc::now().timestamp();
+        let step = (-TOTP_SKEW_STEPS..=TOTP_SKEW_STEPS)
+            .map(|offset| now + offset * TOTP_STEP_SECONDS as i64)
+            .find(|time| totp.check(code.trim(), *time as u64))
+            .map(|time| time / TOTP_STEP_SECONDS as i64);
+
+        Ok(step)
+    }
+
+    fn totp(secret: Vec<u8>, account_name: &str) -> Result<TOTP, AuthError> {
+        let totp_issuer = env::var("TOTP_ISSUER").unwrap_or_else(|_| DEFAULT_TOTP_ISSUER.to_string());
+        TOTP::new(Algorithm::SHA1, TOTP_DIGITS, 0, TOTP_STEP_SECONDS, secret, Some(totp_issuer), account_name.to_string())
+            .map_err(|e| {
+                log::error!("Invalid TOTP parameters: {}", e);
+                AuthError::InternalServerError
+            })
+    }
+
+    fn qr_code_png(data: &str) -> Result<String, AuthError> {
+        let image = QrCode::new(data.as_bytes())
+            .map_err(|_e| AuthError::InternalServerError)?
+            .render::<Luma<u8>>()
+            .min_dimensions(200, 200)
+            .build();
+
+        let mut png = Cursor::new(Vec::new());
+        image.write_to(&mut png, ImageFormat::Png).map_err(|_e| AuthError::InternalServerError)?;
+        Ok(STANDARD.encode(png.into_inner()))
+    }
+
+    fn generate_recovery_code() -> String {
+        let mut rng = rand::thread_rng();
+        let mut code: String = (0..10)
+            .map(|_| RECOVERY_CODE_ALPHABET[rng.gen_range(0..RECOVERY_CODE_ALPHABET.len())] as char)
+            .collect();
+        code.insert(5, '-');
+        code
+    }
+
+    fn normalize_recovery_code(code: &str) -> String {
+        let code: String = code.trim().to_lowercase().chars().filter(|c| c.is_ascii_alphanumeric()).collect();
+        match code.len() {
+            10 => format!("{}-{}", &code[..5], &code[5..]),
+            _ => code,
+        }
+    }
+}
@@ -1,2 +1,16 @@
+pub mod audit_service;
 pub mod auth_service;
-pub mod kafka_service;
\ No newline at end of file
+pub mod email_verification_service;
+pub mod erasure_service;
+pub mod introspection_service;
+pub mod login_attempt_service;
+pub mod mfa_service;
+pub mod oauth_service;
+pub mod oidc_service;
+pub mod passkey_service;
+pub mod password_policy_service;
+pub mod password_reset_service;
+pub mod personal_access_token_service;
+pub mod role_service;
+pub mod session_service;
+pub mod token_revocation_service;
\ No newline at end of file
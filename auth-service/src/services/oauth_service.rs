@@ -0,0 +1,342 @@
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+use chrono::{Duration, Utc};
+use serde_json::json;
+use sha2::{Digest, Sha256};
+use shared::auth::validator::{audience, issuer};
+use url::Url;
+use uuid::Uuid;
+use validator::{Validate, ValidationError, ValidationErrors};
+use crate::config::database::DbPool;
+use crate::config::keyring::Keyring;
+use crate::errors::error::AuthError;
+use crate::errors::oauth_error::OAuthError;
+use crate::models::audit::AuditEventType;
+use crate::models::auth::Claims;
+use crate::models::oauth::{
+    AuthorizeQuery, AuthorizeRedirect, ConsentDecision, ConsentPrompt, NewAuthorizationCode, NewOAuthClient,
+    OAuthClient, RegisterClientRequest, RegisterClientResponse, TokenRequest, TokenResponse,
+};
+use crate::models::refresh_token::{NewRefreshToken, RefreshToken};
+use crate::repositories::oauth_repository::OAuthRepository;
+use crate::repositories::refresh_token_repository::RefreshTokenRepository;
+use crate::repositories::user_repository::UserRepository;
+use crate::services::audit_service::AuditService;
+use crate::services::auth_service::{ACCESS_TOKEN_TTL_SECONDS, REFRESH_TOKEN_TTL_DAYS};
+use crate::services::role_service::RoleService;
+use crate::utils::opaque_token::{generate_opaque_token, hash_opaque_token};
+
+pub const AUTHORIZATION_CODE_TTL_SECONDS: i64 = 60;
+/// Only S256 is accepted; `plain` offers no protection if the code leaks.
+const PKCE_METHOD: &str = "S256";
+
+/// Client credentials, from HTTP Basic or from the form body.
+pub struct ClientCredentials {
+    pub client_id: String,
+    pub client_secret: Option<String>,
+}
+
+pub struct OAuthService;
+
+impl OAuthService {
+    pub async fn register_client(
+        pool: &DbPool,
+        created_by: Uuid,
+        request: RegisterClientRequest,
+    ) -> Result<RegisterClientResponse, AuthError> {
+        request.validate()?;
+
+        let mut errors = ValidationErrors::new();
+        if request.service && !request.confidential {
+            errors.add("confidential", ValidationError::new("service_client_must_be_confidential"));
+        }
+        if !request.service && request.redirect_uris.is_empty() {
+            errors.add("redirect_uris", ValidationError::new("length"));
+        }
+        if !request.service && request.scopes.is_empty() {
+            errors.add("scopes", ValidationError::new("length"));
+        }
+        if !request.redirect_uris.iter().all(|uri| Self::is_valid_redirect_uri(uri)) {
+            errors.add("redirect_uris", ValidationError::new("invalid_redirect_uri"));
+        }
+        let known_scopes = OAuthRepository::known_scopes(pool).await?;
+        if !request.scopes.iter().all(|scope| known_scopes.contains(scope)) {
+            errors.add("scopes", ValidationError::new("unknown_scope"));
+        }
+        if !errors.is_empty() {
+            return Err(AuthError::ValidationError(errors));
+        }
+
+        let client_secret = request.confidential.then(generate_opaque_token);
+        let client = OAuthRepository::create_client(pool, NewOAuthClient {
+            client_id: generate_opaque_token(),
+            client_secret_hash: client_secret.as_deref().map(hash_opaque_token),
+            name: request.name,
+            redirect_uris: request.redirect_uris,
+            allowed_scopes: request.scopes.join(" "),
+            created_by: Some(created_by),
+            is_service: request.service,
+        }).await?;
+
+        Ok(RegisterClientResponse {
+            client_id: client.client_id,
+            client_secret,
+            name: client.name,
+            redirect_uris: client.redirect_uris,
+            scopes: client.allowed_scopes.split_whitespace().map(str::to_string).collect(),
+            service: client.is_service,
+        })
+    }
+
+    /// Checks an authorization request and describes what the user is asked to approve.
+    pub async fn consent_prompt(pool: &DbPool, query: &AuthorizeQuery) -> Result<ConsentPrompt, OAuthError> {
+        let (client, scopes) = Self::validate_authorization(pool, query).await?;
+        Ok(ConsentPrompt {
+            client_id: client.client_id,
+            client_name: client.name,
+            redirect_uri: query.redirect_uri.clone(),
+            scopes,
+            state: query.state.clone(),
+        })
+    }
+
+    /// Records the user's decision. Approval issues a single-use code bound to
+    /// the PKCE challenge; either way the client learns the outcome through
+    /// its redirect URI.
+    pub async fn decide(pool: &DbPool, user_id: Uuid, decision: ConsentDecision) -> Result<AuthorizeRedirect, OAuthError> {
+        let query = decision.request;
+        let (client, scopes) = Self::validate_authorization(pool, &query).await?;
+
+        let mut params = Vec::new();
+        if decision.approve {
+            let code = generate_opaque_token();
+            OAuthRepository::create_code(pool, NewAuthorizationCode {
+                code_hash: hash_opaque_token(&code),
+                client_id: client.id,
+                user_id,
+                redirect_uri: query.redirect_uri.clone(),
+                scope: scopes.join(" "),
+                code_challenge: query.code_challenge.clone().unwrap_or_default(),
+                expires_at: (Utc::now() + Duration::seconds(AUTHORIZATION_CODE_TTL_SECONDS)).naive_utc(),
+            }).await?;
+            params.push(("code", code));
+        } else {
+            params.push(("error", OAuthError::AccessDenied.code().to_string()));
+        }
+        if let Some(state) = query.state {
+            params.push(("state", state));
+        }
+
+        let mut redirect_to = Url::parse(&query.redirect_uri)
+            .map_err(|_e| OAuthError::InvalidRequest("Invalid redirect_uri".to_string()))?;
+        redirect_to.query_pairs_mut().extend_pairs(params);
+        Ok(AuthorizeRedirect { redirect_to: redirect_to.to_string() })
+    }
+
+    pub async fn token(
+        pool: &DbPool,
+        keyring: &Keyring,
+        request: TokenRequest,
+        credentials: ClientCredentials,
+    ) -> Result<TokenResponse, OAuthError> {
+        let client = Self::authenticate_client(pool, &credentials).await?;
+
+        match request.grant_type.as_str() {
+            "authorization_code" => Self::exchange_code(pool, keyring, &client, request).await,
+            "refresh_token" => Self::refresh(pool, keyring, &client, request).await,
+            _ => Err(OAuthError::UnsupportedGrantType),
+        }
+    }
+
+    /// RFC 7636 S256: the challenge is the unpadded base64url SHA-256 of the verifier.
+    pub fn verify_pkce(code_verifier: &str, code_challenge: &str) -> bool {
+        let valid_verifier = (43..=128).contains(&code_verifier.len())
+            && code_verifier.bytes().all(|b| b.is_ascii_alphanumeric() || b"-._~".contains(&b));
+
+        valid_verifier && URL_SAFE_NO_PAD.encode(Sha256::digest(code_verifier.as_bytes())) == code_challenge
+    }
+
+    /// The scopes to grant: the requested ones if the client may have them all,
+    /// or every allowed scope when none were requested.
+    pub fn negotiate_scopes(requested: Option<&str>, allowed: &str) -> Result<Vec<String>, OAuthError> {
+        let allowed: Vec<&str> = allowed.split_whitespace().collect();
+        let mut scopes: Vec<String> = match requested.map(str::trim).filter(|scope| !scope.is_empty()) {
+            Some(requested) => requested.split_whitespace().map(str::to_string).collect(),
+            None => allowed.iter().map(|scope| scope.to_string()).collect(),
+        };
+
+        if !scopes.iter().all(|scope| allowed.contains(&scope.as_str())) {
+            return Err(OAuthError::InvalidScope);
+        }
+
+        scopes.sort();
+        scopes.dedup();
+        Ok(scopes)
+    }
+
+    async fn validate_authorization(pool: &DbPool, query: &AuthorizeQuery) -> Result<(OAuthClient, Vec<String>), OAuthError> {
+        let client = OAuthRepository::find_client(pool, &query.client_id).await?
+            .ok_or(OAuthError::InvalidClient)?;
+        if !client.redirect_uris.contains(&query.redirect_uri) {
+            return Err(OAuthError::InvalidRequest("redirect_uri is not registered for this client".to_string()));
+        }
+        if query.response_type != "code" {
+            return Err(OAuthError::UnsupportedResponseType);
+        }
+        if query.code_challenge.as_deref().is_none_or(str::is_empty) {
+            return Err(OAuthError::InvalidRequest("code_challenge is required".to_string()));
+        }
+        if query.code_challenge_method.as_deref() != Some(PKCE_METHOD) {
+            return Err(OAuthError::InvalidRequest("code_challenge_method must be S256".to_string()));
+        }
+
+        let scopes = Self::negotiate_scopes(query.scope.as_deref(), &client.allowed_scopes)?;
+        Ok((client, scopes))
+    }
+
+    pub async fn authenticate_client(pool: &DbPool, credentials: &ClientCredentials) -> Result<OAuthClient, OAuthError> {
+        let client = OAuthRepository::find_client(pool, &credentials.client_id).await?
+            .ok_or(OAuthError::InvalidClient)?;
+
+        match (&client.client_secret_hash, &credentials.client_secret) {
+            (None, None) => Ok(client),
+            (Some(expected), Some(secret)) if *expected == hash_opaque_token(secret) => Ok(client),
+            _ => Err(OAuthError::InvalidClient),
+        }
+    }
+
+    async fn exchange_code(pool: &DbPool, keyring: &Keyring, client: &OAuthClient, request: TokenRequest) -> Result<TokenResponse, OAuthError> {
+        let code = request.code.ok_or_else(|| OAuthError::InvalidRequest("code is required".to_string()))?;
+        let code_verifier = request.code_verifier
+            .ok_or_else(|| OAuthError::InvalidRequest("code_verifier is required".to_string()))?;
+
+        let grant = OAuthRepository::consume_code(pool, &hash_opaque_token(&code)).await?
+            .ok_or(OAuthError::InvalidGrant)?;
+        if grant.client_id != client.id
+            || request.redirect_uri.as_deref() != Some(grant.redirect_uri.as_str())
+            || !Self::verify_pkce(&code_verifier, &grant.code_challenge)
+        {
+            return Err(OAuthError::InvalidGrant);
+        }
+
+        Self::issue_tokens(pool, keyring, client, grant.user_id, grant.scope, Uuid::new_v4()).await
+    }
+
+    /// Rotates the refresh token like `/auth/refresh` does. The scope may be
+    /// narrowed but never widened.
+    async fn refresh(pool: &DbPool, keyring: &Keyring, client: &OAuthClient, request: TokenRequest) -> Result<TokenResponse, OAuthError> {
+        let refresh_token = request.refresh_token
+            .ok_or_else(|| OAuthError::InvalidRequest("refresh_token is required".to_string()))?;
+
+        let current = match RefreshTokenRepository::find_by_hash(pool, &hash_opaque_token(&refresh_token)).await {
+            Ok(current) if current.client_id == Some(client.id) => current,
+            Ok(_) | Err(diesel::result::Error::NotFound) => return Err(OAuthError::InvalidGrant),
+            Err(e) => return Err(e.into()),
+        };
+        if current.revoked_at.is_some() {
+            return Self::revoke_reused_family(pool, client, &current).await;
+        }
+        if current.expires_at <= Utc::now().naive_utc() {
+            return Err(OAuthError::InvalidGrant);
+        }
+
+        let granted = current.scope.clone().unwrap_or_default();
+        let scope = Self::negotiate_scopes(request.scope.as_deref(), &granted)?.join(" ");
+
+        let access_token = Self::access_token(pool, keyring, client, current.user_id, &scope).await?;
+        let (refresh_token, next) = Self::new_refresh_token(client, current.user_id, current.family_id, &scope);
+        if RefreshTokenRepository::rotate(pool, &current, next).await?.is_none() {
+            return Self::revoke_reused_family(pool, client, &current).await;
+        }
+
+        Ok(Self::token_response(access_token, refresh_token, scope))
+    }
+
+    async fn revoke_reused_family(pool: &DbPool, client: &OAuthClient, current: &RefreshToken) -> Result<TokenResponse, OAuthError> {
+        log::warn!("OAuth refresh token reuse detected, revoking token family {}", current.family_id);
+        AuditService::transaction(pool, |conn, trail| {
+            RefreshTokenRepository::revoke_family(conn, current.family_id)?;
+
+            trail.record(AuditEventType::TokenRevoked, Some(current.user_id), json!({
+                "token_type": "refresh_token",
+                "reason": "reuse_detected",
+                "client_id": client.client_id,
+            }));
+            Ok::<_, OAuthError>(())
+        }).await?;
+        Err(OAuthError::InvalidGrant)
+    }
+
+    async fn issue_tokens(
+        pool: &DbPool,
+        keyring: &Keyring,
+        client: &OAuthClient,
+        user_id: Uuid,
+        scope: String,
+        family_id: Uuid,
+    ) -> Result<TokenResponse, OAuthError> {
+        let access_token = Self::access_token(pool, keyring, client, user_id, &scope).await?;
+        let (refresh_token, new_refresh_token) = Self::new_refresh_token(client, user_id, family_id, &scope);
+        RefreshTokenRepository::create(pool, new_refresh_token).await?;
+
+        Ok(Self::token_response(access_token, refresh_token, scope))
+    }
+
+    /// An access token limited to `scope`: it carries only the user's
+    /// permissions that were also granted to the client, and no roles.
+    async fn access_token(pool: &DbPool, keyring: &Keyring, client: &OAuthClient, user_id: Uuid, scope: &str) -> Result<String, OAuthError> {
+        let user = UserRepository::find_by_id(pool, user_id).await?;
+        let (_roles, permissions) = RoleService::grants_for(pool, user_id).await?;
+        let scopes: Vec<&str> = scope.split_whitespace().collect();
+
+        let now = Utc::now();
+        let claims = Claims {
+            sub: user.id.to_string(),
+            iat: now.timestamp() as usize,
+            exp: (now + Duration::seconds(ACCESS_TOKEN_TTL_SECONDS)).timestamp() as usize,
+            iss: issuer(),
+            aud: audience(),
+            email_verified: user.is_email_verified(),
+            scope: Some(scope.to_string()),
+            roles: Vec::new(),
+            permissions: permissions.into_iter().filter(|permission| scopes.contains(&permission.as_str())).collect(),
+            client_id: Some(client.client_id.clone()),
+            sid: None,
+            jti: Some(Uuid::new_v4().to_string()),
+        };
+
+        keyring.sign(&claims).map_err(|e| {
+            log::error!("Failed to sign OAuth access token: {}", e);
+            OAuthError::ServerError
+        })
+    }
+
+    fn new_refresh_token(client: &OAuthClient, user_id: Uuid, family_id: Uuid, scope: &str) -> (String, NewRefreshToken) {
+        let refresh_token = generate_opaque_token();
+        let new_refresh_token = NewRefreshToken {
+            user_id,
+            family_id,
+            token_hash: hash_opaque_token(&refresh_token),
+            expires_at: (Utc::now() + Duration::days(REFRESH_TOKEN_TTL_DAYS)).naive_utc(),
+            client_id: Some(client.id),
+            scope: Some(scope.to_string()),
+        };
+
+        (refresh_token, new_refresh_token)
+    }
+
+    fn token_response(access_token: String, refresh_token: String, scope: String) -> TokenResponse {
+        TokenResponse {
+            access_token,
+            token_type: "Bearer".to_string(),
+            expires_in: ACCESS_TOKEN_TTL_SECONDS,
+            refresh_token,
+            scope,
+        }
+    }
+
+    /// Absolute http(s) URIs without a fragment, as RFC 6749 section 3.1.2 requires.
+    fn is_valid_redirect_uri(uri: &str) -> bool {
+        Url::parse(uri).is_ok_and(|url| matches!(url.scheme(), "http" | "https") && url.fragment().is_none())
+    }
+}
@@ -0,0 +1,273 @@
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+use chrono::{Duration, Utc};
+use diesel::result::DatabaseErrorKind;
+use jsonwebtoken::{decode, decode_header, Validation};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use sha2::{Digest, Sha256};
+use shared::auth::validator::issuer;
+use uuid::Uuid;
+use crate::config::database::DbPool;
+use crate::config::keyring::Keyring;
+use crate::config::oidc::OidcProvider;
+use crate::errors::error::AuthError;
+use crate::models::audit::AuditEventType;
+use crate::models::auth::LoginOutcome;
+use crate::models::oidc::{IdTokenClaims, NewUserIdentity, OidcCallbackQuery, ProviderTokenResponse, UserIdentity};
+use crate::models::session::ClientInfo;
+use crate::models::user::{normalize_email, NewUser, User};
+use crate::repositories::identity_repository::IdentityRepository;
+use crate::repositories::user_repository::UserRepository;
+use crate::services::audit_service::AuditService;
+use crate::services::auth_service::AuthService;
+use crate::services::role_service::{RoleService, DEFAULT_ROLE};
+use crate::utils::hash_password::hash_password;
+use crate::utils::opaque_token::generate_opaque_token;
+
+/// How long the user has to finish signing in at the provider.
+pub const OIDC_STATE_TTL_SECONDS: i64 = 10 * 60;
+const OIDC_STATE_AUDIENCE: &str = "oidc-state";
+
+/// What `start` remembers about a login until the provider redirects back.
+/// Signed with the keyring and handed to the browser, so no server-side
+/// storage is needed.
+#[derive(Serialize, Deserialize)]
+struct OidcStateClaims {
+    sub: String,
+    state: String,
+    nonce: String,
+    code_verifier: String,
+    iat: usize,
+    exp: usize,
+    iss: String,
+    aud: String,
+}
+
+pub struct OidcLoginStart {
+    /// Where to send the browser.
+    pub authorization_url: String,
+    /// Must come back with the callback, bound to the same browser.
+    pub state_token: String,
+}
+
+pub struct OidcService;
+
+impl OidcService {
+    /// Builds the provider's authorization URL with a fresh state, nonce and
+    /// PKCE verifier.
+    pub async fn start(provider: &OidcProvider, keyring: &Keyring) -> Result<OidcLoginStart, AuthError> {
+        let metadata = provider.metadata().await.map_err(|e| Self::unavailable(provider, e))?;
+        let now = Utc::now();
+        let claims = OidcStateClaims {
+            sub: provider.config.name.clone(),
+            state: generate_opaque_token(),
+            nonce: generate_opaque_token(),
+            code_verifier: generate_opaque_token(),
+            iat: now.timestamp() as usize,
+            exp: (now + Duration::seconds(OIDC_STATE_TTL_SECONDS)).timestamp() as usize,
+            iss: issuer(),
+            aud: OIDC_STATE_AUDIENCE.to_string(),
+        };
+
+        let code_challenge = URL_SAFE_NO_PAD.encode(Sha256::digest(claims.code_verifier.as_bytes()));
+        let authorization_url = url::Url::parse_with_params(&metadata.authorization_endpoint, [
+            ("response_type", "code"),
+            ("client_id", provider.config.client_id.as_str()),
+            ("redirect_uri", provider.config.redirect_uri.as_str()),
+            ("scope", provider.config.scopes.as_str()),
+            ("state", claims.state.as_str()),
+            ("nonce", claims.nonce.as_str()),
+            ("code_challenge", code_challenge.as_str()),
+            ("code_challenge_method", "S256"),
+        ]).map_err(|e| Self::unavailable(provider, e.to_string()))?;
+
+        let state_token = keyring.sign(&claims).map_err(|_e| AuthError::InternalServerError)?;
+        Ok(OidcLoginStart { authorization_url: authorization_url.to_string(), state_token })
+    }
+
+    /// Handles the provider's redirect: checks the state against the token from
+    /// `start`, redeems the code, validates the ID token and signs in the
+    /// linked user, linking or creating one on first use.
+    pub async fn callback(
+        pool: &DbPool,
+        keyring: &Keyring,
+        provider: &OidcProvider,
+        query: OidcCallbackQuery,
+        state_token: Option<&str>,
+        client: &ClientInfo,
+    ) -> Result<LoginOutcome, AuthError> {
+        if let Some(error) = &query.error {
+            log::info!("Provider {} refused the sign-in: {}", provider.config.name, error);
+            return Err(AuthError::OidcLoginDenied);
+        }
+
+        let pending = state_token
+            .and_then(|token| keyring.verify::<OidcStateClaims>(token, OIDC_STATE_AUDIENCE).ok())
+            .filter(|pending| pending.sub == provider.config.name)
+            .ok_or(AuthError::InvalidOidcState)?;
+        let (Some(code), Some(state)) = (&query.code, &query.state) else {
+            return Err(AuthError::InvalidOidcState);
+        };
+        if *state != pending.state {
+            return Err(AuthError::InvalidOidcState);
+        }
+
+        let id_token = Self::exchange_code(provider, code, &pending.code_verifier).await?;
+        let claims = Self::validate_id_token(provider, &id_token, &pending.nonce).await?;
+        let user = Self::resolve_user(pool, &provider.config.name, &claims).await?;
+
+        AuthService::sign_in(pool, keyring, &user, client).await
+    }
+
+    /// Checks the ID token's signature against the provider's published keys,
+    /// then `iss`, `aud`, `exp` and the nonce sent with the authorization request.
+    pub async fn validate_id_token(provider: &OidcProvider, id_token: &str, nonce: &str) -> Result<IdTokenClaims, AuthError> {
+        let metadata = provider.metadata().await.map_err(|e| Self::unavailable(provider, e))?;
+        let jwks = provider.jwks().await.map_err(|e| Self::unavailable(provider, e))?;
+
+        let header = decode_header(id_token).map_err(|_e| AuthError::InvalidIdToken)?;
+        let kid = header.kid.ok_or(AuthError::InvalidIdToken)?;
+        let (key, algorithm) = jwks.decoding_key(&kid).await.ok_or(AuthError::InvalidIdToken)?;
+        if header.alg != algorithm {
+            return Err(AuthError::InvalidIdToken);
+        }
+
+        let mut validation = Validation::new(algorithm);
+        validation.set_issuer(&[&metadata.issuer]);
+        validation.set_audience(&[&provider.config.client_id]);
+        validation.set_required_spec_claims(&["exp", "iat", "iss", "aud", "sub"]);
+
+        let claims = decode::<IdTokenClaims>(id_token, &key, &validation)
+            .map_err(|e| {
+                log::warn!("Rejected ID token from {}: {}", provider.config.name, e);
+                AuthError::InvalidIdToken
+            })?
+            .claims;
+
+        if claims.nonce.as_deref() != Some(nonce) {
+            return Err(AuthError::InvalidIdToken);
+        }
+        Ok(claims)
+    }
+
+    pub async fn identities(pool: &DbPool, user_id: Uuid) -> Result<Vec<UserIdentity>, AuthError> {
+        Ok(IdentityRepository::list_for_user(pool, user_id).await?)
+    }
+
+    pub async fn unlink(pool: &DbPool, user_id: Uuid, identity_id: Uuid) -> Result<(), AuthError> {
+        if IdentityRepository::delete(pool, user_id, identity_id).await? == 0 {
+            return Err(AuthError::IdentityNotFound);
+        }
+
+        AuditService::record(pool, AuditEventType::IdentityUnlinked, Some(user_id), json!({ "identity_id": identity_id })).await;
+        Ok(())
+    }
+
+    async fn exchange_code(provider: &OidcProvider, code: &str, code_verifier: &str) -> Result<String, AuthError> {
+        let metadata = provider.metadata().await.map_err(|e| Self::unavailable(provider, e))?;
+        let config = &provider.config;
+
+        let mut form = vec![
+            ("grant_type", "authorization_code"),
+            ("code", code),
+            ("redirect_uri", config.redirect_uri.as_str()),
+            ("code_verifier", code_verifier),
+        ];
+        let mut request = reqwest::Client::new().post(&metadata.token_endpoint);
+        match &config.client_secret {
+            Some(secret) => request = request.basic_auth(&config.client_id, Some(secret)),
+            None => form.push(("client_id", config.client_id.as_str())),
+        }
+
+        let response = request.form(&form).send().await
+            .map_err(|e| Self::unavailable(provider, e.to_string()))?;
+        if response.status().is_client_error() {
+            log::warn!("Provider {} refused the code exchange with {}", config.name, response.status());
+            return Err(AuthError::OidcLoginDenied);
+        }
+
+        let tokens = response.error_for_status()
+            .map_err(|e| Self::unavailable(provider, e.to_string()))?
+            .json::<ProviderTokenResponse>().await
+            .map_err(|e| Self::unavailable(provider, e.to_string()))?;
+        Ok(tokens.id_token)
+    }
+
+    /// The user behind an external identity. A first login links it to the
+    /// account with the same email, or creates one. Linking needs an email the
+    /// provider has verified and, on our side, an account whose email was
+    /// verified too; otherwise someone could register an address they do not
+    /// own and wait for its owner to sign in with a provider.
+    async fn resolve_user(pool: &DbPool, provider: &str, claims: &IdTokenClaims) -> Result<User, AuthError> {
+        if let Some(identity) = IdentityRepository::find(pool, provider, &claims.sub).await? {
+            IdentityRepository::touch(pool, identity.id, claims.email.as_deref()).await?;
+            return Ok(UserRepository::find_by_id(pool, identity.user_id).await?);
+        }
+
+        let email = match &claims.email {
+            Some(email) if claims.email_verified => normalize_email(email),
+            _ => return Err(AuthError::IdentityNotLinkable),
+        };
+        let (user, created_account) = match UserRepository::find_by_email(pool, &email).await {
+            Ok(user) if user.is_email_verified() => (user, false),
+            Ok(_) => return Err(AuthError::IdentityNotLinkable),
+            Err(diesel::result::Error::NotFound) => (Self::create_user(pool, claims, &email).await?, true),
+            Err(e) => return Err(AuthError::DatabaseError(e)),
+        };
+
+        IdentityRepository::create(pool, NewUserIdentity {
+            user_id: user.id,
+            provider: provider.to_string(),
+            subject: claims.sub.clone(),
+            email: Some(email.clone()),
+            last_login_at: Some(Utc::now().naive_utc()),
+        }).await?;
+
+        AuditService::record(pool, AuditEventType::IdentityLinked, Some(user.id), json!({
+            "provider": provider,
+            "subject": claims.sub,
+            "created_account": created_account,
+        })).await;
+        Ok(user)
+    }
+
+    /// An account for someone who has only ever signed in through a provider.
+    /// Its password is random and never shown, so signing in with a password
+    /// first needs a reset.
+    async fn create_user(pool: &DbPool, claims: &IdTokenClaims, email: &str) -> Result<User, AuthError> {
+        let password = hash_password(&generate_opaque_token()).map_err(|_e| AuthError::InternalServerError)?;
+        AuditService::transaction(pool, |conn, trail| {
+            let user = UserRepository::create(conn, NewUser {
+                username: Self::username_for(claims, email),
+                email: email.to_string(),
+                password,
+            })
+                .map_err(|e| match e {
+                    diesel::result::Error::DatabaseError(DatabaseErrorKind::UniqueViolation, _) => AuthError::EmailAlreadyExists,
+                    e => AuthError::DatabaseError(e),
+                })?;
+
+            let user = UserRepository::set_email_verified(conn, user.id, email)?;
+            RoleService::grant(conn, trail, user.id, DEFAULT_ROLE, None)?;
+            Ok(user)
+        }).await
+    }
+
+    /// The first of the provider's username, display name and the email's
+    /// local part that fits the 3 to 50 characters `NewUser` allows.
+    fn username_for(claims: &IdTokenClaims, email: &str) -> String {
+        let local_part = email.split('@').next().unwrap_or_default();
+        [claims.preferred_username.as_deref(), claims.name.as_deref(), Some(local_part)]
+            .into_iter()
+            .flatten()
+            .map(|candidate| candidate.trim().chars().take(50).collect::<String>())
+            .find(|candidate| candidate.chars().count() >= 3)
+            .unwrap_or_else(|| "member".to_string())
+    }
+
+    fn unavailable(provider: &OidcProvider, error: String) -> AuthError {
+        log::error!("Identity provider {} unavailable: {}", provider.config.name, error);
+        AuthError::IdentityProviderUnavailable
+    }
+}
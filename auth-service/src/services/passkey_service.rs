@@ -0,0 +1,365 @@
+use chrono::{DateTime, Duration, Utc};
+use diesel::result::DatabaseErrorKind;
+use diesel::pg::PgConnection;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use shared::auth::validator::issuer;
+use uuid::Uuid;
+use validator::Validate;
+use crate::config::database::DbPool;
+use crate::config::keyring::Keyring;
+use crate::config::webauthn::{AttestationConveyance, RelyingParty};
+use crate::errors::error::AuthError;
+use crate::models::audit::AuditEventType;
+use crate::models::passkey::{
+    AuthenticatorSelection, CredentialCreationOptions, CredentialDescriptor, CredentialParameters,
+    CredentialRequestOptions, FinishPasskeyLoginRequest, FinishPasskeyRegistrationRequest, NewPasskey, Passkey,
+    PasskeyCeremony, PasskeyResponse, RelyingPartyEntity, StartPasskeyLoginRequest, UserEntity,
+};
+use crate::models::revoked_token::NewRevokedToken;
+use crate::models::user::User;
+use crate::repositories::passkey_repository::PasskeyRepository;
+use crate::repositories::revoked_token_repository::RevokedTokenRepository;
+use crate::repositories::user_repository::UserRepository;
+use crate::services::audit_service::AuditService;
+use crate::utils::opaque_token::generate_opaque_token;
+use crate::utils::webauthn::{
+    self, AttestationObject, AuthenticatorData, ClientData, PublicKey, WebAuthnError, ALG_EDDSA, ALG_ES256,
+    FLAG_BACKED_UP, FLAG_BACKUP_ELIGIBLE, FLAG_USER_PRESENT, FLAG_USER_VERIFIED,
+};
+
+pub const PASSKEY_CEREMONY_TTL_SECONDS: i64 = 5 * 60;
+const REGISTRATION_AUDIENCE: &str = "passkey-registration";
+const AUTHENTICATION_AUDIENCE: &str = "passkey-authentication";
+const DEFAULT_PASSKEY_NAME: &str = "Passkey";
+const PUBLIC_KEY_CREDENTIAL: &str = "public-key";
+
+/// The user a passkey assertion signs in.
+pub struct PasskeyLogin {
+    pub user: User,
+    /// Whether the authenticator verified the user with a PIN or biometrics,
+    /// rather than only checking someone was present.
+    pub user_verified: bool,
+}
+
+/// The challenge of a ceremony in progress. Signed with the keyring and
+/// handed to the client, so no server-side storage is needed until it is
+/// answered; the `jti` is then stored with the revoked tokens, so each
+/// challenge can only be answered once.
+#[derive(Serialize, Deserialize)]
+struct CeremonyClaims {
+    /// The user registering a passkey, or signing in when they gave their email.
+    sub: Option<String>,
+    jti: String,
+    challenge: String,
+    iat: usize,
+    exp: usize,
+    iss: String,
+    aud: String,
+}
+
+pub struct PasskeyService;
+
+impl PasskeyService {
+    /// Options for `navigator.credentials.create()`: a discoverable credential
+    /// for this relying party, bound to the user's id.
+    pub async fn start_registration(
+        pool: &DbPool,
+        keyring: &Keyring,
+        user_id: Uuid,
+    ) -> Result<PasskeyCeremony<CredentialCreationOptions>, AuthError> {
+        let rp = RelyingParty::from_env();
+        let user = UserRepository::find_by_id(pool, user_id).await?;
+        let existing = PasskeyRepository::list_for_user(pool, user_id).await?;
+        let (ceremony_token, challenge) = Self::begin(keyring, Some(user_id), REGISTRATION_AUDIENCE)?;
+
+        Ok(PasskeyCeremony {
+            ceremony_token,
+            public_key: CredentialCreationOptions {
+                rp: RelyingPartyEntity { id: rp.id.clone(), name: rp.name.clone() },
+                user: UserEntity {
+                    id: webauthn::encode(user.id.as_bytes()),
+                    name: user.email.clone(),
+                    display_name: user.username.clone(),
+                },
+                challenge,
+                pub_key_cred_params: [ALG_ES256, ALG_EDDSA].into_iter()
+                    .map(|alg| CredentialParameters { kind: PUBLIC_KEY_CREDENTIAL, alg })
+                    .collect(),
+                timeout: PASSKEY_CEREMONY_TTL_SECONDS * 1000,
+                attestation: rp.attestation,
+                authenticator_selection: AuthenticatorSelection {
+                    resident_key: "required",
+                    require_resident_key: true,
+                    user_verification: rp.user_verification(),
+                },
+                exclude_credentials: existing.into_iter().map(Self::descriptor).collect(),
+            },
+        })
+    }
+
+    /// Verifies the new credential against the challenge from
+    /// `start_registration` and stores it.
+    pub async fn finish_registration(
+        pool: &DbPool,
+        keyring: &Keyring,
+        user_id: Uuid,
+        request: FinishPasskeyRegistrationRequest,
+    ) -> Result<PasskeyResponse, AuthError> {
+        request.validate()?;
+        let rp = RelyingParty::from_env();
+        let claims = Self::verify_ceremony(keyring, &request.ceremony_token, REGISTRATION_AUDIENCE)?;
+        if claims.sub != Some(user_id.to_string()) {
+            return Err(AuthError::InvalidPasskeyCeremony);
+        }
+
+        let credential = request.credential;
+        let client_data_json = webauthn::decode(&credential.response.client_data_json, "client data").map_err(Self::rejected)?;
+        let attestation_object = webauthn::decode(&credential.response.attestation_object, "attestation object")
+            .and_then(|bytes| AttestationObject::parse(&bytes))
+            .map_err(Self::rejected)?;
+        let auth_data = AuthenticatorData::parse(&attestation_object.auth_data).map_err(Self::rejected)?;
+
+        Self::check_client_data(&rp, &claims, &client_data_json, "webauthn.create")?;
+        Self::check_authenticator_data(&rp, &auth_data)?;
+        let attested = auth_data.attested_credential.as_ref().ok_or(AuthError::PasskeyVerificationFailed)?;
+        let credential_id = webauthn::encode(&attested.credential_id);
+        if credential.kind != PUBLIC_KEY_CREDENTIAL || credential.id.trim_end_matches('=') != credential_id {
+            return Err(AuthError::PasskeyVerificationFailed);
+        }
+        let public_key = PublicKey::from_cose(&attested.public_key).map_err(Self::rejected)?;
+        Self::check_attestation(&rp, &attestation_object, &public_key, &client_data_json)?;
+
+        let new_passkey = NewPasskey {
+            user_id,
+            credential_id,
+            public_key: attested.public_key.clone(),
+            algorithm: public_key.algorithm() as i32,
+            sign_count: i64::from(auth_data.sign_count),
+            aaguid: Uuid::from_bytes(attested.aaguid),
+            attestation_format: attestation_object.format.clone(),
+            transports: credential.response.transports,
+            backup_eligible: auth_data.has(FLAG_BACKUP_ELIGIBLE),
+            backed_up: auth_data.has(FLAG_BACKED_UP),
+            name: request.name.unwrap_or_else(|| DEFAULT_PASSKEY_NAME.to_string()),
+        };
+
+        AuditService::transaction(pool, |conn, trail| {
+            Self::spend_ceremony(conn, &claims)?;
+            let passkey = PasskeyRepository::create(conn, new_passkey).map_err(|e| match e {
+                diesel::result::Error::DatabaseError(DatabaseErrorKind::UniqueViolation, _) => AuthError::PasskeyAlreadyRegistered,
+                e => AuthError::DatabaseError(e),
+            })?;
+
+            trail.record(AuditEventType::PasskeyRegistered, Some(user_id), json!({
+                "passkey_id": passkey.id,
+                "name": passkey.name,
+                "aaguid": passkey.aaguid,
+                "attestation_format": passkey.attestation_format,
+            }));
+            Ok(PasskeyResponse::from(passkey))
+        }).await
+    }
+
+    pub async fn list(pool: &DbPool, user_id: Uuid) -> Result<Vec<PasskeyResponse>, AuthError> {
+        let passkeys = PasskeyRepository::list_for_user(pool, user_id).await?;
+        Ok(passkeys.into_iter().map(PasskeyResponse::from).collect())
+    }
+
+    pub async fn remove(pool: &DbPool, user_id: Uuid, passkey_id: Uuid) -> Result<(), AuthError> {
+        AuditService::transaction(pool, |conn, trail| {
+            if PasskeyRepository::delete(conn, user_id, passkey_id)? == 0 {
+                return Err(AuthError::PasskeyNotFound);
+            }
+
+            trail.record(AuditEventType::PasskeyRemoved, Some(user_id), json!({ "passkey_id": passkey_id }));
+            Ok(())
+        }).await
+    }
+
+    /// Options for `navigator.credentials.get()`. With an email, only that
+    /// account's passkeys are allowed; an unknown email gets the same empty
+    /// list as no email at all, so the response does not reveal which
+    /// accounts exist.
+    pub async fn start_login(
+        pool: &DbPool,
+        keyring: &Keyring,
+        request: &StartPasskeyLoginRequest,
+    ) -> Result<PasskeyCeremony<CredentialRequestOptions>, AuthError> {
+        let rp = RelyingParty::from_env();
+        let user = match &request.email {
+            Some(email) => match UserRepository::find_by_email(pool, email).await {
+                Ok(user) => Some(user),
+                Err(diesel::result::Error::NotFound) => None,
+                Err(e) => return Err(AuthError::DatabaseError(e)),
+            },
+            None => None,
+        };
+        let allowed = match &user {
+            Some(user) => PasskeyRepository::list_for_user(pool, user.id).await?,
+            None => Vec::new(),
+        };
+        let (ceremony_token, challenge) = Self::begin(keyring, user.map(|user| user.id), AUTHENTICATION_AUDIENCE)?;
+
+        Ok(PasskeyCeremony {
+            ceremony_token,
+            public_key: CredentialRequestOptions {
+                challenge,
+                rp_id: rp.id.clone(),
+                timeout: PASSKEY_CEREMONY_TTL_SECONDS * 1000,
+                user_verification: rp.user_verification(),
+                allow_credentials: allowed.into_iter().map(Self::descriptor).collect(),
+            },
+        })
+    }
+
+    /// Verifies an assertion against the challenge from `start_login` and
+    /// returns the user it signs in. The signature counter has to go up on
+    /// every use, so a cloned authenticator is refused once the original
+    /// has been used.
+    pub async fn finish_login(
+        pool: &DbPool,
+        keyring: &Keyring,
+        request: FinishPasskeyLoginRequest,
+    ) -> Result<PasskeyLogin, AuthError> {
+        let rp = RelyingParty::from_env();
+        let claims = Self::verify_ceremony(keyring, &request.ceremony_token, AUTHENTICATION_AUDIENCE)?;
+
+        let credential = request.credential;
+        if credential.kind != PUBLIC_KEY_CREDENTIAL {
+            return Err(AuthError::PasskeyVerificationFailed);
+        }
+        let passkey = PasskeyRepository::find_by_credential_id(pool, credential.id.trim_end_matches('=')).await?
+            .ok_or(AuthError::PasskeyVerificationFailed)?;
+        if claims.sub.as_ref().is_some_and(|sub| *sub != passkey.user_id.to_string()) {
+            return Err(AuthError::PasskeyVerificationFailed);
+        }
+        if let Some(user_handle) = &credential.response.user_handle {
+            let user_handle = webauthn::decode(user_handle, "user handle").map_err(Self::rejected)?;
+            if user_handle != passkey.user_id.as_bytes() {
+                return Err(AuthError::PasskeyVerificationFailed);
+            }
+        }
+
+        let client_data_json = webauthn::decode(&credential.response.client_data_json, "client data").map_err(Self::rejected)?;
+        let auth_data_bytes = webauthn::decode(&credential.response.authenticator_data, "authenticator data").map_err(Self::rejected)?;
+        let signature = webauthn::decode(&credential.response.signature, "signature").map_err(Self::rejected)?;
+        let auth_data = AuthenticatorData::parse(&auth_data_bytes).map_err(Self::rejected)?;
+
+        Self::check_client_data(&rp, &claims, &client_data_json, "webauthn.get")?;
+        Self::check_authenticator_data(&rp, &auth_data)?;
+        PublicKey::from_cose(&passkey.public_key)
+            .and_then(|public_key| public_key.verify(&webauthn::signed_message(&auth_data_bytes, &client_data_json), &signature))
+            .map_err(Self::rejected)?;
+
+        let sign_count = i64::from(auth_data.sign_count);
+        AuditService::transaction(pool, |conn, _trail| {
+            Self::spend_ceremony(conn, &claims)?;
+            if PasskeyRepository::record_use(conn, passkey.id, sign_count, auth_data.has(FLAG_BACKED_UP))? == 0 {
+                log::warn!(
+                    "Passkey {} presented signature counter {} after {}, possibly cloned",
+                    passkey.id, sign_count, passkey.sign_count,
+                );
+                return Err(AuthError::PasskeyVerificationFailed);
+            }
+            Ok(())
+        }).await?;
+
+        Ok(PasskeyLogin {
+            user: UserRepository::find_by_id(pool, passkey.user_id).await?,
+            user_verified: auth_data.has(FLAG_USER_VERIFIED),
+        })
+    }
+
+    /// A signed ceremony token and its challenge.
+    fn begin(keyring: &Keyring, user_id: Option<Uuid>, audience: &str) -> Result<(String, String), AuthError> {
+        let now = Utc::now();
+        let claims = CeremonyClaims {
+            sub: user_id.map(|user_id| user_id.to_string()),
+            jti: Uuid::new_v4().to_string(),
+            challenge: generate_opaque_token(),
+            iat: now.timestamp() as usize,
+            exp: (now + Duration::seconds(PASSKEY_CEREMONY_TTL_SECONDS)).timestamp() as usize,
+            iss: issuer(),
+            aud: audience.to_string(),
+        };
+
+        let token = keyring.sign(&claims).map_err(|_e| AuthError::InternalServerError)?;
+        Ok((token, claims.challenge))
+    }
+
+    fn verify_ceremony(keyring: &Keyring, token: &str, audience: &str) -> Result<CeremonyClaims, AuthError> {
+        keyring.verify::<CeremonyClaims>(token, audience).map_err(|_e| AuthError::InvalidPasskeyCeremony)
+    }
+
+    /// Marks the ceremony as answered, failing if it already was.
+    fn spend_ceremony(conn: &mut PgConnection, claims: &CeremonyClaims) -> Result<(), AuthError> {
+        let expires_at = DateTime::from_timestamp(claims.exp as i64, 0)
+            .map(|expires_at| expires_at.naive_utc())
+            .ok_or(AuthError::InvalidPasskeyCeremony)?;
+        let spent = RevokedTokenRepository::create(conn, NewRevokedToken { jti: claims.jti.clone(), expires_at })?;
+        if spent == 0 {
+            return Err(AuthError::InvalidPasskeyCeremony);
+        }
+        Ok(())
+    }
+
+    fn check_client_data(rp: &RelyingParty, claims: &CeremonyClaims, client_data_json: &[u8], ceremony: &str) -> Result<(), AuthError> {
+        let client_data = ClientData::parse(client_data_json).map_err(Self::rejected)?;
+        if client_data.ceremony != ceremony || client_data.challenge.trim_end_matches('=') != claims.challenge {
+            return Err(AuthError::InvalidPasskeyCeremony);
+        }
+        if client_data.cross_origin || !rp.allows_origin(&client_data.origin) {
+            log::warn!("Passkey ceremony from unexpected origin {}", client_data.origin);
+            return Err(AuthError::PasskeyVerificationFailed);
+        }
+        Ok(())
+    }
+
+    fn check_authenticator_data(rp: &RelyingParty, auth_data: &AuthenticatorData) -> Result<(), AuthError> {
+        let verified = !rp.require_user_verification || auth_data.has(FLAG_USER_VERIFIED);
+        if auth_data.rp_id_hash != webauthn::sha256(rp.id.as_bytes()) || !auth_data.has(FLAG_USER_PRESENT) || !verified {
+            return Err(AuthError::PasskeyVerificationFailed);
+        }
+        Ok(())
+    }
+
+    /// Packed self-attestation, signed by the new credential itself, is
+    /// always checked. Certificate chains are not, so attestation that relies
+    /// on one, or none at all, is refused when `WEBAUTHN_ATTESTATION` is
+    /// `direct` and accepted unverified otherwise.
+    fn check_attestation(
+        rp: &RelyingParty,
+        attestation: &AttestationObject,
+        public_key: &PublicKey,
+        client_data_json: &[u8],
+    ) -> Result<(), AuthError> {
+        if attestation.format == "packed" && attestation.statement_field("x5c").is_none() {
+            let alg = attestation.statement_field("alg").and_then(|alg| alg.as_integer()).map(i128::from);
+            let signature = attestation.statement_field("sig").and_then(|sig| sig.as_bytes());
+            let Some(signature) = signature.filter(|_| alg == Some(i128::from(public_key.algorithm()))) else {
+                return Err(AuthError::PasskeyAttestationRejected);
+            };
+            return public_key.verify(&webauthn::signed_message(&attestation.auth_data, client_data_json), signature)
+                .map_err(|_e| AuthError::PasskeyAttestationRejected);
+        }
+
+        if rp.attestation == AttestationConveyance::Direct {
+            return Err(AuthError::PasskeyAttestationRejected);
+        }
+        Ok(())
+    }
+
+    fn descriptor(passkey: Passkey) -> CredentialDescriptor {
+        CredentialDescriptor {
+            kind: PUBLIC_KEY_CREDENTIAL,
+            id: passkey.credential_id,
+            transports: passkey.transports,
+        }
+    }
+
+    fn rejected(e: WebAuthnError) -> AuthError {
+        log::debug!("Passkey rejected: {}", e);
+        AuthError::PasskeyVerificationFailed
+    }
+}
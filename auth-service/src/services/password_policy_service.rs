@@ -0,0 +1,63 @@
+use diesel::pg::PgConnection;
+use shared::password::{PasswordContext, PasswordPolicy};
+use validator::Validate;
+use crate::config::database::DbPool;
+use crate::errors::error::AuthError;
+use crate::models::password_history::NewPasswordHistory;
+use crate::models::user::{NewUser, User};
+use crate::repositories::password_history_repository::PasswordHistoryRepository;
+use crate::utils::verify_password::verify_password;
+
+pub const PASSWORD_FIELD: &str = "password";
+
+/// Applies the shared `PasswordPolicy`, configured from the environment, to
+/// the passwords auth-service takes, and keeps the history it checks reuse
+/// against.
+pub struct PasswordPolicyService;
+
+impl PasswordPolicyService {
+    /// Validates a sign-up: the `NewUser` field rules and the password policy,
+    /// reported together.
+    pub fn validate_new_user(new_user: &NewUser) -> Result<(), AuthError> {
+        let mut errors = new_user.validate().err().unwrap_or_default();
+        let context = PasswordContext::new().with_username(&new_user.username).with_email(&new_user.email);
+        PasswordPolicy::from_env().validate_field(&mut errors, PASSWORD_FIELD, &new_user.password, &context);
+
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(AuthError::ValidationError(errors))
+        }
+    }
+
+    /// Validates a new password for an existing user, refusing the current
+    /// password and the ones in its history.
+    pub async fn validate_new_password(pool: &DbPool, user: &User, password: &str) -> Result<(), AuthError> {
+        let policy = PasswordPolicy::from_env();
+        let mut history = vec![user.password.clone()];
+        if policy.history_size > 1 {
+            history.extend(PasswordHistoryRepository::recent(pool, user.id, policy.history_size - 1).await?);
+        }
+
+        let context = PasswordContext::new()
+            .with_username(&user.username)
+            .with_email(&user.email)
+            .with_history(&history, verify_password);
+        policy.validate(PASSWORD_FIELD, password, &context)?;
+        Ok(())
+    }
+
+    /// Keeps the hash `user` had before a password change, and forgets the
+    /// ones the policy no longer looks at. Runs in the change's transaction.
+    pub fn remember_previous(conn: &mut PgConnection, user: &User) -> Result<(), diesel::result::Error> {
+        let keep = PasswordPolicy::from_env().history_size.saturating_sub(1);
+        if keep > 0 {
+            PasswordHistoryRepository::create(conn, NewPasswordHistory {
+                user_id: user.id,
+                password_hash: user.password.clone(),
+            })?;
+        }
+        PasswordHistoryRepository::prune(conn, user.id, keep)?;
+        Ok(())
+    }
+}
@@ -0,0 +1,97 @@
+use std::env;
+use std::sync::Arc;
+use chrono::{Duration, Utc};
+use serde_json::json;
+use crate::config::database::DbPool;
+use crate::errors::error::AuthError;
+use crate::mailer::{Email, Mailer};
+use crate::models::audit::AuditEventType;
+use crate::models::password_reset_token::NewPasswordResetToken;
+use crate::repositories::password_reset_repository::PasswordResetRepository;
+use crate::repositories::refresh_token_repository::RefreshTokenRepository;
+use crate::repositories::user_repository::UserRepository;
+use crate::services::audit_service::AuditService;
+use crate::services::password_policy_service::PasswordPolicyService;
+use crate::utils::hash_password::hash_password;
+use crate::utils::opaque_token::{generate_opaque_token, hash_opaque_token};
+
+pub const RESET_TOKEN_TTL_MINUTES: i64 = 30;
+const DEFAULT_RESET_URL: &str = "http://localhost:3000/reset-password";
+
+pub struct PasswordResetService;
+
+impl PasswordResetService {
+    /// Emails a single-use reset link when `email` belongs to an account and
+    /// silently does nothing otherwise. Callers run this off the request path
+    /// so the response never depends on whether the account exists.
+    pub async fn request_reset(pool: &DbPool, mailer: Arc<dyn Mailer>, email: &str) {
+        let user = match UserRepository::find_by_email(pool, email).await {
+            Ok(user) => user,
+            Err(diesel::result::Error::NotFound) => return,
+            Err(e) => {
+                log::error!("Failed to look up user for password reset: {}", e);
+                return;
+            }
+        };
+
+        let token = generate_opaque_token();
+        let new_token = NewPasswordResetToken {
+            user_id: user.id,
+            token_hash: hash_opaque_token(&token),
+            expires_at: (Utc::now() + Duration::minutes(RESET_TOKEN_TTL_MINUTES)).naive_utc(),
+        };
+
+        if let Err(e) = PasswordResetRepository::create(pool, new_token).await {
+            log::error!("Failed to store password reset token: {}", e);
+            return;
+        }
+
+        let reset_url = env::var("PASSWORD_RESET_URL").unwrap_or_else(|_| DEFAULT_RESET_URL.to_string());
+        let email = Email {
+            to: user.email,
+            subject: "Reset your password".to_string(),
+            body: format!(
+                "Hi {},\n\nUse the link below to choose a new password. It expires in {} minutes.\n\n{}?token={}\n\nIf you did not ask for this, you can ignore this email.\n",
+                user.username, RESET_TOKEN_TTL_MINUTES, reset_url, token,
+            ),
+        };
+
+        if let Err(e) = mailer.send(email).await {
+            log::error!("Failed to send password reset email: {}", e);
+            return;
+        }
+
+        AuditService::record(pool, AuditEventType::PasswordResetRequested, Some(user.id), json!({})).await;
+    }
+
+    /// Sets a new password with a reset token. The password is checked
+    /// against the policy before the token is used up, so a refused password
+    /// can be retried with the same link.
+    pub async fn reset_password(pool: &DbPool, token: &str, new_password: &str) -> Result<(), AuthError> {
+        let token_hash = hash_opaque_token(token);
+        let reset_token = match PasswordResetRepository::find_valid(pool, &token_hash).await {
+            Ok(reset_token) => reset_token,
+            Err(diesel::result::Error::NotFound) => return Err(AuthError::InvalidResetToken),
+            Err(e) => return Err(AuthError::DatabaseError(e)),
+        };
+        let user = UserRepository::find_by_id(pool, reset_token.user_id).await?;
+        PasswordPolicyService::validate_new_password(pool, &user, new_password).await?;
+
+        let password_hash = hash_password(new_password).map_err(|_e| AuthError::InternalServerError)?;
+        AuditService::transaction(pool, |conn, trail| {
+            let reset_token = match PasswordResetRepository::consume(conn, &token_hash) {
+                Ok(reset_token) => reset_token,
+                Err(diesel::result::Error::NotFound) => return Err(AuthError::InvalidResetToken),
+                Err(e) => return Err(AuthError::DatabaseError(e)),
+            };
+
+            UserRepository::set_password(conn, reset_token.user_id, &password_hash)?;
+            PasswordPolicyService::remember_previous(conn, &user)?;
+            PasswordResetRepository::invalidate_all_for_user(conn, reset_token.user_id)?;
+            let revoked = RefreshTokenRepository::revoke_all_for_user(conn, reset_token.user_id)?;
+
+            trail.record(AuditEventType::PasswordReset, Some(reset_token.user_id), json!({ "revoked_refresh_tokens": revoked }));
+            Ok(())
+        }).await
+    }
+}
@@ -0,0 +1,146 @@
+use async_trait::async_trait;
+use chrono::{Duration, Utc};
+use serde_json::json;
+use shared::auth::personal_access_token::PERSONAL_ACCESS_TOKEN_PREFIX;
+use shared::auth::validator::{audience, issuer};
+use shared::auth::{AuthError as TokenError, Claims, PersonalAccessTokenResolver};
+use uuid::Uuid;
+use validator::{Validate, ValidationError, ValidationErrors};
+use crate::config::database::DbPool;
+use crate::errors::error::AuthError;
+use crate::models::audit::AuditEventType;
+use crate::models::personal_access_token::{
+    CreatePersonalAccessTokenRequest, CreatedPersonalAccessToken, NewPersonalAccessToken, PersonalAccessToken,
+};
+use crate::repositories::personal_access_token_repository::PersonalAccessTokenRepository;
+use crate::repositories::user_repository::UserRepository;
+use crate::services::audit_service::AuditService;
+use crate::services::role_service::RoleService;
+use crate::utils::opaque_token::{generate_opaque_token, hash_opaque_token};
+
+pub const DEFAULT_TOKEN_TTL_DAYS: i64 = 30;
+const TOKEN_HINT_LENGTH: usize = 4;
+/// `last_used_at` is only kept to the minute.
+const LAST_USED_RESOLUTION_SECONDS: i64 = 60;
+
+pub struct PersonalAccessTokenService;
+
+impl PersonalAccessTokenService {
+    /// Creates a token limited to `scopes`, which must all be permissions the
+    /// user holds. The plaintext is returned here and never again.
+    pub async fn create(
+        pool: &DbPool,
+        user_id: Uuid,
+        request: CreatePersonalAccessTokenRequest,
+    ) -> Result<CreatedPersonalAccessToken, AuthError> {
+        request.validate()?;
+
+        let (_roles, permissions) = RoleService::grants_for(pool, user_id).await?;
+        if !request.scopes.iter().all(|scope| permissions.contains(scope)) {
+            let mut errors = ValidationErrors::new();
+            errors.add("scopes", ValidationError::new("scope_not_granted"));
+            return Err(AuthError::ValidationError(errors));
+        }
+
+        let mut scopes = request.scopes;
+        scopes.sort();
+        scopes.dedup();
+
+        let token = format!("{}{}", PERSONAL_ACCESS_TOKEN_PREFIX, generate_opaque_token());
+        let ttl_days = request.expires_in_days.unwrap_or(DEFAULT_TOKEN_TTL_DAYS);
+        let details = PersonalAccessTokenRepository::create(pool, NewPersonalAccessToken {
+            user_id,
+            name: request.name,
+            token_hash: hash_opaque_token(&token),
+            token_hint: token[token.len() - TOKEN_HINT_LENGTH..].to_string(),
+            scopes: scopes.join(" "),
+            expires_at: (Utc::now() + Duration::days(ttl_days)).naive_utc(),
+        }).await?;
+
+        AuditService::record(pool, AuditEventType::PersonalAccessTokenCreated, Some(user_id), json!({
+            "token_id": details.id,
+            "name": details.name,
+            "scopes": details.scopes,
+            "expires_at": details.expires_at,
+        })).await;
+
+        Ok(CreatedPersonalAccessToken { token, details })
+    }
+
+    pub async fn list(pool: &DbPool, user_id: Uuid) -> Result<Vec<PersonalAccessToken>, AuthError> {
+        Ok(PersonalAccessTokenRepository::list_for_user(pool, user_id).await?)
+    }
+
+    pub async fn revoke(pool: &DbPool, user_id: Uuid, token_id: Uuid) -> Result<(), AuthError> {
+        AuditService::transaction(pool, |conn, trail| {
+            if PersonalAccessTokenRepository::revoke(conn, user_id, token_id)? == 0 {
+                return Err(AuthError::PersonalAccessTokenNotFound);
+            }
+
+            trail.record(AuditEventType::PersonalAccessTokenRevoked, Some(user_id), json!({ "token_id": token_id }));
+            Ok(())
+        }).await
+    }
+
+    /// The claims a request made with `token` carries. Permissions are the
+    /// user's current ones narrowed to the token's scopes, so losing a role
+    /// also takes it away from existing tokens.
+    pub async fn resolve(pool: &DbPool, token: &str) -> Result<Claims, TokenError> {
+        let stored = PersonalAccessTokenRepository::find_by_hash(pool, &hash_opaque_token(token)).await
+            .map_err(Self::lookup_failed)?
+            .filter(|stored| stored.revoked_at.is_none())
+            .ok_or(TokenError::InvalidToken)?;
+        if stored.expires_at <= Utc::now().naive_utc() {
+            return Err(TokenError::ExpiredToken);
+        }
+
+        let user = UserRepository::find_by_id(pool, stored.user_id).await.map_err(Self::lookup_failed)?;
+        let (_roles, permissions) = RoleService::grants_for(pool, user.id).await.map_err(|e| {
+            log::error!("Failed to load permissions for personal access token: {}", e);
+            TokenError::InvalidToken
+        })?;
+        let scopes: Vec<&str> = stored.scopes.split_whitespace().collect();
+
+        PersonalAccessTokenRepository::touch(pool, stored.id, Duration::seconds(LAST_USED_RESOLUTION_SECONDS)).await
+            .map_err(Self::lookup_failed)?;
+
+        Ok(Claims {
+            sub: user.id.to_string(),
+            iat: stored.created_at.and_utc().timestamp() as usize,
+            exp: stored.expires_at.and_utc().timestamp() as usize,
+            iss: issuer(),
+            aud: audience(),
+            email_verified: user.is_email_verified(),
+            scope: Some(stored.scopes.clone()),
+            roles: Vec::new(),
+            permissions: permissions.into_iter().filter(|permission| scopes.contains(&permission.as_str())).collect(),
+            client_id: None,
+            token_id: Some(stored.id.to_string()),
+            sid: None,
+            jti: None,
+        })
+    }
+
+    fn lookup_failed(e: diesel::result::Error) -> TokenError {
+        log::error!("Failed to look up personal access token: {}", e);
+        TokenError::InvalidToken
+    }
+}
+
+/// Lets `TokenValidator` accept personal access tokens, straight from the database.
+pub struct DatabasePersonalAccessTokens {
+    pool: DbPool,
+}
+
+impl DatabasePersonalAccessTokens {
+    pub fn new(pool: DbPool) -> DatabasePersonalAccessTokens {
+        DatabasePersonalAccessTokens { pool }
+    }
+}
+
+#[async_trait]
+impl PersonalAccessTokenResolver for DatabasePersonalAccessTokens {
+    async fn resolve(&self, token: &str) -> Result<Claims, TokenError> {
+        PersonalAccessTokenService::resolve(&self.pool, token).await
+    }
+}
@@ -0,0 +1,110 @@
+use std::env;
+use diesel::pg::PgConnection;
+use diesel::result::DatabaseErrorKind;
+use serde_json::json;
+use uuid::Uuid;
+use crate::config::database::DbPool;
+use crate::errors::error::AuthError;
+use crate::models::audit::AuditEventType;
+use crate::models::role::{NewUserRole, Role, RoleResponse, UserRolesResponse};
+use crate::repositories::role_repository::RoleRepository;
+use crate::repositories::user_repository::UserRepository;
+use crate::services::audit_service::{AuditService, AuditTrail};
+
+/// Given to every account at registration.
+pub const DEFAULT_ROLE: &str = "candidate";
+pub const ADMIN_ROLE: &str = "admin";
+
+pub struct RoleService;
+
+impl RoleService {
+    /// The role and permission names that go into the user's access token.
+    pub async fn grants_for(pool: &DbPool, user_id: Uuid) -> Result<(Vec<String>, Vec<String>), AuthError> {
+        let roles = RoleRepository::role_names_for_user(pool, user_id).await?;
+        let permissions = RoleRepository::permission_names_for_user(pool, user_id).await?;
+        Ok((roles, permissions))
+    }
+
+    pub async fn list_roles(pool: &DbPool) -> Result<Vec<RoleResponse>, AuthError> {
+        let roles = RoleRepository::list_with_permissions(pool).await?;
+        Ok(roles.into_iter()
+            .map(|(role, permissions)| RoleResponse { name: role.name, description: role.description, permissions })
+            .collect())
+    }
+
+    pub async fn user_roles(pool: &DbPool, user_id: Uuid) -> Result<UserRolesResponse, AuthError> {
+        UserRepository::find_by_id(pool, user_id).await.map_err(|e| match e {
+            diesel::result::Error::NotFound => AuthError::UserNotFound,
+            e => AuthError::DatabaseError(e),
+        })?;
+
+        let (roles, permissions) = Self::grants_for(pool, user_id).await?;
+        Ok(UserRolesResponse { user_id, roles, permissions })
+    }
+
+    /// Gives `role` to the user. Tokens issued before the change keep their old
+    /// claims until they expire.
+    pub async fn assign(pool: &DbPool, user_id: Uuid, role: &str, assigned_by: Option<Uuid>) -> Result<(), AuthError> {
+        AuditService::transaction(pool, |conn, trail| Self::grant(conn, trail, user_id, role, assigned_by)).await
+    }
+
+    /// `assign` inside the caller's transaction, so a new account and its
+    /// default role are committed together.
+    pub fn grant(
+        conn: &mut PgConnection,
+        trail: &mut AuditTrail,
+        user_id: Uuid,
+        role: &str,
+        assigned_by: Option<Uuid>,
+    ) -> Result<(), AuthError> {
+        let role = Self::find_role(conn, role)?;
+        let assigned = RoleRepository::assign(conn, NewUserRole { user_id, role_id: role.id })
+            .map_err(|e| match e {
+                diesel::result::Error::DatabaseError(DatabaseErrorKind::ForeignKeyViolation, _) => AuthError::UserNotFound,
+                e => AuthError::DatabaseError(e),
+            })?;
+
+        if assigned {
+            trail.record(AuditEventType::RoleAssigned, Some(user_id), json!({
+                "role": role.name,
+                "assigned_by": assigned_by,
+            }));
+        }
+        Ok(())
+    }
+
+    pub async fn revoke(pool: &DbPool, user_id: Uuid, role: &str, revoked_by: Uuid) -> Result<(), AuthError> {
+        let conn = &mut pool.get().expect("Failed to get DB connection from pool");
+        let role = Self::find_role(conn, role)?;
+        if RoleRepository::revoke(pool, user_id, role.id).await? > 0 {
+            AuditService::record(pool, AuditEventType::RoleRevoked, Some(user_id), json!({
+                "role": role.name,
+                "revoked_by": revoked_by,
+            })).await;
+        }
+        Ok(())
+    }
+
+    /// Makes the existing accounts listed in `BOOTSTRAP_ADMIN_EMAILS` admins,
+    /// so a fresh deployment has someone who can assign roles.
+    pub async fn bootstrap_admins(pool: &DbPool) {
+        let emails = env::var("BOOTSTRAP_ADMIN_EMAILS").unwrap_or_default();
+        for email in emails.split(',').map(str::trim).filter(|email| !email.is_empty()) {
+            let result = match UserRepository::find_by_email(pool, email).await {
+                Ok(user) => Self::assign(pool, user.id, ADMIN_ROLE, None).await,
+                Err(e) => Err(AuthError::DatabaseError(e)),
+            };
+
+            if let Err(e) = result {
+                log::warn!("Could not make {} an admin: {}", email, e);
+            }
+        }
+    }
+
+    fn find_role(conn: &mut PgConnection, name: &str) -> Result<Role, AuthError> {
+        RoleRepository::find_by_name(conn, name).map_err(|e| match e {
+            diesel::result::Error::NotFound => AuthError::RoleNotFound,
+            e => AuthError::DatabaseError(e),
+        })
+    }
+}
@@ -0,0 +1,98 @@
+use chrono::Duration;
+use serde_json::json;
+use shared::auth::AuthError as TokenError;
+use uuid::Uuid;
+use crate::config::database::DbPool;
+use crate::errors::error::AuthError;
+use crate::models::audit::AuditEventType;
+use crate::models::session::{ClientInfo, NewSession, Session, SessionResponse};
+use crate::models::user::User;
+use crate::repositories::refresh_token_repository::RefreshTokenRepository;
+use crate::repositories::session_repository::SessionRepository;
+use crate::services::audit_service::AuditService;
+
+/// `last_seen_at` is only kept to the minute.
+const LAST_SEEN_RESOLUTION_SECONDS: i64 = 60;
+
+pub struct SessionService;
+
+impl SessionService {
+    /// Records a new session, which is a successful login. The event carries
+    /// the email, by which user-service finds its copy of the account.
+    pub async fn start(pool: &DbPool, user: &User, client: &ClientInfo) -> Result<Session, AuthError> {
+        AuditService::transaction(pool, |conn, trail| {
+            let session = SessionRepository::create(conn, NewSession {
+                user_id: user.id,
+                user_agent: client.user_agent.clone(),
+                ip_address: client.ip_address.clone(),
+            })?;
+
+            trail.record(AuditEventType::LoginSucceeded, Some(user.id), json!({
+                "email": user.email,
+                "session_id": session.id,
+                "ip": session.ip_address,
+                "user_agent": session.user_agent,
+            }));
+            Ok(session)
+        }).await
+    }
+
+    pub async fn list(pool: &DbPool, user_id: Uuid, current: Option<Uuid>) -> Result<Vec<SessionResponse>, AuthError> {
+        let sessions = SessionRepository::list_active(pool, user_id).await?;
+        Ok(sessions.into_iter()
+            .map(|session| SessionResponse {
+                current: Some(session.id) == current,
+                id: session.id,
+                user_agent: session.user_agent,
+                ip_address: session.ip_address,
+                created_at: session.created_at,
+                last_seen_at: session.last_seen_at,
+            })
+            .collect())
+    }
+
+    /// Ends one of the user's sessions: its refresh tokens stop working at
+    /// once, and so do its access tokens wherever revocation is checked.
+    pub async fn revoke(pool: &DbPool, user_id: Uuid, session_id: Uuid) -> Result<(), AuthError> {
+        AuditService::transaction(pool, |conn, trail| {
+            if SessionRepository::revoke_for_user(conn, user_id, session_id)? == 0 {
+                return Err(AuthError::SessionNotFound);
+            }
+            RefreshTokenRepository::revoke_family(conn, session_id)?;
+
+            trail.record(AuditEventType::SessionRevoked, Some(user_id), json!({ "session_id": session_id }));
+            Ok(())
+        }).await
+    }
+
+    /// Logs the user out everywhere. Grants to OAuth clients are kept.
+    pub async fn revoke_all(pool: &DbPool, user_id: Uuid) -> Result<usize, AuthError> {
+        AuditService::transaction(pool, |conn, trail| {
+            let revoked = SessionRepository::revoke_all_for_user(conn, user_id)?;
+            RefreshTokenRepository::revoke_first_party_for_user(conn, user_id)?;
+
+            trail.record(AuditEventType::AllSessionsRevoked, Some(user_id), json!({ "revoked_sessions": revoked }));
+            Ok(revoked)
+        }).await
+    }
+
+    /// Whether access tokens of session `sid` are still good. Sessions that
+    /// were never recorded count as ended.
+    pub async fn check(pool: &DbPool, sid: &str) -> Result<(), TokenError> {
+        let session_id = Uuid::parse_str(sid).map_err(|_e| TokenError::InvalidToken)?;
+        let session = SessionRepository::find(pool, session_id).await.map_err(Self::lookup_failed)?;
+        let active = session.is_some_and(|session| session.revoked_at.is_none());
+        if !active {
+            return Err(TokenError::RevokedToken);
+        }
+
+        SessionRepository::touch(pool, session_id, Duration::seconds(LAST_SEEN_RESOLUTION_SECONDS)).await
+            .map_err(Self::lookup_failed)?;
+        Ok(())
+    }
+
+    fn lookup_failed(e: diesel::result::Error) -> TokenError {
+        log::error!("Failed to look up session: {}", e);
+        TokenError::InvalidToken
+    }
+}
@@ -0,0 +1,189 @@
+use std::env;
+use std::time::Duration;
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use serde_json::json;
+use shared::auth::personal_access_token::is_personal_access_token;
+use shared::auth::token_cache::TokenCache;
+use shared::auth::{AuthError as TokenError, Claims, RevocationCheck, TokenValidator};
+use uuid::Uuid;
+use crate::config::database::DbPool;
+use crate::errors::oauth_error::OAuthError;
+use crate::models::audit::AuditEventType;
+use crate::models::oauth::OAuthClient;
+use crate::models::revoked_token::NewRevokedToken;
+use crate::repositories::personal_access_token_repository::PersonalAccessTokenRepository;
+use crate::repositories::refresh_token_repository::RefreshTokenRepository;
+use crate::repositories::revoked_token_repository::RevokedTokenRepository;
+use crate::repositories::session_repository::SessionRepository;
+use crate::services::audit_service::{AuditService, AuditTrail};
+use crate::services::introspection_service::looks_like_jwt;
+use crate::services::personal_access_token_service::PersonalAccessTokenService;
+use crate::services::session_service::SessionService;
+use crate::utils::opaque_token::hash_opaque_token;
+
+pub const DEFAULT_REVOCATION_CACHE_TTL: Duration = Duration::from_secs(10);
+const MAX_CACHED_TOKENS: usize = 10_000;
+
+pub struct TokenRevocationService;
+
+impl TokenRevocationService {
+    /// RFC 7009: revokes `token` for `client`. Clients may revoke the tokens
+    /// issued to them, service clients any token. Unknown, expired and already
+    /// revoked tokens are not an error. The token type hint is not needed, as
+    /// the kind of token is told by its shape.
+    pub async fn revoke(pool: &DbPool, validator: &TokenValidator, client: &OAuthClient, token: &str) -> Result<(), OAuthError> {
+        if is_personal_access_token(token) {
+            Self::revoke_personal_access_token(pool, client, token).await
+        } else if looks_like_jwt(token) {
+            Self::revoke_access_token(pool, validator, client, token).await
+        } else {
+            Self::revoke_refresh_token(pool, client, token).await
+        }
+    }
+
+    /// Remembers the token's `jti` until the token expires. Access tokens
+    /// issued before they carried a `jti` cannot be revoked on their own, but
+    /// expire within minutes anyway.
+    async fn revoke_access_token(pool: &DbPool, validator: &TokenValidator, client: &OAuthClient, token: &str) -> Result<(), OAuthError> {
+        let claims = match validator.validate(token).await {
+            Ok(claims) => claims,
+            Err(_) => return Ok(()),
+        };
+        if !client.is_service && claims.client_id.as_deref() != Some(client.client_id.as_str()) {
+            return Err(OAuthError::UnauthorizedClient);
+        }
+        let Some(jti) = claims.jti else {
+            return Ok(());
+        };
+
+        let expires_at = DateTime::from_timestamp(claims.exp as i64, 0).unwrap_or_else(Utc::now).naive_utc();
+        RevokedTokenRepository::purge_expired(pool).await?;
+        AuditService::transaction(pool, |conn, trail| {
+            RevokedTokenRepository::create(conn, NewRevokedToken { jti, expires_at })?;
+            Self::audit(trail, Uuid::parse_str(&claims.sub).ok(), client, "access_token");
+            Ok(())
+        }).await
+    }
+
+    /// Ends the whole token family, and the session for first-party tokens.
+    async fn revoke_refresh_token(pool: &DbPool, client: &OAuthClient, token: &str) -> Result<(), OAuthError> {
+        let current = match RefreshTokenRepository::find_by_hash(pool, &hash_opaque_token(token)).await {
+            Ok(current) => current,
+            Err(diesel::result::Error::NotFound) => return Ok(()),
+            Err(e) => return Err(e.into()),
+        };
+        if !client.is_service && current.client_id != Some(client.id) {
+            return Err(OAuthError::UnauthorizedClient);
+        }
+
+        AuditService::transaction(pool, |conn, trail| {
+            RefreshTokenRepository::revoke_family(conn, current.family_id)?;
+            if current.client_id.is_none() {
+                SessionRepository::revoke(conn, current.family_id)?;
+            }
+            Self::audit(trail, Some(current.user_id), client, "refresh_token");
+            Ok(())
+        }).await
+    }
+
+    /// Personal access tokens belong to no client, so only service clients
+    /// may revoke them here.
+    async fn revoke_personal_access_token(pool: &DbPool, client: &OAuthClient, token: &str) -> Result<(), OAuthError> {
+        if !client.is_service {
+            return Err(OAuthError::UnauthorizedClient);
+        }
+
+        let stored = PersonalAccessTokenRepository::find_by_hash(pool, &hash_opaque_token(token)).await?;
+        if let Some(stored) = stored.filter(|stored| stored.revoked_at.is_none()) {
+            PersonalAccessTokenService::revoke(pool, stored.user_id, stored.id).await?;
+        }
+        Ok(())
+    }
+
+    fn audit(trail: &mut AuditTrail, user_id: Option<Uuid>, client: &OAuthClient, token_type: &str) {
+        trail.record(
+            AuditEventType::TokenRevoked,
+            user_id,
+            json!({ "token_type": token_type, "client_id": client.client_id }),
+        );
+    }
+}
+
+/// Lets `TokenValidator` refuse revoked access tokens and tokens of ended
+/// sessions, straight from the database.
+///
+/// Answers are cached for `cache_ttl`, and never past the token's expiry, so
+/// a revocation can take that long to reach a token already seen.
+pub struct DatabaseRevocations {
+    pool: DbPool,
+    cache_ttl: Duration,
+    /// Whether the token was still active.
+    cache: TokenCache<bool>,
+}
+
+impl DatabaseRevocations {
+    pub fn new(pool: DbPool) -> DatabaseRevocations {
+        DatabaseRevocations { pool, cache_ttl: DEFAULT_REVOCATION_CACHE_TTL, cache: TokenCache::new(MAX_CACHED_TOKENS) }
+    }
+
+    /// `TOKEN_REVOCATION_CACHE_SECONDS` sets how long answers are kept.
+    pub fn from_env(pool: DbPool) -> DatabaseRevocations {
+        let revocations = DatabaseRevocations::new(pool);
+        match env::var("TOKEN_REVOCATION_CACHE_SECONDS").ok().and_then(|value| value.parse().ok()) {
+            Some(seconds) => revocations.with_cache_ttl(Duration::from_secs(seconds)),
+            None => revocations,
+        }
+    }
+
+    /// A zero TTL turns caching off.
+    pub fn with_cache_ttl(mut self, cache_ttl: Duration) -> DatabaseRevocations {
+        self.cache_ttl = cache_ttl;
+        self
+    }
+
+    async fn lookup(&self, claims: &Claims) -> Result<bool, TokenError> {
+        if let Some(jti) = &claims.jti {
+            let revoked = RevokedTokenRepository::is_revoked(&self.pool, jti).await.map_err(|e| {
+                log::error!("Failed to look up revoked token: {}", e);
+                TokenError::InvalidToken
+            })?;
+            if revoked {
+                return Ok(false);
+            }
+        }
+
+        match &claims.sid {
+            Some(sid) => match SessionService::check(&self.pool, sid).await {
+                Ok(()) => Ok(true),
+                Err(TokenError::RevokedToken) => Ok(false),
+                Err(e) => Err(e),
+            },
+            None => Ok(true),
+        }
+    }
+
+    fn ttl_for(&self, claims: &Claims) -> Duration {
+        let remaining = (claims.exp as i64 - Utc::now().timestamp()).max(0) as u64;
+        self.cache_ttl.min(Duration::from_secs(remaining))
+    }
+}
+
+#[async_trait]
+impl RevocationCheck for DatabaseRevocations {
+    async fn check(&self, token: &str, claims: &Claims) -> Result<(), TokenError> {
+        if claims.jti.is_none() && claims.sid.is_none() {
+            return Ok(());
+        }
+
+        let active = match self.cache.get(token) {
+            Some(active) => active,
+            None => {
+                let active = self.lookup(claims).await?;
+                self.cache.insert(token, active, self.ttl_for(claims));
+                active
+            }
+        };
+        if active { Ok(()) } else { Err(TokenError::RevokedToken) }
+    }
+}
@@ -0,0 +1,90 @@
+use std::sync::Arc;
+use actix_web::http::StatusCode;
+use actix_web::{test, web, App};
+use chrono::{Duration, Utc};
+use diesel::prelude::*;
+use uuid::Uuid;
+
+use crate::config::database::DbPool;
+use crate::config::keyring::Keyring;
+use crate::mailer::InMemoryMailer;
+use crate::models::schema::users;
+use crate::repositories::user_repository::UserRepository;
+use crate::middleware::rate_limiter::configure_login_rate_limiter;
+use crate::routes::auth_routes;
+use crate::services::email_verification_service::{EmailVerificationService, RESEND_INTERVAL_SECONDS};
+use crate::tests::support::{pool, registered_user};
+
+async fn register(pool: &DbPool, keyring: &Keyring) -> (Uuid, String) {
+  let registered = registered_user(pool, keyring, "verify").await;
+  (registered.id, registered.email)
+}
+
+fn link_token(body: &str) -> String {
+  let (_, rest) = body.split_once("?token=").expect("the email has a verification link");
+  rest.lines().next().unwrap().trim().to_string()
+}
+
+#[actix_rt::test]
+#[ignore = "needs a Postgres database at DATABASE_URL"]
+async fn test_verification_link_verifies_the_email() {
+  // Given: A new account that was sent a verification link
+  let pool = pool();
+  let keyring = web::Data::new(Keyring::ephemeral());
+  let mailer = Arc::new(InMemoryMailer::default());
+  let app = test::init_service(App::new()
+    .app_data(web::Data::new(pool.clone()))
+    .app_data(keyring.clone())
+    .service(auth_routes(&configure_login_rate_limiter()))).await;
+  let (user_id, email) = register(&pool, &keyring).await;
+  EmailVerificationService::send_verification(&pool, &keyring, mailer.clone(), user_id).await.unwrap();
+  let sent = mailer.sent();
+  assert_eq!(sent.len(), 1);
+  assert_eq!(sent[0].to, email);
+  let token = link_token(&sent[0].body);
+
+  // When: A tampered link is opened, then the real one
+  let req = test::TestRequest::get().uri(&format!("/auth/email/verify?token={}x", token)).to_request();
+  let tampered = test::call_service(&app, req).await.status();
+  let req = test::TestRequest::get().uri(&format!("/auth/email/verify?token={}", token)).to_request();
+  let verified = test::call_service(&app, req).await.status();
+
+  // Then: Only the real link verifies the address
+  assert_eq!(tampered, StatusCode::BAD_REQUEST);
+  assert_eq!(verified, StatusCode::OK);
+  assert!(UserRepository::find_by_id(&pool, user_id).await.unwrap().is_email_verified());
+
+  // When: Another link is asked for
+  EmailVerificationService::resend(&pool, &keyring, mailer.clone(), &email).await;
+
+  // Then: None is sent to a verified address
+  assert_eq!(mailer.sent().len(), 1);
+}
+
+#[actix_rt::test]
+#[ignore = "needs a Postgres database at DATABASE_URL"]
+async fn test_resending_is_throttled() {
+  // Given: A new account that was just sent a verification link
+  let pool = pool();
+  let keyring = Keyring::ephemeral();
+  let mailer = Arc::new(InMemoryMailer::default());
+  let (user_id, email) = register(&pool, &keyring).await;
+  EmailVerificationService::send_verification(&pool, &keyring, mailer.clone(), user_id).await.unwrap();
+
+  // When: Another link is asked for straight away
+  EmailVerificationService::resend(&pool, &keyring, mailer.clone(), &email).await;
+
+  // Then: It is not sent
+  assert_eq!(mailer.sent().len(), 1);
+
+  // When: It is asked for again once the interval has passed
+  let conn = &mut pool.get().unwrap();
+  diesel::update(users::table.find(user_id))
+    .set(users::email_verification_sent_at.eq(Utc::now().naive_utc() - Duration::seconds(RESEND_INTERVAL_SECONDS + 1)))
+    .execute(conn)
+    .unwrap();
+  EmailVerificationService::resend(&pool, &keyring, mailer.clone(), &email).await;
+
+  // Then: A second link goes out
+  assert_eq!(mailer.sent().len(), 2);
+}
@@ -0,0 +1,65 @@
+use actix_web::web;
+use chrono::Utc;
+use diesel::prelude::*;
+use shared::erasure::{ErasureAcknowledged, ErasureRequested, ERASURE_ACKNOWLEDGED_TOPIC};
+use shared::outbox::OutboxMessage;
+use uuid::Uuid;
+
+use crate::config::database::DbPool;
+use crate::config::keyring::Keyring;
+use crate::models::schema::{outbox, sessions};
+use crate::repositories::user_repository::UserRepository;
+use crate::services::erasure_service::ErasureService;
+use crate::tests::support::{pool, registered_user};
+
+fn acknowledgements(pool: &DbPool, user_id: Uuid) -> Vec<ErasureAcknowledged> {
+  let conn = &mut pool.get().expect("Failed to get DB connection from pool");
+  outbox::table
+    .filter(outbox::topic.eq(ERASURE_ACKNOWLEDGED_TOPIC))
+    .filter(outbox::event_key.eq(user_id.to_string()))
+    .load::<OutboxMessage>(conn)
+    .unwrap()
+    .into_iter()
+    .map(|message| serde_json::from_value(message.payload).unwrap())
+    .collect()
+}
+
+#[actix_rt::test]
+#[ignore = "needs a Postgres database at DATABASE_URL"]
+async fn test_erasure_deletes_the_account_and_acknowledges() {
+  // Given: A registered, signed-in candidate
+  let pool = pool();
+  let keyring = web::Data::new(Keyring::ephemeral());
+  let registered = registered_user(&pool, &keyring, "erasure").await;
+  let email = registered.email.clone();
+
+  // When: user-service asks for their erasure
+  let request = ErasureRequested {
+    request_id: Uuid::new_v4(),
+    user_id: Uuid::new_v4(),
+    email: email.clone(),
+    requested_at: Utc::now().naive_utc(),
+  };
+  ErasureService::handle(&pool, &serde_json::to_string(&request).unwrap()).await.unwrap();
+
+  // Then: The account is gone, with its sessions
+  assert!(matches!(UserRepository::find_by_email(&pool, &email).await, Err(diesel::result::Error::NotFound)));
+  let conn = &mut pool.get().unwrap();
+  let remaining: i64 = sessions::table.filter(sessions::user_id.eq(registered.id)).count().get_result(conn).unwrap();
+  assert_eq!(remaining, 0);
+
+  // And: The erasure is acknowledged under this service's name
+  let acknowledged = acknowledgements(&pool, request.user_id);
+  assert_eq!(acknowledged.len(), 1);
+  assert_eq!(acknowledged[0].request_id, request.request_id);
+  assert_eq!(acknowledged[0].service, "auth-service");
+
+  // When: The request is delivered again
+  ErasureService::handle(&pool, &serde_json::to_string(&request).unwrap()).await.unwrap();
+
+  // Then: It is acknowledged again, so a lost acknowledgement is not fatal
+  assert_eq!(acknowledgements(&pool, request.user_id).len(), 2);
+
+  // And: A message that is not a request is skipped rather than retried
+  assert!(ErasureService::handle(&pool, "not json").await.is_ok());
+}
@@ -0,0 +1,51 @@
+use actix_web::body::to_bytes;
+use actix_web::http::StatusCode;
+use actix_web::ResponseError;
+use serde_json::Value;
+use crate::errors::error::AuthError;
+
+async fn body(error: AuthError) -> Value {
+  let bytes = to_bytes(error.error_response().into_body()).await.unwrap();
+  serde_json::from_slice(&bytes).unwrap()
+}
+
+#[actix_rt::test]
+async fn test_error_response_carries_a_stable_code() {
+  // Given: A failed login
+  let error = AuthError::InvalidCredentials;
+
+  // When: It is turned into a response
+  let status = error.status_code();
+  let body = body(error).await;
+
+  // Then: It is a 401 with a machine-readable code
+  assert_eq!(status, StatusCode::UNAUTHORIZED);
+  assert_eq!(body["code"], "invalid_credentials");
+  assert_eq!(body["error"], "Invalid credentials");
+}
+
+#[actix_rt::test]
+async fn test_database_errors_do_not_leak_details() {
+  // Given: A database error
+  let error = AuthError::DatabaseError(diesel::result::Error::NotFound);
+
+  // When: It is turned into a response
+  let body = body(error).await;
+
+  // Then: Only a generic message is sent
+  assert_eq!(body["code"], "internal_error");
+  assert_eq!(body["error"], "Internal server error");
+}
+
+#[actix_rt::test]
+async fn test_too_many_attempts_sets_retry_after() {
+  // Given: A locked-out login
+  let error = AuthError::TooManyLoginAttempts(42);
+
+  // When: It is turned into a response
+  let response = error.error_response();
+
+  // Then: The client is told when to retry
+  assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+  assert_eq!(response.headers().get("retry-after").unwrap(), "42");
+}
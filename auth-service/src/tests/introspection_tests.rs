@@ -0,0 +1,137 @@
+use std::sync::Arc;
+use std::time::Duration;
+use actix_web::http::StatusCode;
+use actix_web::{test, web, App};
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use serde_json::{json, Value};
+use uuid::Uuid;
+
+use crate::config::database::DbPool;
+use crate::config::keyring::Keyring;
+use crate::models::auth::LoginResponse;
+use crate::models::oauth::{RegisterClientRequest, RegisterClientResponse};
+use crate::middleware::rate_limiter::configure_login_rate_limiter;
+use crate::routes::auth_routes;
+use crate::services::auth_service::AuthService;
+use crate::services::oauth_service::OAuthService;
+use crate::services::token_revocation_service::DatabaseRevocations;
+use crate::tests::support::{pool, registered_user};
+
+async fn signed_in(pool: &DbPool, keyring: &Keyring) -> (Uuid, LoginResponse) {
+  let registered = registered_user(pool, keyring, "introspect").await;
+  (registered.id, registered.tokens.unwrap())
+}
+
+async fn client(pool: &DbPool, created_by: Uuid, service: bool) -> RegisterClientResponse {
+  OAuthService::register_client(pool, created_by, RegisterClientRequest {
+    name: if service { "resume-service" } else { "Partner ATS" }.to_string(),
+    redirect_uris: if service { Vec::new() } else { vec!["https://partner.example.com/callback".to_string()] },
+    scopes: if service { Vec::new() } else { vec!["resumes:read".to_string()] },
+    confidential: true,
+    service,
+  }).await.unwrap()
+}
+
+fn post(uri: &str, client: &RegisterClientResponse, token: &str) -> test::TestRequest {
+  test::TestRequest::post()
+    .uri(uri)
+    .set_form([
+      ("token", token),
+      ("client_id", client.client_id.as_str()),
+      ("client_secret", client.client_secret.as_deref().unwrap()),
+    ])
+}
+
+#[actix_rt::test]
+#[ignore = "needs a Postgres database at DATABASE_URL"]
+async fn test_service_clients_introspect_and_revoke_tokens() {
+  // Given: A signed-in candidate, a service client and the auth routes
+  let pool = pool();
+  let keyring = web::Data::new(Keyring::ephemeral());
+  let revocations = DatabaseRevocations::new(pool.clone()).with_cache_ttl(Duration::ZERO);
+  let validator = keyring.token_validator().with_revocation_check(Arc::new(revocations));
+  let app = test::init_service(App::new()
+    .app_data(web::Data::new(pool.clone()))
+    .app_data(keyring.clone())
+    .app_data(web::Data::new(validator))
+    .service(auth_routes(&configure_login_rate_limiter()))).await;
+  let (user_id, tokens) = signed_in(&pool, &keyring).await;
+  let service = client(&pool, user_id, true).await;
+
+  // When: The service introspects the access token
+  let introspected: Value = test::call_and_read_body_json(&app, post("/auth/introspect", &service, &tokens.token).to_request()).await;
+
+  // Then: It is active, with its claims
+  assert_eq!(introspected["active"], true);
+  assert_eq!(introspected["token_type"], "Bearer");
+  assert_eq!(introspected["sub"], user_id.to_string());
+  assert!(introspected["jti"].is_string());
+  assert!(introspected["sid"].is_string());
+
+  // And: The refresh token is active too, for the same session
+  let introspected_refresh: Value = test::call_and_read_body_json(&app, post("/auth/introspect", &service, &tokens.refresh_token).to_request()).await;
+  assert_eq!(introspected_refresh["active"], true);
+  assert_eq!(introspected_refresh["token_type"], "refresh_token");
+  assert_eq!(introspected_refresh["sid"], introspected["sid"]);
+
+  // When: The service revokes the access token alone
+  let response = test::call_service(&app, post("/auth/revoke", &service, &tokens.token).to_request()).await;
+  assert_eq!(response.status(), StatusCode::OK);
+
+  // Then: It is inactive and refused, while the session lives on
+  let introspected: Value = test::call_and_read_body_json(&app, post("/auth/introspect", &service, &tokens.token).to_request()).await;
+  assert_eq!(introspected, json!({ "active": false }));
+  let req = test::TestRequest::get()
+    .uri("/auth/sessions")
+    .insert_header(("Authorization", format!("Bearer {}", tokens.token)))
+    .to_request();
+  assert_eq!(test::call_service(&app, req).await.status(), StatusCode::UNAUTHORIZED);
+  let refreshed = AuthService::refresh(&pool, &keyring, &tokens.refresh_token).await.unwrap();
+
+  // When: The service revokes the refresh token
+  let response = test::call_service(&app, post("/auth/revoke", &service, &refreshed.refresh_token).to_request()).await;
+  assert_eq!(response.status(), StatusCode::OK);
+
+  // Then: The whole session has ended
+  let introspected: Value = test::call_and_read_body_json(&app, post("/auth/introspect", &service, &refreshed.token).to_request()).await;
+  assert_eq!(introspected["active"], false);
+  assert!(AuthService::refresh(&pool, &keyring, &refreshed.refresh_token).await.is_err());
+
+  // And: Unknown tokens are revoked without complaint
+  let response = test::call_service(&app, post("/auth/revoke", &service, "not-a-token").to_request()).await;
+  assert_eq!(response.status(), StatusCode::OK);
+}
+
+#[actix_rt::test]
+#[ignore = "needs a Postgres database at DATABASE_URL"]
+async fn test_other_clients_may_not_introspect_or_revoke_first_party_tokens() {
+  // Given: A signed-in candidate and a partner client
+  let pool = pool();
+  let keyring = web::Data::new(Keyring::ephemeral());
+  let app = test::init_service(App::new()
+    .app_data(web::Data::new(pool.clone()))
+    .app_data(keyring.clone())
+    .app_data(web::Data::new(keyring.token_validator()))
+    .service(auth_routes(&configure_login_rate_limiter()))).await;
+  let (user_id, tokens) = signed_in(&pool, &keyring).await;
+  let partner = client(&pool, user_id, false).await;
+
+  // When / Then: The partner cannot introspect tokens
+  let response = test::call_service(&app, post("/auth/introspect", &partner, &tokens.token).to_request()).await;
+  assert_eq!(response.status(), StatusCode::FORBIDDEN);
+
+  // And: Cannot revoke tokens that were not issued to it
+  let response = test::call_service(&app, post("/auth/revoke", &partner, &tokens.refresh_token).to_request()).await;
+  assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+  let body: Value = test::read_body_json(response).await;
+  assert_eq!(body["error"], "unauthorized_client");
+
+  // And: A wrong secret is refused outright
+  let req = test::TestRequest::post()
+    .uri("/auth/introspect")
+    .insert_header(("Authorization", format!("Basic {}", STANDARD.encode(format!("{}:wrong", partner.client_id)))))
+    .set_form([("token", tokens.token.as_str())])
+    .to_request();
+  assert_eq!(test::call_service(&app, req).await.status(), StatusCode::UNAUTHORIZED);
+}
@@ -0,0 +1,39 @@
+use jsonwebtoken::{decode, decode_header, encode, DecodingKey, Header, Validation};
+use serde::{Deserialize, Serialize};
+
+use crate::config::keyring::Keyring;
+
+#[derive(Serialize, Deserialize)]
+struct TestClaims {
+  sub: String,
+  exp: usize,
+}
+
+#[test]
+fn test_token_verifies_against_published_jwks() {
+  // Given: A keyring and a token signed with its active key
+  let keyring = Keyring::ephemeral();
+  let signing_key = keyring.active();
+  let mut header = Header::new(signing_key.algorithm);
+  header.kid = Some(signing_key.kid.clone());
+  let claims = TestClaims { sub: "user".to_string(), exp: usize::MAX };
+  let token = encode(&header, &claims, signing_key.encoding_key()).unwrap();
+
+  // When: A verifier looks up the key by `kid` in the JWKS
+  let jwks = keyring.jwks();
+  let kid = decode_header(&token).unwrap().kid.unwrap();
+  let jwk = jwks.find(&kid).expect("kid should be published");
+  let decoded = decode::<TestClaims>(&token, &DecodingKey::from_jwk(jwk).unwrap(), &Validation::new(signing_key.algorithm));
+
+  // Then: The token is accepted using only the public key
+  assert_eq!(decoded.unwrap().claims.sub, "user");
+}
+
+#[test]
+fn test_keyring_requires_active_key() {
+  // Given: A keyring built without the requested active key
+  let keyring = Keyring::new("missing", Vec::new());
+
+  // Then: It is rejected
+  assert!(keyring.is_err());
+}
@@ -0,0 +1,88 @@
+use chrono::{Duration, NaiveDateTime, Utc};
+use uuid::Uuid;
+use crate::config::keyring::Keyring;
+use crate::errors::error::AuthError;
+use crate::models::auth::LoginRequest;
+use crate::models::login_attempt::LoginAttempt;
+use crate::models::session::ClientInfo;
+use crate::services::auth_service::AuthService;
+use crate::services::login_attempt_service::LockoutPolicy;
+use crate::tests::support::{pool, registered_user};
+
+fn now() -> NaiveDateTime {
+  Utc::now().naive_utc()
+}
+
+fn fail(policy: &LockoutPolicy, times: i32, at: NaiveDateTime) -> LoginAttempt {
+  (0..times).fold(None, |previous, _| Some(policy.next_failure(previous, "testuser@example.com", Some("127.0.0.1"), at)))
+    .unwrap()
+}
+
+#[test]
+fn test_delay_grows_after_free_attempts() {
+  // Given: The default policy
+  let policy = LockoutPolicy::default();
+
+  // When: Delays are computed for increasing failure counts
+  let delays: Vec<i64> = (1..=10).map(|failures| policy.delay_after(failures)).collect();
+
+  // Then: The first attempts are free, then the delay doubles up to the cap
+  assert_eq!(delays, vec![0, 0, 0, 1, 2, 4, 8, 16, 32, 60]);
+}
+
+#[test]
+fn test_email_is_locked_after_max_attempts() {
+  // Given: The default policy and an email that failed the maximum number of times
+  let policy = LockoutPolicy::default();
+  let attempt = fail(&policy, policy.max_attempts, now());
+
+  // When: The next attempt is checked
+  let retry_after = policy.retry_after(&attempt, now());
+
+  // Then: The email is locked for the lockout period
+  assert!(attempt.locked_until.is_some());
+  assert!(retry_after.unwrap() > (policy.lockout_minutes - 1) * 60);
+}
+
+#[test]
+fn test_counting_starts_over_after_lockout_expires() {
+  // Given: An email whose lockout has run out
+  let policy = LockoutPolicy::default();
+  let locked_at = now() - Duration::minutes(policy.lockout_minutes + 1);
+  let attempt = fail(&policy, policy.max_attempts, locked_at);
+
+  // When: It fails again
+  let next = policy.next_failure(Some(attempt), "testuser@example.com", None, now());
+
+  // Then: It is back to its first failure and not locked
+  assert_eq!(next.failed_attempts, 1);
+  assert_eq!(policy.retry_after(&next, now()), None);
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 8)]
+#[ignore = "needs a Postgres database at DATABASE_URL"]
+async fn test_concurrent_guesses_are_counted_before_passwords_are_checked() {
+  // Given: An account
+  let pool = pool();
+  let keyring = std::sync::Arc::new(Keyring::ephemeral());
+  let user = registered_user(&pool, &keyring, "burst").await;
+  let policy = LockoutPolicy::from_env();
+
+  // When: A burst of wrong passwords arrives at once
+  let guesses = (0..policy.max_attempts * 2).map(|_| {
+    let (pool, keyring) = (pool.clone(), keyring.clone());
+    let request = LoginRequest { email: user.email.clone(), password: format!("wrong-{}", Uuid::new_v4()) };
+    tokio::spawn(async move { AuthService::authenticate(&pool, &keyring, &request, &ClientInfo::default()).await })
+  }).collect::<Vec<_>>();
+  let mut checked = 0;
+  for guess in guesses {
+    match guess.await.unwrap() {
+      Err(AuthError::InvalidCredentials) => checked += 1,
+      Err(AuthError::TooManyLoginAttempts(_)) => {}
+      _ => panic!("a wrong password got through"),
+    }
+  }
+
+  // Then: Only the free attempts and the first delayed one were checked
+  assert_eq!(checked, policy.free_attempts + 1);
+}
@@ -0,0 +1,49 @@
+use crate::mailer::{mailer_from_env, Email, FileDropMailer, InMemoryMailer, Mailer};
+
+fn email() -> Email {
+  Email {
+    to: "testuser@example.com".to_string(),
+    subject: "Reset your password".to_string(),
+    body: "https://example.com/reset-password?token=abc".to_string(),
+  }
+}
+
+#[actix_rt::test]
+async fn test_in_memory_mailer_keeps_sent_emails() {
+  // Given: An in-memory mailer
+  let mailer = InMemoryMailer::default();
+
+  // When: An email is sent
+  mailer.send(email()).await.unwrap();
+
+  // Then: It can be read back
+  assert_eq!(mailer.sent(), vec![email()]);
+}
+
+#[actix_rt::test]
+async fn test_file_drop_mailer_writes_one_file_per_email() {
+  // Given: A file-drop mailer pointing at an empty directory
+  let dir = std::env::temp_dir().join(format!("mail-{}", uuid::Uuid::new_v4()));
+  let mailer = FileDropMailer::new(&dir);
+
+  // When: An email is sent
+  mailer.send(email()).await.unwrap();
+
+  // Then: The email is written to the directory
+  let files: Vec<_> = std::fs::read_dir(&dir).unwrap().flatten().collect();
+  assert_eq!(files.len(), 1);
+  let contents = std::fs::read_to_string(files[0].path()).unwrap();
+  assert!(contents.contains("token=abc"));
+
+  std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+#[should_panic(expected = "MAILER must be smtp, file or memory")]
+fn test_mailer_from_env_refuses_an_unknown_transport() {
+  // Given: A misspelled transport
+  std::env::set_var("MAILER", "smpt");
+
+  // When / Then: Picking the mailer panics instead of falling back to files
+  mailer_from_env();
+}
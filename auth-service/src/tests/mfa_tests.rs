@@ -0,0 +1,112 @@
+use actix_web::web;
+use chrono::Utc;
+use totp_rs::{Algorithm, Secret, TOTP};
+
+use crate::config::database::DbPool;
+use crate::config::keyring::Keyring;
+use crate::errors::error::AuthError;
+use crate::models::auth::{LoginOutcome, LoginRequest};
+use crate::models::mfa::{MfaChallengeResponse, MfaVerifyRequest};
+use crate::models::session::ClientInfo;
+use crate::services::auth_service::AuthService;
+use crate::services::login_attempt_service::LockoutPolicy;
+use crate::services::mfa_service::{MfaService, MFA_CHALLENGE_MAX_ATTEMPTS};
+use crate::tests::support::{pool, registered_user};
+
+/// A registered user with TOTP enabled: their email, an authenticator for
+/// their secret and their recovery codes.
+async fn enrolled_user(pool: &DbPool, keyring: &Keyring) -> (String, TOTP, Vec<String>) {
+  std::env::set_var("TOTP_ENCRYPTION_KEY", "ZGV2LW9ubHktdG90cC1lbmNyeXB0aW9uLWtleS0zMmI=");
+  let user = registered_user(pool, keyring, "mfa").await;
+  let email = user.email.clone();
+
+  let enrollment = MfaService::enroll(pool, user.id).await.unwrap();
+  let secret = Secret::Encoded(enrollment.secret).to_bytes().unwrap();
+  let authenticator = TOTP::new(Algorithm::SHA1, 6, 0, 30, secret, None, email.clone()).unwrap();
+  let recovery_codes = MfaService::confirm(pool, user.id, &authenticator.generate_current().unwrap()).await.unwrap();
+  (email, authenticator, recovery_codes)
+}
+
+async fn challenge(pool: &DbPool, keyring: &Keyring, email: &str) -> MfaChallengeResponse {
+  let request = LoginRequest { email: email.to_string(), password: "Password123!".to_string() };
+  match AuthService::authenticate(pool, keyring, &request, &ClientInfo::default()).await.unwrap() {
+    LoginOutcome::MfaRequired(challenge) => challenge,
+    LoginOutcome::Tokens(_) => panic!("expected an MFA challenge"),
+  }
+}
+
+fn with_code(challenge: &MfaChallengeResponse, code: &str) -> MfaVerifyRequest {
+  MfaVerifyRequest { mfa_token: challenge.mfa_token.clone(), code: Some(code.to_string()), recovery_code: None }
+}
+
+fn with_recovery_code(challenge: &MfaChallengeResponse, recovery_code: &str) -> MfaVerifyRequest {
+  MfaVerifyRequest { mfa_token: challenge.mfa_token.clone(), code: None, recovery_code: Some(recovery_code.to_string()) }
+}
+
+#[actix_rt::test]
+#[ignore = "needs a Postgres database at DATABASE_URL"]
+async fn test_challenge_completes_a_single_login() {
+  // Given: A user who enrolled and confirmed TOTP, and is asked for a code at login
+  let pool = pool();
+  let keyring = web::Data::new(Keyring::ephemeral());
+  let (email, authenticator, recovery_codes) = enrolled_user(&pool, &keyring).await;
+  let challenge = challenge(&pool, &keyring, &email).await;
+  assert!(challenge.mfa_required);
+
+  // When: They answer with the code of the next time step, since the current one was used to confirm
+  let next_code = authenticator.generate(Utc::now().timestamp() as u64 + 30);
+  let login = AuthService::complete_mfa_login(&pool, &keyring, &with_code(&challenge, &next_code), &ClientInfo::default()).await;
+
+  // Then: They get tokens, and the same challenge cannot complete another login
+  assert!(login.is_ok());
+  let replayed = AuthService::complete_mfa_login(
+    &pool, &keyring, &with_recovery_code(&challenge, &recovery_codes[0]), &ClientInfo::default(),
+  ).await;
+  assert!(matches!(replayed, Err(AuthError::InvalidMfaChallenge)));
+}
+
+#[actix_rt::test]
+#[ignore = "needs a Postgres database at DATABASE_URL"]
+async fn test_challenge_closes_after_too_many_wrong_codes() {
+  // Given: A user with TOTP enabled and a challenge
+  let pool = pool();
+  let keyring = web::Data::new(Keyring::ephemeral());
+  let (email, _authenticator, recovery_codes) = enrolled_user(&pool, &keyring).await;
+  let challenge = challenge(&pool, &keyring, &email).await;
+
+  // When: Someone guesses wrong as often as a challenge allows
+  for _ in 0..MFA_CHALLENGE_MAX_ATTEMPTS {
+    let guess = MfaService::verify_challenge(&pool, &keyring, &with_code(&challenge, "not-a-code")).await;
+    assert!(matches!(guess, Err(AuthError::InvalidMfaCode)));
+  }
+
+  // Then: The challenge is closed, even to a valid recovery code
+  let verified = MfaService::verify_challenge(&pool, &keyring, &with_recovery_code(&challenge, &recovery_codes[0])).await;
+  assert!(matches!(verified, Err(AuthError::InvalidMfaChallenge)));
+}
+
+#[actix_rt::test]
+#[ignore = "needs a Postgres database at DATABASE_URL"]
+async fn test_wrong_codes_are_counted_across_challenges() {
+  // Given: A user with TOTP enabled
+  let pool = pool();
+  let keyring = web::Data::new(Keyring::ephemeral());
+  let (email, _authenticator, recovery_codes) = enrolled_user(&pool, &keyring).await;
+
+  // When: Someone who knows the password keeps asking for new challenges to guess at
+  let policy = LockoutPolicy::from_env();
+  let mut failures = 0;
+  while failures < policy.max_attempts {
+    let challenge = challenge(&pool, &keyring, &email).await;
+    for _ in 0..MFA_CHALLENGE_MAX_ATTEMPTS.min(policy.max_attempts - failures) {
+      let guess = MfaService::verify_challenge(&pool, &keyring, &with_code(&challenge, "not-a-code")).await;
+      assert!(matches!(guess, Err(AuthError::InvalidMfaCode)));
+      failures += 1;
+    }
+  }
+
+  // Then: A fresh challenge is refused too, until the lockout is over
+  let challenge = challenge(&pool, &keyring, &email).await;
+  let verified = MfaService::verify_challenge(&pool, &keyring, &with_recovery_code(&challenge, &recovery_codes[0])).await;
+  assert!(matches!(verified, Err(AuthError::TooManyLoginAttempts(retry_after)) if retry_after == policy.lockout_minutes * 60));
+}
@@ -1,2 +1,22 @@
 mod user_tests;
-mod rate_limiter_tests;
\ No newline at end of file
+mod rate_limiter_tests;
+mod keyring_tests;
+mod mailer_tests;
+mod secret_box_tests;
+mod login_attempt_tests;
+mod mfa_tests;
+mod error_tests;
+mod register_tests;
+mod email_verification_tests;
+mod oauth_tests;
+mod oidc_tests;
+mod personal_access_token_tests;
+mod session_tests;
+mod introspection_tests;
+mod password_hash_tests;
+mod password_policy_tests;
+mod outbox_tests;
+mod software_authenticator;
+mod support;
+mod passkey_tests;
+mod erasure_tests;
@@ -0,0 +1,246 @@
+use actix_web::http::StatusCode;
+use actix_web::{test, web, App};
+use serde_json::{json, Value};
+use uuid::Uuid;
+
+use crate::config::database::DbPool;
+use crate::config::keyring::Keyring;
+use crate::errors::oauth_error::OAuthError;
+use crate::models::oauth::{RegisterClientRequest, TokenRequest, TokenResponse};
+use crate::middleware::rate_limiter::configure_login_rate_limiter;
+use crate::routes::{auth_routes, oauth_routes};
+use crate::services::oauth_service::{ClientCredentials, OAuthService};
+use crate::tests::support::{bearer, pool, registered_user};
+
+const CODE_VERIFIER: &str = "dBjftJeZ4CVP-mJ0kBPqxdtl8VD1tZLTXK3KYXEXGRo";
+const CODE_CHALLENGE: &str = "SmQAQBVQhI1AXbCV4bvKvuXY4FyCfav4env_PczM2KA";
+const REDIRECT_URI: &str = "https://partner.example.com/callback";
+
+fn query_param(url: &str, name: &str) -> Option<String> {
+  url::Url::parse(url).unwrap().query_pairs().find(|(key, _)| key == name).map(|(_, value)| value.into_owned())
+}
+
+fn authorize_uri(query: &Value) -> String {
+  let pairs = query.as_object().unwrap().iter().map(|(key, value)| (key.as_str(), value.as_str().unwrap()));
+  let url = url::Url::parse_with_params("http://localhost/oauth/authorize", pairs).unwrap();
+  format!("{}?{}", url.path(), url.query().unwrap())
+}
+
+/// An access token `user_id` granted a partner client, through the whole
+/// authorization code flow.
+async fn partner_access_token(pool: &DbPool, keyring: &Keyring, user_id: Uuid) -> String {
+  let client = OAuthService::register_client(pool, user_id, RegisterClientRequest {
+    name: "Partner ATS".to_string(),
+    redirect_uris: vec![REDIRECT_URI.to_string()],
+    scopes: vec!["resumes:read".to_string()],
+    confidential: false,
+    service: false,
+  }).await.unwrap();
+  let decision = serde_json::from_value(json!({
+    "response_type": "code",
+    "client_id": client.client_id,
+    "redirect_uri": REDIRECT_URI,
+    "code_challenge": CODE_CHALLENGE,
+    "code_challenge_method": "S256",
+    "approve": true,
+  })).unwrap();
+  let redirect = OAuthService::decide(pool, user_id, decision).await.unwrap();
+
+  let request = TokenRequest {
+    grant_type: "authorization_code".to_string(),
+    code: query_param(&redirect.redirect_to, "code"),
+    redirect_uri: Some(REDIRECT_URI.to_string()),
+    code_verifier: Some(CODE_VERIFIER.to_string()),
+    refresh_token: None,
+    scope: None,
+    client_id: None,
+    client_secret: None,
+  };
+  let credentials = ClientCredentials { client_id: client.client_id, client_secret: None };
+  OAuthService::token(pool, keyring, request, credentials).await.unwrap().access_token
+}
+
+#[actix_rt::test]
+async fn test_pkce_accepts_only_the_matching_verifier() {
+  // Given: A challenge derived from a verifier
+  // When / Then: Only that verifier satisfies it
+  assert!(OAuthService::verify_pkce(CODE_VERIFIER, CODE_CHALLENGE));
+  assert!(!OAuthService::verify_pkce(&CODE_VERIFIER.replace('d', "e"), CODE_CHALLENGE));
+  assert!(!OAuthService::verify_pkce("short", CODE_CHALLENGE));
+}
+
+#[actix_rt::test]
+async fn test_scopes_are_limited_to_what_the_client_may_request() {
+  // Given: A client allowed to read resumes and users
+  let allowed = "resumes:read users:read";
+
+  // When / Then: Subsets are granted, anything else is refused
+  assert_eq!(OAuthService::negotiate_scopes(Some("resumes:read"), allowed).unwrap(), vec!["resumes:read"]);
+  assert_eq!(OAuthService::negotiate_scopes(None, allowed).unwrap(), vec!["resumes:read", "users:read"]);
+  assert!(matches!(OAuthService::negotiate_scopes(Some("users:delete"), allowed), Err(OAuthError::InvalidScope)));
+}
+
+#[actix_rt::test]
+#[ignore = "needs a Postgres database at DATABASE_URL"]
+async fn test_authorization_code_flow_with_pkce() {
+  // Given: A candidate, a registered public client and the OAuth routes
+  let pool = pool();
+  let keyring = web::Data::new(Keyring::ephemeral());
+  let candidate = registered_user(&pool, &keyring, "oauth").await;
+  let user_token = candidate.tokens.unwrap().token;
+  let client = OAuthService::register_client(&pool, candidate.id, RegisterClientRequest {
+    name: "Partner ATS".to_string(),
+    redirect_uris: vec![REDIRECT_URI.to_string()],
+    scopes: vec!["resumes:read".to_string(), "users:read".to_string()],
+    confidential: false,
+    service: false,
+  }).await.unwrap();
+
+  let app = test::init_service(App::new()
+    .app_data(web::Data::new(pool.clone()))
+    .app_data(keyring.clone())
+    .app_data(web::Data::new(keyring.token_validator()))
+    .service(oauth_routes())).await;
+  let authorize_query = json!({
+    "response_type": "code",
+    "client_id": client.client_id,
+    "redirect_uri": REDIRECT_URI,
+    "scope": "resumes:read",
+    "state": "xyz",
+    "code_challenge": CODE_CHALLENGE,
+    "code_challenge_method": "S256",
+  });
+
+  // When: The user is shown the consent step and approves it
+  let req = test::TestRequest::get()
+    .uri(&authorize_uri(&authorize_query))
+    .insert_header(("Authorization", format!("Bearer {}", user_token)))
+    .to_request();
+  let prompt: Value = test::call_and_read_body_json(&app, req).await;
+
+  let mut decision = authorize_query.clone();
+  decision["approve"] = json!(true);
+  let req = test::TestRequest::post()
+    .uri("/oauth/authorize")
+    .insert_header(("Authorization", format!("Bearer {}", user_token)))
+    .set_json(&decision)
+    .to_request();
+  let redirect: Value = test::call_and_read_body_json(&app, req).await;
+  let redirect_to = redirect["redirect_to"].as_str().unwrap();
+  let code = query_param(redirect_to, "code").unwrap();
+
+  // And: The client exchanges the code with its verifier
+  let exchange = [
+    ("grant_type", "authorization_code"),
+    ("code", code.as_str()),
+    ("redirect_uri", REDIRECT_URI),
+    ("client_id", client.client_id.as_str()),
+    ("code_verifier", CODE_VERIFIER),
+  ];
+  let req = test::TestRequest::post().uri("/oauth/token").set_form(exchange).to_request();
+  let tokens: TokenResponse = test::call_and_read_body_json(&app, req).await;
+
+  // Then: The consent named the client and scope, and the state came back
+  assert_eq!(prompt["client_name"], "Partner ATS");
+  assert_eq!(prompt["scopes"], json!(["resumes:read"]));
+  assert_eq!(query_param(redirect_to, "state").as_deref(), Some("xyz"));
+
+  // And: The access token is limited to the granted scope
+  let claims = keyring.token_validator().validate(&tokens.access_token).await.unwrap();
+  assert_eq!(tokens.scope, "resumes:read");
+  assert_eq!(claims.client_id.as_deref(), Some(client.client_id.as_str()));
+  assert_eq!(claims.permissions, vec!["resumes:read"]);
+  assert!(claims.roles.is_empty());
+
+  // And: The code cannot be used twice
+  let req = test::TestRequest::post().uri("/oauth/token").set_form(exchange).to_request();
+  let replay = test::call_service(&app, req).await;
+  assert_eq!(replay.status(), actix_web::http::StatusCode::BAD_REQUEST);
+  let body: Value = test::read_body_json(replay).await;
+  assert_eq!(body["error"], "invalid_grant");
+
+  // And: The refresh token rotates, and the old one is then refused
+  let refresh = [
+    ("grant_type", "refresh_token"),
+    ("refresh_token", tokens.refresh_token.as_str()),
+    ("client_id", client.client_id.as_str()),
+  ];
+  let req = test::TestRequest::post().uri("/oauth/token").set_form(refresh).to_request();
+  let refreshed: TokenResponse = test::call_and_read_body_json(&app, req).await;
+  assert_ne!(refreshed.refresh_token, tokens.refresh_token);
+  assert_eq!(refreshed.scope, "resumes:read");
+
+  let req = test::TestRequest::post().uri("/oauth/token").set_form(refresh).to_request();
+  let reused = test::call_service(&app, req).await;
+  assert_eq!(reused.status(), actix_web::http::StatusCode::BAD_REQUEST);
+}
+
+#[actix_rt::test]
+#[ignore = "needs a Postgres database at DATABASE_URL"]
+async fn test_denied_consent_redirects_with_access_denied() {
+  // Given: A user and a registered client
+  let pool = pool();
+  let keyring = Keyring::ephemeral();
+  let user = registered_user(&pool, &keyring, "oauth").await;
+  let client = OAuthService::register_client(&pool, user.id, RegisterClientRequest {
+    name: "Partner ATS".to_string(),
+    redirect_uris: vec![REDIRECT_URI.to_string()],
+    scopes: vec!["resumes:read".to_string()],
+    confidential: true,
+    service: false,
+  }).await.unwrap();
+
+  // When: The user declines
+  let decision = serde_json::from_value(json!({
+    "response_type": "code",
+    "client_id": client.client_id,
+    "redirect_uri": REDIRECT_URI,
+    "state": "abc",
+    "code_challenge": CODE_CHALLENGE,
+    "code_challenge_method": "S256",
+    "approve": false,
+  })).unwrap();
+  let redirect = OAuthService::decide(&pool, user.id, decision).await.unwrap();
+
+  // Then: The client is told so and receives no code
+  assert!(client.client_secret.is_some());
+  assert_eq!(query_param(&redirect.redirect_to, "error").as_deref(), Some("access_denied"));
+  assert_eq!(query_param(&redirect.redirect_to, "code"), None);
+}
+
+#[actix_rt::test]
+#[ignore = "needs a Postgres database at DATABASE_URL"]
+async fn test_partner_tokens_cannot_manage_the_account() {
+  // Given: A candidate who granted a partner client access, and the account routes
+  let pool = pool();
+  let keyring = web::Data::new(Keyring::ephemeral());
+  let user = registered_user(&pool, &keyring, "oauth").await;
+  let email = user.email.clone();
+  let access_token = partner_access_token(&pool, &keyring, user.id).await;
+  let app = test::init_service(App::new()
+    .app_data(web::Data::new(pool.clone()))
+    .app_data(keyring.clone())
+    .app_data(web::Data::new(keyring.token_validator()))
+    .service(auth_routes(&configure_login_rate_limiter()))).await;
+
+  // When / Then: The partner cannot turn on MFA for the account
+  let req = test::TestRequest::post().uri("/auth/mfa/totp/enroll").insert_header(bearer(&access_token)).to_request();
+  assert_eq!(test::call_service(&app, req).await.status(), StatusCode::FORBIDDEN);
+
+  // And: It cannot change the password, even knowing the current one
+  let req = test::TestRequest::post()
+    .uri("/auth/password/change")
+    .insert_header(bearer(&access_token))
+    .set_json(json!({ "email": email, "current_password": "Password123!", "new_password": "Taken0ver!Password" }))
+    .to_request();
+  assert_eq!(test::call_service(&app, req).await.status(), StatusCode::FORBIDDEN);
+
+  // And: It can neither see nor unlink the identities used to sign in
+  let req = test::TestRequest::get().uri("/auth/identities").insert_header(bearer(&access_token)).to_request();
+  assert_eq!(test::call_service(&app, req).await.status(), StatusCode::FORBIDDEN);
+  let req = test::TestRequest::delete()
+    .uri(&format!("/auth/identities/{}", Uuid::new_v4()))
+    .insert_header(bearer(&access_token))
+    .to_request();
+  assert_eq!(test::call_service(&app, req).await.status(), StatusCode::FORBIDDEN);
+}
@@ -0,0 +1,281 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use actix_web::http::{header, StatusCode};
+use actix_web::{test, web, App, HttpResponse, HttpServer};
+use chrono::Utc;
+use serde_json::{json, Value};
+use uuid::Uuid;
+
+use crate::config::database::DbPool;
+use crate::config::keyring::Keyring;
+use crate::config::oidc::{OidcProvider, OidcProviderConfig, OidcProviders};
+use crate::errors::error::AuthError;
+use crate::handlers::oidc::OIDC_STATE_COOKIE;
+use crate::models::auth::LoginOutcome;
+use crate::models::session::ClientInfo;
+use crate::repositories::user_repository::UserRepository;
+use crate::middleware::rate_limiter::configure_login_rate_limiter;
+use crate::routes::auth_routes;
+use crate::services::oauth_service::OAuthService;
+use crate::services::oidc_service::OidcService;
+use crate::tests::support::{pool, registered_user};
+
+const CLIENT_ID: &str = "resume-api";
+const REDIRECT_URI: &str = "http://localhost/auth/oidc/mock/callback";
+
+/// A stand-in OpenID provider served over HTTP on a free local port. The test
+/// plays the user at the authorization step by calling `authorize`.
+struct MockIssuer {
+  base_url: String,
+  keyring: Keyring,
+  /// Issued codes, mapped to the PKCE challenge and the ID token claims.
+  codes: Mutex<HashMap<String, (String, Value)>>,
+}
+
+impl MockIssuer {
+  async fn start() -> Arc<MockIssuer> {
+    let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+    let issuer = Arc::new(MockIssuer {
+      base_url: format!("http://{}", listener.local_addr().unwrap()),
+      keyring: Keyring::ephemeral(),
+      codes: Mutex::new(HashMap::new()),
+    });
+
+    let data = web::Data::from(issuer.clone());
+    let server = HttpServer::new(move || App::new()
+      .app_data(data.clone())
+      .route("/.well-known/openid-configuration", web::get().to(discovery))
+      .route("/jwks", web::get().to(jwks))
+      .route("/token", web::post().to(token)))
+      .workers(1)
+      .listen(listener).unwrap()
+      .run();
+    actix_rt::spawn(server);
+    issuer
+  }
+
+  fn provider(&self) -> OidcProvider {
+    OidcProvider::new(self.config())
+  }
+
+  fn config(&self) -> OidcProviderConfig {
+    OidcProviderConfig {
+      name: "mock".to_string(),
+      issuer_url: self.base_url.clone(),
+      client_id: CLIENT_ID.to_string(),
+      client_secret: Some("mock-secret".to_string()),
+      redirect_uri: REDIRECT_URI.to_string(),
+      scopes: "openid email profile".to_string(),
+    }
+  }
+
+  fn id_token(&self, claims: Value) -> String {
+    let now = Utc::now().timestamp();
+    let mut token = json!({ "iss": self.base_url, "aud": CLIENT_ID, "iat": now, "exp": now + 300 });
+    token.as_object_mut().unwrap().extend(claims.as_object().unwrap().clone());
+    self.keyring.sign(&token).unwrap()
+  }
+
+  /// Signs `identity` in at the provider for the request in `authorization_url`
+  /// and returns the code and state it would redirect back with.
+  fn authorize(&self, authorization_url: &str, identity: Value) -> (String, String) {
+    let mut claims = identity;
+    claims["nonce"] = json!(query_param(authorization_url, "nonce"));
+    let code = Uuid::new_v4().to_string();
+    let challenge = query_param(authorization_url, "code_challenge");
+    self.codes.lock().unwrap().insert(code.clone(), (challenge, claims));
+    (code, query_param(authorization_url, "state"))
+  }
+}
+
+async fn discovery(issuer: web::Data<MockIssuer>) -> HttpResponse {
+  HttpResponse::Ok().json(json!({
+    "issuer": issuer.base_url,
+    "authorization_endpoint": format!("{}/authorize", issuer.base_url),
+    "token_endpoint": format!("{}/token", issuer.base_url),
+    "jwks_uri": format!("{}/jwks", issuer.base_url),
+  }))
+}
+
+async fn jwks(issuer: web::Data<MockIssuer>) -> HttpResponse {
+  HttpResponse::Ok().json(issuer.keyring.jwks())
+}
+
+async fn token(issuer: web::Data<MockIssuer>, form: web::Form<HashMap<String, String>>) -> HttpResponse {
+  let Some((challenge, claims)) = issuer.codes.lock().unwrap().remove(&form["code"]) else {
+    return HttpResponse::BadRequest().json(json!({ "error": "invalid_grant" }));
+  };
+  if !OAuthService::verify_pkce(&form["code_verifier"], &challenge) {
+    return HttpResponse::BadRequest().json(json!({ "error": "invalid_grant" }));
+  }
+
+  HttpResponse::Ok().json(json!({ "access_token": "unused", "token_type": "Bearer", "id_token": issuer.id_token(claims) }))
+}
+
+fn query_param(url: &str, name: &str) -> String {
+  url::Url::parse(url).unwrap().query_pairs().find(|(key, _)| key == name).unwrap().1.into_owned()
+}
+
+/// Runs a whole sign-in against the service, without the HTTP layer.
+async fn sign_in(pool: &DbPool, keyring: &Keyring, issuer: &MockIssuer, provider: &OidcProvider, identity: Value) -> Result<LoginOutcome, AuthError> {
+  let started = OidcService::start(provider, keyring).await.unwrap();
+  let (code, state) = issuer.authorize(&started.authorization_url, identity);
+  let query = serde_json::from_value(json!({ "code": code, "state": state })).unwrap();
+  OidcService::callback(pool, keyring, provider, query, Some(&started.state_token), &ClientInfo::default()).await
+}
+
+#[actix_rt::test]
+async fn test_id_token_is_checked_against_issuer_audience_and_nonce() {
+  // Given: A provider and ID tokens for a sign-in that used the nonce "n-1"
+  let issuer = MockIssuer::start().await;
+  let provider = issuer.provider();
+  let valid = issuer.id_token(json!({ "sub": "alice", "nonce": "n-1" }));
+  let other_audience = issuer.id_token(json!({ "sub": "alice", "nonce": "n-1", "aud": "someone-else" }));
+  let forged = Keyring::ephemeral().sign(&json!({
+    "iss": issuer.base_url, "aud": CLIENT_ID, "sub": "alice", "nonce": "n-1",
+    "iat": Utc::now().timestamp(), "exp": Utc::now().timestamp() + 300,
+  })).unwrap();
+
+  // When / Then: Only the token signed by the provider, for us, with our nonce is accepted
+  let claims = OidcService::validate_id_token(&provider, &valid, "n-1").await.unwrap();
+  assert_eq!(claims.sub, "alice");
+  assert!(matches!(OidcService::validate_id_token(&provider, &valid, "n-2").await, Err(AuthError::InvalidIdToken)));
+  assert!(matches!(OidcService::validate_id_token(&provider, &other_audience, "n-1").await, Err(AuthError::InvalidIdToken)));
+  assert!(matches!(OidcService::validate_id_token(&provider, &forged, "n-1").await, Err(AuthError::InvalidIdToken)));
+}
+
+#[actix_rt::test]
+async fn test_start_redirects_to_the_provider_with_state_nonce_and_pkce() {
+  // Given: The auth routes with one configured provider
+  let issuer = MockIssuer::start().await;
+  let app = test::init_service(App::new()
+    .app_data(web::Data::new(OidcProviders::new(vec![issuer.config()])))
+    .app_data(web::Data::new(Keyring::ephemeral()))
+    .service(auth_routes(&configure_login_rate_limiter()))).await;
+
+  // When: A sign-in is started, and one is started for an unknown provider
+  let response = test::call_service(&app, test::TestRequest::get().uri("/auth/oidc/mock/start").to_request()).await;
+  let unknown = test::call_service(&app, test::TestRequest::get().uri("/auth/oidc/nope/start").to_request()).await;
+
+  // Then: The browser is sent to the discovered authorization endpoint
+  assert_eq!(response.status(), StatusCode::FOUND);
+  let location = response.headers().get(header::LOCATION).unwrap().to_str().unwrap().to_string();
+  assert!(location.starts_with(&format!("{}/authorize?", issuer.base_url)));
+  assert_eq!(query_param(&location, "client_id"), CLIENT_ID);
+  assert_eq!(query_param(&location, "redirect_uri"), REDIRECT_URI);
+  assert_eq!(query_param(&location, "code_challenge_method"), "S256");
+  assert!(!query_param(&location, "nonce").is_empty());
+
+  // And: The state is bound to the browser in an HttpOnly cookie
+  let cookie = response.response().cookies().find(|cookie| cookie.name() == OIDC_STATE_COOKIE).unwrap();
+  assert_eq!(cookie.http_only(), Some(true));
+  assert_eq!(unknown.status(), StatusCode::NOT_FOUND);
+}
+
+#[actix_rt::test]
+#[ignore = "needs a Postgres database at DATABASE_URL"]
+async fn test_first_sign_in_creates_an_account_and_later_ones_reuse_it() {
+  // Given: A provider vouching for a verified email nobody has registered
+  let pool = pool();
+  let issuer = MockIssuer::start().await;
+  let keyring = web::Data::new(Keyring::ephemeral());
+  let app = test::init_service(App::new()
+    .app_data(web::Data::new(pool.clone()))
+    .app_data(web::Data::new(OidcProviders::new(vec![issuer.config()])))
+    .app_data(keyring.clone())
+    .app_data(web::Data::new(keyring.token_validator()))
+    .service(auth_routes(&configure_login_rate_limiter()))).await;
+  let subject = Uuid::new_v4().to_string();
+  let identity = json!({
+    "sub": subject,
+    "email": format!("oidc-{}@example.com", subject),
+    "email_verified": true,
+    "preferred_username": "oidcuser",
+  });
+
+  let mut sessions = Vec::new();
+  for _ in 0..2 {
+    // When: The user signs in with the provider
+    let started = test::call_service(&app, test::TestRequest::get().uri("/auth/oidc/mock/start").to_request()).await;
+    let location = started.headers().get(header::LOCATION).unwrap().to_str().unwrap().to_string();
+    let state_cookie = started.response().cookies().find(|cookie| cookie.name() == OIDC_STATE_COOKIE).unwrap().into_owned();
+    let (code, state) = issuer.authorize(&location, identity.clone());
+
+    let req = test::TestRequest::get()
+      .uri(&format!("/auth/oidc/mock/callback?code={}&state={}", code, state))
+      .cookie(state_cookie)
+      .to_request();
+    let outcome: Value = test::call_and_read_body_json(&app, req).await;
+
+    // Then: They get our own tokens
+    let claims = keyring.token_validator().validate(outcome["token"].as_str().unwrap()).await.unwrap();
+    assert!(claims.email_verified);
+    assert_eq!(claims.roles, vec!["candidate"]);
+    sessions.push((claims.sub, outcome["token"].as_str().unwrap().to_string()));
+  }
+
+  // And: Both sign-ins reached the same account, which lists the linked identity
+  assert_eq!(sessions[0].0, sessions[1].0);
+  let req = test::TestRequest::get()
+    .uri("/auth/identities")
+    .insert_header(("Authorization", format!("Bearer {}", sessions[1].1)))
+    .to_request();
+  let identities: Value = test::call_and_read_body_json(&app, req).await;
+  assert_eq!(identities.as_array().unwrap().len(), 1);
+  assert_eq!(identities[0]["provider"], "mock");
+  assert_eq!(identities[0]["subject"], subject.as_str());
+}
+
+#[actix_rt::test]
+#[ignore = "needs a Postgres database at DATABASE_URL"]
+async fn test_callback_rejects_a_state_from_another_sign_in() {
+  // Given: A sign-in started in this browser
+  let pool = pool();
+  let issuer = MockIssuer::start().await;
+  let app = test::init_service(App::new()
+    .app_data(web::Data::new(pool))
+    .app_data(web::Data::new(OidcProviders::new(vec![issuer.config()])))
+    .app_data(web::Data::new(Keyring::ephemeral()))
+    .service(auth_routes(&configure_login_rate_limiter()))).await;
+  let started = test::call_service(&app, test::TestRequest::get().uri("/auth/oidc/mock/start").to_request()).await;
+  let state_cookie = started.response().cookies().find(|cookie| cookie.name() == OIDC_STATE_COOKIE).unwrap().into_owned();
+
+  // When: The callback arrives with a state this browser never received
+  let req = test::TestRequest::get()
+    .uri("/auth/oidc/mock/callback?code=abc&state=attacker-state")
+    .cookie(state_cookie)
+    .to_request();
+  let response = test::call_service(&app, req).await;
+
+  // Then: It is refused before the code is redeemed
+  assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+  let body: Value = test::read_body_json(response).await;
+  assert_eq!(body["code"], "invalid_oidc_state");
+}
+
+#[actix_rt::test]
+#[ignore = "needs a Postgres database at DATABASE_URL"]
+async fn test_identity_links_to_an_existing_account_only_once_its_email_is_verified() {
+  // Given: A password account and a provider vouching for the same email
+  let pool = pool();
+  let issuer = MockIssuer::start().await;
+  let provider = issuer.provider();
+  let keyring = Keyring::ephemeral();
+  let registered = registered_user(&pool, &keyring, "oidc").await;
+  let email = registered.email.clone();
+  let identity = json!({ "sub": Uuid::new_v4().to_string(), "email": email, "email_verified": true });
+
+  // When: The provider identity is used while the account's email is unverified
+  let unverified = sign_in(&pool, &keyring, &issuer, &provider, identity.clone()).await;
+
+  // Then: It is not linked, so an unverified registration cannot capture it
+  assert!(matches!(unverified, Err(AuthError::IdentityNotLinkable)));
+
+  // When: The account's email is verified and the user signs in again
+  UserRepository::mark_email_verified(&pool, registered.id, &email).await.unwrap();
+  let outcome = serde_json::to_value(sign_in(&pool, &keyring, &issuer, &provider, identity).await.unwrap()).unwrap();
+
+  // Then: They are signed in to the existing account
+  let claims = keyring.token_validator().validate(outcome["token"].as_str().unwrap()).await.unwrap();
+  assert_eq!(claims.sub, registered.id.to_string());
+}
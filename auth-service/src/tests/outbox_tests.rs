@@ -0,0 +1,114 @@
+use std::sync::Arc;
+
+use actix_web::web;
+use chrono::Utc;
+use diesel::prelude::*;
+use serde_json::Value;
+use shared::events::InMemoryEventBus;
+use shared::outbox::{OutboxMessage, OutboxRelay};
+use uuid::Uuid;
+
+use crate::config::database::DbPool;
+use crate::config::keyring::Keyring;
+use crate::models::auth::{LoginRequest, UpdatePasswordRequest};
+use crate::models::schema::outbox;
+use crate::models::session::ClientInfo;
+use crate::services::auth_service::AuthService;
+use crate::tests::support::{pool, registered_user};
+
+fn messages(pool: &DbPool, key: &str) -> Vec<OutboxMessage> {
+  let conn = &mut pool.get().expect("Failed to get DB connection from pool");
+  outbox::table
+    .filter(outbox::event_key.eq(key))
+    .order(outbox::created_at.asc())
+    .load(conn)
+    .unwrap()
+}
+
+fn event_types(messages: &[OutboxMessage]) -> Vec<String> {
+  messages.iter().map(|message| message.payload["event_type"].as_str().unwrap().to_string()).collect()
+}
+
+#[actix_rt::test]
+#[ignore = "needs a Postgres database at DATABASE_URL"]
+async fn test_events_are_written_with_the_change_they_describe() {
+  // Given: A registered candidate, given the default role and signed in
+  let pool = pool();
+  let keyring = web::Data::new(Keyring::ephemeral());
+  let registered = registered_user(&pool, &keyring, "outbox").await;
+  let email = registered.email.clone();
+  let key = registered.id.to_string();
+  let recorded = messages(&pool, &key);
+  assert_eq!(event_types(&recorded), vec!["role_assigned", "login_succeeded"]);
+  assert_eq!(recorded[1].payload["metadata"]["email"], email.as_str());
+
+  // When: A password change is refused by the policy
+  let change = |new: &str| UpdatePasswordRequest {
+    email: email.clone(),
+    current_password: "Password123!".to_string(),
+    new_password: new.to_string(),
+  };
+  assert!(AuthService::change_password(&pool, &keyring, registered.id, change("short"), &ClientInfo::default()).await.is_err());
+
+  // Then: Nothing was changed, so nothing is recorded
+  assert_eq!(messages(&pool, &key).len(), 2);
+
+  // When: The change goes through
+  AuthService::change_password(&pool, &keyring, registered.id, change("Second-Pass-2"), &ClientInfo::default()).await.unwrap();
+
+  // Then: The change and the new session are both recorded, for the user
+  let recorded = messages(&pool, &key);
+  assert_eq!(event_types(&recorded), vec!["role_assigned", "login_succeeded", "password_changed", "login_succeeded"]);
+  assert!(recorded.iter().all(|message| message.topic == "auth.audit" && message.published_at.is_none()));
+  assert_eq!(recorded[2].payload["user_id"], Value::String(key));
+}
+
+#[actix_rt::test]
+#[ignore = "needs a Postgres database at DATABASE_URL"]
+async fn test_relay_retries_until_the_bus_accepts() {
+  // Given: A failed login, recorded under the email
+  let pool = pool();
+  let keyring = web::Data::new(Keyring::ephemeral());
+  let email = format!("outbox-{}@example.com", Uuid::new_v4());
+  let request = LoginRequest { email: email.clone(), password: "Password123!".to_string() };
+  assert!(AuthService::authenticate(&pool, &keyring, &request, &ClientInfo::default()).await.is_err());
+  let id = messages(&pool, &email)[0].id;
+
+  // When: The broker is down while the relay runs
+  let bus = Arc::new(InMemoryEventBus::default());
+  let relay = OutboxRelay::new(pool.clone(), bus.clone());
+  bus.fail_next(usize::MAX);
+  for _ in 0..1000 {
+    relay.relay_due().await.unwrap();
+    if messages(&pool, &email)[0].attempts > 0 {
+      break;
+    }
+  }
+
+  // Then: The event stays in the outbox, with the error, for a later retry
+  let failed = &messages(&pool, &email)[0];
+  assert_eq!(failed.attempts, 1);
+  assert!(failed.published_at.is_none());
+  assert!(failed.last_error.as_deref().unwrap().contains("broker unavailable"));
+  assert!(failed.next_attempt_at > Utc::now().naive_utc());
+
+  // When: The broker is back and the retry is due
+  bus.fail_next(0);
+  {
+    let conn = &mut pool.get().expect("Failed to get DB connection from pool");
+    diesel::update(outbox::table.find(id)).set(outbox::next_attempt_at.eq(Utc::now().naive_utc())).execute(conn).unwrap();
+  }
+  while relay.relay_due().await.unwrap() > 0 {}
+
+  // Then: It is published once, keyed by the email
+  let published = messages(&pool, &email);
+  assert!(published[0].published_at.is_some());
+  assert_eq!(published[0].attempts, 2);
+  let events: Vec<Value> = bus.published().iter()
+    .filter(|event| event.key == email)
+    .map(|event| serde_json::from_str(&event.payload).unwrap())
+    .collect();
+  assert_eq!(events.len(), 1);
+  assert_eq!(events[0]["event_type"], "login_failed");
+  assert_eq!(events[0]["id"], id.to_string());
+}
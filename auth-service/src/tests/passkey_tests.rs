@@ -0,0 +1,225 @@
+use std::sync::Arc;
+use actix_web::http::StatusCode;
+use actix_web::{test, web, App};
+use serde_json::{json, Value};
+use totp_rs::{Algorithm, Secret, TOTP};
+
+use crate::config::database::DbPool;
+use crate::config::keyring::Keyring;
+use crate::models::auth::{LoginOutcome, LoginRequest};
+use crate::models::session::ClientInfo;
+use crate::middleware::rate_limiter::configure_login_rate_limiter;
+use crate::routes::auth_routes;
+use crate::services::auth_service::AuthService;
+use crate::services::mfa_service::MfaService;
+use crate::services::token_revocation_service::DatabaseRevocations;
+use crate::tests::software_authenticator::{Attestation, SoftwareAuthenticator};
+use crate::tests::support::{bearer, pool, registered_user, PASSWORD};
+
+const ORIGIN: &str = "http://localhost:3000";
+
+/// Registers a candidate and signs them in with their password.
+async fn password_session(pool: &DbPool, keyring: &Keyring) -> String {
+  let email = registered_user(pool, keyring, "passkeys").await.email;
+  let request = LoginRequest { email, password: PASSWORD.to_string() };
+  match AuthService::authenticate(pool, keyring, &request, &ClientInfo::default()).await.unwrap() {
+    LoginOutcome::Tokens(tokens) => tokens.token,
+    LoginOutcome::MfaRequired(_) => panic!("expected tokens"),
+  }
+}
+
+#[actix_rt::test]
+#[ignore = "needs a Postgres database at DATABASE_URL"]
+async fn test_register_passkey_and_sign_in_with_it() {
+  // Given: A signed-in candidate with a software authenticator
+  let pool = pool();
+  let keyring = web::Data::new(Keyring::ephemeral());
+  let validator = keyring.token_validator().with_revocation_check(Arc::new(DatabaseRevocations::new(pool.clone())));
+  let app = test::init_service(App::new()
+    .app_data(web::Data::new(pool.clone()))
+    .app_data(keyring.clone())
+    .app_data(web::Data::new(validator))
+    .service(auth_routes(&configure_login_rate_limiter()))).await;
+  let token = password_session(&pool, &keyring).await;
+  let mut authenticator = SoftwareAuthenticator::new(ORIGIN);
+
+  // When: They register a passkey with packed self-attestation
+  let req = test::TestRequest::post().uri("/auth/passkeys/register/start").insert_header(bearer(&token)).to_request();
+  let ceremony: Value = test::call_and_read_body_json(&app, req).await;
+  assert_eq!(ceremony["public_key"]["authenticatorSelection"]["residentKey"], "required");
+  let finish = json!({
+    "ceremony_token": ceremony["ceremony_token"],
+    "name": "Laptop",
+    "credential": authenticator.create(&ceremony["public_key"], Attestation::PackedSelf),
+  });
+  let req = test::TestRequest::post()
+    .uri("/auth/passkeys/register/finish")
+    .insert_header(bearer(&token))
+    .set_json(&finish)
+    .to_request();
+  let res = test::call_service(&app, req).await;
+
+  // Then: The passkey is stored under its name
+  assert_eq!(res.status(), StatusCode::CREATED);
+  let passkey: Value = test::read_body_json(res).await;
+  assert_eq!(passkey["name"], "Laptop");
+
+  // And: The same registration cannot be replayed
+  let req = test::TestRequest::post()
+    .uri("/auth/passkeys/register/finish")
+    .insert_header(bearer(&token))
+    .set_json(&finish)
+    .to_request();
+  assert_eq!(test::call_service(&app, req).await.status(), StatusCode::BAD_REQUEST);
+
+  // When: They sign in with the passkey, without giving an email
+  let req = test::TestRequest::post().uri("/auth/passkeys/login/start").set_json(json!({})).to_request();
+  let ceremony: Value = test::call_and_read_body_json(&app, req).await;
+  assert_eq!(ceremony["public_key"]["allowCredentials"], json!([]));
+  let finish = json!({
+    "ceremony_token": ceremony["ceremony_token"],
+    "credential": authenticator.get(&ceremony["public_key"]),
+  });
+  let req = test::TestRequest::post().uri("/auth/passkeys/login/finish").set_json(&finish).to_request();
+  let res = test::call_service(&app, req).await;
+
+  // Then: They get a token pair that works like a password login's
+  assert_eq!(res.status(), StatusCode::OK);
+  let tokens: Value = test::read_body_json(res).await;
+  let req = test::TestRequest::get()
+    .uri("/auth/passkeys")
+    .insert_header(bearer(tokens["token"].as_str().unwrap()))
+    .to_request();
+  let passkeys: Value = test::call_and_read_body_json(&app, req).await;
+  assert_eq!(passkeys.as_array().unwrap().len(), 1);
+  assert!(passkeys[0]["last_used_at"].is_string());
+
+  // And: The assertion cannot be replayed
+  let req = test::TestRequest::post().uri("/auth/passkeys/login/finish").set_json(&finish).to_request();
+  assert_eq!(test::call_service(&app, req).await.status(), StatusCode::BAD_REQUEST);
+}
+
+#[actix_rt::test]
+#[ignore = "needs a Postgres database at DATABASE_URL"]
+async fn test_cloned_authenticator_and_foreign_origin_are_refused() {
+  // Given: A candidate with a registered passkey, and a copy of their authenticator
+  let pool = pool();
+  let keyring = web::Data::new(Keyring::ephemeral());
+  let validator = keyring.token_validator().with_revocation_check(Arc::new(DatabaseRevocations::new(pool.clone())));
+  let app = test::init_service(App::new()
+    .app_data(web::Data::new(pool.clone()))
+    .app_data(keyring.clone())
+    .app_data(web::Data::new(validator))
+    .service(auth_routes(&configure_login_rate_limiter()))).await;
+  let token = password_session(&pool, &keyring).await;
+  let mut authenticator = SoftwareAuthenticator::new(ORIGIN);
+  let req = test::TestRequest::post().uri("/auth/passkeys/register/start").insert_header(bearer(&token)).to_request();
+  let ceremony: Value = test::call_and_read_body_json(&app, req).await;
+  let req = test::TestRequest::post()
+    .uri("/auth/passkeys/register/finish")
+    .insert_header(bearer(&token))
+    .set_json(json!({
+      "ceremony_token": ceremony["ceremony_token"],
+      "credential": authenticator.create(&ceremony["public_key"], Attestation::None),
+    }))
+    .to_request();
+  assert_eq!(test::call_service(&app, req).await.status(), StatusCode::CREATED);
+  let mut clone = authenticator.clone();
+
+  // When: The original signs in, then the copy does
+  let mut statuses = Vec::new();
+  for authenticator in [&mut authenticator, &mut clone] {
+    let req = test::TestRequest::post().uri("/auth/passkeys/login/start").set_json(json!({})).to_request();
+    let ceremony: Value = test::call_and_read_body_json(&app, req).await;
+    let req = test::TestRequest::post()
+      .uri("/auth/passkeys/login/finish")
+      .set_json(json!({
+        "ceremony_token": ceremony["ceremony_token"],
+        "credential": authenticator.get(&ceremony["public_key"]),
+      }))
+      .to_request();
+    statuses.push(test::call_service(&app, req).await.status());
+  }
+
+  // Then: The copy's repeated signature counter is refused
+  assert_eq!(statuses, vec![StatusCode::OK, StatusCode::UNAUTHORIZED]);
+
+  // When: The authenticator is used from another site
+  authenticator.origin = "https://phishing.example".to_string();
+  let req = test::TestRequest::post().uri("/auth/passkeys/login/start").set_json(json!({})).to_request();
+  let ceremony: Value = test::call_and_read_body_json(&app, req).await;
+  let req = test::TestRequest::post()
+    .uri("/auth/passkeys/login/finish")
+    .set_json(json!({
+      "ceremony_token": ceremony["ceremony_token"],
+      "credential": authenticator.get(&ceremony["public_key"]),
+    }))
+    .to_request();
+  let res = test::call_service(&app, req).await;
+
+  // Then: The assertion is refused
+  assert_eq!(res.status(), StatusCode::UNAUTHORIZED);
+  let body: Value = test::read_body_json(res).await;
+  assert_eq!(body["code"], "passkey_verification_failed");
+}
+
+#[actix_rt::test]
+#[ignore = "needs a Postgres database at DATABASE_URL"]
+async fn test_passkey_without_user_verification_still_asks_for_totp() {
+  // Given: A candidate with TOTP enabled, and a passkey on an authenticator that
+  // does not verify the user, which the relying party allows
+  std::env::set_var("WEBAUTHN_USER_VERIFICATION", "preferred");
+  std::env::set_var("TOTP_ENCRYPTION_KEY", "ZGV2LW9ubHktdG90cC1lbmNyeXB0aW9uLWtleS0zMmI=");
+  let pool = pool();
+  let keyring = web::Data::new(Keyring::ephemeral());
+  let validator = keyring.token_validator().with_revocation_check(Arc::new(DatabaseRevocations::new(pool.clone())));
+  let app = test::init_service(App::new()
+    .app_data(web::Data::new(pool.clone()))
+    .app_data(keyring.clone())
+    .app_data(web::Data::new(validator))
+    .service(auth_routes(&configure_login_rate_limiter()))).await;
+  let user = registered_user(&pool, &keyring, "passkeys-mfa").await;
+  let token = user.tokens.unwrap().token;
+  let mut authenticator = SoftwareAuthenticator::new(ORIGIN);
+  authenticator.user_verification = false;
+  let req = test::TestRequest::post().uri("/auth/passkeys/register/start").insert_header(bearer(&token)).to_request();
+  let ceremony: Value = test::call_and_read_body_json(&app, req).await;
+  let req = test::TestRequest::post()
+    .uri("/auth/passkeys/register/finish")
+    .insert_header(bearer(&token))
+    .set_json(json!({
+      "ceremony_token": ceremony["ceremony_token"],
+      "credential": authenticator.create(&ceremony["public_key"], Attestation::None),
+    }))
+    .to_request();
+  assert_eq!(test::call_service(&app, req).await.status(), StatusCode::CREATED);
+  let enrollment = MfaService::enroll(&pool, user.id).await.unwrap();
+  let secret = Secret::Encoded(enrollment.secret).to_bytes().unwrap();
+  let totp = TOTP::new(Algorithm::SHA1, 6, 0, 30, secret, None, user.email.clone()).unwrap();
+  MfaService::confirm(&pool, user.id, &totp.generate_current().unwrap()).await.unwrap();
+  let sign_in = |authenticator: &mut SoftwareAuthenticator, ceremony: &Value| test::TestRequest::post()
+    .uri("/auth/passkeys/login/finish")
+    .set_json(json!({
+      "ceremony_token": ceremony["ceremony_token"],
+      "credential": authenticator.get(&ceremony["public_key"]),
+    }))
+    .to_request();
+
+  // When: They sign in with the passkey
+  let req = test::TestRequest::post().uri("/auth/passkeys/login/start").set_json(json!({})).to_request();
+  let ceremony: Value = test::call_and_read_body_json(&app, req).await;
+  let body: Value = test::call_and_read_body_json(&app, sign_in(&mut authenticator, &ceremony)).await;
+
+  // Then: They are asked for their TOTP code instead of getting tokens
+  assert_eq!(body["mfa_required"], true);
+  assert!(body.get("token").is_none());
+
+  // When: The authenticator verifies them
+  authenticator.user_verification = true;
+  let req = test::TestRequest::post().uri("/auth/passkeys/login/start").set_json(json!({})).to_request();
+  let ceremony: Value = test::call_and_read_body_json(&app, req).await;
+  let body: Value = test::call_and_read_body_json(&app, sign_in(&mut authenticator, &ceremony)).await;
+
+  // Then: The passkey is both factors, and they get tokens
+  assert!(body["token"].is_string());
+}
@@ -0,0 +1,77 @@
+use actix_web::web;
+use argon2::{Algorithm, Argon2, Params, PasswordHasher, Version};
+use argon2::password_hash::SaltString;
+use uuid::Uuid;
+
+use crate::config::keyring::Keyring;
+use crate::models::auth::{LoginOutcome, LoginRequest};
+use crate::models::session::ClientInfo;
+use crate::models::user::NewUser;
+use crate::repositories::user_repository::UserRepository;
+use crate::services::auth_service::AuthService;
+use crate::utils::hash_password::{hash_password, hash_password_with};
+use crate::utils::verify_password::{needs_rehash, verify_password, HashScheme};
+use crate::tests::support::pool;
+
+#[test]
+fn test_legacy_bcrypt_hashes_verify_and_need_rehash() {
+  // Given: A hash from the old system
+  let hash = bcrypt::hash("Password123!", 4).unwrap();
+
+  // When / Then: It is recognised, checked, and due for an upgrade
+  assert_eq!(HashScheme::of(&hash), HashScheme::Bcrypt);
+  assert!(verify_password(&hash, "Password123!"));
+  assert!(!verify_password(&hash, "Password123?"));
+  assert!(needs_rehash(&hash));
+}
+
+#[test]
+fn test_only_current_argon2id_parameters_are_kept() {
+  // Given: A hash made with the current parameters, a cheaper one, and an Argon2i one
+  let current = hash_password("Password123!").unwrap();
+  let cheaper = hash_password_with("Password123!", Params::new(8 * 1024, 1, 1, None).unwrap()).unwrap();
+  let salt = SaltString::generate(&mut rand::thread_rng());
+  let argon2i = Argon2::new(Algorithm::Argon2i, Version::V0x13, Params::default())
+    .hash_password(b"Password123!", &salt).unwrap().to_string();
+
+  // When / Then: All of them verify, but only the current one is kept
+  for hash in [&current, &cheaper, &argon2i] {
+    assert!(verify_password(hash, "Password123!"));
+  }
+  assert!(!needs_rehash(&current));
+  assert!(needs_rehash(&cheaper));
+  assert!(needs_rehash(&argon2i));
+}
+
+#[test]
+fn test_unknown_schemes_never_verify() {
+  // Given / When / Then: Plaintext or unrecognised hashes are refused
+  assert_eq!(HashScheme::of("Password123!"), HashScheme::Unknown);
+  assert!(!verify_password("Password123!", "Password123!"));
+  assert!(!verify_password("$1$salt$hash", "Password123!"));
+}
+
+#[actix_rt::test]
+#[ignore = "needs a Postgres database at DATABASE_URL"]
+async fn test_login_upgrades_a_bcrypt_hash() {
+  // Given: An account migrated with a bcrypt hash
+  let pool = pool();
+  let keyring = web::Data::new(Keyring::ephemeral());
+  let email = format!("legacy-{}@example.com", Uuid::new_v4());
+  let user = UserRepository::create(&mut pool.get().unwrap(), NewUser {
+    username: "legacy".to_string(),
+    email: email.clone(),
+    password: bcrypt::hash("Password123!", 4).unwrap(),
+  }).unwrap();
+
+  // When: They log in
+  let request = LoginRequest { email, password: "Password123!".to_string() };
+  let outcome = AuthService::authenticate(&pool, &keyring, &request, &ClientInfo::default()).await.unwrap();
+
+  // Then: The login succeeds and the stored hash is now Argon2id
+  assert!(matches!(outcome, LoginOutcome::Tokens(_)));
+  let stored = UserRepository::find_by_id(&pool, user.id).await.unwrap();
+  assert!(stored.password.starts_with("$argon2id$"));
+  assert!(verify_password(&stored.password, "Password123!"));
+  assert!(!needs_rehash(&stored.password));
+}
@@ -0,0 +1,94 @@
+use actix_web::web;
+use chrono::{Duration, Utc};
+use uuid::Uuid;
+
+use crate::config::keyring::Keyring;
+use crate::errors::error::AuthError;
+use crate::models::auth::{RegisterRequest, UpdatePasswordRequest};
+use crate::models::password_reset_token::NewPasswordResetToken;
+use crate::models::session::ClientInfo;
+use crate::repositories::password_reset_repository::PasswordResetRepository;
+use crate::services::auth_service::AuthService;
+use crate::services::password_reset_service::PasswordResetService;
+use crate::utils::opaque_token::{generate_opaque_token, hash_opaque_token};
+use crate::tests::support::pool;
+
+fn error_codes(error: AuthError) -> Vec<String> {
+  match error {
+    AuthError::ValidationError(errors) => errors.field_errors()["password"].iter().map(|e| e.code.to_string()).collect(),
+    other => panic!("expected validation errors, got {:?}", other),
+  }
+}
+
+#[actix_rt::test]
+#[ignore = "needs a Postgres database at DATABASE_URL"]
+async fn test_sign_up_refuses_passwords_with_personal_info() {
+  // Given: A candidate signing up as "traveller"
+  let pool = pool();
+  let keyring = web::Data::new(Keyring::ephemeral());
+  let email = format!("policy-{}@example.com", Uuid::new_v4());
+
+  // When: Their password is their username
+  let result = AuthService::register(&pool, &keyring, RegisterRequest {
+    username: "traveller".to_string(),
+    email: email.clone(),
+    password: "Traveller-2024!".to_string(),
+  }, &ClientInfo::default()).await;
+
+  // Then: The password field explains why
+  assert_eq!(error_codes(result.err().unwrap()), vec!["password_contains_username"]);
+
+  // When: It is too weak as well as too short
+  let result = AuthService::register(&pool, &keyring, RegisterRequest {
+    username: "traveller".to_string(),
+    email,
+    password: "abc".to_string(),
+  }, &ClientInfo::default()).await;
+
+  // Then: Every reason is listed
+  let codes = error_codes(result.err().unwrap());
+  assert!(codes.contains(&"password_too_short".to_string()));
+  assert!(codes.contains(&"password_missing_digit".to_string()));
+}
+
+#[actix_rt::test]
+#[ignore = "needs a Postgres database at DATABASE_URL"]
+async fn test_recent_passwords_cannot_be_reused() {
+  // Given: A candidate who has changed their password once
+  let pool = pool();
+  let keyring = web::Data::new(Keyring::ephemeral());
+  let email = format!("policy-{}@example.com", Uuid::new_v4());
+  let registered = AuthService::register(&pool, &keyring, RegisterRequest {
+    username: "traveller".to_string(),
+    email: email.clone(),
+    password: "Password123!".to_string(),
+  }, &ClientInfo::default()).await.unwrap();
+  let change = |current: &str, new: &str| UpdatePasswordRequest {
+    email: email.clone(),
+    current_password: current.to_string(),
+    new_password: new.to_string(),
+  };
+  AuthService::change_password(&pool, &keyring, registered.id, change("Password123!", "Second-Pass-2"), &ClientInfo::default())
+    .await.unwrap();
+
+  // When: They try to go back to the first password, or keep the current one
+  let back = AuthService::change_password(&pool, &keyring, registered.id, change("Second-Pass-2", "Password123!"), &ClientInfo::default()).await;
+  let same = AuthService::change_password(&pool, &keyring, registered.id, change("Second-Pass-2", "Second-Pass-2"), &ClientInfo::default()).await;
+
+  // Then: Both are refused
+  assert_eq!(error_codes(back.err().unwrap()), vec!["password_recently_used"]);
+  assert_eq!(error_codes(same.err().unwrap()), vec!["password_recently_used"]);
+
+  // When: A reset link is used with an old password
+  let token = generate_opaque_token();
+  PasswordResetRepository::create(&pool, NewPasswordResetToken {
+    user_id: registered.id,
+    token_hash: hash_opaque_token(&token),
+    expires_at: (Utc::now() + Duration::minutes(30)).naive_utc(),
+  }).await.unwrap();
+  let result = PasswordResetService::reset_password(&pool, &token, "Password123!").await;
+
+  // Then: It is refused, and the link still works for a fresh password
+  assert_eq!(error_codes(result.err().unwrap()), vec!["password_recently_used"]);
+  PasswordResetService::reset_password(&pool, &token, "Third-Pass-3").await.unwrap();
+}
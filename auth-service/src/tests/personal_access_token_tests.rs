@@ -0,0 +1,175 @@
+use std::sync::Arc;
+use actix_web::http::StatusCode;
+use actix_web::{test, web, App};
+use serde_json::{json, Value};
+
+use crate::config::database::DbPool;
+use crate::config::keyring::Keyring;
+use crate::models::oauth::RegisterClientRequest;
+use crate::models::personal_access_token::CreatePersonalAccessTokenRequest;
+use crate::middleware::rate_limiter::configure_login_rate_limiter;
+use crate::routes::{auth_routes, oauth_routes};
+use crate::services::oauth_service::OAuthService;
+use crate::services::personal_access_token_service::{DatabasePersonalAccessTokens, PersonalAccessTokenService};
+use crate::tests::support::{bearer, pool, registered_user};
+
+async fn session_token(pool: &DbPool, keyring: &Keyring) -> String {
+  registered_user(pool, keyring, "pat").await.tokens.unwrap().token
+}
+
+#[actix_rt::test]
+#[ignore = "needs a Postgres database at DATABASE_URL"]
+async fn test_personal_access_token_lifecycle() {
+  // Given: A signed-in candidate and the auth routes, validating tokens against the database
+  let pool = pool();
+  let keyring = web::Data::new(Keyring::ephemeral());
+  let validator = keyring.token_validator().with_personal_access_tokens(Arc::new(DatabasePersonalAccessTokens::new(pool.clone())));
+  let app = test::init_service(App::new()
+    .app_data(web::Data::new(pool.clone()))
+    .app_data(keyring.clone())
+    .app_data(web::Data::new(validator))
+    .service(auth_routes(&configure_login_rate_limiter()))).await;
+  let session = session_token(&pool, &keyring).await;
+
+  // When: They create a token for re-rendering resumes
+  let req = test::TestRequest::post()
+    .uri("/auth/tokens")
+    .insert_header(("Authorization", format!("Bearer {}", session)))
+    .set_json(json!({ "name": "CI", "scopes": ["resumes:read"], "expires_in_days": 7 }))
+    .to_request();
+  let response = test::call_service(&app, req).await;
+  assert_eq!(response.status(), StatusCode::CREATED);
+  let created: Value = test::read_body_json(response).await;
+  let pat = created["token"].as_str().unwrap().to_string();
+
+  // Then: The plaintext is shown once, and the hash never
+  assert!(pat.starts_with("pat_"));
+  assert!(pat.ends_with(created["token_hint"].as_str().unwrap()));
+  assert!(created.get("token_hash").is_none());
+
+  // When: The token is used as a bearer token
+  let req = test::TestRequest::get()
+    .uri("/auth/tokens/current")
+    .insert_header(("Authorization", format!("Bearer {}", pat)))
+    .to_request();
+  let claims: Value = test::call_and_read_body_json(&app, req).await;
+
+  // Then: It carries only the scoped permissions, and is marked as a token
+  assert_eq!(claims["permissions"], json!(["resumes:read"]));
+  assert_eq!(claims["scope"], "resumes:read");
+  assert_eq!(claims["token_id"], created["id"]);
+
+  // And: It cannot mint further tokens
+  let req = test::TestRequest::post()
+    .uri("/auth/tokens")
+    .insert_header(("Authorization", format!("Bearer {}", pat)))
+    .set_json(json!({ "name": "escalate", "scopes": ["resumes:write"] }))
+    .to_request();
+  assert_eq!(test::call_service(&app, req).await.status(), StatusCode::FORBIDDEN);
+
+  // And: The listing shows when it was last used
+  let req = test::TestRequest::get()
+    .uri("/auth/tokens")
+    .insert_header(("Authorization", format!("Bearer {}", session)))
+    .to_request();
+  let listed: Value = test::call_and_read_body_json(&app, req).await;
+  assert_eq!(listed.as_array().unwrap().len(), 1);
+  assert!(listed[0]["last_used_at"].is_string());
+
+  // When: It is revoked
+  let req = test::TestRequest::delete()
+    .uri(&format!("/auth/tokens/{}", created["id"].as_str().unwrap()))
+    .insert_header(("Authorization", format!("Bearer {}", session)))
+    .to_request();
+  assert_eq!(test::call_service(&app, req).await.status(), StatusCode::NO_CONTENT);
+
+  // Then: It is no longer accepted
+  let req = test::TestRequest::get()
+    .uri("/auth/tokens/current")
+    .insert_header(("Authorization", format!("Bearer {}", pat)))
+    .to_request();
+  assert_eq!(test::call_service(&app, req).await.status(), StatusCode::UNAUTHORIZED);
+}
+
+#[actix_rt::test]
+#[ignore = "needs a Postgres database at DATABASE_URL"]
+async fn test_token_scopes_must_be_held_by_the_user() {
+  // Given: A candidate, who cannot delete users
+  let pool = pool();
+  let keyring = web::Data::new(Keyring::ephemeral());
+  let app = test::init_service(App::new()
+    .app_data(web::Data::new(pool.clone()))
+    .app_data(web::Data::new(keyring.token_validator()))
+    .service(auth_routes(&configure_login_rate_limiter()))).await;
+  let session = session_token(&pool, &keyring).await;
+
+  // When: They ask for a token that could
+  let req = test::TestRequest::post()
+    .uri("/auth/tokens")
+    .insert_header(("Authorization", format!("Bearer {}", session)))
+    .set_json(json!({ "name": "too broad", "scopes": ["users:delete"] }))
+    .to_request();
+  let response = test::call_service(&app, req).await;
+
+  // Then: It is refused, naming the field
+  assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+  let body: Value = test::read_body_json(response).await;
+  assert!(body["fields"]["scopes"].is_array());
+}
+
+#[actix_rt::test]
+#[ignore = "needs a Postgres database at DATABASE_URL"]
+async fn test_personal_access_tokens_cannot_approve_clients() {
+  // Given: A candidate holding a personal access token, and a partner client
+  let pool = pool();
+  let keyring = web::Data::new(Keyring::ephemeral());
+  let validator = keyring.token_validator().with_personal_access_tokens(Arc::new(DatabasePersonalAccessTokens::new(pool.clone())));
+  let app = test::init_service(App::new()
+    .app_data(web::Data::new(pool.clone()))
+    .app_data(keyring.clone())
+    .app_data(web::Data::new(validator))
+    .service(oauth_routes())).await;
+  let user = registered_user(&pool, &keyring, "pat").await;
+  let pat = PersonalAccessTokenService::create(&pool, user.id, CreatePersonalAccessTokenRequest {
+    name: "CI".to_string(),
+    scopes: vec!["resumes:read".to_string()],
+    expires_in_days: Some(1),
+  }).await.unwrap().token;
+  let client = OAuthService::register_client(&pool, user.id, RegisterClientRequest {
+    name: "Partner ATS".to_string(),
+    redirect_uris: vec!["https://partner.example.com/callback".to_string()],
+    scopes: vec!["resumes:read".to_string()],
+    confidential: false,
+    service: false,
+  }).await.unwrap();
+  let authorization = json!({
+    "response_type": "code",
+    "client_id": client.client_id,
+    "redirect_uri": "https://partner.example.com/callback",
+    "code_challenge": "SmQAQBVQhI1AXbCV4bvKvuXY4FyCfav4env_PczM2KA",
+    "code_challenge_method": "S256",
+  });
+
+  // When: The token is used to show the consent step
+  let pairs = authorization.as_object().unwrap().iter().map(|(key, value)| (key.as_str(), value.as_str().unwrap()));
+  let url = url::Url::parse_with_params("http://localhost/oauth/authorize", pairs).unwrap();
+  let req = test::TestRequest::get()
+    .uri(&format!("{}?{}", url.path(), url.query().unwrap()))
+    .insert_header(bearer(&pat))
+    .to_request();
+  let shown = test::call_service(&app, req).await;
+
+  // And: To approve the client
+  let mut decision = authorization.clone();
+  decision["approve"] = json!(true);
+  let req = test::TestRequest::post()
+    .uri("/oauth/authorize")
+    .insert_header(bearer(&pat))
+    .set_json(&decision)
+    .to_request();
+  let approved = test::call_service(&app, req).await;
+
+  // Then: Both are forbidden, so no authorization code is minted
+  assert_eq!(shown.status(), StatusCode::FORBIDDEN);
+  assert_eq!(approved.status(), StatusCode::FORBIDDEN);
+}
@@ -42,3 +42,18 @@
 //   let resp = test::call_service(&mut app, req).await;
 //   assert_eq!(resp.status(), actix_web::http::StatusCode::TOO_MANY_REQUESTS);
 // }
+
+use crate::middleware::rate_limiter::configure_login_rate_limiter;
+
+#[test]
+fn test_login_rate_limiter_falls_back_on_zero_settings() {
+  // Given: A login limit configured with zeros, which no limiter can be built from
+  std::env::set_var("LOGIN_RATE_LIMIT_BURST", "0");
+  std::env::set_var("LOGIN_RATE_LIMIT_SECONDS", "0");
+
+  // When: The limiter is configured
+  let limit = std::panic::catch_unwind(configure_login_rate_limiter);
+
+  // Then: It falls back to the defaults instead of panicking
+  assert!(limit.is_ok());
+}
@@ -0,0 +1,100 @@
+use std::sync::Arc;
+use actix_web::http::StatusCode;
+use actix_web::{test, web, App};
+use serde_json::{json, Value};
+use uuid::Uuid;
+
+use crate::config::keyring::Keyring;
+use crate::errors::error::AuthError;
+use crate::mailer::{InMemoryMailer, Mailer};
+use crate::models::auth::{LoginOutcome, LoginRequest, RegisterRequest};
+use crate::models::session::ClientInfo;
+use crate::middleware::rate_limiter::configure_login_rate_limiter;
+use crate::routes::auth_routes;
+use crate::services::auth_service::AuthService;
+use crate::tests::support::{pool, PASSWORD};
+
+#[actix_rt::test]
+#[ignore = "needs a Postgres database at DATABASE_URL"]
+async fn test_register_reports_every_invalid_field() {
+  // Given: The auth routes
+  let mailer: Arc<dyn Mailer> = Arc::new(InMemoryMailer::default());
+  let app = test::init_service(App::new()
+    .app_data(web::Data::new(pool()))
+    .app_data(web::Data::new(Keyring::ephemeral()))
+    .app_data(web::Data::from(mailer))
+    .service(auth_routes(&configure_login_rate_limiter()))).await;
+
+  // When: Someone registers with a malformed email and a short password
+  let req = test::TestRequest::post()
+    .uri("/auth/register")
+    .set_json(json!({ "username": "traveller", "email": "not-an-email", "password": "short" }))
+    .to_request();
+  let resp = test::call_service(&app, req).await;
+
+  // Then: It is refused, naming both fields
+  assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+  let body: Value = test::read_body_json(resp).await;
+  assert_eq!(body["code"], "validation_failed");
+  assert!(body["fields"].get("email").is_some());
+  assert!(body["fields"].get("password").is_some());
+  assert!(body["fields"].get("username").is_none());
+}
+
+#[actix_rt::test]
+#[ignore = "needs a Postgres database at DATABASE_URL"]
+async fn test_register_refuses_an_email_already_registered() {
+  // Given: An account for an email
+  let mailer: Arc<dyn Mailer> = Arc::new(InMemoryMailer::default());
+  let app = test::init_service(App::new()
+    .app_data(web::Data::new(pool()))
+    .app_data(web::Data::new(Keyring::ephemeral()))
+    .app_data(web::Data::from(mailer))
+    .service(auth_routes(&configure_login_rate_limiter()))).await;
+  let email = format!("register-{}@example.com", Uuid::new_v4());
+  let register = |username: &str| test::TestRequest::post()
+    .uri("/auth/register")
+    .set_json(json!({ "username": username, "email": email, "password": "Password123!" }))
+    .to_request();
+  assert_eq!(test::call_service(&app, register("traveller")).await.status(), StatusCode::CREATED);
+
+  // When: Someone registers the same email again
+  let resp = test::call_service(&app, register("impostor")).await;
+
+  // Then: It is a conflict
+  assert_eq!(resp.status(), StatusCode::CONFLICT);
+  let body: Value = test::read_body_json(resp).await;
+  assert_eq!(body["code"], "email_already_exists");
+}
+
+#[actix_rt::test]
+#[ignore = "needs a Postgres database at DATABASE_URL"]
+async fn test_emails_are_registered_and_looked_up_ignoring_case() {
+  // Given: An account registered with a mixed-case email
+  let pool = pool();
+  let keyring = Keyring::ephemeral();
+  let email = format!("register-{}@example.com", Uuid::new_v4());
+  let register = |email: String| RegisterRequest {
+    username: "traveller".to_string(),
+    email,
+    password: PASSWORD.to_string(),
+  };
+  let registered = AuthService::register(&pool, &keyring, register(format!(" {} ", email.to_uppercase())), &ClientInfo::default())
+    .await.unwrap();
+
+  // Then: It is stored lowercased
+  assert_eq!(registered.email, email);
+
+  // When: Someone registers the lowercase email
+  let again = AuthService::register(&pool, &keyring, register(email.clone()), &ClientInfo::default()).await;
+
+  // Then: It is the same account
+  assert!(matches!(again, Err(AuthError::EmailAlreadyExists)));
+
+  // And: Logging in with either spelling finds it
+  for email in [email.to_uppercase(), email.clone()] {
+    let request = LoginRequest { email, password: PASSWORD.to_string() };
+    let outcome = AuthService::authenticate(&pool, &keyring, &request, &ClientInfo::default()).await.unwrap();
+    assert!(matches!(outcome, LoginOutcome::Tokens(_)));
+  }
+}
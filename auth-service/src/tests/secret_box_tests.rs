@@ -0,0 +1,32 @@
+use crate::utils::secret_box::{decrypt_secret, encrypt_secret};
+
+fn set_key() {
+  std::env::set_var("TOTP_ENCRYPTION_KEY", "ZGV2LW9ubHktdG90cC1lbmNyeXB0aW9uLWtleS0zMmI=");
+}
+
+#[actix_rt::test]
+async fn test_secret_round_trips() {
+  // Given: An encryption key
+  set_key();
+
+  // When: A secret is sealed and opened with the same context
+  let sealed = encrypt_secret(b"totp-secret", b"user-1").unwrap();
+  let opened = decrypt_secret(&sealed, b"user-1").unwrap();
+
+  // Then: The plaintext comes back and is not stored in the clear
+  assert_eq!(opened, b"totp-secret");
+  assert!(!sealed.contains("totp-secret"));
+}
+
+#[actix_rt::test]
+async fn test_secret_is_bound_to_its_context() {
+  // Given: A secret sealed for one user
+  set_key();
+  let sealed = encrypt_secret(b"totp-secret", b"user-1").unwrap();
+
+  // When: It is opened for another user
+  let opened = decrypt_secret(&sealed, b"user-2");
+
+  // Then: Decryption fails
+  assert!(opened.is_err());
+}
@@ -0,0 +1,168 @@
+use std::sync::Arc;
+use std::time::Duration;
+use actix_web::http::StatusCode;
+use actix_web::{test, web, App};
+use serde_json::Value;
+use shared::auth::RevocationCheck;
+
+use crate::config::database::DbPool;
+use crate::config::keyring::Keyring;
+use crate::errors::error::AuthError;
+use crate::models::auth::{LoginOutcome, LoginRequest, LoginResponse};
+use crate::models::session::ClientInfo;
+use crate::middleware::rate_limiter::configure_login_rate_limiter;
+use crate::routes::auth_routes;
+use crate::services::auth_service::AuthService;
+use crate::services::session_service::SessionService;
+use crate::services::token_revocation_service::DatabaseRevocations;
+use crate::tests::support::{pool, registered_user, PASSWORD};
+
+fn device(user_agent: &str) -> ClientInfo {
+  ClientInfo { user_agent: Some(user_agent.to_string()), ip_address: Some("203.0.113.7".to_string()) }
+}
+
+async fn log_in(pool: &DbPool, keyring: &Keyring, email: &str, client: &ClientInfo) -> LoginResponse {
+  let request = LoginRequest { email: email.to_string(), password: PASSWORD.to_string() };
+  match AuthService::authenticate(pool, keyring, &request, client).await.unwrap() {
+    LoginOutcome::Tokens(tokens) => tokens,
+    LoginOutcome::MfaRequired(_) => panic!("expected tokens"),
+  }
+}
+
+#[actix_rt::test]
+#[ignore = "needs a Postgres database at DATABASE_URL"]
+async fn test_revoked_session_is_logged_out() {
+  // Given: A candidate signed in on a laptop and a phone, and routes that check sessions
+  let pool = pool();
+  let keyring = web::Data::new(Keyring::ephemeral());
+  let validator = keyring.token_validator().with_revocation_check(Arc::new(DatabaseRevocations::new(pool.clone())));
+  let app = test::init_service(App::new()
+    .app_data(web::Data::new(pool.clone()))
+    .app_data(keyring.clone())
+    .app_data(web::Data::new(validator))
+    .service(auth_routes(&configure_login_rate_limiter()))).await;
+  let email = registered_user(&pool, &keyring, "sessions").await.email;
+  let laptop = log_in(&pool, &keyring, &email, &device("Laptop")).await;
+  let phone = log_in(&pool, &keyring, &email, &device("Phone")).await;
+
+  // When: They list their sessions from the phone
+  let req = test::TestRequest::get()
+    .uri("/auth/sessions")
+    .insert_header(("Authorization", format!("Bearer {}", phone.token)))
+    .to_request();
+  let sessions: Value = test::call_and_read_body_json(&app, req).await;
+
+  // Then: Both devices show, with the phone marked as current
+  let sessions = sessions.as_array().unwrap();
+  let laptop_session = sessions.iter().find(|session| session["user_agent"] == "Laptop").unwrap();
+  let phone_session = sessions.iter().find(|session| session["user_agent"] == "Phone").unwrap();
+  assert_eq!(laptop_session["ip_address"], "203.0.113.7");
+  assert_eq!(laptop_session["current"], false);
+  assert_eq!(phone_session["current"], true);
+
+  // When: The phone ends the laptop's session
+  let req = test::TestRequest::delete()
+    .uri(&format!("/auth/sessions/{}", laptop_session["id"].as_str().unwrap()))
+    .insert_header(("Authorization", format!("Bearer {}", phone.token)))
+    .to_request();
+  assert_eq!(test::call_service(&app, req).await.status(), StatusCode::NO_CONTENT);
+
+  // Then: The laptop's access token is refused, and its refresh token too
+  let req = test::TestRequest::get()
+    .uri("/auth/sessions")
+    .insert_header(("Authorization", format!("Bearer {}", laptop.token)))
+    .to_request();
+  assert_eq!(test::call_service(&app, req).await.status(), StatusCode::UNAUTHORIZED);
+  let refreshed = AuthService::refresh(&pool, &keyring, &laptop.refresh_token).await;
+  assert!(matches!(refreshed, Err(AuthError::InvalidRefreshToken)));
+
+  // And: The phone carries on
+  let refreshed = AuthService::refresh(&pool, &keyring, &phone.refresh_token).await.unwrap();
+  let req = test::TestRequest::get()
+    .uri("/auth/sessions")
+    .insert_header(("Authorization", format!("Bearer {}", refreshed.token)))
+    .to_request();
+  let sessions: Value = test::call_and_read_body_json(&app, req).await;
+  assert_eq!(sessions.as_array().unwrap().len(), 2);
+}
+
+#[actix_rt::test]
+#[ignore = "needs a Postgres database at DATABASE_URL"]
+async fn test_log_out_everywhere() {
+  // Given: A candidate signed in on two devices
+  let pool = pool();
+  let keyring = web::Data::new(Keyring::ephemeral());
+  let revocations = DatabaseRevocations::new(pool.clone()).with_cache_ttl(Duration::ZERO);
+  let validator = keyring.token_validator().with_revocation_check(Arc::new(revocations));
+  let app = test::init_service(App::new()
+    .app_data(web::Data::new(pool.clone()))
+    .app_data(keyring.clone())
+    .app_data(web::Data::new(validator))
+    .service(auth_routes(&configure_login_rate_limiter()))).await;
+  let email = registered_user(&pool, &keyring, "sessions").await.email;
+  let laptop = log_in(&pool, &keyring, &email, &device("Laptop")).await;
+  let phone = log_in(&pool, &keyring, &email, &device("Phone")).await;
+
+  // When: They log out everywhere from the laptop
+  let req = test::TestRequest::delete()
+    .uri("/auth/sessions")
+    .insert_header(("Authorization", format!("Bearer {}", laptop.token)))
+    .to_request();
+  assert_eq!(test::call_service(&app, req).await.status(), StatusCode::NO_CONTENT);
+
+  // Then: No token of either device is accepted any more
+  for tokens in [&laptop, &phone] {
+    let req = test::TestRequest::get()
+      .uri("/auth/sessions")
+      .insert_header(("Authorization", format!("Bearer {}", tokens.token)))
+      .to_request();
+    assert_eq!(test::call_service(&app, req).await.status(), StatusCode::UNAUTHORIZED);
+    assert!(AuthService::refresh(&pool, &keyring, &tokens.refresh_token).await.is_err());
+  }
+}
+
+#[actix_rt::test]
+#[ignore = "needs a Postgres database at DATABASE_URL"]
+async fn test_replayed_refresh_token_revokes_its_family() {
+  // Given: A signed-in candidate who has refreshed their tokens once
+  let pool = pool();
+  let keyring = Keyring::ephemeral();
+  let revocations = DatabaseRevocations::new(pool.clone()).with_cache_ttl(Duration::ZERO);
+  let email = registered_user(&pool, &keyring, "sessions").await.email;
+  let original = log_in(&pool, &keyring, &email, &device("Laptop")).await;
+  let rotated = AuthService::refresh(&pool, &keyring, &original.refresh_token).await.unwrap();
+
+  // When: The rotated-out refresh token is presented again
+  let replayed = AuthService::refresh(&pool, &keyring, &original.refresh_token).await;
+
+  // Then: It is refused, and so is the token it was rotated into
+  assert!(matches!(replayed, Err(AuthError::InvalidRefreshToken)));
+  let refreshed = AuthService::refresh(&pool, &keyring, &rotated.refresh_token).await;
+  assert!(matches!(refreshed, Err(AuthError::InvalidRefreshToken)));
+
+  // And: The session has ended, taking its access token with it
+  let claims = keyring.token_validator().validate(&rotated.token).await.unwrap();
+  assert!(revocations.check(&rotated.token, &claims).await.is_err());
+}
+
+#[actix_rt::test]
+#[ignore = "needs a Postgres database at DATABASE_URL"]
+async fn test_revocation_checks_are_cached() {
+  // Given: A signed-in candidate whose token has been checked once
+  let pool = pool();
+  let keyring = Keyring::ephemeral();
+  let revocations = DatabaseRevocations::new(pool.clone());
+  let user = registered_user(&pool, &keyring, "sessions").await;
+  let email = user.email.clone();
+  let tokens = log_in(&pool, &keyring, &email, &device("Laptop")).await;
+  let claims = keyring.token_validator().validate(&tokens.token).await.unwrap();
+  assert!(revocations.check(&tokens.token, &claims).await.is_ok());
+
+  // When: Their sessions are ended
+  SessionService::revoke_all(&pool, user.id).await.unwrap();
+
+  // Then: The cached answer holds until it expires, while an uncached check sees the revocation
+  assert!(revocations.check(&tokens.token, &claims).await.is_ok());
+  let uncached = DatabaseRevocations::new(pool.clone()).with_cache_ttl(Duration::ZERO);
+  assert!(uncached.check(&tokens.token, &claims).await.is_err());
+}
@@ -0,0 +1,140 @@
+use ciborium::value::Value;
+use p256::ecdsa::signature::Signer;
+use p256::ecdsa::{Signature, SigningKey};
+use rand::rngs::OsRng;
+use rand::RngCore;
+use serde_json::{json, Value as Json};
+
+use crate::utils::webauthn::{self, sha256, signed_message, ALG_ES256, FLAG_ATTESTED_CREDENTIAL, FLAG_USER_PRESENT, FLAG_USER_VERIFIED};
+
+/// How `create` attests the new credential.
+#[derive(Clone, Copy)]
+pub enum Attestation {
+  None,
+  /// `packed`, signed with the credential key itself.
+  PackedSelf,
+}
+
+/// A platform authenticator in software: one ES256 passkey, created and
+/// used the way a browser would, answering with the JSON the API expects.
+/// Cloning it copies the key and the signature counter, like a cloned
+/// hardware key.
+#[derive(Clone)]
+pub struct SoftwareAuthenticator {
+  pub origin: String,
+  /// Whether it sets the user verified flag, as if it had asked for a PIN.
+  pub user_verification: bool,
+  key: SigningKey,
+  credential_id: Vec<u8>,
+  user_handle: Vec<u8>,
+  sign_count: u32,
+}
+
+impl SoftwareAuthenticator {
+  pub fn new(origin: &str) -> SoftwareAuthenticator {
+    let mut credential_id = vec![0u8; 16];
+    OsRng.fill_bytes(&mut credential_id);
+    SoftwareAuthenticator {
+      origin: origin.to_string(),
+      user_verification: true,
+      key: SigningKey::random(&mut OsRng),
+      credential_id,
+      user_handle: Vec::new(),
+      sign_count: 0,
+    }
+  }
+
+  pub fn credential_id(&self) -> String {
+    webauthn::encode(&self.credential_id)
+  }
+
+  /// `navigator.credentials.create()` with the `public_key` options from `/passkeys/register/start`.
+  pub fn create(&mut self, options: &Json, attestation: Attestation) -> Json {
+    self.user_handle = webauthn::decode(options["user"]["id"].as_str().unwrap(), "user id").unwrap();
+    let client_data_json = self.client_data("webauthn.create", options["challenge"].as_str().unwrap());
+
+    let mut auth_data = self.auth_data(options["rp"]["id"].as_str().unwrap(), FLAG_ATTESTED_CREDENTIAL);
+    auth_data.extend_from_slice(&[0u8; 16]);
+    auth_data.extend_from_slice(&(self.credential_id.len() as u16).to_be_bytes());
+    auth_data.extend_from_slice(&self.credential_id);
+    auth_data.extend_from_slice(&self.cose_key());
+
+    let (format, statement) = match attestation {
+      Attestation::None => ("none", vec![]),
+      Attestation::PackedSelf => ("packed", vec![
+        (Value::Text("alg".to_string()), Value::Integer(ALG_ES256.into())),
+        (Value::Text("sig".to_string()), Value::Bytes(self.sign(&signed_message(&auth_data, &client_data_json)))),
+      ]),
+    };
+    let attestation_object = cbor(&Value::Map(vec![
+      (Value::Text("fmt".to_string()), Value::Text(format.to_string())),
+      (Value::Text("attStmt".to_string()), Value::Map(statement)),
+      (Value::Text("authData".to_string()), Value::Bytes(auth_data)),
+    ]));
+
+    json!({
+      "id": self.credential_id(),
+      "rawId": self.credential_id(),
+      "type": "public-key",
+      "response": {
+        "clientDataJSON": webauthn::encode(&client_data_json),
+        "attestationObject": webauthn::encode(&attestation_object),
+        "transports": ["internal"],
+      },
+    })
+  }
+
+  /// `navigator.credentials.get()` with the `public_key` options from `/passkeys/login/start`.
+  pub fn get(&mut self, options: &Json) -> Json {
+    self.sign_count += 1;
+    let client_data_json = self.client_data("webauthn.get", options["challenge"].as_str().unwrap());
+    let auth_data = self.auth_data(options["rpId"].as_str().unwrap(), 0);
+    let signature = self.sign(&signed_message(&auth_data, &client_data_json));
+
+    json!({
+      "id": self.credential_id(),
+      "rawId": self.credential_id(),
+      "type": "public-key",
+      "response": {
+        "clientDataJSON": webauthn::encode(&client_data_json),
+        "authenticatorData": webauthn::encode(&auth_data),
+        "signature": webauthn::encode(&signature),
+        "userHandle": webauthn::encode(&self.user_handle),
+      },
+    })
+  }
+
+  fn client_data(&self, ceremony: &str, challenge: &str) -> Vec<u8> {
+    serde_json::to_vec(&json!({ "type": ceremony, "challenge": challenge, "origin": self.origin, "crossOrigin": false })).unwrap()
+  }
+
+  fn auth_data(&self, rp_id: &str, flags: u8) -> Vec<u8> {
+    let mut auth_data = sha256(rp_id.as_bytes()).to_vec();
+    let verified = if self.user_verification { FLAG_USER_VERIFIED } else { 0 };
+    auth_data.push(FLAG_USER_PRESENT | verified | flags);
+    auth_data.extend_from_slice(&self.sign_count.to_be_bytes());
+    auth_data
+  }
+
+  fn cose_key(&self) -> Vec<u8> {
+    let point = self.key.verifying_key().to_encoded_point(false);
+    cbor(&Value::Map(vec![
+      (Value::Integer(1.into()), Value::Integer(2.into())),
+      (Value::Integer(3.into()), Value::Integer(ALG_ES256.into())),
+      (Value::Integer((-1).into()), Value::Integer(1.into())),
+      (Value::Integer((-2).into()), Value::Bytes(point.x().unwrap().to_vec())),
+      (Value::Integer((-3).into()), Value::Bytes(point.y().unwrap().to_vec())),
+    ]))
+  }
+
+  fn sign(&self, message: &[u8]) -> Vec<u8> {
+    let signature: Signature = self.key.sign(message);
+    signature.to_der().as_bytes().to_vec()
+  }
+}
+
+fn cbor(value: &Value) -> Vec<u8> {
+  let mut bytes = Vec::new();
+  ciborium::ser::into_writer(value, &mut bytes).unwrap();
+  bytes
+}
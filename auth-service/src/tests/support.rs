@@ -0,0 +1,36 @@
+use diesel_migrations::{embed_migrations, EmbeddedMigrations, MigrationHarness};
+use uuid::Uuid;
+
+use crate::config::database::{establish_connection, DbPool};
+use crate::config::keyring::Keyring;
+use crate::models::auth::{RegisterRequest, RegisterResponse};
+use crate::models::session::ClientInfo;
+use crate::services::auth_service::AuthService;
+
+const MIGRATIONS: EmbeddedMigrations = embed_migrations!("./migrations");
+
+/// The password `registered_user` signs up with.
+pub const PASSWORD: &str = "Password123!";
+
+/// A pool on `DATABASE_URL`, with every migration applied.
+pub fn pool() -> DbPool {
+  let pool = establish_connection();
+  let mut conn = pool.get().expect("Failed to get DB connection from pool");
+  conn.run_pending_migrations(MIGRATIONS).expect("Failed to run migrations");
+  pool
+}
+
+pub fn bearer(token: &str) -> (&'static str, String) {
+  ("Authorization", format!("Bearer {}", token))
+}
+
+/// A newly registered candidate, with `PASSWORD` and an email starting with
+/// `prefix` that no other test uses. They are signed in unless the email
+/// verification policy blocks it.
+pub async fn registered_user(pool: &DbPool, keyring: &Keyring, prefix: &str) -> RegisterResponse {
+  AuthService::register(pool, keyring, RegisterRequest {
+    username: format!("{}-user", prefix),
+    email: format!("{}-{}@example.com", prefix, Uuid::new_v4()),
+    password: PASSWORD.to_string(),
+  }, &ClientInfo::default()).await.unwrap()
+}
@@ -0,0 +1,34 @@
+use std::env;
+use argon2::password_hash::SaltString;
+use argon2::{Algorithm, Argon2, Params, PasswordHasher, Version};
+
+/// Argon2id cost parameters for new hashes, from `PASSWORD_HASH_MEMORY_KIB`,
+/// `PASSWORD_HASH_ITERATIONS` and `PASSWORD_HASH_PARALLELISM`. Unset or
+/// invalid values fall back to the argon2 crate's defaults. Raising them
+/// upgrades existing hashes as their users log in.
+pub fn hash_params() -> Params {
+    let memory_kib = env_or("PASSWORD_HASH_MEMORY_KIB", Params::DEFAULT_M_COST);
+    let iterations = env_or("PASSWORD_HASH_ITERATIONS", Params::DEFAULT_T_COST);
+    let parallelism = env_or("PASSWORD_HASH_PARALLELISM", Params::DEFAULT_P_COST);
+
+    Params::new(memory_kib, iterations, parallelism, None).unwrap_or_else(|e| {
+        log::warn!("Invalid password hash parameters, using the defaults: {}", e);
+        Params::default()
+    })
+}
+
+pub fn hash_password(password: &str) -> Result<String, argon2::password_hash::Error> {
+    hash_password_with(password, hash_params())
+}
+
+pub fn hash_password_with(password: &str, params: Params) -> Result<String, argon2::password_hash::Error> {
+    let salt = SaltString::generate(&mut rand::thread_rng());
+    let password_hash = Argon2::new(Algorithm::Argon2id, Version::V0x13, params)
+        .hash_password(password.as_bytes(), &salt)?;
+
+    Ok(password_hash.to_string())
+}
+
+fn env_or<T: std::str::FromStr>(name: &str, default: T) -> T {
+    env::var(name).ok().and_then(|value| value.parse().ok()).unwrap_or(default)
+}
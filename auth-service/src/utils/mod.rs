@@ -1 +1,5 @@
-pub mod verify_password;
\ No newline at end of file
+pub mod hash_password;
+pub mod opaque_token;
+pub mod secret_box;
+pub mod verify_password;
+pub mod webauthn;
\ No newline at end of file
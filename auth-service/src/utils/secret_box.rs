@@ -0,0 +1,45 @@
+use std::env;
+
+use aes_gcm::aead::{Aead, AeadCore, KeyInit, OsRng, Payload};
+use aes_gcm::{Aes256Gcm, Key, Nonce};
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+
+const NONCE_LEN: usize = 12;
+
+/// Encrypts `plaintext` with AES-256-GCM under `TOTP_ENCRYPTION_KEY`. `context`
+/// is bound as associated data, so a ciphertext copied to another row fails to
+/// decrypt. The result is `base64(nonce || ciphertext)`.
+pub fn encrypt_secret(plaintext: &[u8], context: &[u8]) -> Result<String, String> {
+    let cipher = cipher()?;
+    let nonce = Aes256Gcm::generate_nonce(&mut OsRng);
+    let ciphertext = cipher
+        .encrypt(&nonce, Payload { msg: plaintext, aad: context })
+        .map_err(|_| "Failed to encrypt secret".to_string())?;
+
+    let mut sealed = nonce.to_vec();
+    sealed.extend_from_slice(&ciphertext);
+    Ok(STANDARD.encode(sealed))
+}
+
+pub fn decrypt_secret(sealed: &str, context: &[u8]) -> Result<Vec<u8>, String> {
+    let sealed = STANDARD.decode(sealed).map_err(|e| e.to_string())?;
+    if sealed.len() <= NONCE_LEN {
+        return Err("Sealed secret is too short".to_string());
+    }
+
+    let (nonce, ciphertext) = sealed.split_at(NONCE_LEN);
+    cipher()?
+        .decrypt(Nonce::from_slice(nonce), Payload { msg: ciphertext, aad: context })
+        .map_err(|_| "Failed to decrypt secret".to_string())
+}
+
+fn cipher() -> Result<Aes256Gcm, String> {
+    let key = env::var("TOTP_ENCRYPTION_KEY").map_err(|_| "TOTP_ENCRYPTION_KEY not set".to_string())?;
+    let key = STANDARD.decode(key).map_err(|e| e.to_string())?;
+    if key.len() != 32 {
+        return Err("TOTP_ENCRYPTION_KEY must be 32 bytes, base64-encoded".to_string());
+    }
+
+    Ok(Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(&key)))
+}
@@ -1,8 +1,69 @@
-use argon2::{Argon2, PasswordHash, PasswordVerifier};
+use std::sync::OnceLock;
+use argon2::{Algorithm, Argon2, Params, PasswordHash, PasswordVerifier, Version};
+use crate::utils::hash_password::{hash_params, hash_password};
+
+/// The schemes stored hashes can be in. Accounts migrated from the old system
+/// still have bcrypt hashes until their next login.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HashScheme {
+    Argon2,
+    Bcrypt,
+    Unknown,
+}
+
+impl HashScheme {
+    pub fn of(hash: &str) -> HashScheme {
+        if hash.starts_with("$argon2") {
+            HashScheme::Argon2
+        } else if ["$2a$", "$2b$", "$2y$"].iter().any(|prefix| hash.starts_with(prefix)) {
+            HashScheme::Bcrypt
+        } else {
+            HashScheme::Unknown
+        }
+    }
+}
 
 pub fn verify_password(hash: &str, password: &str) -> bool {
-    match PasswordHash::new(hash) {
-        Ok(parsed_hash) => Argon2::default().verify_password(password.as_bytes(), &parsed_hash).is_ok(),
-        Err(_) => false,
+    match HashScheme::of(hash) {
+        HashScheme::Argon2 => match PasswordHash::new(hash) {
+            Ok(parsed_hash) => Argon2::default().verify_password(password.as_bytes(), &parsed_hash).is_ok(),
+            Err(_) => false,
+        },
+        HashScheme::Bcrypt => bcrypt::verify(password, hash).unwrap_or(false),
+        HashScheme::Unknown => false,
     }
 }
+
+/// Whether a hash that just verified should be replaced: anything but
+/// Argon2id with the current version and `hash_params()`.
+pub fn needs_rehash(hash: &str) -> bool {
+    let parsed_hash = match PasswordHash::new(hash) {
+        Ok(parsed_hash) if HashScheme::of(hash) == HashScheme::Argon2 => parsed_hash,
+        _ => return true,
+    };
+    if parsed_hash.algorithm != Algorithm::Argon2id.ident() || parsed_hash.version != Some(Version::V0x13.into()) {
+        return true;
+    }
+
+    let current = hash_params();
+    match Params::try_from(&parsed_hash) {
+        Ok(params) => {
+            params.m_cost() != current.m_cost()
+                || params.t_cost() != current.t_cost()
+                || params.p_cost() != current.p_cost()
+        }
+        Err(_) => true,
+    }
+}
+
+/// Runs a verification that always fails, against a hash made with the same
+/// parameters as real ones. Used when the account does not exist, so that
+/// answering "wrong password" and "no such user" takes the same time.
+pub fn dummy_verify_password(password: &str) {
+    static DUMMY_HASH: OnceLock<String> = OnceLock::new();
+    let hash = DUMMY_HASH.get_or_init(|| {
+        hash_password("dummy password for unknown accounts").expect("Failed to hash dummy password")
+    });
+
+    verify_password(hash, password);
+}
@@ -0,0 +1,252 @@
+use std::io::Cursor;
+
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+use ciborium::value::Value;
+use p256::ecdsa::signature::Verifier;
+use serde::Deserialize;
+use sha2::{Digest, Sha256};
+use thiserror::Error;
+
+/// COSE algorithm identifiers we accept, in order of preference.
+pub const ALG_ES256: i64 = -7;
+pub const ALG_EDDSA: i64 = -8;
+
+pub const FLAG_USER_PRESENT: u8 = 0x01;
+pub const FLAG_USER_VERIFIED: u8 = 0x04;
+pub const FLAG_BACKUP_ELIGIBLE: u8 = 0x08;
+pub const FLAG_BACKED_UP: u8 = 0x10;
+pub const FLAG_ATTESTED_CREDENTIAL: u8 = 0x40;
+
+const COSE_KTY: i128 = 1;
+const COSE_ALG: i128 = 3;
+const COSE_CRV: i128 = -1;
+const COSE_X: i128 = -2;
+const COSE_Y: i128 = -3;
+const COSE_KTY_OKP: i128 = 1;
+const COSE_KTY_EC2: i128 = 2;
+const COSE_CRV_P256: i128 = 1;
+const COSE_CRV_ED25519: i128 = 6;
+
+#[derive(Debug, Error)]
+pub enum WebAuthnError {
+    #[error("Malformed {0}")]
+    Malformed(&'static str),
+
+    #[error("Unsupported public key algorithm")]
+    UnsupportedAlgorithm,
+
+    #[error("Invalid signature")]
+    InvalidSignature,
+}
+
+pub fn encode(bytes: &[u8]) -> String {
+    URL_SAFE_NO_PAD.encode(bytes)
+}
+
+/// Decodes base64url, with or without padding, as browsers and libraries differ.
+pub fn decode(value: &str, what: &'static str) -> Result<Vec<u8>, WebAuthnError> {
+    URL_SAFE_NO_PAD.decode(value.trim_end_matches('=')).map_err(|_e| WebAuthnError::Malformed(what))
+}
+
+pub fn sha256(bytes: &[u8]) -> [u8; 32] {
+    Sha256::digest(bytes).into()
+}
+
+/// `clientDataJSON`, as assembled by the browser.
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct ClientData {
+    /// `webauthn.create` or `webauthn.get`.
+    #[serde(rename = "type")]
+    pub ceremony: String,
+    pub challenge: String,
+    pub origin: String,
+    #[serde(default)]
+    pub cross_origin: bool,
+}
+
+impl ClientData {
+    pub fn parse(client_data_json: &[u8]) -> Result<ClientData, WebAuthnError> {
+        serde_json::from_slice(client_data_json).map_err(|_e| WebAuthnError::Malformed("client data"))
+    }
+}
+
+/// The credential an authenticator created, from registration authenticator data.
+pub struct AttestedCredential {
+    pub aaguid: [u8; 16],
+    pub credential_id: Vec<u8>,
+    /// The public key as a CBOR-encoded COSE_Key.
+    pub public_key: Vec<u8>,
+}
+
+pub struct AuthenticatorData {
+    pub rp_id_hash: [u8; 32],
+    pub flags: u8,
+    pub sign_count: u32,
+    pub attested_credential: Option<AttestedCredential>,
+}
+
+impl AuthenticatorData {
+    pub fn parse(bytes: &[u8]) -> Result<AuthenticatorData, WebAuthnError> {
+        let malformed = || WebAuthnError::Malformed("authenticator data");
+        if bytes.len() < 37 {
+            return Err(malformed());
+        }
+
+        let flags = bytes[32];
+        let attested_credential = if flags & FLAG_ATTESTED_CREDENTIAL != 0 {
+            let rest = &bytes[37..];
+            if rest.len() < 18 {
+                return Err(malformed());
+            }
+            let id_length = u16::from_be_bytes([rest[16], rest[17]]) as usize;
+            if rest.len() < 18 + id_length {
+                return Err(malformed());
+            }
+            let (credential_id, key_and_extensions) = rest[18..].split_at(id_length);
+
+            // Extensions may follow the key, so its length is only known once it is decoded.
+            let mut cursor = Cursor::new(key_and_extensions);
+            ciborium::de::from_reader::<Value, _>(&mut cursor).map_err(|_e| malformed())?;
+            let key_length = cursor.position() as usize;
+
+            Some(AttestedCredential {
+                aaguid: rest[..16].try_into().map_err(|_e| malformed())?,
+                credential_id: credential_id.to_vec(),
+                public_key: key_and_extensions[..key_length].to_vec(),
+            })
+        } else {
+            None
+        };
+
+        Ok(AuthenticatorData {
+            rp_id_hash: bytes[..32].try_into().map_err(|_e| malformed())?,
+            flags,
+            sign_count: u32::from_be_bytes([bytes[33], bytes[34], bytes[35], bytes[36]]),
+            attested_credential,
+        })
+    }
+
+    pub fn has(&self, flag: u8) -> bool {
+        self.flags & flag != 0
+    }
+}
+
+/// The CBOR `attestationObject` returned by `navigator.credentials.create()`.
+pub struct AttestationObject {
+    pub format: String,
+    pub statement: Vec<(Value, Value)>,
+    pub auth_data: Vec<u8>,
+}
+
+impl AttestationObject {
+    pub fn parse(bytes: &[u8]) -> Result<AttestationObject, WebAuthnError> {
+        let malformed = || WebAuthnError::Malformed("attestation object");
+        let Value::Map(entries) = ciborium::de::from_reader::<Value, _>(bytes).map_err(|_e| malformed())? else {
+            return Err(malformed());
+        };
+
+        let mut format = None;
+        let mut statement = None;
+        let mut auth_data = None;
+        for (key, value) in entries {
+            match (key.as_text(), value) {
+                (Some("fmt"), Value::Text(value)) => format = Some(value),
+                (Some("attStmt"), Value::Map(value)) => statement = Some(value),
+                (Some("authData"), Value::Bytes(value)) => auth_data = Some(value),
+                _ => {}
+            }
+        }
+
+        match (format, statement, auth_data) {
+            (Some(format), Some(statement), Some(auth_data)) => Ok(AttestationObject { format, statement, auth_data }),
+            _ => Err(malformed()),
+        }
+    }
+
+    /// A field of the attestation statement, e.g. `sig` or `x5c`.
+    pub fn statement_field(&self, name: &str) -> Option<&Value> {
+        self.statement.iter()
+            .find(|(key, _)| key.as_text() == Some(name))
+            .map(|(_, value)| value)
+    }
+}
+
+/// A credential public key, decoded from its COSE_Key.
+pub enum PublicKey {
+    Es256(p256::ecdsa::VerifyingKey),
+    EdDsa(ed25519_dalek::VerifyingKey),
+}
+
+impl PublicKey {
+    pub fn from_cose(bytes: &[u8]) -> Result<PublicKey, WebAuthnError> {
+        let malformed = || WebAuthnError::Malformed("public key");
+        let Value::Map(entries) = ciborium::de::from_reader::<Value, _>(bytes).map_err(|_e| malformed())? else {
+            return Err(malformed());
+        };
+        let integer = |label: i128| entries.iter()
+            .find(|(key, _)| key.as_integer().map(i128::from) == Some(label))
+            .and_then(|(_, value)| value.as_integer())
+            .map(i128::from);
+        let bytes = |label: i128| entries.iter()
+            .find(|(key, _)| key.as_integer().map(i128::from) == Some(label))
+            .and_then(|(_, value)| value.as_bytes());
+
+        match (integer(COSE_KTY), integer(COSE_ALG), integer(COSE_CRV)) {
+            (Some(COSE_KTY_EC2), Some(alg), Some(COSE_CRV_P256)) if alg == ALG_ES256 as i128 => {
+                let (Some(x), Some(y)) = (bytes(COSE_X), bytes(COSE_Y)) else {
+                    return Err(malformed());
+                };
+                if x.len() != 32 || y.len() != 32 {
+                    return Err(malformed());
+                }
+                let mut point = vec![0x04];
+                point.extend_from_slice(x);
+                point.extend_from_slice(y);
+                p256::ecdsa::VerifyingKey::from_sec1_bytes(&point)
+                    .map(PublicKey::Es256)
+                    .map_err(|_e| malformed())
+            }
+            (Some(COSE_KTY_OKP), Some(alg), Some(COSE_CRV_ED25519)) if alg == ALG_EDDSA as i128 => {
+                let x: [u8; 32] = bytes(COSE_X)
+                    .and_then(|x| x.as_slice().try_into().ok())
+                    .ok_or_else(malformed)?;
+                ed25519_dalek::VerifyingKey::from_bytes(&x)
+                    .map(PublicKey::EdDsa)
+                    .map_err(|_e| malformed())
+            }
+            _ => Err(WebAuthnError::UnsupportedAlgorithm),
+        }
+    }
+
+    pub fn algorithm(&self) -> i64 {
+        match self {
+            PublicKey::Es256(_) => ALG_ES256,
+            PublicKey::EdDsa(_) => ALG_EDDSA,
+        }
+    }
+
+    /// Checks a signature over `message`: DER-encoded for ES256, raw for EdDSA.
+    pub fn verify(&self, message: &[u8], signature: &[u8]) -> Result<(), WebAuthnError> {
+        let verified = match self {
+            PublicKey::Es256(key) => p256::ecdsa::Signature::from_der(signature)
+                .is_ok_and(|signature| key.verify(message, &signature).is_ok()),
+            PublicKey::EdDsa(key) => ed25519_dalek::Signature::from_slice(signature)
+                .is_ok_and(|signature| key.verify_strict(message, &signature).is_ok()),
+        };
+
+        if verified {
+            Ok(())
+        } else {
+            Err(WebAuthnError::InvalidSignature)
+        }
+    }
+}
+
+/// What authenticators sign: their data followed by the SHA-256 of the client data.
+pub fn signed_message(auth_data: &[u8], client_data_json: &[u8]) -> Vec<u8> {
+    let mut message = auth_data.to_vec();
+    message.extend_from_slice(&sha256(client_data_json));
+    message
+}
@@ -0,0 +1,112 @@
+//! `EMAIL_VERIFICATION_POLICY` is read from the environment on every sign-in,
+//! so these tests change it in a process of their own, one at a time, where
+//! it cannot reach the library's other tests.
+
+use std::env;
+use actix_web::http::StatusCode;
+use actix_web::{test, web, App, HttpResponse};
+use auth_service::config::database::{establish_connection, DbPool};
+use auth_service::config::keyring::Keyring;
+use auth_service::errors::error::AuthError;
+use auth_service::models::auth::{LoginOutcome, LoginRequest, RegisterRequest};
+use auth_service::models::session::ClientInfo;
+use auth_service::repositories::user_repository::UserRepository;
+use auth_service::services::auth_service::AuthService;
+use diesel_migrations::{embed_migrations, EmbeddedMigrations, MigrationHarness};
+use serial_test::serial;
+use shared::auth::require_permission;
+use uuid::Uuid;
+
+const MIGRATIONS: EmbeddedMigrations = embed_migrations!("./migrations");
+
+fn pool() -> DbPool {
+  let pool = establish_connection();
+  let mut conn = pool.get().expect("Failed to get DB connection from pool");
+  conn.run_pending_migrations(MIGRATIONS).expect("Failed to run migrations");
+  pool
+}
+
+fn login_request(email: &str) -> LoginRequest {
+  LoginRequest { email: email.to_string(), password: "Password123!".to_string() }
+}
+
+async fn register(pool: &DbPool, keyring: &Keyring) -> (Uuid, String, Option<String>) {
+  let email = format!("policy-{}@example.com", Uuid::new_v4());
+  let registered = AuthService::register(pool, keyring, RegisterRequest {
+    username: "traveller".to_string(),
+    email: email.clone(),
+    password: "Password123!".to_string(),
+  }, &ClientInfo::default()).await.unwrap();
+  (registered.id, email, registered.tokens.map(|tokens| tokens.token))
+}
+
+#[actix_rt::test]
+#[serial]
+#[ignore = "needs a Postgres database at DATABASE_URL"]
+async fn test_block_login_withholds_tokens_until_the_email_is_verified() {
+  // Given: Logins are blocked for unverified accounts
+  env::set_var("EMAIL_VERIFICATION_POLICY", "block_login");
+  let pool = pool();
+  let keyring = Keyring::ephemeral();
+
+  // When: Someone registers, then signs in
+  let (user_id, email, tokens) = register(&pool, &keyring).await;
+  let login = AuthService::authenticate(&pool, &keyring, &login_request(&email), &ClientInfo::default()).await;
+
+  // Then: Neither gives them tokens
+  assert!(tokens.is_none());
+  assert!(matches!(login, Err(AuthError::EmailNotVerified)));
+
+  // When: They verify their email and sign in again
+  UserRepository::mark_email_verified(&pool, user_id, &email).await.unwrap();
+  let login = AuthService::authenticate(&pool, &keyring, &login_request(&email), &ClientInfo::default()).await;
+
+  // Then: They get tokens
+  assert!(matches!(login, Ok(LoginOutcome::Tokens(_))));
+  env::remove_var("EMAIL_VERIFICATION_POLICY");
+}
+
+#[actix_rt::test]
+#[serial]
+#[ignore = "needs a Postgres database at DATABASE_URL"]
+async fn test_restrict_scopes_refuses_unverified_tokens_on_guarded_routes() {
+  // Given: Unverified accounts get tokens scoped to profile:read, and a route
+  // that needs resumes:read, which every candidate holds
+  env::set_var("EMAIL_VERIFICATION_POLICY", "restrict_scopes");
+  env::remove_var("UNVERIFIED_SCOPES");
+  let pool = pool();
+  let keyring = Keyring::ephemeral();
+  let app = test::init_service(App::new()
+    .app_data(web::Data::new(keyring.token_validator()))
+    .service(web::resource("/resumes")
+      .wrap(require_permission("resumes:read"))
+      .route(web::get().to(|| async { HttpResponse::Ok().finish() })))).await;
+  let (user_id, email, token) = register(&pool, &keyring).await;
+  let token = token.expect("restrict_scopes still signs the user in");
+
+  // When: The unverified candidate calls the route
+  let req = test::TestRequest::get()
+    .uri("/resumes")
+    .insert_header(("Authorization", format!("Bearer {}", token)))
+    .to_request();
+  let err = test::try_call_service(&app, req).await.unwrap_err();
+
+  // Then: Their token's scope does not cover it
+  assert_eq!(err.error_response().status(), StatusCode::FORBIDDEN);
+  assert_eq!(err.as_error::<shared::auth::AuthError>().unwrap().code(), "insufficient_scope");
+
+  // When: They verify their email, sign in again and call it
+  UserRepository::mark_email_verified(&pool, user_id, &email).await.unwrap();
+  let token = match AuthService::authenticate(&pool, &keyring, &login_request(&email), &ClientInfo::default()).await.unwrap() {
+    LoginOutcome::Tokens(tokens) => tokens.token,
+    LoginOutcome::MfaRequired(_) => panic!("expected tokens"),
+  };
+  let req = test::TestRequest::get()
+    .uri("/resumes")
+    .insert_header(("Authorization", format!("Bearer {}", token)))
+    .to_request();
+
+  // Then: They get through
+  assert_eq!(test::call_service(&app, req).await.status(), StatusCode::OK);
+  env::remove_var("EMAIL_VERIFICATION_POLICY");
+}
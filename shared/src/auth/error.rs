@@ -0,0 +1,66 @@
+use actix_web::http::header::{self, HeaderValue};
+use actix_web::http::StatusCode;
+use actix_web::{HttpResponse, ResponseError};
+use serde_json::json;
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum AuthError {
+    #[error("Missing bearer token")]
+    MissingToken,
+
+    #[error("Token has expired")]
+    ExpiredToken,
+
+    #[error("Token has been revoked")]
+    RevokedToken,
+
+    #[error("Token was signed by an unknown key")]
+    UnknownKey,
+
+    #[error("Invalid token")]
+    InvalidToken,
+
+    #[error("Missing permission {0}")]
+    MissingPermission(String),
+
+    #[error("Token scope does not cover {0}")]
+    InsufficientScope(String),
+
+    #[error("Token validator is not configured")]
+    NotConfigured,
+}
+
+impl AuthError {
+    pub fn code(&self) -> &'static str {
+        match self {
+            AuthError::MissingToken => "missing_token",
+            AuthError::ExpiredToken => "expired_token",
+            AuthError::RevokedToken => "revoked_token",
+            AuthError::UnknownKey => "unknown_key",
+            AuthError::InvalidToken => "invalid_token",
+            AuthError::MissingPermission(_) => "insufficient_permission",
+            AuthError::InsufficientScope(_) => "insufficient_scope",
+            AuthError::NotConfigured => "internal_error",
+        }
+    }
+}
+
+impl ResponseError for AuthError {
+    fn status_code(&self) -> StatusCode {
+        match self {
+            AuthError::MissingPermission(_) | AuthError::InsufficientScope(_) => StatusCode::FORBIDDEN,
+            AuthError::NotConfigured => StatusCode::INTERNAL_SERVER_ERROR,
+            _ => StatusCode::UNAUTHORIZED,
+        }
+    }
+
+    fn error_response(&self) -> HttpResponse {
+        let mut response = HttpResponse::build(self.status_code());
+        if self.status_code() == StatusCode::UNAUTHORIZED {
+            response.insert_header((header::WWW_AUTHENTICATE, HeaderValue::from_static("Bearer")));
+        }
+
+        response.json(json!({ "error": self.to_string(), "code": self.code() }))
+    }
+}
@@ -0,0 +1,96 @@
+use std::future::{ready, Ready};
+use std::rc::Rc;
+
+use actix_web::dev::{forward_ready, Service, ServiceRequest, ServiceResponse, Transform};
+use actix_web::{Error, HttpMessage};
+use futures::future::LocalBoxFuture;
+
+use crate::auth::extractor::{authenticate, AuthenticatedUser};
+
+/// Requires a valid bearer token carrying `permission` on every route of the
+/// wrapped scope or resource. Callers without it get a 403.
+///
+/// ```ignore
+/// web::resource("/{id}")
+///     .wrap(require_permission("users:delete"))
+///     .route(web::delete().to(delete_user))
+/// ```
+pub fn require_permission(permission: &str) -> RequirePermission {
+    RequirePermission { permission: Rc::from(permission), owner_param: None }
+}
+
+/// Like `require_permission`, but also lets through the user the path is
+/// about: a token whose `sub` is the `{owner_param}` segment of the path.
+/// Its scope must still cover `permission`.
+///
+/// ```ignore
+/// web::resource("/{id}/profile")
+///     .wrap(require_permission_or_owner("users:write", "id"))
+///     .route(web::patch().to(update_profile))
+/// ```
+pub fn require_permission_or_owner(permission: &str, owner_param: &str) -> RequirePermission {
+    RequirePermission { permission: Rc::from(permission), owner_param: Some(Rc::from(owner_param)) }
+}
+
+pub struct RequirePermission {
+    permission: Rc<str>,
+    owner_param: Option<Rc<str>>,
+}
+
+impl<S, B> Transform<S, ServiceRequest> for RequirePermission
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<B>;
+    type Error = Error;
+    type Transform = RequirePermissionMiddleware<S>;
+    type InitError = ();
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ready(Ok(RequirePermissionMiddleware {
+            service: Rc::new(service),
+            permission: Rc::clone(&self.permission),
+            owner_param: self.owner_param.clone(),
+        }))
+    }
+}
+
+pub struct RequirePermissionMiddleware<S> {
+    service: Rc<S>,
+    permission: Rc<str>,
+    owner_param: Option<Rc<str>>,
+}
+
+impl<S, B> Service<ServiceRequest> for RequirePermissionMiddleware<S>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<B>;
+    type Error = Error;
+    type Future = LocalBoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    forward_ready!(service);
+
+    fn call(&self, req: ServiceRequest) -> Self::Future {
+        let service = Rc::clone(&self.service);
+        let permission = Rc::clone(&self.permission);
+        let owner = self.owner_param.as_deref()
+            .and_then(|param| req.match_info().get(param))
+            .map(str::to_string);
+
+        Box::pin(async move {
+            let existing = req.extensions().get::<AuthenticatedUser>().cloned();
+            let user = match existing {
+                Some(user) => user,
+                None => authenticate(req.request()).await?,
+            };
+            user.require_permission_or_owner(&permission, owner.as_deref())?;
+
+            req.extensions_mut().insert(user);
+            service.call(req).await
+        })
+    }
+}
@@ -0,0 +1,101 @@
+use std::time::Duration;
+
+use async_trait::async_trait;
+use serde_json::Value;
+
+use crate::auth::claims::Claims;
+use crate::auth::error::AuthError;
+use crate::auth::personal_access_token::PersonalAccessTokenResolver;
+use crate::auth::revocation::RevocationCheck;
+use crate::auth::token_cache::TokenCache;
+
+pub const DEFAULT_INTROSPECTION_CACHE_TTL: Duration = Duration::from_secs(30);
+const MAX_CACHED_TOKENS: usize = 10_000;
+
+/// Asks auth-service's `/auth/introspect` (RFC 7662) about a token, for
+/// services without access to its database: the claims behind a personal
+/// access token, and whether a JWT has been revoked. Calls are authenticated
+/// with the service's client credentials.
+///
+/// Answers are cached for `cache_ttl`, and never past the token's expiry, so
+/// a revocation can take that long to reach this service.
+pub struct RemoteIntrospection {
+    url: String,
+    client_id: String,
+    client_secret: String,
+    cache_ttl: Duration,
+    cache: TokenCache<Option<Claims>>,
+    client: reqwest::Client,
+}
+
+impl RemoteIntrospection {
+    pub fn new(url: &str, client_id: &str, client_secret: &str) -> RemoteIntrospection {
+        RemoteIntrospection {
+            url: url.to_string(),
+            client_id: client_id.to_string(),
+            client_secret: client_secret.to_string(),
+            cache_ttl: DEFAULT_INTROSPECTION_CACHE_TTL,
+            cache: TokenCache::new(MAX_CACHED_TOKENS),
+            client: reqwest::Client::new(),
+        }
+    }
+
+    /// A zero TTL turns caching off.
+    pub fn with_cache_ttl(mut self, cache_ttl: Duration) -> RemoteIntrospection {
+        self.cache_ttl = cache_ttl;
+        self
+    }
+
+    /// The claims of an active token, or `None` for a revoked, expired or
+    /// unknown one.
+    pub async fn introspect(&self, token: &str) -> Result<Option<Claims>, AuthError> {
+        if let Some(cached) = self.cache.get(token) {
+            return Ok(cached);
+        }
+
+        let response = self.client.post(&self.url)
+            .basic_auth(&self.client_id, Some(&self.client_secret))
+            .form(&[("token", token)])
+            .send().await
+            .and_then(|response| response.error_for_status())
+            .map_err(|e| {
+                log::warn!("Failed to introspect token: {}", e);
+                AuthError::InvalidToken
+            })?;
+        let body = response.json::<Value>().await.map_err(|_e| AuthError::InvalidToken)?;
+
+        let claims = match body.get("active").and_then(Value::as_bool) {
+            Some(true) => Some(serde_json::from_value::<Claims>(body).map_err(|_e| AuthError::InvalidToken)?),
+            _ => None,
+        };
+        self.cache.insert(token, claims.clone(), self.ttl_for(claims.as_ref()));
+        Ok(claims)
+    }
+
+    fn ttl_for(&self, claims: Option<&Claims>) -> Duration {
+        match claims {
+            Some(claims) => {
+                let remaining = (claims.exp as u64).saturating_sub(jsonwebtoken::get_current_timestamp());
+                self.cache_ttl.min(Duration::from_secs(remaining))
+            }
+            None => self.cache_ttl,
+        }
+    }
+}
+
+#[async_trait]
+impl PersonalAccessTokenResolver for RemoteIntrospection {
+    async fn resolve(&self, token: &str) -> Result<Claims, AuthError> {
+        self.introspect(token).await?.ok_or(AuthError::InvalidToken)
+    }
+}
+
+#[async_trait]
+impl RevocationCheck for RemoteIntrospection {
+    async fn check(&self, token: &str, _claims: &Claims) -> Result<(), AuthError> {
+        match self.introspect(token).await? {
+            Some(_) => Ok(()),
+            None => Err(AuthError::RevokedToken),
+        }
+    }
+}
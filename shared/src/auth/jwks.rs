@@ -0,0 +1,83 @@
+use std::sync::RwLock;
+use std::time::{Duration, Instant};
+
+use jsonwebtoken::jwk::{AlgorithmParameters, JwkSet};
+use jsonwebtoken::{Algorithm, DecodingKey};
+
+/// Unknown `kid`s trigger a refetch, but never more often than this.
+const MIN_REFRESH_INTERVAL: Duration = Duration::from_secs(30);
+
+/// Public keys published by auth-service at `/.well-known/jwks.json`. Keys are
+/// refetched when a token names a `kid` we have not seen yet, so a rotation on
+/// the auth side needs no restart here.
+pub struct JwksCache {
+    url: Option<String>,
+    keys: RwLock<JwkSet>,
+    last_refresh: RwLock<Option<Instant>>,
+}
+
+impl JwksCache {
+    pub fn new(url: &str) -> JwksCache {
+        JwksCache {
+            url: Some(url.to_string()),
+            keys: RwLock::new(JwkSet { keys: Vec::new() }),
+            last_refresh: RwLock::new(None),
+        }
+    }
+
+    /// A fixed key set that is never refetched, for tests and offline setups.
+    pub fn from_jwks(jwks: JwkSet) -> JwksCache {
+        JwksCache {
+            url: None,
+            keys: RwLock::new(jwks),
+            last_refresh: RwLock::new(None),
+        }
+    }
+
+    pub async fn decoding_key(&self, kid: &str) -> Option<(DecodingKey, Algorithm)> {
+        if let Some(key) = self.find(kid) {
+            return Some(key);
+        }
+
+        if let Err(e) = self.refresh().await {
+            log::warn!("Failed to refresh JWKS: {}", e);
+        }
+
+        self.find(kid)
+    }
+
+    pub async fn refresh(&self) -> Result<(), String> {
+        let url = match &self.url {
+            Some(url) => url,
+            None => return Ok(()),
+        };
+
+        let recently_refreshed = self.last_refresh.read().unwrap()
+            .is_some_and(|at| at.elapsed() < MIN_REFRESH_INTERVAL);
+        if recently_refreshed {
+            return Ok(());
+        }
+        *self.last_refresh.write().unwrap() = Some(Instant::now());
+
+        let jwks = reqwest::get(url).await
+            .and_then(|response| response.error_for_status())
+            .map_err(|e| e.to_string())?
+            .json::<JwkSet>().await
+            .map_err(|e| e.to_string())?;
+
+        *self.keys.write().unwrap() = jwks;
+        Ok(())
+    }
+
+    fn find(&self, kid: &str) -> Option<(DecodingKey, Algorithm)> {
+        let keys = self.keys.read().unwrap();
+        let jwk = keys.find(kid)?;
+        let algorithm = match jwk.algorithm {
+            AlgorithmParameters::RSA(_) => Algorithm::RS256,
+            AlgorithmParameters::OctetKeyPair(_) => Algorithm::EdDSA,
+            _ => return None,
+        };
+
+        DecodingKey::from_jwk(jwk).ok().map(|key| (key, algorithm))
+    }
+}
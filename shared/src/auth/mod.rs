@@ -0,0 +1,20 @@
+pub mod claims;
+pub mod error;
+pub mod extractor;
+pub mod guard;
+pub mod introspection;
+pub mod jwks;
+pub mod middleware;
+pub mod personal_access_token;
+pub mod revocation;
+pub mod token_cache;
+pub mod validator;
+
+pub use claims::Claims;
+pub use error::AuthError;
+pub use extractor::AuthenticatedUser;
+pub use guard::{require_permission, require_permission_or_owner, RequirePermission};
+pub use middleware::RequireAuth;
+pub use personal_access_token::PersonalAccessTokenResolver;
+pub use revocation::RevocationCheck;
+pub use validator::TokenValidator;
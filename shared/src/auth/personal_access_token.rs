@@ -0,0 +1,20 @@
+use async_trait::async_trait;
+
+use crate::auth::claims::Claims;
+use crate::auth::error::AuthError;
+
+/// Every personal access token starts with this, so it can be told apart from
+/// a JWT without a lookup.
+pub const PERSONAL_ACCESS_TOKEN_PREFIX: &str = "pat_";
+
+pub fn is_personal_access_token(token: &str) -> bool {
+    token.starts_with(PERSONAL_ACCESS_TOKEN_PREFIX)
+}
+
+/// Looks up personal access tokens for `TokenValidator`. Auth-service answers
+/// from its database; the claims returned are checked like a JWT's would be.
+#[async_trait]
+pub trait PersonalAccessTokenResolver: Send + Sync {
+    /// `InvalidToken` for unknown or revoked tokens, `ExpiredToken` once past expiry.
+    async fn resolve(&self, token: &str) -> Result<Claims, AuthError>;
+}
@@ -0,0 +1,12 @@
+use async_trait::async_trait;
+
+use crate::auth::claims::Claims;
+use crate::auth::error::AuthError;
+
+/// Asked by `TokenValidator` about every JWT, after the signature and expiry
+/// have been checked.
+#[async_trait]
+pub trait RevocationCheck: Send + Sync {
+    /// `RevokedToken` once the token, or the session behind it, has been revoked.
+    async fn check(&self, token: &str, claims: &Claims) -> Result<(), AuthError>;
+}
@@ -0,0 +1,49 @@
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// What a service learned about a token, kept in memory until the entry
+/// expires. Bounded: once `max_entries` is reached, expired entries are
+/// dropped, and if that is not enough the cache starts over.
+pub struct TokenCache<V> {
+    entries: Mutex<HashMap<String, (V, Instant)>>,
+    max_entries: usize,
+}
+
+impl<V: Clone> TokenCache<V> {
+    pub fn new(max_entries: usize) -> TokenCache<V> {
+        TokenCache { entries: Mutex::new(HashMap::new()), max_entries }
+    }
+
+    pub fn get(&self, token: &str) -> Option<V> {
+        let mut entries = self.entries.lock().unwrap();
+        match entries.get(token) {
+            Some((value, expires_at)) if *expires_at > Instant::now() => Some(value.clone()),
+            Some(_) => {
+                entries.remove(token);
+                None
+            }
+            None => None,
+        }
+    }
+
+    pub fn insert(&self, token: &str, value: V, ttl: Duration) {
+        if ttl.is_zero() {
+            return;
+        }
+
+        let mut entries = self.entries.lock().unwrap();
+        if entries.len() >= self.max_entries {
+            let now = Instant::now();
+            entries.retain(|_, (_, expires_at)| *expires_at > now);
+            if entries.len() >= self.max_entries {
+                entries.clear();
+            }
+        }
+        entries.insert(token.to_string(), (value, Instant::now() + ttl));
+    }
+
+    pub fn remove(&self, token: &str) {
+        self.entries.lock().unwrap().remove(token);
+    }
+}
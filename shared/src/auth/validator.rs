@@ -0,0 +1,137 @@
+use std::env;
+use std::sync::Arc;
+use std::time::Duration;
+
+use jsonwebtoken::errors::ErrorKind;
+use jsonwebtoken::{decode, decode_header, Validation};
+
+use crate::auth::claims::Claims;
+use crate::auth::error::AuthError;
+use crate::auth::introspection::RemoteIntrospection;
+use crate::auth::jwks::JwksCache;
+use crate::auth::personal_access_token::{is_personal_access_token, PersonalAccessTokenResolver};
+use crate::auth::revocation::RevocationCheck;
+
+pub const DEFAULT_ISSUER: &str = "auth-service";
+pub const DEFAULT_AUDIENCE: &str = "resume-api";
+
+/// `JWT_ISSUER`, or the default issuer when unset.
+pub fn issuer() -> String {
+    env::var("JWT_ISSUER").unwrap_or_else(|_| DEFAULT_ISSUER.to_string())
+}
+
+/// `JWT_AUDIENCE`, or the default audience when unset.
+pub fn audience() -> String {
+    env::var("JWT_AUDIENCE").unwrap_or_else(|_| DEFAULT_AUDIENCE.to_string())
+}
+
+fn required_env(name: &str) -> String {
+    env::var(name).ok()
+        .filter(|value| !value.is_empty())
+        .unwrap_or_else(|| panic!("{} must be set so revoked tokens are refused", name))
+}
+
+/// Verifies access tokens issued by auth-service: signature against the
+/// published JWKS, then `exp`, `iss` and `aud`. Personal access tokens are
+/// accepted too once a resolver is attached, and JWTs are checked for
+/// revocation once a `RevocationCheck` is.
+pub struct TokenValidator {
+    jwks: JwksCache,
+    issuer: String,
+    audience: String,
+    personal_access_tokens: Option<Arc<dyn PersonalAccessTokenResolver>>,
+    revocations: Option<Arc<dyn RevocationCheck>>,
+}
+
+impl TokenValidator {
+    pub fn new(jwks: JwksCache, issuer: &str, audience: &str) -> TokenValidator {
+        TokenValidator {
+            jwks,
+            issuer: issuer.to_string(),
+            audience: audience.to_string(),
+            personal_access_tokens: None,
+            revocations: None,
+        }
+    }
+
+    pub fn with_personal_access_tokens(mut self, resolver: Arc<dyn PersonalAccessTokenResolver>) -> TokenValidator {
+        self.personal_access_tokens = Some(resolver);
+        self
+    }
+
+    pub fn with_revocation_check(mut self, check: Arc<dyn RevocationCheck>) -> TokenValidator {
+        self.revocations = Some(check);
+        self
+    }
+
+    /// Reads `JWKS_URL`, `JWT_ISSUER` and `JWT_AUDIENCE`. Personal access
+    /// tokens and revocation are checked with auth-service at
+    /// `TOKEN_INTROSPECTION_URL`, authenticated as `TOKEN_INTROSPECTION_CLIENT_ID`
+    /// and `TOKEN_INTROSPECTION_CLIENT_SECRET`;
+    /// `TOKEN_INTROSPECTION_CACHE_SECONDS` sets how long answers are kept.
+    ///
+    /// Panics when any of the three is missing or empty: a service that could
+    /// not check revocation would accept a logged-out user's token until it
+    /// expires.
+    pub fn from_env() -> TokenValidator {
+        let url = env::var("JWKS_URL").expect("JWKS_URL must be set");
+        let validator = TokenValidator::new(JwksCache::new(&url), &issuer(), &audience());
+
+        let url = required_env("TOKEN_INTROSPECTION_URL");
+        let client_id = required_env("TOKEN_INTROSPECTION_CLIENT_ID");
+        let client_secret = required_env("TOKEN_INTROSPECTION_CLIENT_SECRET");
+        let mut introspection = RemoteIntrospection::new(&url, &client_id, &client_secret);
+        if let Some(seconds) = env::var("TOKEN_INTROSPECTION_CACHE_SECONDS").ok().and_then(|value| value.parse().ok()) {
+            introspection = introspection.with_cache_ttl(Duration::from_secs(seconds));
+        }
+
+        let introspection = Arc::new(introspection);
+        validator
+            .with_personal_access_tokens(introspection.clone())
+            .with_revocation_check(introspection)
+    }
+
+    pub async fn validate(&self, token: &str) -> Result<Claims, AuthError> {
+        if is_personal_access_token(token) {
+            return self.validate_personal_access_token(token).await;
+        }
+
+        let kid = decode_header(token)
+            .map_err(|_| AuthError::InvalidToken)?
+            .kid
+            .ok_or(AuthError::InvalidToken)?;
+
+        let (decoding_key, algorithm) = self.jwks.decoding_key(&kid).await
+            .ok_or(AuthError::UnknownKey)?;
+
+        let mut validation = Validation::new(algorithm);
+        validation.set_issuer(&[&self.issuer]);
+        validation.set_audience(&[&self.audience]);
+        validation.set_required_spec_claims(&["exp", "iss", "aud", "sub"]);
+
+        let claims = decode::<Claims>(token, &decoding_key, &validation)
+            .map(|data| data.claims)
+            .map_err(|e| match e.kind() {
+                ErrorKind::ExpiredSignature => AuthError::ExpiredToken,
+                _ => AuthError::InvalidToken,
+            })?;
+
+        if let Some(revocations) = &self.revocations {
+            revocations.check(token, &claims).await?;
+        }
+        Ok(claims)
+    }
+
+    async fn validate_personal_access_token(&self, token: &str) -> Result<Claims, AuthError> {
+        let resolver = self.personal_access_tokens.as_ref().ok_or(AuthError::InvalidToken)?;
+        let claims = resolver.resolve(token).await?;
+
+        if claims.iss != self.issuer || claims.aud != self.audience {
+            return Err(AuthError::InvalidToken);
+        }
+        if claims.exp as u64 <= jsonwebtoken::get_current_timestamp() {
+            return Err(AuthError::ExpiredToken);
+        }
+        Ok(claims)
+    }
+}
@@ -0,0 +1,35 @@
+//! The events by which services erase a user together. user-service
+//! anonymizes the account and publishes `ErasureRequested`; each service
+//! holding data about the user erases it and answers with
+//! `ErasureAcknowledged`. Once every service has answered and the grace
+//! period is over, user-service deletes the account for good.
+//!
+//! Delivery is at least once, so both sides must handle an event they have
+//! already seen: erasing a user that is gone still acknowledges.
+
+use chrono::NaiveDateTime;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+pub const ERASURE_REQUESTED_TOPIC: &str = "user.erasure.requested";
+pub const ERASURE_ACKNOWLEDGED_TOPIC: &str = "user.erasure.acknowledged";
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct ErasureRequested {
+    pub request_id: Uuid,
+    /// The account's id in user-service.
+    pub user_id: Uuid,
+    /// The address the account had before it was anonymized. Services that
+    /// keep their own accounts find the user by it.
+    pub email: String,
+    pub requested_at: NaiveDateTime,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct ErasureAcknowledged {
+    pub request_id: Uuid,
+    pub user_id: Uuid,
+    /// The name the service is listed under in user-service's `ERASURE_SERVICES`.
+    pub service: String,
+    pub erased_at: NaiveDateTime,
+}
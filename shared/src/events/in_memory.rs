@@ -0,0 +1,48 @@
+use std::sync::Mutex;
+
+use async_trait::async_trait;
+
+use crate::events::{EventBus, EventBusError};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PublishedEvent {
+    pub topic: String,
+    pub key: String,
+    pub payload: String,
+}
+
+/// Keeps published events in memory so tests can assert on them, without a
+/// broker. `fail_next` makes the next publishes fail, to exercise retries.
+#[derive(Default)]
+pub struct InMemoryEventBus {
+    published: Mutex<Vec<PublishedEvent>>,
+    failures: Mutex<usize>,
+}
+
+impl InMemoryEventBus {
+    pub fn published(&self) -> Vec<PublishedEvent> {
+        self.published.lock().unwrap().clone()
+    }
+
+    pub fn fail_next(&self, count: usize) {
+        *self.failures.lock().unwrap() = count;
+    }
+}
+
+#[async_trait]
+impl EventBus for InMemoryEventBus {
+    async fn publish(&self, topic: &str, key: &str, payload: &str) -> Result<(), EventBusError> {
+        let mut failures = self.failures.lock().unwrap();
+        if *failures > 0 {
+            *failures -= 1;
+            return Err(EventBusError::Delivery("broker unavailable".to_string()));
+        }
+
+        self.published.lock().unwrap().push(PublishedEvent {
+            topic: topic.to_string(),
+            key: key.to_string(),
+            payload: payload.to_string(),
+        });
+        Ok(())
+    }
+}
@@ -0,0 +1,103 @@
+use std::fmt::Display;
+use std::future::Future;
+use std::time::Duration;
+
+use async_trait::async_trait;
+use rdkafka::config::ClientConfig;
+use rdkafka::consumer::{CommitMode, Consumer, StreamConsumer};
+use rdkafka::producer::{FutureProducer, FutureRecord};
+use rdkafka::Message;
+
+use crate::events::{EventBus, EventBusError};
+
+/// How long a message may wait for delivery, queueing included, before the
+/// publish fails and the outbox retries it later.
+const DELIVERY_TIMEOUT: Duration = Duration::from_secs(10);
+/// How long a consumer waits before handling a failed message again.
+const RETRY_DELAY: Duration = Duration::from_secs(5);
+
+pub struct KafkaProducer {
+    producer: FutureProducer,
+}
+
+impl KafkaProducer {
+    pub fn new(brokers: &str) -> KafkaProducer {
+        let producer: FutureProducer = ClientConfig::new()
+            .set("bootstrap.servers", brokers)
+            .set("message.timeout.ms", DELIVERY_TIMEOUT.as_millis().to_string())
+            .set("enable.idempotence", "true")
+            .create()
+            .expect("Producer creation error");
+        KafkaProducer { producer }
+    }
+
+    /// Waits until the broker acknowledges the message.
+    pub async fn send(&self, topic: &str, key: &str, payload: &str) -> Result<(), EventBusError> {
+        let record = FutureRecord::to(topic)
+            .payload(payload)
+            .key(key);
+
+        self.producer.send(record, DELIVERY_TIMEOUT).await
+            .map(|_delivery| ())
+            .map_err(|(e, _message)| EventBusError::Delivery(e.to_string()))
+    }
+}
+
+#[async_trait]
+impl EventBus for KafkaProducer {
+    async fn publish(&self, topic: &str, key: &str, payload: &str) -> Result<(), EventBusError> {
+        self.send(topic, key, payload).await
+    }
+}
+
+pub struct KafkaConsumer {
+    consumer: StreamConsumer,
+}
+
+impl KafkaConsumer {
+    /// Joins `group_id` and subscribes to `topics`. A new group starts from
+    /// the oldest message still on each topic.
+    pub fn new(brokers: &str, group_id: &str, topics: &[&str]) -> KafkaConsumer {
+        let consumer: StreamConsumer = ClientConfig::new()
+            .set("bootstrap.servers", brokers)
+            .set("group.id", group_id)
+            .set("enable.auto.commit", "false")
+            .set("auto.offset.reset", "earliest")
+            .create()
+            .expect("Consumer creation error");
+        consumer.subscribe(topics).expect("Failed to subscribe to topics");
+        KafkaConsumer { consumer }
+    }
+
+    /// Hands each message's topic and payload to `handle`, and commits its
+    /// offset once handled. A message that fails is handled again until it
+    /// succeeds, so messages are handled at least once, and in order within
+    /// a partition.
+    pub async fn run<F, Fut, E>(self, handle: F)
+    where
+        F: Fn(String, String) -> Fut,
+        Fut: Future<Output = Result<(), E>>,
+        E: Display,
+    {
+        loop {
+            let message = match self.consumer.recv().await {
+                Ok(message) => message,
+                Err(e) => {
+                    log::error!("Failed to receive event: {}", e);
+                    tokio::time::sleep(RETRY_DELAY).await;
+                    continue;
+                }
+            };
+
+            let topic = message.topic().to_string();
+            let payload = String::from_utf8_lossy(message.payload().unwrap_or_default()).into_owned();
+            while let Err(e) = handle(topic.clone(), payload.clone()).await {
+                log::warn!("Failed to handle event from {} at offset {}: {}", message.topic(), message.offset(), e);
+                tokio::time::sleep(RETRY_DELAY).await;
+            }
+            if let Err(e) = self.consumer.commit_message(&message, CommitMode::Async) {
+                log::error!("Failed to commit offset {} of {}: {}", message.offset(), message.topic(), e);
+            }
+        }
+    }
+}
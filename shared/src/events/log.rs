@@ -0,0 +1,15 @@
+use async_trait::async_trait;
+
+use crate::events::{EventBus, EventBusError};
+
+/// Writes each event to the `events` log target instead of a broker. Handy
+/// for local development.
+pub struct LogEventBus;
+
+#[async_trait]
+impl EventBus for LogEventBus {
+    async fn publish(&self, topic: &str, key: &str, payload: &str) -> Result<(), EventBusError> {
+        log::info!(target: "events", "{} {} {}", topic, key, payload);
+        Ok(())
+    }
+}
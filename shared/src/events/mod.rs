@@ -0,0 +1,53 @@
+use std::env;
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use thiserror::Error;
+
+pub mod in_memory;
+pub mod kafka;
+pub mod log;
+
+pub use in_memory::InMemoryEventBus;
+pub use kafka::{KafkaConsumer, KafkaProducer};
+pub use log::LogEventBus;
+
+#[derive(Debug, Error)]
+pub enum EventBusError {
+    #[error("Failed to publish event: {0}")]
+    Delivery(String),
+}
+
+/// Where `OutboxRelay` publishes the events stored in the outbox.
+#[async_trait]
+pub trait EventBus: Send + Sync {
+    async fn publish(&self, topic: &str, key: &str, payload: &str) -> Result<(), EventBusError>;
+}
+
+/// Picks the bus from `EVENT_BUS`: `kafka` (needs `KAFKA_BROKERS`), `memory`
+/// or `log`, which writes events to the `events` log target.
+pub fn event_bus_from_env() -> Arc<dyn EventBus> {
+    dotenv::dotenv().ok();
+    match env::var("EVENT_BUS").unwrap_or_else(|_| "log".to_string()).as_str() {
+        "kafka" => {
+            let brokers = env::var("KAFKA_BROKERS").expect("KAFKA_BROKERS must be set");
+            Arc::new(KafkaProducer::new(&brokers))
+        }
+        "memory" => Arc::new(InMemoryEventBus::default()),
+        _ => Arc::new(LogEventBus),
+    }
+}
+
+/// A consumer of `topics` when `EVENT_BUS` is `kafka`, in the consumer group
+/// `KAFKA_GROUP_ID`, or `default_group_id` when that is unset. Other buses
+/// only publish, so there is nothing to consume.
+pub fn event_consumer_from_env(default_group_id: &str, topics: &[&str]) -> Option<KafkaConsumer> {
+    dotenv::dotenv().ok();
+    if env::var("EVENT_BUS").ok().as_deref() != Some("kafka") {
+        return None;
+    }
+
+    let brokers = env::var("KAFKA_BROKERS").expect("KAFKA_BROKERS must be set");
+    let group_id = env::var("KAFKA_GROUP_ID").unwrap_or_else(|_| default_group_id.to_string());
+    Some(KafkaConsumer::new(&brokers, &group_id, topics))
+}
@@ -0,0 +1,11 @@
+pub mod auth;
+pub mod erasure;
+#[cfg(feature = "events")]
+pub mod events;
+#[cfg(feature = "outbox")]
+pub mod outbox;
+pub mod pagination;
+pub mod password;
+
+#[cfg(test)]
+mod tests;
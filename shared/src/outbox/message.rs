@@ -0,0 +1,29 @@
+use chrono::NaiveDateTime;
+use diesel::{Insertable, Queryable};
+use serde_json::Value;
+use uuid::Uuid;
+use crate::outbox::schema::outbox;
+
+#[derive(Queryable, Debug)]
+pub struct OutboxMessage {
+    pub id: Uuid,
+    pub topic: String,
+    /// The message key on the bus; events with the same key keep their order
+    /// within a partition.
+    pub event_key: String,
+    pub payload: Value,
+    pub created_at: NaiveDateTime,
+    pub attempts: i32,
+    pub next_attempt_at: NaiveDateTime,
+    pub last_error: Option<String>,
+    pub published_at: Option<NaiveDateTime>,
+}
+
+#[derive(Insertable)]
+#[diesel(table_name = outbox)]
+pub struct NewOutboxMessage {
+    pub id: Uuid,
+    pub topic: String,
+    pub event_key: String,
+    pub payload: Value,
+}
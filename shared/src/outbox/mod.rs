@@ -0,0 +1,14 @@
+mod message;
+mod relay;
+mod repository;
+pub mod schema;
+
+pub use message::{NewOutboxMessage, OutboxMessage};
+pub use relay::OutboxRelay;
+pub use repository::OutboxRepository;
+
+use diesel::pg::PgConnection;
+use diesel::r2d2::{ConnectionManager, Pool};
+
+/// The pool type every service's `config::database` defines.
+pub type DbPool = Pool<ConnectionManager<PgConnection>>;
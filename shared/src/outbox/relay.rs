@@ -0,0 +1,79 @@
+use std::env;
+use std::sync::Arc;
+use std::time::Duration as StdDuration;
+use chrono::{Duration, Utc};
+use crate::events::EventBus;
+use crate::outbox::{DbPool, OutboxMessage, OutboxRepository};
+
+const DEFAULT_BATCH_SIZE: i64 = 100;
+const DEFAULT_POLL_SECONDS: u64 = 5;
+/// How long a claimed message is left alone by other relays.
+const CLAIM_LEASE_SECONDS: i64 = 60;
+/// Retries back off exponentially up to this delay.
+const MAX_RETRY_DELAY_SECONDS: i64 = 15 * 60;
+/// Published messages are kept this long, for inspection, then deleted.
+const PUBLISHED_RETENTION_DAYS: i64 = 7;
+
+/// Publishes outbox messages to the event bus. Delivery is at least once: a
+/// relay stopped between publishing and marking a message publishes it again.
+/// Failed messages are retried with a growing delay, and never dropped.
+pub struct OutboxRelay {
+    pool: DbPool,
+    bus: Arc<dyn EventBus>,
+    batch_size: i64,
+}
+
+impl OutboxRelay {
+    pub fn new(pool: DbPool, bus: Arc<dyn EventBus>) -> OutboxRelay {
+        OutboxRelay { pool, bus, batch_size: DEFAULT_BATCH_SIZE }
+    }
+
+    /// Publishes the messages that are due. Returns how many were published.
+    pub async fn relay_due(&self) -> Result<usize, diesel::result::Error> {
+        let due = OutboxRepository::claim_due(&self.pool, self.batch_size, Duration::seconds(CLAIM_LEASE_SECONDS)).await?;
+
+        let mut published = 0;
+        for message in due {
+            match self.bus.publish(&message.topic, &message.event_key, &message.payload.to_string()).await {
+                Ok(()) => {
+                    OutboxRepository::mark_published(&self.pool, message.id).await?;
+                    published += 1;
+                }
+                Err(e) => {
+                    log::warn!("Failed to publish outbox message {} (attempt {}): {}", message.id, message.attempts + 1, e);
+                    let retry_at = Utc::now().naive_utc() + retry_delay(&message);
+                    OutboxRepository::mark_failed(&self.pool, message.id, &e.to_string(), retry_at).await?;
+                }
+            }
+        }
+        Ok(published)
+    }
+
+    /// Relays forever, every `OUTBOX_POLL_SECONDS`, and sooner while there is
+    /// a backlog.
+    pub async fn run(self) {
+        let poll_interval = StdDuration::from_secs(
+            env::var("OUTBOX_POLL_SECONDS").ok().and_then(|value| value.parse().ok()).unwrap_or(DEFAULT_POLL_SECONDS),
+        );
+
+        loop {
+            match self.relay_due().await {
+                Ok(published) if published as i64 == self.batch_size => continue,
+                Ok(_) => {}
+                Err(e) => log::error!("Failed to relay outbox: {}", e),
+            }
+
+            let before = Utc::now().naive_utc() - Duration::days(PUBLISHED_RETENTION_DAYS);
+            if let Err(e) = OutboxRepository::purge_published(&self.pool, before).await {
+                log::error!("Failed to purge published outbox messages: {}", e);
+            }
+            tokio::time::sleep(poll_interval).await;
+        }
+    }
+}
+
+/// 1s, 2s, 4s... after each failed attempt, up to `MAX_RETRY_DELAY_SECONDS`.
+fn retry_delay(message: &OutboxMessage) -> Duration {
+    let exponent = message.attempts.clamp(0, 30) as u32;
+    Duration::seconds(2i64.saturating_pow(exponent).min(MAX_RETRY_DELAY_SECONDS))
+}
@@ -0,0 +1,71 @@
+use chrono::{Duration, NaiveDateTime, Utc};
+use diesel::pg::PgConnection;
+use diesel::prelude::*;
+use uuid::Uuid;
+use crate::outbox::schema::outbox;
+use crate::outbox::{DbPool, NewOutboxMessage, OutboxMessage};
+
+pub struct OutboxRepository;
+
+impl OutboxRepository {
+    /// Takes a connection rather than the pool, so messages are stored in the
+    /// caller's transaction.
+    pub fn create(conn: &mut PgConnection, messages: &[NewOutboxMessage]) -> Result<usize, diesel::result::Error> {
+        diesel::insert_into(outbox::table)
+            .values(messages)
+            .execute(conn)
+    }
+
+    /// Claims up to `limit` unpublished messages that are due, oldest first,
+    /// by pushing their next attempt `lease` into the future. Rows another
+    /// relay is claiming at the same moment are skipped.
+    pub async fn claim_due(pool: &DbPool, limit: i64, lease: Duration) -> Result<Vec<OutboxMessage>, diesel::result::Error> {
+        let conn = &mut pool.get().expect("Failed to get DB connection from pool");
+        let now = Utc::now().naive_utc();
+        conn.transaction(|conn| {
+            let due = outbox::table
+                .filter(outbox::published_at.is_null())
+                .filter(outbox::next_attempt_at.le(now))
+                .order(outbox::created_at.asc())
+                .limit(limit)
+                .for_update()
+                .skip_locked()
+                .load::<OutboxMessage>(conn)?;
+
+            let ids: Vec<Uuid> = due.iter().map(|message| message.id).collect();
+            diesel::update(outbox::table.filter(outbox::id.eq_any(&ids)))
+                .set(outbox::next_attempt_at.eq(now + lease))
+                .execute(conn)?;
+            Ok(due)
+        })
+    }
+
+    pub async fn mark_published(pool: &DbPool, id: Uuid) -> Result<usize, diesel::result::Error> {
+        let conn = &mut pool.get().expect("Failed to get DB connection from pool");
+        diesel::update(outbox::table.find(id))
+            .set((
+                outbox::published_at.eq(Utc::now().naive_utc()),
+                outbox::attempts.eq(outbox::attempts + 1),
+                outbox::last_error.eq(None::<String>),
+            ))
+            .execute(conn)
+    }
+
+    pub async fn mark_failed(pool: &DbPool, id: Uuid, error: &str, retry_at: NaiveDateTime) -> Result<usize, diesel::result::Error> {
+        let conn = &mut pool.get().expect("Failed to get DB connection from pool");
+        diesel::update(outbox::table.find(id))
+            .set((
+                outbox::attempts.eq(outbox::attempts + 1),
+                outbox::next_attempt_at.eq(retry_at),
+                outbox::last_error.eq(error),
+            ))
+            .execute(conn)
+    }
+
+    /// Drops messages published before `before`.
+    pub async fn purge_published(pool: &DbPool, before: NaiveDateTime) -> Result<usize, diesel::result::Error> {
+        let conn = &mut pool.get().expect("Failed to get DB connection from pool");
+        diesel::delete(outbox::table.filter(outbox::published_at.lt(before)))
+            .execute(conn)
+    }
+}
@@ -0,0 +1,15 @@
+// The `outbox` table, as each service's migrations create it.
+
+diesel::table! {
+    outbox (id) {
+        id -> Uuid,
+        topic -> Varchar,
+        event_key -> Varchar,
+        payload -> Jsonb,
+        created_at -> Timestamp,
+        attempts -> Int4,
+        next_attempt_at -> Timestamp,
+        last_error -> Nullable<Varchar>,
+        published_at -> Nullable<Timestamp>,
+    }
+}
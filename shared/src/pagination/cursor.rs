@@ -0,0 +1,52 @@
+use std::fmt::Display;
+use std::str::FromStr;
+
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::pagination::error::PaginationError;
+
+/// Where a page ended: the sort it was listed with, and the sort key and id
+/// of its last item. The next page starts strictly after that pair, so rows
+/// inserted or deleted meanwhile neither repeat nor go missing. Clients get
+/// it base64url-encoded and should treat it as opaque.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Cursor {
+    #[serde(rename = "s")]
+    pub sort: String,
+    #[serde(rename = "k")]
+    pub key: Value,
+    #[serde(rename = "i")]
+    pub id: String,
+}
+
+impl Cursor {
+    pub fn new<K: Serialize, I: Display>(sort: &str, key: K, id: I) -> Cursor {
+        Cursor {
+            sort: sort.to_string(),
+            key: serde_json::to_value(key).unwrap_or(Value::Null),
+            id: id.to_string(),
+        }
+    }
+
+    pub fn encode(&self) -> String {
+        URL_SAFE_NO_PAD.encode(serde_json::to_vec(self).expect("Cursors always serialize"))
+    }
+
+    pub fn decode(token: &str) -> Result<Cursor, PaginationError> {
+        let bytes = URL_SAFE_NO_PAD.decode(token).map_err(|_e| PaginationError::InvalidCursor)?;
+        serde_json::from_slice(&bytes).map_err(|_e| PaginationError::InvalidCursor)
+    }
+
+    /// The sort key, as the type of the field it was taken from.
+    pub fn key<T: DeserializeOwned>(&self) -> Result<T, PaginationError> {
+        serde_json::from_value(self.key.clone()).map_err(|_e| PaginationError::InvalidCursor)
+    }
+
+    pub fn id<T: FromStr>(&self) -> Result<T, PaginationError> {
+        self.id.parse().map_err(|_e| PaginationError::InvalidCursor)
+    }
+}
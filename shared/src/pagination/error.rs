@@ -0,0 +1,40 @@
+use actix_web::http::StatusCode;
+use actix_web::{HttpResponse, ResponseError};
+use serde_json::json;
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum PaginationError {
+    #[error("limit must be between 1 and {0}")]
+    InvalidLimit(i64),
+
+    #[error("Cannot sort by {0}")]
+    InvalidSort(String),
+
+    #[error("Invalid cursor")]
+    InvalidCursor,
+
+    #[error("Invalid query: {0}")]
+    InvalidQuery(String),
+}
+
+impl PaginationError {
+    pub fn code(&self) -> &'static str {
+        match self {
+            PaginationError::InvalidLimit(_) => "invalid_limit",
+            PaginationError::InvalidSort(_) => "invalid_sort",
+            PaginationError::InvalidCursor => "invalid_cursor",
+            PaginationError::InvalidQuery(_) => "invalid_query",
+        }
+    }
+}
+
+impl ResponseError for PaginationError {
+    fn status_code(&self) -> StatusCode {
+        StatusCode::BAD_REQUEST
+    }
+
+    fn error_response(&self) -> HttpResponse {
+        HttpResponse::build(self.status_code()).json(json!({ "error": self.to_string(), "code": self.code() }))
+    }
+}
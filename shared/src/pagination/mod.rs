@@ -0,0 +1,7 @@
+pub mod cursor;
+pub mod error;
+pub mod page;
+
+pub use cursor::Cursor;
+pub use error::PaginationError;
+pub use page::{Page, PageRequest, Sort, SortField, SortOrder, DEFAULT_PAGE_SIZE, MAX_PAGE_SIZE};
@@ -0,0 +1,150 @@
+use std::fmt;
+
+use actix_web::dev::Payload;
+use actix_web::{web, FromRequest, HttpRequest};
+use futures::future::{ready, Ready};
+use serde::{Deserialize, Serialize};
+
+use crate::pagination::cursor::Cursor;
+use crate::pagination::error::PaginationError;
+
+pub const DEFAULT_PAGE_SIZE: i64 = 20;
+pub const MAX_PAGE_SIZE: i64 = 100;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SortOrder {
+    Asc,
+    Desc,
+}
+
+/// The fields a listing can be sorted on. Anything else in `?sort=` is
+/// refused, so clients cannot order by unindexed or private columns.
+pub trait SortField: Copy + PartialEq + 'static {
+    /// Every sortable field, by its name in `?sort=`.
+    const FIELDS: &'static [(&'static str, Self)];
+
+    /// Used when the request has no `sort`.
+    fn default_sort() -> Sort<Self>;
+
+    fn name(&self) -> &'static str {
+        Self::FIELDS.iter()
+            .find(|(_, field)| field == self)
+            .map(|(name, _)| *name)
+            .expect("Every sort field is listed in FIELDS")
+    }
+}
+
+/// `?sort=created_at` sorts ascending, `?sort=-created_at` descending.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Sort<F> {
+    pub field: F,
+    pub order: SortOrder,
+}
+
+impl<F: SortField> Sort<F> {
+    pub fn parse(value: &str) -> Result<Sort<F>, PaginationError> {
+        let (name, order) = match value.strip_prefix('-') {
+            Some(name) => (name, SortOrder::Desc),
+            None => (value, SortOrder::Asc),
+        };
+        F::FIELDS.iter()
+            .find(|(field_name, _)| *field_name == name)
+            .map(|(_, field)| Sort { field: *field, order })
+            .ok_or_else(|| PaginationError::InvalidSort(value.to_string()))
+    }
+}
+
+impl<F: SortField> fmt::Display for Sort<F> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.order {
+            SortOrder::Asc => write!(f, "{}", self.field.name()),
+            SortOrder::Desc => write!(f, "-{}", self.field.name()),
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct PageQuery {
+    limit: Option<i64>,
+    cursor: Option<String>,
+    sort: Option<String>,
+}
+
+/// `?limit=&cursor=&sort=` of a keyset-paginated listing. Filters are the
+/// listing's own and can be extracted from the same query string alongside.
+///
+/// ```ignore
+/// async fn list_users(page: PageRequest<UserSort>, filter: web::Query<UserFilter>) -> ...
+/// ```
+#[derive(Debug, Clone)]
+pub struct PageRequest<F> {
+    pub limit: i64,
+    pub sort: Sort<F>,
+    /// The previous page's cursor; `None` for the first page.
+    pub after: Option<Cursor>,
+}
+
+impl<F: SortField> PageRequest<F> {
+    pub fn from_query(query_string: &str) -> Result<PageRequest<F>, PaginationError> {
+        let query = web::Query::<PageQuery>::from_query(query_string)
+            .map_err(|e| PaginationError::InvalidQuery(e.to_string()))?
+            .into_inner();
+
+        let limit = query.limit.unwrap_or(DEFAULT_PAGE_SIZE);
+        if !(1..=MAX_PAGE_SIZE).contains(&limit) {
+            return Err(PaginationError::InvalidLimit(MAX_PAGE_SIZE));
+        }
+        let sort = match &query.sort {
+            Some(sort) => Sort::parse(sort)?,
+            None => F::default_sort(),
+        };
+        let after = query.cursor.as_deref().map(Cursor::decode).transpose()?;
+        // A cursor only means something in the order it was taken in.
+        if after.as_ref().is_some_and(|cursor| cursor.sort != sort.to_string()) {
+            return Err(PaginationError::InvalidCursor);
+        }
+
+        Ok(PageRequest { limit, sort, after })
+    }
+
+    /// How many rows to load: one more than the page, to tell whether
+    /// another page follows.
+    pub fn fetch_limit(&self) -> i64 {
+        self.limit + 1
+    }
+
+    /// Cuts the rows loaded with `fetch_limit` down to the page, with a
+    /// cursor after the last one when more follow.
+    pub fn page<T>(&self, mut rows: Vec<T>, cursor: impl Fn(&T) -> Cursor) -> Page<T> {
+        let has_more = rows.len() as i64 > self.limit;
+        rows.truncate(self.limit as usize);
+        let next_cursor = rows.last()
+            .filter(|_| has_more)
+            .map(|last| cursor(last).encode());
+
+        Page { items: rows, next_cursor }
+    }
+}
+
+impl<F: SortField> FromRequest for PageRequest<F> {
+    type Error = PaginationError;
+    type Future = Ready<Result<Self, Self::Error>>;
+
+    fn from_request(req: &HttpRequest, _payload: &mut Payload) -> Self::Future {
+        ready(PageRequest::from_query(req.query_string()))
+    }
+}
+
+#[derive(Serialize, Debug)]
+pub struct Page<T> {
+    pub items: Vec<T>,
+    /// Pass as `?cursor=` for the next page; absent on the last one.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub next_cursor: Option<String>,
+}
+
+impl<T> Page<T> {
+    pub fn map<U>(self, f: impl FnMut(T) -> U) -> Page<U> {
+        Page { items: self.items.into_iter().map(f).collect(), next_cursor: self.next_cursor }
+    }
+}
@@ -0,0 +1,59 @@
+use std::fs::File;
+use std::io::{self, BufRead, BufReader};
+use std::path::{Path, PathBuf};
+
+use sha1::{Digest, Sha1};
+
+/// Length of the hash prefix that names a range file.
+const PREFIX_LENGTH: usize = 5;
+
+/// A local copy of a breached-password corpus, split the k-anonymity way: a
+/// directory of range files named after the first five hex digits of the
+/// SHA-1, each listing `SUFFIX:COUNT` lines, as the Pwned Passwords range API
+/// and its downloader produce them. A lookup reads only the one range file.
+#[derive(Debug, Clone)]
+pub struct BreachCorpus {
+    dir: PathBuf,
+}
+
+impl BreachCorpus {
+    pub fn new(dir: impl AsRef<Path>) -> BreachCorpus {
+        BreachCorpus { dir: dir.as_ref().to_path_buf() }
+    }
+
+    /// Whether the password appears in the corpus. A corpus that cannot be
+    /// read counts as a miss, so it never blocks a password change.
+    pub fn contains(&self, password: &str) -> bool {
+        let hash = sha1_hex(password);
+        let (prefix, suffix) = hash.split_at(PREFIX_LENGTH);
+
+        match self.find(prefix, suffix) {
+            Ok(found) => found,
+            Err(e) => {
+                if e.kind() != io::ErrorKind::NotFound {
+                    log::warn!("Failed to read breach corpus range {}: {}", prefix, e);
+                }
+                false
+            }
+        }
+    }
+
+    fn find(&self, prefix: &str, suffix: &str) -> io::Result<bool> {
+        let file = File::open(self.dir.join(prefix))
+            .or_else(|_| File::open(self.dir.join(format!("{}.txt", prefix))))?;
+
+        for line in BufReader::new(file).lines() {
+            let line = line?;
+            let listed = line.split(':').next().unwrap_or_default().trim();
+            if listed.eq_ignore_ascii_case(suffix) {
+                return Ok(true);
+            }
+        }
+        Ok(false)
+    }
+}
+
+/// Uppercase hex SHA-1, the form the corpus uses.
+pub fn sha1_hex(password: &str) -> String {
+    format!("{:X}", Sha1::digest(password.as_bytes()))
+}
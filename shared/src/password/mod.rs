@@ -0,0 +1,5 @@
+pub mod breach;
+pub mod policy;
+
+pub use breach::BreachCorpus;
+pub use policy::{PasswordContext, PasswordPolicy, PasswordViolation};
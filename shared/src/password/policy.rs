@@ -0,0 +1,239 @@
+use std::borrow::Cow;
+use std::env;
+
+use validator::{ValidationError, ValidationErrors};
+
+use crate::password::breach::BreachCorpus;
+
+pub const DEFAULT_MIN_LENGTH: usize = 8;
+pub const DEFAULT_MAX_LENGTH: usize = 128;
+pub const DEFAULT_HISTORY_SIZE: usize = 5;
+/// Usernames and email local parts shorter than this are not looked for
+/// inside passwords; they would match too much.
+const MIN_PERSONAL_INFO_LENGTH: usize = 3;
+
+/// Why a password was refused. Each becomes a `ValidationError` on the
+/// password field, with `code()` as its code.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PasswordViolation {
+    TooShort(usize),
+    TooLong(usize),
+    MissingUppercase,
+    MissingLowercase,
+    MissingDigit,
+    MissingSymbol,
+    ContainsUsername,
+    ContainsEmail,
+    RecentlyUsed(usize),
+    Breached,
+}
+
+impl PasswordViolation {
+    pub fn code(&self) -> &'static str {
+        match self {
+            PasswordViolation::TooShort(_) => "password_too_short",
+            PasswordViolation::TooLong(_) => "password_too_long",
+            PasswordViolation::MissingUppercase => "password_missing_uppercase",
+            PasswordViolation::MissingLowercase => "password_missing_lowercase",
+            PasswordViolation::MissingDigit => "password_missing_digit",
+            PasswordViolation::MissingSymbol => "password_missing_symbol",
+            PasswordViolation::ContainsUsername => "password_contains_username",
+            PasswordViolation::ContainsEmail => "password_contains_email",
+            PasswordViolation::RecentlyUsed(_) => "password_recently_used",
+            PasswordViolation::Breached => "password_breached",
+        }
+    }
+
+    pub fn message(&self) -> String {
+        match self {
+            PasswordViolation::TooShort(min) => format!("Must be at least {} characters long", min),
+            PasswordViolation::TooLong(max) => format!("Must be at most {} characters long", max),
+            PasswordViolation::MissingUppercase => "Must contain an uppercase letter".to_string(),
+            PasswordViolation::MissingLowercase => "Must contain a lowercase letter".to_string(),
+            PasswordViolation::MissingDigit => "Must contain a digit".to_string(),
+            PasswordViolation::MissingSymbol => "Must contain a symbol".to_string(),
+            PasswordViolation::ContainsUsername => "Must not contain the username".to_string(),
+            PasswordViolation::ContainsEmail => "Must not contain the email address".to_string(),
+            PasswordViolation::RecentlyUsed(count) => format!("Must differ from the last {} passwords", count),
+            PasswordViolation::Breached => "Appears in a known data breach".to_string(),
+        }
+    }
+
+    pub fn to_validation_error(&self) -> ValidationError {
+        let mut error = ValidationError::new(self.code());
+        error.message = Some(Cow::from(self.message()));
+        match self {
+            PasswordViolation::TooShort(min) => error.add_param(Cow::from("min"), min),
+            PasswordViolation::TooLong(max) => error.add_param(Cow::from("max"), max),
+            PasswordViolation::RecentlyUsed(count) => error.add_param(Cow::from("history_size"), count),
+            _ => {}
+        }
+        error
+    }
+}
+
+/// Who the password is for. Everything is optional: a new account has no
+/// history, and some callers do not know the username.
+#[derive(Default)]
+pub struct PasswordContext<'a> {
+    username: Option<&'a str>,
+    email: Option<&'a str>,
+    history: &'a [String],
+    verify: Option<fn(&str, &str) -> bool>,
+}
+
+impl<'a> PasswordContext<'a> {
+    pub fn new() -> PasswordContext<'a> {
+        PasswordContext::default()
+    }
+
+    pub fn with_username(mut self, username: &'a str) -> PasswordContext<'a> {
+        self.username = Some(username);
+        self
+    }
+
+    pub fn with_email(mut self, email: &'a str) -> PasswordContext<'a> {
+        self.email = Some(email);
+        self
+    }
+
+    /// Hashes of the current and previous passwords, newest first, and how
+    /// to check a plaintext against one of them: `verify(hash, password)`.
+    pub fn with_history(mut self, history: &'a [String], verify: fn(&str, &str) -> bool) -> PasswordContext<'a> {
+        self.history = history;
+        self.verify = Some(verify);
+        self
+    }
+}
+
+/// The one set of password rules, shared by every service that takes
+/// passwords. `from_env` reads:
+///
+/// - `PASSWORD_MIN_LENGTH`, `PASSWORD_MAX_LENGTH`
+/// - `PASSWORD_REQUIRE_UPPERCASE`, `PASSWORD_REQUIRE_LOWERCASE`,
+///   `PASSWORD_REQUIRE_DIGIT`, `PASSWORD_REQUIRE_SYMBOL`
+/// - `PASSWORD_REJECT_PERSONAL_INFO`: no username or email inside
+/// - `PASSWORD_HISTORY_SIZE`: how many recent passwords cannot be reused, 0 to allow any
+/// - `PASSWORD_BREACH_CORPUS_DIR`: a `BreachCorpus`, unchecked when unset
+#[derive(Debug, Clone)]
+pub struct PasswordPolicy {
+    pub min_length: usize,
+    pub max_length: usize,
+    pub require_uppercase: bool,
+    pub require_lowercase: bool,
+    pub require_digit: bool,
+    pub require_symbol: bool,
+    pub reject_personal_info: bool,
+    pub history_size: usize,
+    pub breach_corpus: Option<BreachCorpus>,
+}
+
+impl Default for PasswordPolicy {
+    fn default() -> Self {
+        PasswordPolicy {
+            min_length: DEFAULT_MIN_LENGTH,
+            max_length: DEFAULT_MAX_LENGTH,
+            require_uppercase: true,
+            require_lowercase: true,
+            require_digit: true,
+            require_symbol: true,
+            reject_personal_info: true,
+            history_size: DEFAULT_HISTORY_SIZE,
+            breach_corpus: None,
+        }
+    }
+}
+
+impl PasswordPolicy {
+    pub fn from_env() -> PasswordPolicy {
+        let defaults = PasswordPolicy::default();
+        PasswordPolicy {
+            min_length: env_or("PASSWORD_MIN_LENGTH", defaults.min_length),
+            max_length: env_or("PASSWORD_MAX_LENGTH", defaults.max_length),
+            require_uppercase: env_or("PASSWORD_REQUIRE_UPPERCASE", defaults.require_uppercase),
+            require_lowercase: env_or("PASSWORD_REQUIRE_LOWERCASE", defaults.require_lowercase),
+            require_digit: env_or("PASSWORD_REQUIRE_DIGIT", defaults.require_digit),
+            require_symbol: env_or("PASSWORD_REQUIRE_SYMBOL", defaults.require_symbol),
+            reject_personal_info: env_or("PASSWORD_REJECT_PERSONAL_INFO", defaults.reject_personal_info),
+            history_size: env_or("PASSWORD_HISTORY_SIZE", defaults.history_size),
+            breach_corpus: env::var("PASSWORD_BREACH_CORPUS_DIR").ok()
+                .filter(|dir| !dir.is_empty())
+                .map(BreachCorpus::new),
+        }
+    }
+
+    /// Every rule `password` breaks, in a stable order. The breach corpus is
+    /// only consulted for passwords that pass everything else.
+    pub fn check(&self, password: &str, context: &PasswordContext) -> Vec<PasswordViolation> {
+        let mut violations = Vec::new();
+
+        let length = password.chars().count();
+        if length < self.min_length {
+            violations.push(PasswordViolation::TooShort(self.min_length));
+        }
+        if length > self.max_length {
+            violations.push(PasswordViolation::TooLong(self.max_length));
+        }
+        if self.require_uppercase && !password.chars().any(char::is_uppercase) {
+            violations.push(PasswordViolation::MissingUppercase);
+        }
+        if self.require_lowercase && !password.chars().any(char::is_lowercase) {
+            violations.push(PasswordViolation::MissingLowercase);
+        }
+        if self.require_digit && !password.chars().any(|c| c.is_ascii_digit()) {
+            violations.push(PasswordViolation::MissingDigit);
+        }
+        if self.require_symbol && password.chars().all(char::is_alphanumeric) {
+            violations.push(PasswordViolation::MissingSymbol);
+        }
+
+        if self.reject_personal_info {
+            let lowered = password.to_lowercase();
+            if context.username.is_some_and(|username| contains_fragment(&lowered, username)) {
+                violations.push(PasswordViolation::ContainsUsername);
+            }
+            let local_part = context.email.map(|email| email.split('@').next().unwrap_or(email));
+            if local_part.is_some_and(|local_part| contains_fragment(&lowered, local_part)) {
+                violations.push(PasswordViolation::ContainsEmail);
+            }
+        }
+
+        if let Some(verify) = context.verify {
+            let recent = context.history.iter().take(self.history_size);
+            if recent.into_iter().any(|hash| verify(hash, password)) {
+                violations.push(PasswordViolation::RecentlyUsed(self.history_size));
+            }
+        }
+
+        if violations.is_empty() && self.breach_corpus.as_ref().is_some_and(|corpus| corpus.contains(password)) {
+            violations.push(PasswordViolation::Breached);
+        }
+        violations
+    }
+
+    /// Adds a `ValidationError` under `field` for every broken rule, so the
+    /// reasons come back next to the other field errors of a request.
+    pub fn validate_field(&self, errors: &mut ValidationErrors, field: &'static str, password: &str, context: &PasswordContext) {
+        for violation in self.check(password, context) {
+            errors.add(field, violation.to_validation_error());
+        }
+    }
+
+    pub fn validate(&self, field: &'static str, password: &str, context: &PasswordContext) -> Result<(), ValidationErrors> {
+        let mut errors = ValidationErrors::new();
+        self.validate_field(&mut errors, field, password, context);
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors)
+        }
+    }
+}
+
+fn contains_fragment(lowered_password: &str, fragment: &str) -> bool {
+    fragment.chars().count() >= MIN_PERSONAL_INFO_LENGTH && lowered_password.contains(&fragment.to_lowercase())
+}
+
+fn env_or<T: std::str::FromStr>(name: &str, default: T) -> T {
+    env::var(name).ok().and_then(|value| value.parse().ok()).unwrap_or(default)
+}
@@ -0,0 +1,29 @@
+use chrono::NaiveDate;
+use serde_json::json;
+use uuid::Uuid;
+
+use crate::erasure::{ErasureAcknowledged, ErasureRequested};
+
+#[test]
+fn test_erasure_events_keep_their_wire_format() {
+    // Given: A request and its acknowledgement
+    let request_id = Uuid::new_v4();
+    let user_id = Uuid::new_v4();
+    let at = NaiveDate::from_ymd_opt(2024, 12, 3).unwrap().and_hms_opt(9, 0, 0).unwrap();
+    let requested = ErasureRequested { request_id, user_id, email: "ada@example.com".to_string(), requested_at: at };
+    let acknowledged = ErasureAcknowledged { request_id, user_id, service: "auth-service".to_string(), erased_at: at };
+
+    // When: They are serialized
+    let requested_json = serde_json::to_value(&requested).unwrap();
+    let acknowledged_json = serde_json::to_value(&acknowledged).unwrap();
+
+    // Then: Other services can rely on the field names
+    assert_eq!(requested_json, json!({
+        "request_id": request_id,
+        "user_id": user_id,
+        "email": "ada@example.com",
+        "requested_at": "2024-12-03T09:00:00",
+    }));
+    assert_eq!(acknowledged_json["service"], "auth-service");
+    assert_eq!(serde_json::from_value::<ErasureAcknowledged>(acknowledged_json).unwrap(), acknowledged);
+}
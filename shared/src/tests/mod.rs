@@ -0,0 +1,4 @@
+mod auth_tests;
+mod erasure_tests;
+mod pagination_tests;
+mod password_tests;
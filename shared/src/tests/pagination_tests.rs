@@ -0,0 +1,93 @@
+use actix_web::test as actix_test;
+use actix_web::{web, App, HttpResponse};
+
+use crate::pagination::{Cursor, Page, PageRequest, PaginationError, Sort, SortField, SortOrder, MAX_PAGE_SIZE};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum ItemSort {
+    Name,
+    CreatedAt,
+}
+
+impl SortField for ItemSort {
+    const FIELDS: &'static [(&'static str, Self)] = &[("name", ItemSort::Name), ("created_at", ItemSort::CreatedAt)];
+
+    fn default_sort() -> Sort<Self> {
+        Sort { field: ItemSort::CreatedAt, order: SortOrder::Desc }
+    }
+}
+
+#[test]
+fn test_page_request_defaults_and_whitelists_sort_fields() {
+    // Given: A query without paging parameters
+    let request = PageRequest::<ItemSort>::from_query("").unwrap();
+
+    // Then: The default size and sort apply
+    assert_eq!(request.limit, 20);
+    assert_eq!(request.sort.to_string(), "-created_at");
+    assert!(request.after.is_none());
+
+    // And: Only listed fields and sizes within bounds are accepted
+    let sort = PageRequest::<ItemSort>::from_query("sort=name&limit=5").unwrap().sort;
+    assert_eq!(sort, Sort { field: ItemSort::Name, order: SortOrder::Asc });
+    assert!(matches!(PageRequest::<ItemSort>::from_query("sort=password"), Err(PaginationError::InvalidSort(_))));
+    assert!(matches!(PageRequest::<ItemSort>::from_query("limit=0"), Err(PaginationError::InvalidLimit(_))));
+    let too_many = format!("limit={}", MAX_PAGE_SIZE + 1);
+    assert!(matches!(PageRequest::<ItemSort>::from_query(&too_many), Err(PaginationError::InvalidLimit(_))));
+}
+
+#[test]
+fn test_cursor_only_continues_the_sort_it_was_taken_in() {
+    // Given: A cursor from a listing sorted by name
+    let cursor = Cursor::new("name", "mallory", 42).encode();
+
+    // When: It is used with the same sort
+    let request = PageRequest::<ItemSort>::from_query(&format!("sort=name&cursor={}", cursor)).unwrap();
+
+    // Then: The key and id come back typed
+    let after = request.after.unwrap();
+    assert_eq!(after.key::<String>().unwrap(), "mallory");
+    assert_eq!(after.id::<i64>().unwrap(), 42);
+
+    // And: It is refused with another sort, or when it is not a cursor at all
+    let other_sort = format!("sort=-name&cursor={}", cursor);
+    assert!(matches!(PageRequest::<ItemSort>::from_query(&other_sort), Err(PaginationError::InvalidCursor)));
+    assert!(matches!(PageRequest::<ItemSort>::from_query("cursor=not-a-cursor"), Err(PaginationError::InvalidCursor)));
+}
+
+#[test]
+fn test_page_has_a_cursor_only_when_more_rows_follow() {
+    // Given: A page size of 2
+    let request = PageRequest::<ItemSort>::from_query("sort=name&limit=2").unwrap();
+    let cursor = |name: &&str| Cursor::new("name", *name, name.len());
+
+    // When: One more row than the page was loaded
+    let page = request.page(vec!["ada", "grace", "hedy"], cursor);
+
+    // Then: The page is cut to size, and continues after its last row
+    assert_eq!(page.items, vec!["ada", "grace"]);
+    let next = Cursor::decode(page.next_cursor.as_deref().unwrap()).unwrap();
+    assert_eq!(next.key::<String>().unwrap(), "grace");
+
+    // And: The last page has no cursor
+    let page: Page<&str> = request.page(vec!["ada"], cursor);
+    assert!(page.next_cursor.is_none());
+}
+
+#[actix_rt::test]
+async fn test_invalid_paging_is_a_bad_request() {
+    // Given: A route taking a page request
+    let app = actix_test::init_service(App::new().route(
+        "/items",
+        web::get().to(|page: PageRequest<ItemSort>| async move { HttpResponse::Ok().body(page.sort.to_string()) }),
+    )).await;
+
+    // When: It is called with a sort field that is not allowed
+    let req = actix_test::TestRequest::get().uri("/items?sort=secret").to_request();
+    let res = actix_test::call_service(&app, req).await;
+
+    // Then: The request is refused with a stable code
+    assert_eq!(res.status(), actix_web::http::StatusCode::BAD_REQUEST);
+    let body: serde_json::Value = actix_test::read_body_json(res).await;
+    assert_eq!(body["code"], "invalid_sort");
+}
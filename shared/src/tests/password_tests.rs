@@ -0,0 +1,95 @@
+use std::fs;
+
+use crate::password::breach::sha1_hex;
+use crate::password::{BreachCorpus, PasswordContext, PasswordPolicy, PasswordViolation};
+
+fn codes(policy: &PasswordPolicy, password: &str, context: &PasswordContext) -> Vec<&'static str> {
+    policy.check(password, context).iter().map(PasswordViolation::code).collect()
+}
+
+#[test]
+fn test_character_classes_and_length_are_reported_together() {
+    // Given: The default policy
+    let policy = PasswordPolicy::default();
+
+    // When: A short, lowercase-only password is checked
+    let codes = codes(&policy, "abc", &PasswordContext::new());
+
+    // Then: Every broken rule is reported
+    assert_eq!(codes, vec![
+        "password_too_short",
+        "password_missing_uppercase",
+        "password_missing_digit",
+        "password_missing_symbol",
+    ]);
+
+    // And: A password meeting every rule passes
+    assert!(policy.check("Correct-Horse-9", &PasswordContext::new()).is_empty());
+}
+
+#[test]
+fn test_relaxed_policy_only_checks_length() {
+    // Given: A policy without character class requirements
+    let policy = PasswordPolicy {
+        min_length: 12,
+        require_uppercase: false,
+        require_lowercase: false,
+        require_digit: false,
+        require_symbol: false,
+        ..PasswordPolicy::default()
+    };
+
+    // Then: A long passphrase passes and a short one does not
+    assert!(policy.check("correct horse battery staple", &PasswordContext::new()).is_empty());
+    assert_eq!(policy.check("correct", &PasswordContext::new()), vec![PasswordViolation::TooShort(12)]);
+}
+
+#[test]
+fn test_username_and_email_are_rejected_case_insensitively() {
+    // Given: A user called "traveller" with an email at "jane.doe"
+    let policy = PasswordPolicy::default();
+    let context = PasswordContext::new().with_username("Traveller").with_email("jane.doe@example.com");
+
+    // Then: Passwords containing either are refused
+    assert_eq!(codes(&policy, "My-tRaVeLLeR-2024!", &context), vec!["password_contains_username"]);
+    assert_eq!(codes(&policy, "Jane.Doe-2024!", &context), vec!["password_contains_email"]);
+
+    // And: Very short usernames are not looked for
+    let context = PasswordContext::new().with_username("al");
+    assert!(policy.check("Always-Alert-7", &context).is_empty());
+}
+
+#[test]
+fn test_recent_passwords_cannot_be_reused() {
+    // Given: A history of three hashes, newest first, and a policy remembering two
+    let policy = PasswordPolicy { history_size: 2, ..PasswordPolicy::default() };
+    let history = vec!["First-Pass-1".to_string(), "Second-Pass-2".to_string(), "Third-Pass-3".to_string()];
+    let context = PasswordContext::new().with_history(&history, |hash, password| hash == password);
+
+    // Then: The two most recent are refused, the older one is allowed again
+    assert_eq!(policy.check("Second-Pass-2", &context), vec![PasswordViolation::RecentlyUsed(2)]);
+    assert!(policy.check("Third-Pass-3", &context).is_empty());
+}
+
+#[test]
+fn test_breached_passwords_are_found_in_the_corpus() {
+    // Given: A corpus holding the range file for one breached password
+    let dir = std::env::temp_dir().join(format!("breach-corpus-{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    let hash = sha1_hex("Password123!");
+    let (prefix, suffix) = hash.split_at(5);
+    fs::write(dir.join(prefix), format!("0018A45C4D1DEF81644B54AB7F969B88D65:1\n{}:2048\n", suffix.to_lowercase())).unwrap();
+    let policy = PasswordPolicy { breach_corpus: Some(BreachCorpus::new(&dir)), ..PasswordPolicy::default() };
+
+    // Then: That password is refused with a field-level reason
+    let errors = policy.validate("password", "Password123!", &PasswordContext::new()).unwrap_err();
+    let field_errors = errors.field_errors();
+    assert_eq!(field_errors["password"][0].code, "password_breached");
+
+    // And: Passwords missing from the corpus, or from a missing range file, pass
+    assert!(policy.validate("password", "Correct-Horse-9", &PasswordContext::new()).is_ok());
+    let policy = PasswordPolicy { breach_corpus: Some(BreachCorpus::new(dir.join("missing"))), ..PasswordPolicy::default() };
+    assert!(policy.validate("password", "Password123!", &PasswordContext::new()).is_ok());
+
+    fs::remove_dir_all(&dir).unwrap();
+}
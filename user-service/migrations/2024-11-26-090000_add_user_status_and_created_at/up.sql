@@ -0,0 +1,8 @@
+ALTER TABLE users
+    ADD COLUMN IF NOT EXISTS status VARCHAR NOT NULL DEFAULT 'active',
+    ADD COLUMN IF NOT EXISTS created_at TIMESTAMP NOT NULL DEFAULT NOW();
+
+-- Keyset pagination walks (sort key, id) for each sortable field.
+CREATE INDEX IF NOT EXISTS users_username_id_idx ON users (username, id);
+CREATE INDEX IF NOT EXISTS users_email_id_idx ON users (email, id);
+CREATE INDEX IF NOT EXISTS users_created_at_id_idx ON users (created_at, id);
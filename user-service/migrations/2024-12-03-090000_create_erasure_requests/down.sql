@@ -0,0 +1,5 @@
+DROP TABLE IF EXISTS outbox;
+DROP TABLE IF EXISTS erasure_acknowledgements;
+DROP TABLE IF EXISTS erasure_requests;
+
+ALTER TABLE users DROP COLUMN IF EXISTS deleted_at;
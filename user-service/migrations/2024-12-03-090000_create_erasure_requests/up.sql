@@ -0,0 +1,39 @@
+-- Set when an account is erased. The anonymized row stays until the grace
+-- period is over and every service has acknowledged the erasure.
+ALTER TABLE users ADD COLUMN IF NOT EXISTS deleted_at TIMESTAMP;
+
+-- No foreign key to users: the request is kept as a record once the
+-- account is gone.
+CREATE TABLE IF NOT EXISTS erasure_requests (
+    id uuid PRIMARY KEY,
+    user_id uuid NOT NULL,
+    requested_at TIMESTAMP NOT NULL DEFAULT NOW(),
+    purge_after TIMESTAMP NOT NULL,
+    completed_at TIMESTAMP
+);
+
+CREATE INDEX IF NOT EXISTS erasure_requests_pending_idx ON erasure_requests (purge_after) WHERE completed_at IS NULL;
+
+-- One row per service expected to erase its copy of the user's data.
+CREATE TABLE IF NOT EXISTS erasure_acknowledgements (
+    request_id uuid NOT NULL REFERENCES erasure_requests (id) ON DELETE CASCADE,
+    service VARCHAR NOT NULL,
+    acknowledged_at TIMESTAMP,
+    PRIMARY KEY (request_id, service)
+);
+
+-- Events written in the same transaction as the change they describe, and
+-- published to the event bus by the outbox relay afterwards.
+CREATE TABLE IF NOT EXISTS outbox (
+    id uuid PRIMARY KEY,
+    topic VARCHAR NOT NULL,
+    event_key VARCHAR NOT NULL,
+    payload JSONB NOT NULL,
+    created_at TIMESTAMP NOT NULL DEFAULT NOW(),
+    attempts INTEGER NOT NULL DEFAULT 0,
+    next_attempt_at TIMESTAMP NOT NULL DEFAULT NOW(),
+    last_error VARCHAR,
+    published_at TIMESTAMP
+);
+
+CREATE INDEX IF NOT EXISTS outbox_pending_idx ON outbox (next_attempt_at) WHERE published_at IS NULL;
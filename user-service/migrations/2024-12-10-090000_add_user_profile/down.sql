@@ -0,0 +1,11 @@
+DROP TRIGGER IF EXISTS set_updated_at ON users;
+
+ALTER TABLE users
+    DROP COLUMN IF EXISTS last_login_at,
+    DROP COLUMN IF EXISTS updated_at,
+    DROP COLUMN IF EXISTS phone,
+    DROP COLUMN IF EXISTS bio,
+    DROP COLUMN IF EXISTS website,
+    DROP COLUMN IF EXISTS location,
+    DROP COLUMN IF EXISTS headline,
+    DROP COLUMN IF EXISTS display_name;
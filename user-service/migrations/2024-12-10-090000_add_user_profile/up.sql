@@ -0,0 +1,12 @@
+ALTER TABLE users
+    ADD COLUMN IF NOT EXISTS display_name VARCHAR,
+    ADD COLUMN IF NOT EXISTS headline VARCHAR,
+    ADD COLUMN IF NOT EXISTS location VARCHAR,
+    ADD COLUMN IF NOT EXISTS website VARCHAR,
+    ADD COLUMN IF NOT EXISTS bio TEXT,
+    ADD COLUMN IF NOT EXISTS phone VARCHAR,
+    ADD COLUMN IF NOT EXISTS updated_at TIMESTAMP NOT NULL DEFAULT NOW(),
+    ADD COLUMN IF NOT EXISTS last_login_at TIMESTAMP;
+
+-- Any change to the row, a recorded login included, moves `updated_at`.
+SELECT diesel_manage_updated_at('users');
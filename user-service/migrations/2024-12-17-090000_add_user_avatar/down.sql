@@ -0,0 +1,3 @@
+ALTER TABLE users
+    DROP COLUMN IF EXISTS avatar_format,
+    DROP COLUMN IF EXISTS avatar_id;
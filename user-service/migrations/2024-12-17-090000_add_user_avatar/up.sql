@@ -0,0 +1,5 @@
+-- The current avatar: its renditions are kept in the blob store under
+-- avatars/<user id>/<avatar_id>/<size>.<avatar_format>.
+ALTER TABLE users
+    ADD COLUMN IF NOT EXISTS avatar_id UUID,
+    ADD COLUMN IF NOT EXISTS avatar_format VARCHAR;
@@ -0,0 +1,5 @@
+-- The username and email filters match a case-insensitive prefix as
+-- lower(column) LIKE 'prefix%'. text_pattern_ops lets these indexes serve
+-- that match whatever the database collation is.
+CREATE INDEX IF NOT EXISTS users_lower_username_idx ON users (lower(username) text_pattern_ops);
+CREATE INDEX IF NOT EXISTS users_lower_email_idx ON users (lower(email) text_pattern_ops);
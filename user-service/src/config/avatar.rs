@@ -0,0 +1,20 @@
+use std::env;
+
+const DEFAULT_MAX_BYTES: usize = 5 * 1024 * 1024;
+
+/// Limits on avatar uploads, from `AVATAR_MAX_BYTES`.
+pub struct AvatarPolicy {
+    /// The largest upload accepted, before it is processed.
+    pub max_bytes: usize,
+}
+
+impl AvatarPolicy {
+    pub fn from_env() -> AvatarPolicy {
+        dotenv::dotenv().ok();
+        let max_bytes = env::var("AVATAR_MAX_BYTES").ok()
+            .and_then(|value| value.parse().ok())
+            .unwrap_or(DEFAULT_MAX_BYTES);
+
+        AvatarPolicy { max_bytes }
+    }
+}
@@ -0,0 +1,32 @@
+use std::env;
+use chrono::Duration;
+
+const DEFAULT_GRACE_DAYS: i64 = 30;
+const DEFAULT_SERVICES: &str = "auth-service";
+
+/// How erasure requests are carried out, from `ERASURE_GRACE_DAYS` and
+/// `ERASURE_SERVICES`.
+pub struct ErasurePolicy {
+    /// How long an erased account is kept, anonymized, before it is deleted
+    /// for good.
+    pub grace_period: Duration,
+    /// The services that must acknowledge an erasure before it completes.
+    pub services: Vec<String>,
+}
+
+impl ErasurePolicy {
+    pub fn from_env() -> ErasurePolicy {
+        dotenv::dotenv().ok();
+        let grace_days = env::var("ERASURE_GRACE_DAYS").ok()
+            .and_then(|value| value.parse().ok())
+            .unwrap_or(DEFAULT_GRACE_DAYS);
+        let services = env::var("ERASURE_SERVICES").unwrap_or_else(|_| DEFAULT_SERVICES.to_string())
+            .split(',')
+            .map(str::trim)
+            .filter(|service| !service.is_empty())
+            .map(str::to_string)
+            .collect();
+
+        ErasurePolicy { grace_period: Duration::days(grace_days), services }
+    }
+}
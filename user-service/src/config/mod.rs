@@ -1 +1,3 @@
+pub mod avatar;
 pub mod database;
+pub mod erasure;
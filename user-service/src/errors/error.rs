@@ -0,0 +1,64 @@
+use shared::pagination::PaginationError;
+use thiserror::Error;
+use validator::ValidationErrors;
+use crate::storage::BlobStoreError;
+
+#[derive(Debug, Error)]
+pub enum UserError {
+    #[error("Database error: {0}")]
+    DatabaseError(#[from] diesel::result::Error),
+
+    #[error("User not found")]
+    UserNotFound,
+
+    #[error("Invalid input")]
+    ValidationError(#[from] ValidationErrors),
+
+    #[error(transparent)]
+    Pagination(#[from] PaginationError),
+
+    #[error("Erasure request not found")]
+    ErasureRequestNotFound,
+
+    #[error("Email already registered")]
+    EmailAlreadyExists,
+
+    #[error("Invalid upload: {0}")]
+    InvalidUpload(String),
+
+    #[error("Avatar is too large")]
+    AvatarTooLarge,
+
+    #[error("Avatar must be a JPEG, PNG or WebP image")]
+    UnsupportedMediaType,
+
+    #[error("Avatar not found")]
+    AvatarNotFound,
+
+    #[error("Storage error: {0}")]
+    Storage(#[from] BlobStoreError),
+
+    #[error("Internal server error")]
+    InternalServerError,
+}
+
+impl UserError {
+    /// Stable, machine-readable identifier sent as `code` in error responses,
+    /// the same way auth-service does.
+    pub fn code(&self) -> &'static str {
+        match self {
+            UserError::DatabaseError(_) => "internal_error",
+            UserError::UserNotFound => "user_not_found",
+            UserError::ValidationError(_) => "validation_failed",
+            UserError::Pagination(e) => e.code(),
+            UserError::ErasureRequestNotFound => "erasure_request_not_found",
+            UserError::EmailAlreadyExists => "email_already_exists",
+            UserError::InvalidUpload(_) => "invalid_upload",
+            UserError::AvatarTooLarge => "avatar_too_large",
+            UserError::UnsupportedMediaType => "unsupported_media_type",
+            UserError::AvatarNotFound => "avatar_not_found",
+            UserError::Storage(_) => "internal_error",
+            UserError::InternalServerError => "internal_error",
+        }
+    }
+}
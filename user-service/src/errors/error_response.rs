@@ -0,0 +1,42 @@
+use actix_web::{HttpResponse, ResponseError};
+use actix_web::http::StatusCode;
+use serde_json::json;
+use crate::errors::error::UserError;
+
+impl ResponseError for UserError {
+    fn status_code(&self) -> StatusCode {
+        match *self {
+            UserError::DatabaseError(_) => StatusCode::INTERNAL_SERVER_ERROR,
+            UserError::UserNotFound => StatusCode::NOT_FOUND,
+            UserError::ValidationError(_) => StatusCode::BAD_REQUEST,
+            UserError::Pagination(_) => StatusCode::BAD_REQUEST,
+            UserError::ErasureRequestNotFound => StatusCode::NOT_FOUND,
+            UserError::EmailAlreadyExists => StatusCode::CONFLICT,
+            UserError::InvalidUpload(_) => StatusCode::BAD_REQUEST,
+            UserError::AvatarTooLarge => StatusCode::PAYLOAD_TOO_LARGE,
+            UserError::UnsupportedMediaType => StatusCode::UNSUPPORTED_MEDIA_TYPE,
+            UserError::AvatarNotFound => StatusCode::NOT_FOUND,
+            UserError::Storage(_) => StatusCode::INTERNAL_SERVER_ERROR,
+            UserError::InternalServerError => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+
+    fn error_response(&self) -> HttpResponse {
+        let mut response = HttpResponse::build(self.status_code());
+        match self {
+            UserError::ValidationError(errors) => response
+                .json(json!({ "error": self.to_string(), "code": self.code(), "fields": errors })),
+            UserError::DatabaseError(e) => {
+                // The diesel message can include SQL details, so it only goes to the log.
+                log::error!("Database error: {}", e);
+                response.json(json!({ "error": UserError::InternalServerError.to_string(), "code": self.code() }))
+            },
+            UserError::Storage(e) => {
+                // Store errors can name paths, buckets or endpoints.
+                log::error!("Storage error: {}", e);
+                response.json(json!({ "error": UserError::InternalServerError.to_string(), "code": self.code() }))
+            },
+            _ => response.json(json!({ "error": self.to_string(), "code": self.code() })),
+        }
+    }
+}
@@ -0,0 +1,80 @@
+use actix_multipart::Multipart;
+use actix_web::http::header;
+use actix_web::{web, HttpResponse};
+use futures::StreamExt;
+use uuid::Uuid;
+
+use crate::config::avatar::AvatarPolicy;
+use crate::config::database::DbPool;
+use crate::errors::error::UserError;
+use crate::models::user::AvatarQuery;
+use crate::services::avatar_service::AvatarService;
+use crate::storage::BlobStore;
+
+/// The multipart field the image is sent in.
+const AVATAR_FIELD: &str = "avatar";
+
+/// `multipart/form-data` with the image in an `avatar` field; other fields
+/// are ignored. Responds with the profile, whose `avatar_url` is the new one.
+pub async fn upload_avatar(
+    pool: web::Data<DbPool>,
+    store: web::Data<dyn BlobStore>,
+    user_id: web::Path<Uuid>,
+    payload: Multipart,
+) -> Result<HttpResponse, UserError> {
+    let upload = read_avatar(payload, AvatarPolicy::from_env().max_bytes).await?;
+    let profile = AvatarService::upload_avatar(&pool, store.get_ref(), user_id.into_inner(), upload).await?;
+    Ok(HttpResponse::Ok().json(profile))
+}
+
+pub async fn get_avatar(
+    pool: web::Data<DbPool>,
+    store: web::Data<dyn BlobStore>,
+    user_id: web::Path<Uuid>,
+    query: web::Query<AvatarQuery>,
+) -> Result<HttpResponse, UserError> {
+    let avatar = AvatarService::get_avatar(&pool, store.get_ref(), user_id.into_inner(), query.size).await?;
+    // Renditions never change under their id, but a URL without it must
+    // follow the user's next upload.
+    let cache_control = if query.v == Some(avatar.avatar_id) {
+        "private, max-age=31536000, immutable"
+    } else {
+        "private, no-cache"
+    };
+    Ok(HttpResponse::Ok()
+        .content_type(avatar.blob.content_type)
+        .insert_header((header::CACHE_CONTROL, cache_control))
+        .insert_header((header::ETAG, format!("\"{}-{}\"", avatar.avatar_id, avatar.size)))
+        .body(avatar.blob.bytes))
+}
+
+pub async fn delete_avatar(
+    pool: web::Data<DbPool>,
+    store: web::Data<dyn BlobStore>,
+    user_id: web::Path<Uuid>,
+) -> Result<HttpResponse, UserError> {
+    AvatarService::delete_avatar(&pool, store.get_ref(), user_id.into_inner()).await?;
+    Ok(HttpResponse::NoContent().finish())
+}
+
+/// The contents of the `avatar` field, refused as soon as they pass
+/// `max_bytes` rather than once they have all been read.
+async fn read_avatar(mut payload: Multipart, max_bytes: usize) -> Result<Vec<u8>, UserError> {
+    while let Some(field) = payload.next().await {
+        let mut field = field.map_err(|e| UserError::InvalidUpload(e.to_string()))?;
+        if field.name() != Some(AVATAR_FIELD) {
+            continue;
+        }
+
+        let mut upload = Vec::new();
+        while let Some(chunk) = field.next().await {
+            let chunk = chunk.map_err(|e| UserError::InvalidUpload(e.to_string()))?;
+            if upload.len() + chunk.len() > max_bytes {
+                return Err(UserError::AvatarTooLarge);
+            }
+            upload.extend_from_slice(&chunk);
+        }
+        return Ok(upload);
+    }
+    Err(UserError::InvalidUpload(format!("no `{}` field", AVATAR_FIELD)))
+}
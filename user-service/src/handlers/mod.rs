@@ -1 +1,3 @@
+pub mod avatar_handler;
+pub mod privacy_handler;
 pub mod user_handler;
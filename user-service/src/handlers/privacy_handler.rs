@@ -0,0 +1,39 @@
+use actix_web::http::header;
+use actix_web::{web, HttpResponse};
+use uuid::Uuid;
+
+use crate::config::database::DbPool;
+use crate::errors::error::UserError;
+use crate::services::erasure_service::ErasureService;
+use crate::services::export_service::ExportService;
+use crate::storage::BlobStore;
+
+/// Downloads everything held about the user as a zip archive.
+pub async fn export_user(
+    pool: web::Data<DbPool>,
+    store: web::Data<dyn BlobStore>,
+    user_id: web::Path<Uuid>,
+) -> Result<HttpResponse, UserError> {
+    let user_id = user_id.into_inner();
+    let archive = ExportService::export_user(&pool, store.get_ref(), user_id).await?;
+    Ok(HttpResponse::Ok()
+        .content_type("application/zip")
+        .insert_header((header::CONTENT_DISPOSITION, format!("attachment; filename=\"user-{}.zip\"", user_id)))
+        .body(archive))
+}
+
+/// Erases the account at once and deletes it for good later; the response
+/// is the request, to follow with `get_erasure`.
+pub async fn request_erasure(
+    pool: web::Data<DbPool>,
+    store: web::Data<dyn BlobStore>,
+    user_id: web::Path<Uuid>,
+) -> Result<HttpResponse, UserError> {
+    let request = ErasureService::request_erasure(&pool, store.get_ref(), user_id.into_inner()).await?;
+    Ok(HttpResponse::Accepted().json(request))
+}
+
+pub async fn get_erasure(pool: web::Data<DbPool>, request_id: web::Path<Uuid>) -> Result<HttpResponse, UserError> {
+    let request = ErasureService::get_erasure(&pool, request_id.into_inner()).await?;
+    Ok(HttpResponse::Ok().json(request))
+}
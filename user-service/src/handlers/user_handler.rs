@@ -1,44 +1,66 @@
-use actix_web::{web, HttpResponse, Responder};
-use validator::Validate;
+use actix_web::{web, HttpResponse};
+use shared::pagination::PageRequest;
+use uuid::Uuid;
 
 use crate::config::database::DbPool;
-use crate::models::user::{NewUser};
-
+use crate::errors::error::UserError;
+use crate::models::user::{NewUser, UpdateProfile, UpdateUser, UserFilter, UserSort};
 use crate::services::user_service::UserService;
+use crate::storage::BlobStore;
 
 pub async fn create_user(
 	pool: web::Data<DbPool>,
 	new_user: web::Json<NewUser>
-) -> impl Responder {
-    let new_user = new_user.into_inner();
-    if let Err(e) = new_user.validate() {
-        return HttpResponse::BadRequest().json(format!("Invalid input: {}", e));
-    }
-
-    match UserService::create_user(&pool, new_user).await {
-        Ok(user) => HttpResponse::Ok().json(user),
-        Err(e) => HttpResponse::InternalServerError().body(e),
-    }
+) -> Result<HttpResponse, UserError> {
+    let user = UserService::create_user(&pool, new_user.into_inner()).await?;
+    Ok(HttpResponse::Ok().json(user))
+}
+
+/// `?limit=&cursor=&sort=` plus the `UserFilter` fields, e.g.
+/// `?email=ada&status=active&sort=-created_at`.
+pub async fn list_users(
+    pool: web::Data<DbPool>,
+    filter: web::Query<UserFilter>,
+    page: PageRequest<UserSort>,
+) -> Result<HttpResponse, UserError> {
+    let users = UserService::list_users(&pool, &filter, &page).await?;
+    Ok(HttpResponse::Ok().json(users))
 }
 
+pub async fn get_user(pool: web::Data<DbPool>, user_id: web::Path<Uuid>) -> Result<HttpResponse, UserError> {
+    let user = UserService::get_user(&pool, user_id.into_inner()).await?;
+    Ok(HttpResponse::Ok().json(user))
+}
+
+pub async fn update_user(
+    pool: web::Data<DbPool>,
+    user_id: web::Path<Uuid>,
+    changes: web::Json<UpdateUser>,
+) -> Result<HttpResponse, UserError> {
+    let user = UserService::update_user(&pool, user_id.into_inner(), changes.into_inner()).await?;
+    Ok(HttpResponse::Ok().json(user))
+}
 
-// pub async fn create_user(
-//     pool: web::Data<DbPool>,
-//     new_user: web::Json<NewUser>
-// ) -> impl Responder {
-//     let conn = &mut pool.get().expect("F banco de dados");
-//     let hashed_password = hash_password(&new_user.password).unwrap().to_string();
-//     let new_user = NewUser {
-//         password: hashed_password,
-//         ..new_user.into_inner()
-//     };
-//
-//     let user_created = diesel::insert_into(users)
-//         .values(new_user)
-//         .execute(conn);
-//
-//     match user_created {
-//         Ok(_) => HttpResponse::Created().body("User created successful"),
-//         Err(_) => HttpResponse::InternalServerError().body("Error to trying create user!")
-//     }
-// }
+pub async fn get_profile(pool: web::Data<DbPool>, user_id: web::Path<Uuid>) -> Result<HttpResponse, UserError> {
+    let profile = UserService::get_profile(&pool, user_id.into_inner()).await?;
+    Ok(HttpResponse::Ok().json(profile))
+}
+
+/// Absent fields are kept; `null` or blank ones are cleared.
+pub async fn update_profile(
+    pool: web::Data<DbPool>,
+    user_id: web::Path<Uuid>,
+    changes: web::Json<UpdateProfile>,
+) -> Result<HttpResponse, UserError> {
+    let profile = UserService::update_profile(&pool, user_id.into_inner(), changes.into_inner()).await?;
+    Ok(HttpResponse::Ok().json(profile))
+}
+
+pub async fn delete_user(
+    pool: web::Data<DbPool>,
+    store: web::Data<dyn BlobStore>,
+    user_id: web::Path<Uuid>,
+) -> Result<HttpResponse, UserError> {
+    UserService::delete_user(&pool, store.get_ref(), user_id.into_inner()).await?;
+    Ok(HttpResponse::NoContent().finish())
+}
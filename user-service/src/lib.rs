@@ -1,7 +1,12 @@
 pub mod config;
+pub mod errors;
 pub mod handlers;
 pub mod models;
 pub mod repositories;
 pub mod services;
+pub mod storage;
 pub mod utils;
 pub mod routes;
+
+#[cfg(test)]
+mod tests;
@@ -1,10 +1,16 @@
 use actix_web::{middleware, web, App, HttpServer};
 use env_logger::Env;
-
-mod config;
-use config::database::establish_connection;
-
+use shared::auth::TokenValidator;
+use shared::erasure::ERASURE_ACKNOWLEDGED_TOPIC;
+use shared::events::{event_bus_from_env, event_consumer_from_env};
+use shared::outbox::OutboxRelay;
+use user_service::config::database::establish_connection;
+use user_service::models::audit::audit_topic;
 use user_service::routes::user_routes;
+use user_service::services::erasure_service::ErasureService;
+use user_service::services::erasure_sweeper::ErasureSweeper;
+use user_service::services::user_service::UserService;
+use user_service::storage::blob_store_from_env;
 
 #[actix_web::main]
 async fn main() -> std::io::Result<()> {
@@ -13,11 +19,32 @@ async fn main() -> std::io::Result<()> {
     
     let pool = establish_connection();
     let data = web::Data::new(pool.clone());
+    let token_validator = web::Data::new(TokenValidator::from_env());
+    let blob_store = web::Data::from(blob_store_from_env());
+
+    tokio::spawn(OutboxRelay::new(pool.clone(), event_bus_from_env()).run());
+    tokio::spawn(ErasureSweeper::new(pool.clone()).run());
+    let audit_topic = audit_topic();
+    if let Some(consumer) = event_consumer_from_env("user-service", &[ERASURE_ACKNOWLEDGED_TOPIC, &audit_topic]) {
+        let pool = pool.clone();
+        tokio::spawn(consumer.run(move |topic, payload| {
+            let pool = pool.clone();
+            async move {
+                if topic == ERASURE_ACKNOWLEDGED_TOPIC {
+                    ErasureService::handle_acknowledgement(&pool, &payload).await
+                } else {
+                    UserService::handle_audit_event(&pool, &payload).await
+                }
+            }
+        }));
+    }
 
     println!("Server is running on port 8080!");
     HttpServer::new(move || {
         App::new()
             .app_data(data.clone())
+            .app_data(token_validator.clone())
+            .app_data(blob_store.clone())
             .wrap(middleware::Logger::default())
             .service(user_routes())
     })
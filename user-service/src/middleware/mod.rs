@@ -1 +0,0 @@
-pub mod rate_limiter;
\ No newline at end of file
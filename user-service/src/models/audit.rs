@@ -0,0 +1,26 @@
+use std::env;
+use chrono::NaiveDateTime;
+use serde::Deserialize;
+
+const DEFAULT_AUDIT_TOPIC: &str = "auth.audit";
+pub const LOGIN_SUCCEEDED: &str = "login_succeeded";
+
+/// Where auth-service publishes its audit events: `AUDIT_TOPIC`, the same
+/// variable auth-service reads.
+pub fn audit_topic() -> String {
+    env::var("AUDIT_TOPIC").unwrap_or_else(|_| DEFAULT_AUDIT_TOPIC.to_string())
+}
+
+/// The parts of an auth-service audit event that user-service reads.
+#[derive(Deserialize, Debug)]
+pub struct AuditEvent {
+    pub event_type: String,
+    pub occurred_at: NaiveDateTime,
+    #[serde(default)]
+    pub metadata: AuditMetadata,
+}
+
+#[derive(Deserialize, Default, Debug)]
+pub struct AuditMetadata {
+    pub email: Option<String>,
+}
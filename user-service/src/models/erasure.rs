@@ -0,0 +1,75 @@
+use chrono::NaiveDateTime;
+use diesel::{Insertable, Queryable};
+use serde::Serialize;
+use uuid::Uuid;
+use crate::models::schema::{erasure_acknowledgements, erasure_requests};
+
+#[derive(Queryable, Debug)]
+pub struct ErasureRequest {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    pub requested_at: NaiveDateTime,
+    /// The anonymized account is deleted for good after this, once every
+    /// service has acknowledged.
+    pub purge_after: NaiveDateTime,
+    pub completed_at: Option<NaiveDateTime>,
+}
+
+#[derive(Insertable)]
+#[diesel(table_name = erasure_requests)]
+pub struct NewErasureRequest {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    pub requested_at: NaiveDateTime,
+    pub purge_after: NaiveDateTime,
+}
+
+#[derive(Queryable, Debug)]
+pub struct ErasureAcknowledgement {
+    pub request_id: Uuid,
+    pub service: String,
+    pub acknowledged_at: Option<NaiveDateTime>,
+}
+
+#[derive(Insertable)]
+#[diesel(table_name = erasure_acknowledgements)]
+pub struct NewErasureAcknowledgement {
+    pub request_id: Uuid,
+    pub service: String,
+}
+
+#[derive(Serialize, Debug)]
+pub struct AcknowledgementResponse {
+    pub service: String,
+    /// Absent until the service has erased its data.
+    pub acknowledged_at: Option<NaiveDateTime>,
+}
+
+#[derive(Serialize, Debug)]
+pub struct ErasureRequestResponse {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    pub requested_at: NaiveDateTime,
+    pub purge_after: NaiveDateTime,
+    /// Set once the account is deleted for good.
+    pub completed_at: Option<NaiveDateTime>,
+    pub acknowledgements: Vec<AcknowledgementResponse>,
+}
+
+impl ErasureRequestResponse {
+    pub fn new(request: ErasureRequest, acknowledgements: Vec<ErasureAcknowledgement>) -> ErasureRequestResponse {
+        ErasureRequestResponse {
+            id: request.id,
+            user_id: request.user_id,
+            requested_at: request.requested_at,
+            purge_after: request.purge_after,
+            completed_at: request.completed_at,
+            acknowledgements: acknowledgements.into_iter()
+                .map(|acknowledgement| AcknowledgementResponse {
+                    service: acknowledgement.service,
+                    acknowledged_at: acknowledgement.acknowledged_at,
+                })
+                .collect(),
+        }
+    }
+}
@@ -1,2 +1,4 @@
+pub mod audit;
+pub mod erasure;
 pub mod user;
 pub mod schema;
@@ -1,10 +1,64 @@
 // @generated automatically by Diesel CLI.
 
+diesel::table! {
+    erasure_acknowledgements (request_id, service) {
+        request_id -> Uuid,
+        service -> Varchar,
+        acknowledged_at -> Nullable<Timestamp>,
+    }
+}
+
+diesel::table! {
+    erasure_requests (id) {
+        id -> Uuid,
+        user_id -> Uuid,
+        requested_at -> Timestamp,
+        purge_after -> Timestamp,
+        completed_at -> Nullable<Timestamp>,
+    }
+}
+
+diesel::table! {
+    outbox (id) {
+        id -> Uuid,
+        topic -> Varchar,
+        event_key -> Varchar,
+        payload -> Jsonb,
+        created_at -> Timestamp,
+        attempts -> Int4,
+        next_attempt_at -> Timestamp,
+        last_error -> Nullable<Varchar>,
+        published_at -> Nullable<Timestamp>,
+    }
+}
+
 diesel::table! {
     users (id) {
         id -> Uuid,
         username -> Varchar,
         email -> Varchar,
         password -> Varchar,
+        status -> Varchar,
+        created_at -> Timestamp,
+        deleted_at -> Nullable<Timestamp>,
+        display_name -> Nullable<Varchar>,
+        headline -> Nullable<Varchar>,
+        location -> Nullable<Varchar>,
+        website -> Nullable<Varchar>,
+        bio -> Nullable<Text>,
+        phone -> Nullable<Varchar>,
+        updated_at -> Timestamp,
+        last_login_at -> Nullable<Timestamp>,
+        avatar_id -> Nullable<Uuid>,
+        avatar_format -> Nullable<Varchar>,
     }
 }
+
+diesel::joinable!(erasure_acknowledgements -> erasure_requests (request_id));
+
+diesel::allow_tables_to_appear_in_same_query!(
+    erasure_acknowledgements,
+    erasure_requests,
+    outbox,
+    users,
+);
@@ -1,15 +1,61 @@
-use diesel::{Insertable, Queryable};
-use serde::{Deserialize, Serialize};
+use chrono::{DateTime, NaiveDateTime, Utc};
+use diesel::{AsChangeset, Insertable, Queryable};
+use serde::{Deserialize, Deserializer, Serialize};
+use shared::pagination::{Cursor, PaginationError, Sort, SortField, SortOrder};
 use uuid::Uuid;
-use validator::Validate;
+use validator::{Validate, ValidateUrl, ValidationError};
 use crate::models::schema::users;
 
-#[derive(Queryable, Serialize, Deserialize)]
+/// A row of `users`, password hash included. Not serializable: responses
+/// go through `UserResponse`.
+#[derive(Queryable, Debug)]
 pub struct User {
     pub id: Uuid,
     pub username: String,
     pub email: String,
     pub password: String,
+    pub status: String,
+    pub created_at: NaiveDateTime,
+    /// Set when the account is erased; such accounts are hidden everywhere.
+    pub deleted_at: Option<NaiveDateTime>,
+    pub display_name: Option<String>,
+    pub headline: Option<String>,
+    pub location: Option<String>,
+    pub website: Option<String>,
+    pub bio: Option<String>,
+    pub phone: Option<String>,
+    /// Moved by the database on every change to the row.
+    pub updated_at: NaiveDateTime,
+    /// The last sign-in auth-service reported for the account's email.
+    pub last_login_at: Option<NaiveDateTime>,
+    /// Identifies the current avatar's renditions in the blob store.
+    pub avatar_id: Option<Uuid>,
+    /// The renditions' extension, `jpg` or `png`.
+    pub avatar_format: Option<String>,
+}
+
+impl User {
+    /// Where the current avatar is served, versioned so that a new one is
+    /// never mistaken for a cached old one.
+    pub fn avatar_url(&self) -> Option<String> {
+        self.avatar_id.map(|avatar_id| format!("/users/{}/avatar?v={}", self.id, avatar_id))
+    }
+}
+
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum UserStatus {
+    Active,
+    Suspended,
+}
+
+impl UserStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            UserStatus::Active => "active",
+            UserStatus::Suspended => "suspended",
+        }
+    }
 }
 
 #[derive(Insertable, Serialize, Deserialize, Validate)]
@@ -19,20 +65,268 @@ pub struct NewUser {
     pub username: String,
     #[validate(email)]
     pub email: String,
-    #[validate(length(min = 8))]
-    // #[validate(custom(function = "validate_password_strength"))]
+    /// Checked against the shared password policy by `UserService`.
     pub password: String,
 }
 
-// fn validate_password_strength(password: &str) -> Result<(), validator::ValidationError> {
-//     let has_upper = password.chars().any(|c| c.is_uppercase());
-//     let has_lower = password.chars().any(|c| c.is_lowercase());
-//     let has_digit = password.chars().any(|c| c.is_digit(10));
-//     let has_special = password.chars().any(|c| !c.is_alphanumeric());
-//
-//     if has_upper && has_lower && has_digit && has_special {
-//         Ok(())
-//     } else {
-//         Err(ValidationError::new("password_strength"))
-//     }
-// }
+/// A partial update: only the fields present are changed.
+#[derive(AsChangeset, Serialize, Deserialize, Validate, Default)]
+#[diesel(table_name = users)]
+pub struct UpdateUser {
+    #[validate(length(min = 3, max = 50))]
+    pub username: Option<String>,
+    #[validate(email)]
+    pub email: Option<String>,
+    /// Plaintext in requests, hashed by `UserService` before it is stored.
+    pub password: Option<String>,
+}
+
+impl UpdateUser {
+    pub fn is_empty(&self) -> bool {
+        self.username.is_none() && self.email.is_none() && self.password.is_none()
+    }
+}
+
+/// `PATCH /users/{id}/profile`. A field that is absent stays as it is; one
+/// that is `null` or blank is cleared.
+#[derive(AsChangeset, Deserialize, Validate, Default, Debug)]
+#[diesel(table_name = users)]
+pub struct UpdateProfile {
+    #[serde(default, deserialize_with = "present")]
+    #[validate(length(min = 1, max = 100))]
+    pub display_name: Option<Option<String>>,
+    #[serde(default, deserialize_with = "present")]
+    #[validate(length(max = 150))]
+    pub headline: Option<Option<String>>,
+    #[serde(default, deserialize_with = "present")]
+    #[validate(length(max = 100))]
+    pub location: Option<Option<String>>,
+    /// An http or https URL.
+    #[serde(default, deserialize_with = "present")]
+    #[validate(length(max = 2048), custom(function = "validate_website"))]
+    pub website: Option<Option<String>>,
+    #[serde(default, deserialize_with = "present")]
+    #[validate(length(max = 2000))]
+    pub bio: Option<Option<String>>,
+    /// 7 to 15 digits, optionally with a leading `+` and spaces, dots,
+    /// dashes or parentheses between them.
+    #[serde(default, deserialize_with = "present")]
+    #[validate(custom(function = "validate_phone"))]
+    pub phone: Option<Option<String>>,
+}
+
+impl UpdateProfile {
+    pub fn is_empty(&self) -> bool {
+        self.display_name.is_none()
+            && self.headline.is_none()
+            && self.location.is_none()
+            && self.website.is_none()
+            && self.bio.is_none()
+            && self.phone.is_none()
+    }
+
+    /// Trims every value given, and turns blank ones into `null`.
+    pub fn normalized(self) -> UpdateProfile {
+        let normalize = |field: Option<Option<String>>| field.map(|value| {
+            value.map(|value| value.trim().to_string()).filter(|value| !value.is_empty())
+        });
+        UpdateProfile {
+            display_name: normalize(self.display_name),
+            headline: normalize(self.headline),
+            location: normalize(self.location),
+            website: normalize(self.website),
+            bio: normalize(self.bio),
+            phone: normalize(self.phone),
+        }
+    }
+}
+
+/// Tells a `null` field apart from an absent one: with `#[serde(default)]`,
+/// absent is `None` and `null` is `Some(None)`.
+fn present<'de, D, T>(deserializer: D) -> Result<Option<Option<T>>, D::Error>
+where
+    D: Deserializer<'de>,
+    T: Deserialize<'de>,
+{
+    Option::<T>::deserialize(deserializer).map(Some)
+}
+
+fn validate_website(website: &str) -> Result<(), ValidationError> {
+    let scheme_ok = website.starts_with("https://") || website.starts_with("http://");
+    if scheme_ok && website.validate_url() {
+        Ok(())
+    } else {
+        Err(ValidationError::new("website"))
+    }
+}
+
+fn validate_phone(phone: &str) -> Result<(), ValidationError> {
+    let digits = phone.chars().filter(char::is_ascii_digit).count();
+    let allowed = phone.strip_prefix('+').unwrap_or(phone)
+        .chars()
+        .all(|c| c.is_ascii_digit() || matches!(c, ' ' | '.' | '-' | '(' | ')'));
+    if allowed && (7..=15).contains(&digits) {
+        Ok(())
+    } else {
+        Err(ValidationError::new("phone"))
+    }
+}
+
+/// Everything about an account but its password.
+#[derive(Serialize, Debug)]
+pub struct UserResponse {
+    pub id: Uuid,
+    pub username: String,
+    pub email: String,
+    pub status: String,
+    pub display_name: Option<String>,
+    pub headline: Option<String>,
+    pub location: Option<String>,
+    pub website: Option<String>,
+    pub bio: Option<String>,
+    pub phone: Option<String>,
+    pub avatar_url: Option<String>,
+    pub created_at: NaiveDateTime,
+    pub updated_at: NaiveDateTime,
+    pub last_login_at: Option<NaiveDateTime>,
+}
+
+impl From<User> for UserResponse {
+    fn from(user: User) -> Self {
+        let avatar_url = user.avatar_url();
+        UserResponse {
+            id: user.id,
+            username: user.username,
+            email: user.email,
+            status: user.status,
+            display_name: user.display_name,
+            headline: user.headline,
+            location: user.location,
+            website: user.website,
+            bio: user.bio,
+            phone: user.phone,
+            avatar_url,
+            created_at: user.created_at,
+            updated_at: user.updated_at,
+            last_login_at: user.last_login_at,
+        }
+    }
+}
+
+/// The profile of an account, without its email, status or password.
+#[derive(Serialize, Debug)]
+pub struct ProfileResponse {
+    pub id: Uuid,
+    pub username: String,
+    pub display_name: Option<String>,
+    pub headline: Option<String>,
+    pub location: Option<String>,
+    pub website: Option<String>,
+    pub bio: Option<String>,
+    pub phone: Option<String>,
+    pub avatar_url: Option<String>,
+    pub updated_at: NaiveDateTime,
+}
+
+impl From<User> for ProfileResponse {
+    fn from(user: User) -> Self {
+        let avatar_url = user.avatar_url();
+        ProfileResponse {
+            id: user.id,
+            username: user.username,
+            display_name: user.display_name,
+            headline: user.headline,
+            location: user.location,
+            website: user.website,
+            bio: user.bio,
+            phone: user.phone,
+            avatar_url,
+            updated_at: user.updated_at,
+        }
+    }
+}
+
+/// `GET /users/{id}/avatar?size=&v=`.
+#[derive(Deserialize, Debug)]
+pub struct AvatarQuery {
+    /// The size wanted, in pixels; the closest rendition is served.
+    #[serde(default = "default_avatar_size")]
+    pub size: u32,
+    /// The avatar id from `avatar_url`. When it is the current one the
+    /// response can be cached for good.
+    pub v: Option<Uuid>,
+}
+
+fn default_avatar_size() -> u32 {
+    256
+}
+
+/// `GET /users` filters, next to the paging parameters in the query string.
+#[derive(Deserialize, Validate, Default, Debug)]
+pub struct UserFilter {
+    /// Usernames starting with this, ignoring case.
+    #[validate(length(min = 1, max = 50))]
+    pub username: Option<String>,
+    /// Emails starting with this, ignoring case.
+    #[validate(length(min = 1, max = 254))]
+    pub email: Option<String>,
+    pub status: Option<UserStatus>,
+    /// Created at or after this instant.
+    pub created_after: Option<DateTime<Utc>>,
+    /// Created before this instant.
+    pub created_before: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum UserSort {
+    Username,
+    Email,
+    CreatedAt,
+}
+
+impl SortField for UserSort {
+    const FIELDS: &'static [(&'static str, Self)] = &[
+        ("username", UserSort::Username),
+        ("email", UserSort::Email),
+        ("created_at", UserSort::CreatedAt),
+    ];
+
+    /// Newest first.
+    fn default_sort() -> Sort<Self> {
+        Sort { field: UserSort::CreatedAt, order: SortOrder::Desc }
+    }
+}
+
+/// A user's position in a listing: the sort key and the id that breaks ties.
+pub enum UserKey {
+    Username(String, Uuid),
+    Email(String, Uuid),
+    CreatedAt(NaiveDateTime, Uuid),
+}
+
+impl UserKey {
+    pub fn of(user: &User, field: UserSort) -> UserKey {
+        match field {
+            UserSort::Username => UserKey::Username(user.username.clone(), user.id),
+            UserSort::Email => UserKey::Email(user.email.clone(), user.id),
+            UserSort::CreatedAt => UserKey::CreatedAt(user.created_at, user.id),
+        }
+    }
+
+    pub fn from_cursor(field: UserSort, cursor: &Cursor) -> Result<UserKey, PaginationError> {
+        let id = cursor.id()?;
+        Ok(match field {
+            UserSort::Username => UserKey::Username(cursor.key()?, id),
+            UserSort::Email => UserKey::Email(cursor.key()?, id),
+            UserSort::CreatedAt => UserKey::CreatedAt(cursor.key()?, id),
+        })
+    }
+
+    pub fn to_cursor(&self, sort: Sort<UserSort>) -> Cursor {
+        let sort = sort.to_string();
+        match self {
+            UserKey::Username(key, id) | UserKey::Email(key, id) => Cursor::new(&sort, key, id),
+            UserKey::CreatedAt(key, id) => Cursor::new(&sort, key, id),
+        }
+    }
+}
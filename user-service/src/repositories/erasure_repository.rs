@@ -0,0 +1,79 @@
+use chrono::NaiveDateTime;
+use diesel::dsl::{exists, not};
+use diesel::pg::PgConnection;
+use diesel::prelude::*;
+use uuid::Uuid;
+use crate::config::database::DbPool;
+use crate::models::erasure::{ErasureAcknowledgement, ErasureRequest, NewErasureAcknowledgement, NewErasureRequest};
+use crate::models::schema::{erasure_acknowledgements, erasure_requests};
+
+pub struct ErasureRepository;
+
+impl ErasureRepository {
+    /// Stores the request with an unanswered acknowledgement for each
+    /// service, in the caller's transaction.
+    pub fn create(
+        conn: &mut PgConnection,
+        request: &NewErasureRequest,
+        acknowledgements: &[NewErasureAcknowledgement],
+    ) -> Result<(ErasureRequest, Vec<ErasureAcknowledgement>), diesel::result::Error> {
+        let request = diesel::insert_into(erasure_requests::table)
+            .values(request)
+            .get_result::<ErasureRequest>(conn)?;
+        let acknowledgements = diesel::insert_into(erasure_acknowledgements::table)
+            .values(acknowledgements)
+            .get_results::<ErasureAcknowledgement>(conn)?;
+        Ok((request, acknowledgements))
+    }
+
+    pub async fn find(pool: &DbPool, request_id: Uuid) -> Result<(ErasureRequest, Vec<ErasureAcknowledgement>), diesel::result::Error> {
+        let conn = &mut pool.get().expect("Failed to get DB connection from pool");
+        let request = erasure_requests::table.find(request_id).first::<ErasureRequest>(conn)?;
+        let acknowledgements = erasure_acknowledgements::table
+            .filter(erasure_acknowledgements::request_id.eq(request_id))
+            .order(erasure_acknowledgements::service.asc())
+            .load::<ErasureAcknowledgement>(conn)?;
+        Ok((request, acknowledgements))
+    }
+
+    /// Records the service's answer. Matches nothing when the service is not
+    /// expected to answer, or already has.
+    pub async fn acknowledge(pool: &DbPool, request_id: Uuid, service: &str, at: NaiveDateTime) -> Result<usize, diesel::result::Error> {
+        let conn = &mut pool.get().expect("Failed to get DB connection from pool");
+        diesel::update(
+            erasure_acknowledgements::table
+                .find((request_id, service))
+                .filter(erasure_acknowledgements::acknowledged_at.is_null()),
+        )
+            .set(erasure_acknowledgements::acknowledged_at.eq(at))
+            .execute(conn)
+    }
+
+    /// Open requests past their grace period that every service has acknowledged.
+    pub async fn due(pool: &DbPool, now: NaiveDateTime, limit: i64) -> Result<Vec<ErasureRequest>, diesel::result::Error> {
+        let conn = &mut pool.get().expect("Failed to get DB connection from pool");
+        let unanswered = erasure_acknowledgements::table
+            .filter(erasure_acknowledgements::request_id.eq(erasure_requests::id))
+            .filter(erasure_acknowledgements::acknowledged_at.is_null());
+
+        erasure_requests::table
+            .filter(erasure_requests::completed_at.is_null())
+            .filter(erasure_requests::purge_after.le(now))
+            .filter(not(exists(unanswered)))
+            .order(erasure_requests::purge_after.asc())
+            .limit(limit)
+            .load::<ErasureRequest>(conn)
+    }
+
+    /// Matches nothing when the request was already completed, by another
+    /// instance for example.
+    pub fn complete(conn: &mut PgConnection, request_id: Uuid, at: NaiveDateTime) -> Result<usize, diesel::result::Error> {
+        diesel::update(
+            erasure_requests::table
+                .find(request_id)
+                .filter(erasure_requests::completed_at.is_null()),
+        )
+            .set(erasure_requests::completed_at.eq(at))
+            .execute(conn)
+    }
+}
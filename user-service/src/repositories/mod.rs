@@ -1 +1,2 @@
+pub mod erasure_repository;
 pub mod user_repository;
@@ -1,8 +1,25 @@
+use chrono::NaiveDateTime;
+use diesel::pg::PgConnection;
 use diesel::prelude::*;
-use crate::models::user::{User, NewUser};
+use diesel::sql_types::Text;
+use shared::pagination::{Sort, SortOrder};
+use uuid::Uuid;
+use crate::models::user::{User, NewUser, UpdateProfile, UpdateUser, UserFilter, UserKey, UserSort};
 use crate::config::database::DbPool;
 use crate::models::schema::users::dsl::*;
 
+define_sql_function!(fn lower(value: Text) -> Text);
+
+/// Narrows `$query` to the rows strictly after `($key, $id)` in the listing order.
+macro_rules! after {
+    ($query:expr, $column:expr, $key:expr, $id:expr, $descending:expr) => {
+        if $descending {
+            $query.filter($column.lt($key.clone()).or($column.eq($key).and(id.lt($id))))
+        } else {
+            $query.filter($column.gt($key.clone()).or($column.eq($key).and(id.gt($id))))
+        }
+    };
+}
 
 pub struct UserRepository;
 
@@ -13,4 +30,140 @@ impl UserRepository {
             .values(&new_user)
             .get_result(conn)
     }
+
+    /// Up to `limit` live users matching `filter`, in `sort` order, starting after `after`.
+    /// Username and email prefixes are compared lowercased, the way the
+    /// `users_lower_*_idx` indexes store them.
+    pub async fn list_users(
+        pool: &DbPool,
+        filter: &UserFilter,
+        sort: Sort<UserSort>,
+        after: Option<UserKey>,
+        limit: i64,
+    ) -> Result<Vec<User>, diesel::result::Error> {
+        let conn = &mut pool.get().expect("Failed to get DB connection from pool");
+        let mut query = users.filter(deleted_at.is_null()).into_boxed();
+
+        if let Some(prefix) = &filter.username {
+            query = query.filter(lower(username).like(lower(starts_with(prefix))));
+        }
+        if let Some(prefix) = &filter.email {
+            query = query.filter(lower(email).like(lower(starts_with(prefix))));
+        }
+        if let Some(user_status) = filter.status {
+            query = query.filter(status.eq(user_status.as_str()));
+        }
+        if let Some(from) = filter.created_after {
+            query = query.filter(created_at.ge(from.naive_utc()));
+        }
+        if let Some(until) = filter.created_before {
+            query = query.filter(created_at.lt(until.naive_utc()));
+        }
+
+        let descending = sort.order == SortOrder::Desc;
+        query = match after {
+            Some(UserKey::Username(key, after_id)) => after!(query, username, key, after_id, descending),
+            Some(UserKey::Email(key, after_id)) => after!(query, email, key, after_id, descending),
+            Some(UserKey::CreatedAt(key, after_id)) => after!(query, created_at, key, after_id, descending),
+            None => query,
+        };
+        query = match (sort.field, descending) {
+            (UserSort::Username, false) => query.order((username.asc(), id.asc())),
+            (UserSort::Username, true) => query.order((username.desc(), id.desc())),
+            (UserSort::Email, false) => query.order((email.asc(), id.asc())),
+            (UserSort::Email, true) => query.order((email.desc(), id.desc())),
+            (UserSort::CreatedAt, false) => query.order((created_at.asc(), id.asc())),
+            (UserSort::CreatedAt, true) => query.order((created_at.desc(), id.desc())),
+        };
+
+        query.limit(limit).load::<User>(conn)
+    }
+
+    pub async fn find_user(pool: &DbPool, user_id: Uuid) -> Result<User, diesel::result::Error> {
+        let conn = &mut pool.get().expect("Failed to get DB connection from pool");
+        users.find(user_id).filter(deleted_at.is_null()).first::<User>(conn)
+    }
+
+    /// `find_user` inside the caller's transaction, locking the row.
+    pub fn lock_user(conn: &mut PgConnection, user_id: Uuid) -> Result<User, diesel::result::Error> {
+        users.find(user_id).filter(deleted_at.is_null()).for_update().first::<User>(conn)
+    }
+
+    /// Applies the fields present in `changes`; there must be at least one.
+    pub async fn update_user(pool: &DbPool, user_id: Uuid, changes: UpdateUser) -> Result<User, diesel::result::Error> {
+        let conn = &mut pool.get().expect("Failed to get DB connection from pool");
+        diesel::update(users.find(user_id).filter(deleted_at.is_null()))
+            .set(&changes)
+            .get_result(conn)
+    }
+
+    /// Applies the fields present in `changes`; there must be at least one.
+    pub async fn update_profile(pool: &DbPool, user_id: Uuid, changes: UpdateProfile) -> Result<User, diesel::result::Error> {
+        let conn = &mut pool.get().expect("Failed to get DB connection from pool");
+        diesel::update(users.find(user_id).filter(deleted_at.is_null()))
+            .set(&changes)
+            .get_result(conn)
+    }
+
+    /// Points the account at another avatar, or at none, inside the caller's
+    /// transaction.
+    pub fn set_avatar(conn: &mut PgConnection, user_id: Uuid, new_avatar_id: Option<Uuid>, new_avatar_format: Option<&str>) -> Result<User, diesel::result::Error> {
+        diesel::update(users.find(user_id).filter(deleted_at.is_null()))
+            .set((avatar_id.eq(new_avatar_id), avatar_format.eq(new_avatar_format)))
+            .get_result(conn)
+    }
+
+    /// Records a sign-in at `at` for the live account with `user_email`,
+    /// unless a later one is already recorded: events may arrive out of order.
+    pub async fn record_login(pool: &DbPool, user_email: &str, at: NaiveDateTime) -> Result<usize, diesel::result::Error> {
+        let conn = &mut pool.get().expect("Failed to get DB connection from pool");
+        diesel::update(
+            users
+                .filter(email.eq(user_email))
+                .filter(deleted_at.is_null())
+                .filter(last_login_at.is_null().or(last_login_at.lt(at))),
+        )
+            .set(last_login_at.eq(at))
+            .execute(conn)
+    }
+
+    /// The deleted user, or `None` when there was no live one.
+    pub async fn delete_user(pool: &DbPool, user_id: Uuid) -> Result<Option<User>, diesel::result::Error> {
+        let conn = &mut pool.get().expect("Failed to get DB connection from pool");
+        diesel::delete(users.find(user_id).filter(deleted_at.is_null())).get_result(conn).optional()
+    }
+
+    /// Replaces or clears everything that identifies the user, makes the
+    /// password unusable and marks the account deleted. The id stays, so the row can
+    /// be deleted for good once the erasure completes.
+    pub fn anonymize(conn: &mut PgConnection, user_id: Uuid, at: NaiveDateTime) -> Result<User, diesel::result::Error> {
+        diesel::update(users.find(user_id))
+            .set((
+                username.eq(format!("erased-{}", user_id.simple())),
+                email.eq(format!("{}@erased.invalid", user_id)),
+                password.eq(""),
+                display_name.eq(None::<String>),
+                headline.eq(None::<String>),
+                location.eq(None::<String>),
+                website.eq(None::<String>),
+                bio.eq(None::<String>),
+                phone.eq(None::<String>),
+                last_login_at.eq(None::<NaiveDateTime>),
+                avatar_id.eq(None::<Uuid>),
+                avatar_format.eq(None::<String>),
+                deleted_at.eq(at),
+            ))
+            .get_result(conn)
+    }
+
+    /// Deletes an anonymized account for good.
+    pub fn purge(conn: &mut PgConnection, user_id: Uuid) -> Result<usize, diesel::result::Error> {
+        diesel::delete(users.find(user_id).filter(deleted_at.is_not_null())).execute(conn)
+    }
+}
+
+/// A `LIKE` pattern matching values that start with `prefix` taken literally.
+fn starts_with(prefix: &str) -> String {
+    let escaped = prefix.replace('\\', "\\\\").replace('%', "\\%").replace('_', "\\_");
+    format!("{}%", escaped)
 }
@@ -1,8 +1,96 @@
-use actix_web::{web, Scope};
-use crate::handlers::user_handler::{create_user};
+use actix_web::dev::HttpServiceFactory;
+use actix_web::{guard, web};
+use shared::auth::{require_permission, require_permission_or_owner, RequireAuth};
+use crate::handlers::avatar_handler::{delete_avatar, get_avatar, upload_avatar};
+use crate::handlers::privacy_handler::{export_user, get_erasure, request_erasure};
+use crate::handlers::user_handler::{create_user, delete_user, get_profile, get_user, list_users, update_profile, update_user};
 
-pub fn user_routes() -> Scope {
+/// Every route needs a valid bearer token. Each method of a path also needs
+/// its own permission, so every one is a resource of its own, picked by a
+/// method guard. Users may change their own profile and avatar without
+/// `users:write`.
+pub fn user_routes() -> impl HttpServiceFactory {
     web::scope("/users")
-        .route("/create", web::post().to(create_user))
-        // .route("/{id}", web::get().to(get_user))
+        .wrap(RequireAuth)
+        .service(
+            web::resource("")
+                .guard(guard::Get())
+                .wrap(require_permission("users:read"))
+                .route(web::get().to(list_users)),
+        )
+        .service(
+            web::resource("")
+                .guard(guard::Post())
+                .wrap(require_permission("users:write"))
+                .route(web::post().to(create_user)),
+        )
+        // The original path for creating users, kept for existing clients.
+        .service(
+            web::resource("/create")
+                .wrap(require_permission("users:write"))
+                .route(web::post().to(create_user)),
+        )
+        .service(
+            web::resource("/erasures/{id}")
+                .wrap(require_permission("users:erase"))
+                .route(web::get().to(get_erasure)),
+        )
+        .service(
+            web::resource("/{id}")
+                .guard(guard::Get())
+                .wrap(require_permission("users:read"))
+                .route(web::get().to(get_user)),
+        )
+        .service(
+            web::resource("/{id}")
+                .guard(guard::Put())
+                .wrap(require_permission("users:write"))
+                .route(web::put().to(update_user)),
+        )
+        .service(
+            web::resource("/{id}")
+                .guard(guard::Delete())
+                .wrap(require_permission("users:delete"))
+                .route(web::delete().to(delete_user)),
+        )
+        .service(
+            web::resource("/{id}/profile")
+                .guard(guard::Get())
+                .wrap(require_permission("users:read"))
+                .route(web::get().to(get_profile)),
+        )
+        .service(
+            web::resource("/{id}/profile")
+                .guard(guard::Patch())
+                .wrap(require_permission_or_owner("users:write", "id"))
+                .route(web::patch().to(update_profile)),
+        )
+        .service(
+            web::resource("/{id}/avatar")
+                .guard(guard::Get())
+                .wrap(require_permission("users:read"))
+                .route(web::get().to(get_avatar)),
+        )
+        .service(
+            web::resource("/{id}/avatar")
+                .guard(guard::Put())
+                .wrap(require_permission_or_owner("users:write", "id"))
+                .route(web::put().to(upload_avatar)),
+        )
+        .service(
+            web::resource("/{id}/avatar")
+                .guard(guard::Delete())
+                .wrap(require_permission_or_owner("users:write", "id"))
+                .route(web::delete().to(delete_avatar)),
+        )
+        .service(
+            web::resource("/{id}/export")
+                .wrap(require_permission("users:export"))
+                .route(web::get().to(export_user)),
+        )
+        .service(
+            web::resource("/{id}/erasure")
+                .wrap(require_permission("users:erase"))
+                .route(web::post().to(request_erasure)),
+        )
 }
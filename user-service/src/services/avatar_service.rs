@@ -0,0 +1,121 @@
+use diesel::Connection;
+use uuid::Uuid;
+use crate::config::database::DbPool;
+use crate::errors::error::UserError;
+use crate::models::user::{ProfileResponse, User};
+use crate::repositories::user_repository::UserRepository;
+use crate::storage::{Blob, BlobStore};
+use crate::utils::avatar_image::{avatar_key, process_avatar, rendition_size, AvatarFormat, AVATAR_SIZES};
+
+/// A rendition of a user's current avatar.
+pub struct AvatarImage {
+    pub avatar_id: Uuid,
+    pub size: u32,
+    pub blob: Blob,
+}
+
+/// Avatars: the renditions live in the blob store, and the user's row says
+/// which ones are current. Every upload gets new keys, so a rendition is
+/// never overwritten while it may be served.
+pub struct AvatarService;
+
+impl AvatarService {
+    /// Processes the upload, stores its renditions and makes them the
+    /// user's avatar. The previous avatar's renditions are deleted after.
+    pub async fn upload_avatar(pool: &DbPool, store: &dyn BlobStore, user_id: Uuid, upload: Vec<u8>) -> Result<ProfileResponse, UserError> {
+        UserRepository::find_user(pool, user_id).await.map_err(Self::not_found)?;
+
+        let processed = tokio::task::spawn_blocking(move || process_avatar(&upload)).await
+            .map_err(|e| {
+                log::error!("Avatar processing failed: {}", e);
+                UserError::InternalServerError
+            })??;
+
+        let avatar_id = Uuid::new_v4();
+        for rendition in processed.renditions {
+            let key = avatar_key(user_id, avatar_id, rendition.size, processed.format);
+            if let Err(e) = store.put(&key, rendition.bytes, processed.format.content_type()).await {
+                Self::delete_renditions(store, user_id, avatar_id, processed.format).await;
+                return Err(e.into());
+            }
+        }
+
+        let conn = &mut pool.get().expect("Failed to get DB connection from pool");
+        let swapped = conn.transaction(|conn| {
+            let previous = UserRepository::lock_user(conn, user_id)?;
+            let user = UserRepository::set_avatar(conn, user_id, Some(avatar_id), Some(processed.format.extension()))?;
+            Ok::<_, diesel::result::Error>((previous, user))
+        });
+        let (previous, user) = match swapped {
+            Ok(swapped) => swapped,
+            Err(e) => {
+                Self::delete_renditions(store, user_id, avatar_id, processed.format).await;
+                return Err(Self::not_found(e));
+            }
+        };
+
+        Self::delete_avatar_of(store, &previous).await;
+        Ok(ProfileResponse::from(user))
+    }
+
+    /// The rendition of the user's avatar that best fits `size`, see
+    /// `rendition_size`.
+    pub async fn get_avatar(pool: &DbPool, store: &dyn BlobStore, user_id: Uuid, size: u32) -> Result<AvatarImage, UserError> {
+        let user = UserRepository::find_user(pool, user_id).await.map_err(Self::not_found)?;
+        let (avatar_id, format) = Self::current_avatar(&user).ok_or(UserError::AvatarNotFound)?;
+
+        let size = rendition_size(size);
+        let blob = store.get(&avatar_key(user_id, avatar_id, size, format)).await?
+            .ok_or_else(|| {
+                log::warn!("Avatar {} of user {} is missing its {}px rendition", avatar_id, user_id, size);
+                UserError::AvatarNotFound
+            })?;
+        Ok(AvatarImage { avatar_id, size, blob })
+    }
+
+    pub async fn delete_avatar(pool: &DbPool, store: &dyn BlobStore, user_id: Uuid) -> Result<(), UserError> {
+        let conn = &mut pool.get().expect("Failed to get DB connection from pool");
+        let previous = conn.transaction(|conn| {
+            let previous = UserRepository::lock_user(conn, user_id)?;
+            if previous.avatar_id.is_some() {
+                UserRepository::set_avatar(conn, user_id, None, None)?;
+            }
+            Ok::<_, diesel::result::Error>(previous)
+        }).map_err(Self::not_found)?;
+
+        if previous.avatar_id.is_none() {
+            return Err(UserError::AvatarNotFound);
+        }
+        Self::delete_avatar_of(store, &previous).await;
+        Ok(())
+    }
+
+    /// Deletes the renditions of the avatar `user` has, if any. Called once
+    /// the row no longer points at them, so failures are only logged: they
+    /// leave orphaned blobs behind, not a broken avatar.
+    pub async fn delete_avatar_of(store: &dyn BlobStore, user: &User) {
+        if let Some((avatar_id, format)) = Self::current_avatar(user) {
+            Self::delete_renditions(store, user.id, avatar_id, format).await;
+        }
+    }
+
+    async fn delete_renditions(store: &dyn BlobStore, user_id: Uuid, avatar_id: Uuid, format: AvatarFormat) {
+        for size in AVATAR_SIZES {
+            if let Err(e) = store.delete(&avatar_key(user_id, avatar_id, size, format)).await {
+                log::warn!("Failed to delete the {}px rendition of avatar {}: {}", size, avatar_id, e);
+            }
+        }
+    }
+
+    fn current_avatar(user: &User) -> Option<(Uuid, AvatarFormat)> {
+        let format = user.avatar_format.as_deref().and_then(AvatarFormat::from_extension)?;
+        user.avatar_id.map(|avatar_id| (avatar_id, format))
+    }
+
+    fn not_found(e: diesel::result::Error) -> UserError {
+        match e {
+            diesel::result::Error::NotFound => UserError::UserNotFound,
+            e => UserError::DatabaseError(e),
+        }
+    }
+}
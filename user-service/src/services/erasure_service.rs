@@ -0,0 +1,120 @@
+use chrono::Utc;
+use diesel::Connection;
+use shared::erasure::{ErasureAcknowledged, ErasureRequested, ERASURE_REQUESTED_TOPIC};
+use shared::outbox::{NewOutboxMessage, OutboxRepository};
+use uuid::Uuid;
+use crate::config::database::DbPool;
+use crate::config::erasure::ErasurePolicy;
+use crate::errors::error::UserError;
+use crate::models::erasure::{ErasureRequestResponse, NewErasureAcknowledgement, NewErasureRequest};
+use crate::repositories::erasure_repository::ErasureRepository;
+use crate::repositories::user_repository::UserRepository;
+use crate::services::avatar_service::AvatarService;
+use crate::storage::BlobStore;
+
+/// How many due requests a sweep completes at most.
+const COMPLETION_BATCH_SIZE: i64 = 100;
+
+/// Right-to-erasure requests, coordinated with the other services through
+/// the events in `shared::erasure`:
+///
+/// 1. `request_erasure` anonymizes the account and hides it, deletes its
+///    avatar, and publishes `ErasureRequested` through the outbox.
+/// 2. Each service in `ERASURE_SERVICES` erases its data and answers with
+///    `ErasureAcknowledged`, recorded by `handle_acknowledgement`.
+/// 3. `complete_due` deletes the account for good once the grace period is
+///    over and every service has answered.
+pub struct ErasureService;
+
+impl ErasureService {
+    pub async fn request_erasure(pool: &DbPool, store: &dyn BlobStore, user_id: Uuid) -> Result<ErasureRequestResponse, UserError> {
+        let policy = ErasurePolicy::from_env();
+        let conn = &mut pool.get().expect("Failed to get DB connection from pool");
+        let (response, user) = conn.transaction(|conn| {
+            let user = UserRepository::lock_user(conn, user_id).map_err(|e| match e {
+                diesel::result::Error::NotFound => UserError::UserNotFound,
+                e => UserError::DatabaseError(e),
+            })?;
+
+            let now = Utc::now().naive_utc();
+            UserRepository::anonymize(conn, user.id, now)?;
+
+            let request_id = Uuid::new_v4();
+            let new_request = NewErasureRequest {
+                id: request_id,
+                user_id: user.id,
+                requested_at: now,
+                purge_after: now + policy.grace_period,
+            };
+            let acknowledgements: Vec<NewErasureAcknowledgement> = policy.services.iter()
+                .map(|service| NewErasureAcknowledgement { request_id, service: service.clone() })
+                .collect();
+            let (request, acknowledgements) = ErasureRepository::create(conn, &new_request, &acknowledgements)?;
+
+            let event = ErasureRequested { request_id, user_id: user.id, email: user.email.clone(), requested_at: request.requested_at };
+            let message = NewOutboxMessage {
+                id: Uuid::new_v4(),
+                topic: ERASURE_REQUESTED_TOPIC.to_string(),
+                event_key: user.id.to_string(),
+                payload: serde_json::to_value(&event)
+                    .map_err(|e| diesel::result::Error::SerializationError(Box::new(e)))?,
+            };
+            OutboxRepository::create(conn, &[message])?;
+
+            Ok::<_, UserError>((ErasureRequestResponse::new(request, acknowledgements), user))
+        })?;
+
+        AvatarService::delete_avatar_of(store, &user).await;
+        Ok(response)
+    }
+
+    pub async fn get_erasure(pool: &DbPool, request_id: Uuid) -> Result<ErasureRequestResponse, UserError> {
+        let (request, acknowledgements) = ErasureRepository::find(pool, request_id).await.map_err(|e| match e {
+            diesel::result::Error::NotFound => UserError::ErasureRequestNotFound,
+            e => UserError::DatabaseError(e),
+        })?;
+        Ok(ErasureRequestResponse::new(request, acknowledgements))
+    }
+
+    /// Handles a message from `ERASURE_ACKNOWLEDGED_TOPIC`. Answers from
+    /// services that are not expected to answer, and repeated answers, are
+    /// ignored; so is a payload that is not an acknowledgement.
+    pub async fn handle_acknowledgement(pool: &DbPool, payload: &str) -> Result<(), UserError> {
+        let acknowledged = match serde_json::from_str::<ErasureAcknowledged>(payload) {
+            Ok(acknowledged) => acknowledged,
+            Err(e) => {
+                log::warn!("Skipping malformed erasure acknowledgement: {}", e);
+                return Ok(());
+            }
+        };
+
+        let recorded = ErasureRepository::acknowledge(pool, acknowledged.request_id, &acknowledged.service, acknowledged.erased_at).await?;
+        if recorded == 0 {
+            log::info!(
+                "Ignoring acknowledgement of erasure {} from {}: not expected, or already recorded",
+                acknowledged.request_id, acknowledged.service,
+            );
+        }
+        Ok(())
+    }
+
+    /// Deletes the accounts whose erasure is due, and marks their requests
+    /// completed. Returns how many were completed.
+    pub async fn complete_due(pool: &DbPool) -> Result<usize, UserError> {
+        let now = Utc::now().naive_utc();
+        let due = ErasureRepository::due(pool, now, COMPLETION_BATCH_SIZE).await?;
+
+        let conn = &mut pool.get().expect("Failed to get DB connection from pool");
+        let mut completed = 0;
+        for request in due {
+            completed += conn.transaction(|conn| {
+                if ErasureRepository::complete(conn, request.id, now)? == 0 {
+                    return Ok::<_, diesel::result::Error>(0);
+                }
+                UserRepository::purge(conn, request.user_id)?;
+                Ok(1)
+            })?;
+        }
+        Ok(completed)
+    }
+}
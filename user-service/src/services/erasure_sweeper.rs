@@ -0,0 +1,33 @@
+use std::env;
+use std::time::Duration;
+use crate::config::database::DbPool;
+use crate::services::erasure_service::ErasureService;
+
+const DEFAULT_SWEEP_SECONDS: u64 = 60 * 60;
+
+/// Completes due erasure requests every `ERASURE_SWEEP_SECONDS`. See
+/// `ErasureService::complete_due`.
+pub struct ErasureSweeper {
+    pool: DbPool,
+}
+
+impl ErasureSweeper {
+    pub fn new(pool: DbPool) -> ErasureSweeper {
+        ErasureSweeper { pool }
+    }
+
+    pub async fn run(self) {
+        let interval = Duration::from_secs(
+            env::var("ERASURE_SWEEP_SECONDS").ok().and_then(|value| value.parse().ok()).unwrap_or(DEFAULT_SWEEP_SECONDS),
+        );
+
+        loop {
+            match ErasureService::complete_due(&self.pool).await {
+                Ok(0) => {}
+                Ok(completed) => log::info!("Completed {} erasure requests", completed),
+                Err(e) => log::error!("Failed to complete erasure requests: {}", e),
+            }
+            tokio::time::sleep(interval).await;
+        }
+    }
+}
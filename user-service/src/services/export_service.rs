@@ -0,0 +1,63 @@
+use chrono::Utc;
+use serde_json::json;
+use uuid::Uuid;
+use crate::config::database::DbPool;
+use crate::errors::error::UserError;
+use crate::models::user::UserResponse;
+use crate::repositories::user_repository::UserRepository;
+use crate::services::avatar_service::AvatarService;
+use crate::storage::BlobStore;
+use crate::utils::archive::Archive;
+use crate::utils::avatar_image::AVATAR_SIZES;
+
+const ACCOUNT_FILE: &str = "account.json";
+/// Named with the avatar's extension.
+const AVATAR_FILE: &str = "avatar";
+const MANIFEST_FILE: &str = "manifest.json";
+
+/// Builds subject-access exports: everything user-service holds about a user.
+pub struct ExportService;
+
+impl ExportService {
+    /// A zip archive with the account, the avatar at its largest, and a
+    /// manifest of what the archive holds. The password hash is held but not
+    /// exported; the manifest says so.
+    pub async fn export_user(pool: &DbPool, store: &dyn BlobStore, user_id: Uuid) -> Result<Vec<u8>, UserError> {
+        let user = UserRepository::find_user(pool, user_id).await.map_err(|e| match e {
+            diesel::result::Error::NotFound => UserError::UserNotFound,
+            e => UserError::DatabaseError(e),
+        })?;
+
+        let avatar = match AvatarService::get_avatar(pool, store, user_id, AVATAR_SIZES[0]).await {
+            Ok(avatar) => Some((format!("{}.{}", AVATAR_FILE, user.avatar_format.as_deref().unwrap_or("jpg")), avatar.blob.bytes)),
+            Err(UserError::AvatarNotFound) => None,
+            Err(e) => return Err(e),
+        };
+        let mut files = vec![ACCOUNT_FILE.to_string()];
+        files.extend(avatar.iter().map(|(name, _)| name.clone()));
+
+        let manifest = json!({
+            "service": "user-service",
+            "user_id": user.id,
+            "generated_at": Utc::now().naive_utc(),
+            "files": files,
+            "withheld": {
+                "password": "Kept only as a one-way hash, which is not included",
+            },
+        });
+
+        let build = || {
+            let mut archive = Archive::new();
+            archive.add_json(MANIFEST_FILE, &manifest)?;
+            archive.add_json(ACCOUNT_FILE, &UserResponse::from(user))?;
+            if let Some((name, bytes)) = &avatar {
+                archive.add_file(name, bytes)?;
+            }
+            archive.finish()
+        };
+        build().map_err(|e| {
+            log::error!("Failed to build export for user {}: {}", user_id, e);
+            UserError::InternalServerError
+        })
+    }
+}
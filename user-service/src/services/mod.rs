@@ -1 +1,5 @@
+pub mod avatar_service;
+pub mod erasure_service;
+pub mod erasure_sweeper;
+pub mod export_service;
 pub mod user_service;
@@ -1,20 +1,142 @@
-use crate::models::user::{User, NewUser};
+use diesel::result::DatabaseErrorKind;
+use shared::pagination::{Page, PageRequest};
+use shared::password::{PasswordContext, PasswordPolicy};
+use uuid::Uuid;
+use validator::Validate;
+use crate::errors::error::UserError;
+use crate::models::audit::{AuditEvent, LOGIN_SUCCEEDED};
+use crate::models::user::{NewUser, ProfileResponse, UpdateProfile, UpdateUser, UserFilter, UserKey, UserResponse, UserSort};
 use crate::config::database::DbPool;
 use crate::repositories::user_repository::UserRepository;
+use crate::services::avatar_service::AvatarService;
+use crate::storage::BlobStore;
 use crate::utils::hash_password::hash_password;
 
 pub struct UserService;
 
 impl UserService {
-    pub async fn create_user(pool: &DbPool, new_user: NewUser) -> Result<User, String> {
-        let encrypted_password = hash_password(&new_user.password)
-            .expect("Error to encrypt password");
+    /// Field rules and the shared password policy are checked together, so
+    /// every reason comes back in one response.
+    pub async fn create_user(pool: &DbPool, new_user: NewUser) -> Result<UserResponse, UserError> {
+        let mut errors = new_user.validate().err().unwrap_or_default();
+        let context = PasswordContext::new().with_username(&new_user.username).with_email(&new_user.email);
+        PasswordPolicy::from_env().validate_field(&mut errors, "password", &new_user.password, &context);
+        if !errors.is_empty() {
+            return Err(UserError::ValidationError(errors));
+        }
 
+        let encrypted_password = hash_password(&new_user.password).map_err(|_e| UserError::InternalServerError)?;
         let new_user = NewUser {
             password: encrypted_password,
             ..new_user
         };
 
-        UserRepository::create_user(pool, new_user).await.map_err(|e| e.to_string())
+        UserRepository::create_user(pool, new_user).await
+            .map(UserResponse::from)
+            .map_err(Self::conflict)
+    }
+
+    pub async fn list_users(pool: &DbPool, filter: &UserFilter, page: &PageRequest<UserSort>) -> Result<Page<UserResponse>, UserError> {
+        filter.validate()?;
+        let after = page.after.as_ref()
+            .map(|cursor| UserKey::from_cursor(page.sort.field, cursor))
+            .transpose()?;
+
+        let users = UserRepository::list_users(pool, filter, page.sort, after, page.fetch_limit()).await?;
+        Ok(page.page(users, |user| UserKey::of(user, page.sort.field).to_cursor(page.sort)).map(UserResponse::from))
+    }
+
+    pub async fn get_user(pool: &DbPool, user_id: Uuid) -> Result<UserResponse, UserError> {
+        UserRepository::find_user(pool, user_id).await
+            .map(UserResponse::from)
+            .map_err(Self::not_found)
+    }
+
+    /// Changes only the fields present. A new password is checked against the
+    /// policy with the username and email the account will have afterwards.
+    pub async fn update_user(pool: &DbPool, user_id: Uuid, changes: UpdateUser) -> Result<UserResponse, UserError> {
+        let user = UserRepository::find_user(pool, user_id).await.map_err(Self::not_found)?;
+        if changes.is_empty() {
+            return Ok(UserResponse::from(user));
+        }
+
+        let mut errors = changes.validate().err().unwrap_or_default();
+        if let Some(password) = &changes.password {
+            let username = changes.username.as_deref().unwrap_or(&user.username);
+            let email = changes.email.as_deref().unwrap_or(&user.email);
+            let context = PasswordContext::new().with_username(username).with_email(email);
+            PasswordPolicy::from_env().validate_field(&mut errors, "password", password, &context);
+        }
+        if !errors.is_empty() {
+            return Err(UserError::ValidationError(errors));
+        }
+
+        let password = match &changes.password {
+            Some(password) => Some(hash_password(password).map_err(|_e| UserError::InternalServerError)?),
+            None => None,
+        };
+        let changes = UpdateUser { password, ..changes };
+
+        UserRepository::update_user(pool, user_id, changes).await
+            .map(UserResponse::from)
+            .map_err(Self::conflict)
+    }
+
+    pub async fn get_profile(pool: &DbPool, user_id: Uuid) -> Result<ProfileResponse, UserError> {
+        UserRepository::find_user(pool, user_id).await
+            .map(ProfileResponse::from)
+            .map_err(Self::not_found)
+    }
+
+    /// Changes only the profile fields present; blank ones are cleared.
+    pub async fn update_profile(pool: &DbPool, user_id: Uuid, changes: UpdateProfile) -> Result<ProfileResponse, UserError> {
+        let changes = changes.normalized();
+        changes.validate()?;
+        if changes.is_empty() {
+            return Self::get_profile(pool, user_id).await;
+        }
+
+        UserRepository::update_profile(pool, user_id, changes).await
+            .map(ProfileResponse::from)
+            .map_err(Self::not_found)
+    }
+
+    /// Handles a message from auth-service's audit topic, recording
+    /// successful sign-ins as `last_login_at`. Other events, and payloads
+    /// that are not events, are skipped.
+    pub async fn handle_audit_event(pool: &DbPool, payload: &str) -> Result<(), UserError> {
+        let event = match serde_json::from_str::<AuditEvent>(payload) {
+            Ok(event) => event,
+            Err(e) => {
+                log::warn!("Skipping malformed audit event: {}", e);
+                return Ok(());
+            }
+        };
+
+        if let (LOGIN_SUCCEEDED, Some(email)) = (event.event_type.as_str(), &event.metadata.email) {
+            UserRepository::record_login(pool, email, event.occurred_at).await?;
+        }
+        Ok(())
+    }
+
+    /// Deletes the avatar's renditions along with the account.
+    pub async fn delete_user(pool: &DbPool, store: &dyn BlobStore, user_id: Uuid) -> Result<(), UserError> {
+        let user = UserRepository::delete_user(pool, user_id).await?.ok_or(UserError::UserNotFound)?;
+        AvatarService::delete_avatar_of(store, &user).await;
+        Ok(())
+    }
+
+    fn not_found(e: diesel::result::Error) -> UserError {
+        match e {
+            diesel::result::Error::NotFound => UserError::UserNotFound,
+            e => UserError::DatabaseError(e),
+        }
+    }
+
+    fn conflict(e: diesel::result::Error) -> UserError {
+        match e {
+            diesel::result::Error::DatabaseError(DatabaseErrorKind::UniqueViolation, _) => UserError::EmailAlreadyExists,
+            e => Self::not_found(e),
+        }
     }
 }
@@ -0,0 +1,41 @@
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+use async_trait::async_trait;
+
+use crate::storage::{check_key, Blob, BlobStore, BlobStoreError};
+
+/// Keeps blobs in memory, so tests can assert on them without a disk or a bucket.
+#[derive(Default)]
+pub struct InMemoryBlobStore {
+    blobs: Mutex<HashMap<String, Blob>>,
+}
+
+impl InMemoryBlobStore {
+    pub fn keys(&self) -> Vec<String> {
+        let mut keys: Vec<String> = self.blobs.lock().unwrap().keys().cloned().collect();
+        keys.sort();
+        keys
+    }
+}
+
+#[async_trait]
+impl BlobStore for InMemoryBlobStore {
+    async fn put(&self, key: &str, bytes: Vec<u8>, content_type: &str) -> Result<(), BlobStoreError> {
+        check_key(key)?;
+        let blob = Blob { bytes, content_type: content_type.to_string() };
+        self.blobs.lock().unwrap().insert(key.to_string(), blob);
+        Ok(())
+    }
+
+    async fn get(&self, key: &str) -> Result<Option<Blob>, BlobStoreError> {
+        check_key(key)?;
+        Ok(self.blobs.lock().unwrap().get(key).cloned())
+    }
+
+    async fn delete(&self, key: &str) -> Result<(), BlobStoreError> {
+        check_key(key)?;
+        self.blobs.lock().unwrap().remove(key);
+        Ok(())
+    }
+}
@@ -0,0 +1,69 @@
+use std::env;
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use thiserror::Error;
+
+pub mod in_memory;
+pub mod local;
+pub mod s3;
+
+pub use in_memory::InMemoryBlobStore;
+pub use local::LocalBlobStore;
+pub use s3::{S3BlobStore, S3Config};
+
+#[derive(Debug, Error)]
+pub enum BlobStoreError {
+    #[error("Blob store I/O failed: {0}")]
+    Io(#[from] std::io::Error),
+
+    #[error("Blob store request failed: {0}")]
+    Request(String),
+
+    #[error("Invalid blob key: {0}")]
+    InvalidKey(String),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Blob {
+    pub bytes: Vec<u8>,
+    pub content_type: String,
+}
+
+/// Where uploaded files are kept, under `/`-separated keys such as
+/// `avatars/<user id>/<avatar id>/256.jpg`.
+#[async_trait]
+pub trait BlobStore: Send + Sync {
+    /// Stores `bytes` under `key`, replacing any blob already there.
+    async fn put(&self, key: &str, bytes: Vec<u8>, content_type: &str) -> Result<(), BlobStoreError>;
+
+    /// `None` when there is no blob under `key`.
+    async fn get(&self, key: &str) -> Result<Option<Blob>, BlobStoreError>;
+
+    /// Deleting a blob that is not there is not an error.
+    async fn delete(&self, key: &str) -> Result<(), BlobStoreError>;
+}
+
+/// Picks the store from `BLOB_STORE`: `local` (files under `BLOB_STORE_DIR`),
+/// `s3` (any S3-compatible service, configured with `S3_*`) or `memory`.
+pub fn blob_store_from_env() -> Arc<dyn BlobStore> {
+    dotenv::dotenv().ok();
+    match env::var("BLOB_STORE").unwrap_or_else(|_| "local".to_string()).as_str() {
+        "s3" => Arc::new(S3BlobStore::new(S3Config::from_env())),
+        "memory" => Arc::new(InMemoryBlobStore::default()),
+        _ => Arc::new(LocalBlobStore::new(env::var("BLOB_STORE_DIR").unwrap_or_else(|_| "uploads".to_string()))),
+    }
+}
+
+/// Refuses keys that could reach outside the store: empty ones, absolute
+/// ones, and ones with empty, `.` or `..` segments.
+pub(crate) fn check_key(key: &str) -> Result<(), BlobStoreError> {
+    let valid = !key.is_empty()
+        && key.split('/').all(|segment| !segment.is_empty() && segment != "." && segment != "..")
+        && !key.contains('\\');
+    if valid {
+        Ok(())
+    } else {
+        Err(BlobStoreError::InvalidKey(key.to_string()))
+    }
+}
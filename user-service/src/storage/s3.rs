@@ -0,0 +1,185 @@
+use std::env;
+use std::time::Duration;
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use hmac::{Hmac, Mac};
+use reqwest::header::CONTENT_TYPE;
+use reqwest::{Client, Method, Response, StatusCode, Url};
+use sha2::{Digest, Sha256};
+
+use crate::storage::{check_key, Blob, BlobStore, BlobStoreError};
+
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(30);
+const DEFAULT_REGION: &str = "us-east-1";
+
+pub struct S3Config {
+    /// e.g. `https://s3.eu-west-1.amazonaws.com`, or `http://localhost:9000`
+    /// for a local MinIO.
+    pub endpoint: String,
+    pub region: String,
+    pub bucket: String,
+    pub access_key_id: String,
+    pub secret_access_key: String,
+}
+
+impl S3Config {
+    /// From `S3_ENDPOINT`, `S3_REGION` (`us-east-1` when unset), `S3_BUCKET`,
+    /// `S3_ACCESS_KEY_ID` and `S3_SECRET_ACCESS_KEY`.
+    pub fn from_env() -> S3Config {
+        dotenv::dotenv().ok();
+        S3Config {
+            endpoint: env::var("S3_ENDPOINT").expect("S3_ENDPOINT must be set"),
+            region: env::var("S3_REGION").unwrap_or_else(|_| DEFAULT_REGION.to_string()),
+            bucket: env::var("S3_BUCKET").expect("S3_BUCKET must be set"),
+            access_key_id: env::var("S3_ACCESS_KEY_ID").expect("S3_ACCESS_KEY_ID must be set"),
+            secret_access_key: env::var("S3_SECRET_ACCESS_KEY").expect("S3_SECRET_ACCESS_KEY must be set"),
+        }
+    }
+}
+
+/// Keeps blobs as objects in a bucket of an S3-compatible service. Objects
+/// are addressed path-style, `<endpoint>/<bucket>/<key>`, which AWS, MinIO
+/// and the other compatible services all accept, and requests are signed
+/// with AWS Signature Version 4.
+pub struct S3BlobStore {
+    client: Client,
+    config: S3Config,
+}
+
+impl S3BlobStore {
+    pub fn new(config: S3Config) -> S3BlobStore {
+        let client = Client::builder()
+            .timeout(REQUEST_TIMEOUT)
+            .build()
+            .expect("Failed to build HTTP client");
+        S3BlobStore { client, config }
+    }
+
+    fn url(&self, key: &str) -> Result<Url, BlobStoreError> {
+        check_key(key)?;
+        let key: Vec<String> = key.split('/').map(uri_encode).collect();
+        let url = format!("{}/{}/{}", self.config.endpoint.trim_end_matches('/'), uri_encode(&self.config.bucket), key.join("/"));
+        Url::parse(&url).map_err(|e| BlobStoreError::Request(format!("invalid object URL {}: {}", url, e)))
+    }
+
+    async fn send(&self, method: Method, key: &str, body: Vec<u8>, content_type: Option<&str>) -> Result<Response, BlobStoreError> {
+        let url = self.url(key)?;
+        let host = match url.port() {
+            Some(port) => format!("{}:{}", url.host_str().unwrap_or_default(), port),
+            None => url.host_str().unwrap_or_default().to_string(),
+        };
+        let now = Utc::now();
+        let payload_hash = hex(&Sha256::digest(&body));
+
+        let mut headers = vec![
+            ("host".to_string(), host),
+            ("x-amz-content-sha256".to_string(), payload_hash.clone()),
+            ("x-amz-date".to_string(), now.format("%Y%m%dT%H%M%SZ").to_string()),
+        ];
+        if let Some(content_type) = content_type {
+            headers.push(("content-type".to_string(), content_type.to_string()));
+        }
+        let authorization = authorization(&self.config, method.as_str(), url.path(), &headers, &payload_hash, now);
+
+        let mut request = self.client.request(method, url).header("authorization", authorization);
+        // reqwest sets `Host` from the URL, to the value signed above.
+        for (name, value) in headers.into_iter().filter(|(name, _)| name != "host") {
+            request = request.header(name, value);
+        }
+        request.body(body).send().await.map_err(|e| BlobStoreError::Request(e.to_string()))
+    }
+}
+
+#[async_trait]
+impl BlobStore for S3BlobStore {
+    async fn put(&self, key: &str, bytes: Vec<u8>, content_type: &str) -> Result<(), BlobStoreError> {
+        let response = self.send(Method::PUT, key, bytes, Some(content_type)).await?;
+        check_status(response, &[StatusCode::OK]).await.map(|_| ())
+    }
+
+    async fn get(&self, key: &str) -> Result<Option<Blob>, BlobStoreError> {
+        let response = self.send(Method::GET, key, Vec::new(), None).await?;
+        if response.status() == StatusCode::NOT_FOUND {
+            return Ok(None);
+        }
+
+        let response = check_status(response, &[StatusCode::OK]).await?;
+        let content_type = response.headers().get(CONTENT_TYPE)
+            .and_then(|value| value.to_str().ok())
+            .unwrap_or("application/octet-stream")
+            .to_string();
+        let bytes = response.bytes().await.map_err(|e| BlobStoreError::Request(e.to_string()))?;
+        Ok(Some(Blob { bytes: bytes.to_vec(), content_type }))
+    }
+
+    async fn delete(&self, key: &str) -> Result<(), BlobStoreError> {
+        let response = self.send(Method::DELETE, key, Vec::new(), None).await?;
+        check_status(response, &[StatusCode::NO_CONTENT, StatusCode::OK, StatusCode::NOT_FOUND]).await.map(|_| ())
+    }
+}
+
+/// The error body S3 sends is XML with a code and message; it goes into the
+/// error as is.
+async fn check_status(response: Response, expected: &[StatusCode]) -> Result<Response, BlobStoreError> {
+    if expected.contains(&response.status()) {
+        return Ok(response);
+    }
+    let status = response.status();
+    let body = response.text().await.unwrap_or_default();
+    Err(BlobStoreError::Request(format!("{}: {}", status, body)))
+}
+
+/// The `Authorization` header of AWS Signature Version 4 for a request with
+/// no query string. `headers` are the signed ones, lowercase, and must
+/// include `host`, `x-amz-date` and `x-amz-content-sha256`.
+pub(crate) fn authorization(
+    config: &S3Config,
+    method: &str,
+    path: &str,
+    headers: &[(String, String)],
+    payload_hash: &str,
+    now: DateTime<Utc>,
+) -> String {
+    let mut headers = headers.to_vec();
+    headers.sort();
+    let canonical_headers: String = headers.iter().map(|(name, value)| format!("{}:{}\n", name, value.trim())).collect();
+    let signed_headers = headers.iter().map(|(name, _)| name.as_str()).collect::<Vec<_>>().join(";");
+    let canonical_request = format!("{}\n{}\n\n{}\n{}\n{}", method, path, canonical_headers, signed_headers, payload_hash);
+
+    let date = now.format("%Y%m%d").to_string();
+    let scope = format!("{}/{}/s3/aws4_request", date, config.region);
+    let string_to_sign = format!(
+        "AWS4-HMAC-SHA256\n{}\n{}\n{}",
+        now.format("%Y%m%dT%H%M%SZ"), scope, hex(&Sha256::digest(canonical_request.as_bytes())),
+    );
+
+    let signing_key = [date.as_str(), config.region.as_str(), "s3", "aws4_request"].iter()
+        .fold(format!("AWS4{}", config.secret_access_key).into_bytes(), |key, part| hmac_sha256(&key, part.as_bytes()));
+    let signature = hex(&hmac_sha256(&signing_key, string_to_sign.as_bytes()));
+
+    format!(
+        "AWS4-HMAC-SHA256 Credential={}/{}, SignedHeaders={}, Signature={}",
+        config.access_key_id, scope, signed_headers, signature,
+    )
+}
+
+fn hmac_sha256(key: &[u8], message: &[u8]) -> Vec<u8> {
+    let mut mac = Hmac::<Sha256>::new_from_slice(key).expect("HMAC accepts keys of any length");
+    mac.update(message);
+    mac.finalize().into_bytes().to_vec()
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+/// Percent-encodes everything but the unreserved characters, as SigV4 expects.
+fn uri_encode(segment: &str) -> String {
+    segment.bytes()
+        .map(|byte| match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => (byte as char).to_string(),
+            _ => format!("%{:02X}", byte),
+        })
+        .collect()
+}
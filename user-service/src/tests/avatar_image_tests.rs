@@ -0,0 +1,136 @@
+use std::io::Cursor;
+use image::codecs::jpeg::JpegEncoder;
+use image::{DynamicImage, ImageEncoder, ImageFormat, Rgb, RgbImage, Rgba, RgbaImage};
+
+use crate::errors::error::UserError;
+use crate::tests::support::png;
+use crate::utils::avatar_image::{process_avatar, rendition_size, AvatarFormat, AVATAR_SIZES};
+
+const RED: Rgb<u8> = Rgb([220, 20, 20]);
+const BLUE: Rgb<u8> = Rgb([20, 20, 220]);
+
+/// A TIFF-encoded EXIF block whose only entry is an orientation of 6: the
+/// image is to be rotated 90° clockwise.
+const EXIF_ROTATE_90: [u8; 26] = [
+  0x4d, 0x4d, 0x00, 0x2a, 0x00, 0x00, 0x00, 0x08,
+  0x00, 0x01,
+  0x01, 0x12, 0x00, 0x03, 0x00, 0x00, 0x00, 0x01, 0x00, 0x06, 0x00, 0x00,
+  0x00, 0x00, 0x00, 0x00,
+];
+
+/// A JPEG twice as wide as it is tall: red on the top half, blue below.
+fn striped_jpeg(exif: &[u8]) -> Vec<u8> {
+  let image = RgbImage::from_fn(80, 40, |_, y| if y < 20 { RED } else { BLUE });
+  let mut bytes = Vec::new();
+  let mut encoder = JpegEncoder::new_with_quality(&mut bytes, 95);
+  encoder.set_exif_metadata(exif.to_vec()).unwrap();
+  encoder.write_image(image.as_raw(), 80, 40, image::ExtendedColorType::Rgb8).unwrap();
+  bytes
+}
+
+fn transparent_png() -> Vec<u8> {
+  let mut bytes = Vec::new();
+  RgbaImage::from_pixel(32, 32, Rgba([0, 0, 0, 0]))
+    .write_to(&mut Cursor::new(&mut bytes), ImageFormat::Png)
+    .unwrap();
+  bytes
+}
+
+fn encoded(image: DynamicImage, format: ImageFormat) -> Vec<u8> {
+  let mut bytes = Vec::new();
+  image.write_to(&mut Cursor::new(&mut bytes), format).unwrap();
+  bytes
+}
+
+fn is_red(pixel: Rgb<u8>) -> bool {
+  pixel[0] > 150 && pixel[2] < 100
+}
+
+fn is_blue(pixel: Rgb<u8>) -> bool {
+  pixel[2] > 150 && pixel[0] < 100
+}
+
+#[test]
+fn test_rendition_size_picks_the_smallest_that_fits() {
+  // Given / When / Then: Each size is served as itself
+  for size in AVATAR_SIZES {
+    assert_eq!(rendition_size(size), size);
+  }
+
+  // And: Other sizes get the next rendition up, or the largest there is
+  assert_eq!(rendition_size(0), 64);
+  assert_eq!(rendition_size(65), 128);
+  assert_eq!(rendition_size(300), 512);
+  assert_eq!(rendition_size(4096), 512);
+}
+
+#[test]
+fn test_process_avatar_renders_every_size() {
+  // Given: An opaque, non-square PNG
+  let upload = png(300, 200);
+
+  // When: It is processed
+  let processed = process_avatar(&upload).unwrap();
+
+  // Then: There is a square JPEG rendition per size, largest first
+  assert_eq!(processed.format, AvatarFormat::Jpeg);
+  let sizes: Vec<u32> = processed.renditions.iter().map(|rendition| rendition.size).collect();
+  assert_eq!(sizes, AVATAR_SIZES);
+  for rendition in &processed.renditions {
+    assert_eq!(image::guess_format(&rendition.bytes).unwrap(), ImageFormat::Jpeg);
+    let decoded = image::load_from_memory(&rendition.bytes).unwrap();
+    assert_eq!((decoded.width(), decoded.height()), (rendition.size, rendition.size));
+  }
+
+  // And: An upload with transparency keeps it, as PNG
+  let processed = process_avatar(&transparent_png()).unwrap();
+  assert_eq!(processed.format, AvatarFormat::Png);
+  let decoded = image::load_from_memory(&processed.renditions[0].bytes).unwrap();
+  assert_eq!(decoded.to_rgba8().get_pixel(0, 0)[3], 0);
+}
+
+#[test]
+fn test_process_avatar_tells_the_format_from_the_bytes() {
+  // Given / When / Then: A WebP is accepted, whatever it would be called
+  let webp = encoded(DynamicImage::ImageRgba8(RgbaImage::from_pixel(16, 16, Rgba([1, 2, 3, 255]))), ImageFormat::WebP);
+  assert!(process_avatar(&webp).is_ok());
+
+  // And: Other image formats, and bytes that are no image, are unsupported
+  let gif = b"GIF89a\x01\x00\x01\x00\x00\x00\x00;";
+  assert!(matches!(process_avatar(gif), Err(UserError::UnsupportedMediaType)));
+  assert!(matches!(process_avatar(b"<svg xmlns=\"http://www.w3.org/2000/svg\"/>"), Err(UserError::UnsupportedMediaType)));
+
+  // And: A PNG that is cut short cannot be read
+  let truncated = &png(64, 64)[..40];
+  assert!(matches!(process_avatar(truncated), Err(UserError::InvalidUpload(_))));
+}
+
+#[test]
+fn test_process_avatar_refuses_images_too_large_to_decode() {
+  // Given: A PNG wider than avatars may be, small as a file
+  let upload = png(8001, 1);
+
+  // When / Then: It is refused before it is decoded
+  assert!(matches!(process_avatar(&upload), Err(UserError::AvatarTooLarge)));
+}
+
+#[test]
+fn test_process_avatar_applies_the_exif_orientation() {
+  // Given: The same striped photo, without orientation and turned a quarter clockwise by EXIF
+  let upright = process_avatar(&striped_jpeg(&[])).unwrap();
+  let rotated = process_avatar(&striped_jpeg(&EXIF_ROTATE_90)).unwrap();
+
+  // Then: Without orientation, the square crop is red above and blue below
+  let upright = image::load_from_memory(&upright.renditions[3].bytes).unwrap().to_rgb8();
+  assert!(is_red(*upright.get_pixel(32, 4)));
+  assert!(is_blue(*upright.get_pixel(32, 60)));
+
+  // And: Turned, the red half is on the right and the blue one on the left
+  let rotated_bytes = &rotated.renditions[3].bytes;
+  let rotated = image::load_from_memory(rotated_bytes).unwrap().to_rgb8();
+  assert!(is_blue(*rotated.get_pixel(4, 32)));
+  assert!(is_red(*rotated.get_pixel(60, 32)));
+
+  // And: The rendition carries no EXIF of its own
+  assert!(!rotated_bytes.windows(4).any(|window| window == b"Exif"));
+}
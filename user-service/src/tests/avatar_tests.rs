@@ -0,0 +1,203 @@
+use std::sync::Arc;
+use actix_web::http::StatusCode;
+use actix_web::{test, web, App};
+use serde_json::{json, Value};
+use uuid::Uuid;
+
+use crate::config::avatar::AvatarPolicy;
+use crate::config::database::DbPool;
+use crate::errors::error::UserError;
+use crate::models::user::{NewUser, UserResponse};
+use crate::routes::user_routes;
+use crate::services::avatar_service::AvatarService;
+use crate::services::user_service::UserService;
+use crate::storage::in_memory::InMemoryBlobStore;
+use crate::storage::BlobStore;
+use crate::tests::support::{bearer, png, pool, token, validator};
+use crate::utils::avatar_image::AVATAR_SIZES;
+
+const BOUNDARY: &str = "avatar-boundary";
+
+/// A `multipart/form-data` body with `bytes` in the field `name`.
+fn multipart(name: &str, bytes: &[u8]) -> ((&'static str, String), Vec<u8>) {
+  let mut body = format!(
+    "--{}\r\nContent-Disposition: form-data; name=\"{}\"; filename=\"avatar\"\r\nContent-Type: application/octet-stream\r\n\r\n",
+    BOUNDARY, name,
+  ).into_bytes();
+  body.extend_from_slice(bytes);
+  body.extend_from_slice(format!("\r\n--{}--\r\n", BOUNDARY).as_bytes());
+  (("Content-Type", format!("multipart/form-data; boundary={}", BOUNDARY)), body)
+}
+
+fn store() -> Arc<InMemoryBlobStore> {
+  Arc::new(InMemoryBlobStore::default())
+}
+
+async fn existing_user(pool: &DbPool) -> UserResponse {
+  let tag = &Uuid::new_v4().simple().to_string()[..12];
+  UserService::create_user(pool, NewUser {
+    username: format!("avatar-{}", tag),
+    email: format!("avatar-{}@example.com", tag),
+    password: "Password123!".to_string(),
+  }).await.unwrap()
+}
+
+#[actix_rt::test]
+#[ignore = "needs a Postgres database at DATABASE_URL"]
+async fn test_avatar_service_keeps_only_the_current_renditions() {
+  // Given: A user and an empty store
+  let pool = pool();
+  let store = store();
+  let user = existing_user(&pool).await;
+
+  // When: They upload an avatar
+  let profile = AvatarService::upload_avatar(&pool, store.as_ref(), user.id, png(100, 100)).await.unwrap();
+
+  // Then: Every rendition is stored, and the profile points at them
+  assert_eq!(store.keys().len(), AVATAR_SIZES.len());
+  let avatar = AvatarService::get_avatar(&pool, store.as_ref(), user.id, 100).await.unwrap();
+  assert_eq!(avatar.size, 128);
+  assert_eq!(avatar.blob.content_type, "image/jpeg");
+  assert_eq!(profile.avatar_url, Some(format!("/users/{}/avatar?v={}", user.id, avatar.avatar_id)));
+
+  // When: They upload another
+  AvatarService::upload_avatar(&pool, store.as_ref(), user.id, png(80, 80)).await.unwrap();
+
+  // Then: Only the new renditions are left
+  let keys = store.keys();
+  assert_eq!(keys.len(), AVATAR_SIZES.len());
+  assert!(keys.iter().all(|key| !key.contains(&avatar.avatar_id.to_string())));
+
+  // When: They delete it
+  AvatarService::delete_avatar(&pool, store.as_ref(), user.id).await.unwrap();
+
+  // Then: The store is empty, and there is no avatar to get or delete
+  assert!(store.keys().is_empty());
+  assert!(matches!(AvatarService::get_avatar(&pool, store.as_ref(), user.id, 64).await, Err(UserError::AvatarNotFound)));
+  assert!(matches!(AvatarService::delete_avatar(&pool, store.as_ref(), user.id).await, Err(UserError::AvatarNotFound)));
+}
+
+#[actix_rt::test]
+#[ignore = "needs a Postgres database at DATABASE_URL"]
+async fn test_avatar_service_refuses_bad_uploads_without_storing_them() {
+  // Given: A user and an empty store
+  let pool = pool();
+  let store = store();
+  let user = existing_user(&pool).await;
+
+  // When / Then: An upload that is not an image is refused, and nothing is stored
+  let refused = AvatarService::upload_avatar(&pool, store.as_ref(), user.id, b"not an image".to_vec()).await;
+  assert!(matches!(refused, Err(UserError::UnsupportedMediaType)));
+  assert!(store.keys().is_empty());
+
+  // And: Uploading for an unknown user finds no one
+  let unknown = AvatarService::upload_avatar(&pool, store.as_ref(), Uuid::new_v4(), png(64, 64)).await;
+  assert!(matches!(unknown, Err(UserError::UserNotFound)));
+  assert!(store.keys().is_empty());
+}
+
+#[actix_rt::test]
+#[ignore = "needs a Postgres database at DATABASE_URL"]
+async fn test_users_change_their_own_profile_and_avatar() {
+  // Given: A user signed in with no permissions beyond their own account, and another user
+  let pool = pool();
+  let store = store();
+  let app = test::init_service(App::new()
+    .app_data(web::Data::new(pool.clone()))
+    .app_data(web::Data::from(store.clone() as Arc<dyn BlobStore>))
+    .app_data(validator())
+    .service(user_routes())).await;
+  let user = existing_user(&pool).await;
+  let other = existing_user(&pool).await;
+  let own = token(user.id, &["users:read"]);
+
+  // When: They change their own profile and upload their own avatar
+  let req = test::TestRequest::patch()
+    .uri(&format!("/users/{}/profile", user.id))
+    .insert_header(bearer(&own))
+    .set_json(json!({ "headline": "Rust developer" }))
+    .to_request();
+  let profile: Value = test::call_and_read_body_json(&app, req).await;
+  let (content_type, body) = multipart("avatar", &png(64, 64));
+  let req = test::TestRequest::put()
+    .uri(&format!("/users/{}/avatar", user.id))
+    .insert_header(bearer(&own))
+    .insert_header(content_type)
+    .set_payload(body)
+    .to_request();
+  let uploaded = test::call_service(&app, req).await;
+
+  // Then: Both succeed
+  assert_eq!(profile["headline"], "Rust developer");
+  assert_eq!(uploaded.status(), StatusCode::OK);
+
+  // And: They can delete their avatar
+  let req = test::TestRequest::delete()
+    .uri(&format!("/users/{}/avatar", user.id))
+    .insert_header(bearer(&own))
+    .to_request();
+  assert_eq!(test::call_service(&app, req).await.status(), StatusCode::NO_CONTENT);
+
+  // And: They cannot change another user's profile or avatar
+  let req = test::TestRequest::patch()
+    .uri(&format!("/users/{}/profile", other.id))
+    .insert_header(bearer(&own))
+    .set_json(json!({ "headline": "Hijacked" }))
+    .to_request();
+  let err = test::try_call_service(&app, req).await.unwrap_err();
+  assert_eq!(err.error_response().status(), StatusCode::FORBIDDEN);
+  let req = test::TestRequest::delete()
+    .uri(&format!("/users/{}/avatar", other.id))
+    .insert_header(bearer(&own))
+    .to_request();
+  let err = test::try_call_service(&app, req).await.unwrap_err();
+  assert_eq!(err.error_response().status(), StatusCode::FORBIDDEN);
+
+  // And: An administrator with users:write can
+  let req = test::TestRequest::patch()
+    .uri(&format!("/users/{}/profile", other.id))
+    .insert_header(bearer(&token(Uuid::new_v4(), &["users:write"])))
+    .set_json(json!({ "headline": "Moderated" }))
+    .to_request();
+  assert_eq!(test::call_service(&app, req).await.status(), StatusCode::OK);
+}
+
+#[actix_rt::test]
+#[ignore = "needs a Postgres database at DATABASE_URL"]
+async fn test_avatar_uploads_are_capped_before_they_are_processed() {
+  // Given: A user and the user routes
+  let pool = pool();
+  let app = test::init_service(App::new()
+    .app_data(web::Data::new(pool.clone()))
+    .app_data(web::Data::from(store() as Arc<dyn BlobStore>))
+    .app_data(validator())
+    .service(user_routes())).await;
+  let user = existing_user(&pool).await;
+  let own = token(user.id, &[]);
+  let max_bytes = AvatarPolicy::from_env().max_bytes;
+  let upload = |bytes: &[u8], field: &str| {
+    let (content_type, body) = multipart(field, bytes);
+    test::TestRequest::put()
+      .uri(&format!("/users/{}/avatar", user.id))
+      .insert_header(bearer(&own))
+      .insert_header(content_type)
+      .set_payload(body)
+      .to_request()
+  };
+
+  // When: An upload one byte past the limit is sent
+  let resp = test::call_service(&app, upload(&vec![0; max_bytes + 1], "avatar")).await;
+
+  // Then: It is refused as too large
+  assert_eq!(resp.status(), StatusCode::PAYLOAD_TOO_LARGE);
+  let body: Value = test::read_body_json(resp).await;
+  assert_eq!(body["code"], "avatar_too_large");
+
+  // And: One at the limit is read, and only then found not to be an image
+  let resp = test::call_service(&app, upload(&vec![0; max_bytes], "avatar")).await;
+  assert_eq!(resp.status(), StatusCode::UNSUPPORTED_MEDIA_TYPE);
+
+  // And: An upload without an avatar field is invalid
+  let resp = test::call_service(&app, upload(&png(64, 64), "picture")).await;
+  assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+}
@@ -0,0 +1,170 @@
+use chrono::{Duration, Utc};
+use diesel::prelude::*;
+use shared::erasure::{ErasureAcknowledged, ErasureRequested, ERASURE_REQUESTED_TOPIC};
+use uuid::Uuid;
+
+use crate::config::database::DbPool;
+use crate::config::erasure::ErasurePolicy;
+use crate::errors::error::UserError;
+use crate::models::erasure::ErasureRequestResponse;
+use crate::models::schema::{erasure_requests, outbox, users};
+use crate::models::user::{NewUser, UserResponse};
+use crate::services::avatar_service::AvatarService;
+use crate::services::erasure_service::ErasureService;
+use crate::services::user_service::UserService;
+use crate::storage::in_memory::InMemoryBlobStore;
+use crate::tests::support::{png, pool};
+
+async fn existing_user(pool: &DbPool) -> UserResponse {
+  let tag = &Uuid::new_v4().simple().to_string()[..12];
+  UserService::create_user(pool, NewUser {
+    username: format!("erased-{}", tag),
+    email: format!("erased-{}@example.com", tag),
+    password: "Password123!".to_string(),
+  }).await.unwrap()
+}
+
+fn acknowledgement(request: &ErasureRequestResponse, service: &str) -> String {
+  serde_json::to_string(&ErasureAcknowledged {
+    request_id: request.id,
+    user_id: request.user_id,
+    service: service.to_string(),
+    erased_at: Utc::now().naive_utc(),
+  }).unwrap()
+}
+
+/// Moves the end of the grace period into the past.
+fn make_due(pool: &DbPool, request_id: Uuid) {
+  let conn = &mut pool.get().expect("Failed to get DB connection from pool");
+  diesel::update(erasure_requests::table.find(request_id))
+    .set(erasure_requests::purge_after.eq(Utc::now().naive_utc() - Duration::minutes(1)))
+    .execute(conn)
+    .unwrap();
+}
+
+fn row_exists(pool: &DbPool, user_id: Uuid) -> bool {
+  let conn = &mut pool.get().expect("Failed to get DB connection from pool");
+  let count: i64 = users::table.find(user_id).count().get_result(conn).unwrap();
+  count == 1
+}
+
+#[actix_rt::test]
+#[ignore = "needs a Postgres database at DATABASE_URL"]
+async fn test_request_erasure_anonymizes_and_publishes() {
+  // Given: A user with an avatar
+  let pool = pool();
+  let store = InMemoryBlobStore::default();
+  let user = existing_user(&pool).await;
+  AvatarService::upload_avatar(&pool, &store, user.id, png(64, 64)).await.unwrap();
+
+  // When: Their erasure is requested
+  let request = ErasureService::request_erasure(&pool, &store, user.id).await.unwrap();
+
+  // Then: The account is hidden, and its avatar gone
+  assert!(matches!(UserService::get_user(&pool, user.id).await, Err(UserError::UserNotFound)));
+  assert!(store.keys().iter().all(|key| !key.contains(&user.id.to_string())));
+
+  // And: Every service is waiting to acknowledge, and nothing is completed
+  let policy = ErasurePolicy::from_env();
+  assert_eq!(request.user_id, user.id);
+  assert!(request.purge_after > request.requested_at);
+  assert_eq!(request.completed_at, None);
+  let services: Vec<&str> = request.acknowledgements.iter().map(|ack| ack.service.as_str()).collect();
+  assert_eq!(services, policy.services.iter().map(String::as_str).collect::<Vec<_>>());
+  assert!(request.acknowledgements.iter().all(|ack| ack.acknowledged_at.is_none()));
+
+  // And: The request is published with the address the account had
+  let conn = &mut pool.get().unwrap();
+  let payloads: Vec<serde_json::Value> = outbox::table
+    .filter(outbox::topic.eq(ERASURE_REQUESTED_TOPIC))
+    .filter(outbox::event_key.eq(user.id.to_string()))
+    .select(outbox::payload)
+    .load(conn)
+    .unwrap();
+  assert_eq!(payloads.len(), 1);
+  let published: ErasureRequested = serde_json::from_value(payloads[0].clone()).unwrap();
+  assert_eq!(published.request_id, request.id);
+  assert_eq!(published.email, user.email);
+
+  // And: Asking again finds no account to erase
+  let again = ErasureService::request_erasure(&pool, &store, user.id).await;
+  assert!(matches!(again, Err(UserError::UserNotFound)));
+}
+
+#[actix_rt::test]
+#[ignore = "needs a Postgres database at DATABASE_URL"]
+async fn test_acknowledgements_are_recorded_once() {
+  // Given: An erasure waiting on its services
+  let pool = pool();
+  let store = InMemoryBlobStore::default();
+  let user = existing_user(&pool).await;
+  let request = ErasureService::request_erasure(&pool, &store, user.id).await.unwrap();
+  let service = request.acknowledgements[0].service.clone();
+
+  // When: The service acknowledges
+  ErasureService::handle_acknowledgement(&pool, &acknowledgement(&request, &service)).await.unwrap();
+
+  // Then: Its acknowledgement is recorded
+  let recorded = ErasureService::get_erasure(&pool, request.id).await.unwrap();
+  let first = recorded.acknowledgements.iter().find(|ack| ack.service == service).unwrap().acknowledged_at;
+  assert!(first.is_some());
+
+  // When: It acknowledges again, another service answers and a payload is not an acknowledgement
+  ErasureService::handle_acknowledgement(&pool, &acknowledgement(&request, &service)).await.unwrap();
+  ErasureService::handle_acknowledgement(&pool, &acknowledgement(&request, "unexpected-service")).await.unwrap();
+  ErasureService::handle_acknowledgement(&pool, "{\"not\": \"an acknowledgement\"}").await.unwrap();
+
+  // Then: None of them changes what was recorded
+  let recorded = ErasureService::get_erasure(&pool, request.id).await.unwrap();
+  assert_eq!(recorded.acknowledgements.len(), request.acknowledgements.len());
+  assert_eq!(recorded.acknowledgements.iter().find(|ack| ack.service == service).unwrap().acknowledged_at, first);
+  assert!(recorded.acknowledgements.iter().all(|ack| ack.service != "unexpected-service"));
+}
+
+#[actix_rt::test]
+#[ignore = "needs a Postgres database at DATABASE_URL"]
+async fn test_complete_due_waits_for_every_acknowledgement() {
+  // Given: An erasure past its grace period, which no service has acknowledged
+  let pool = pool();
+  let store = InMemoryBlobStore::default();
+  let user = existing_user(&pool).await;
+  let request = ErasureService::request_erasure(&pool, &store, user.id).await.unwrap();
+  make_due(&pool, request.id);
+
+  // When: Due erasures are completed
+  ErasureService::complete_due(&pool).await.unwrap();
+
+  // Then: This one is not, and the anonymized account is kept
+  assert_eq!(ErasureService::get_erasure(&pool, request.id).await.unwrap().completed_at, None);
+  assert!(row_exists(&pool, user.id));
+
+  // When: Every service acknowledges, and due erasures are completed again
+  for ack in &request.acknowledgements {
+    ErasureService::handle_acknowledgement(&pool, &acknowledgement(&request, &ack.service)).await.unwrap();
+  }
+  ErasureService::complete_due(&pool).await.unwrap();
+
+  // Then: The request is completed and the account deleted for good
+  assert!(ErasureService::get_erasure(&pool, request.id).await.unwrap().completed_at.is_some());
+  assert!(!row_exists(&pool, user.id));
+}
+
+#[actix_rt::test]
+#[ignore = "needs a Postgres database at DATABASE_URL"]
+async fn test_complete_due_waits_for_the_grace_period() {
+  // Given: An erasure every service has acknowledged, still in its grace period
+  let pool = pool();
+  let store = InMemoryBlobStore::default();
+  let user = existing_user(&pool).await;
+  let request = ErasureService::request_erasure(&pool, &store, user.id).await.unwrap();
+  for ack in &request.acknowledgements {
+    ErasureService::handle_acknowledgement(&pool, &acknowledgement(&request, &ack.service)).await.unwrap();
+  }
+
+  // When: Due erasures are completed
+  ErasureService::complete_due(&pool).await.unwrap();
+
+  // Then: This one is not, and the anonymized account is kept
+  assert_eq!(ErasureService::get_erasure(&pool, request.id).await.unwrap().completed_at, None);
+  assert!(row_exists(&pool, user.id));
+}
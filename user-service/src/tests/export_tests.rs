@@ -0,0 +1,97 @@
+use std::io::{Cursor, Read};
+use serde_json::Value;
+use uuid::Uuid;
+use zip::ZipArchive;
+
+use crate::config::database::DbPool;
+use crate::errors::error::UserError;
+use crate::models::user::{NewUser, UserResponse};
+use crate::services::avatar_service::AvatarService;
+use crate::services::export_service::ExportService;
+use crate::services::user_service::UserService;
+use crate::storage::in_memory::InMemoryBlobStore;
+use crate::tests::support::{png, pool};
+
+async fn existing_user(pool: &DbPool) -> UserResponse {
+  let tag = &Uuid::new_v4().simple().to_string()[..12];
+  UserService::create_user(pool, NewUser {
+    username: format!("export-{}", tag),
+    email: format!("export-{}@example.com", tag),
+    password: "Password123!".to_string(),
+  }).await.unwrap()
+}
+
+fn file_names(archive: &mut ZipArchive<Cursor<Vec<u8>>>) -> Vec<String> {
+  let mut names: Vec<String> = archive.file_names().map(str::to_string).collect();
+  names.sort();
+  names
+}
+
+fn read(archive: &mut ZipArchive<Cursor<Vec<u8>>>, name: &str) -> Vec<u8> {
+  let mut bytes = Vec::new();
+  archive.by_name(name).unwrap().read_to_end(&mut bytes).unwrap();
+  bytes
+}
+
+#[actix_rt::test]
+#[ignore = "needs a Postgres database at DATABASE_URL"]
+async fn test_export_holds_the_account_and_a_manifest() {
+  // Given: A user without an avatar
+  let pool = pool();
+  let store = InMemoryBlobStore::default();
+  let user = existing_user(&pool).await;
+
+  // When: Their data is exported
+  let export = ExportService::export_user(&pool, &store, user.id).await.unwrap();
+
+  // Then: The archive holds the account and the manifest, and nothing else
+  let mut archive = ZipArchive::new(Cursor::new(export)).unwrap();
+  assert_eq!(file_names(&mut archive), vec!["account.json", "manifest.json"]);
+
+  // And: The account is there without its password
+  let account: Value = serde_json::from_slice(&read(&mut archive, "account.json")).unwrap();
+  assert_eq!(account["id"], user.id.to_string());
+  assert_eq!(account["email"], user.email.as_str());
+  assert!(account.get("password").is_none());
+
+  // And: The manifest lists the account and says the password is withheld
+  let manifest: Value = serde_json::from_slice(&read(&mut archive, "manifest.json")).unwrap();
+  assert_eq!(manifest["user_id"], user.id.to_string());
+  assert_eq!(manifest["files"], serde_json::json!(["account.json"]));
+  assert!(manifest["withheld"]["password"].is_string());
+}
+
+#[actix_rt::test]
+#[ignore = "needs a Postgres database at DATABASE_URL"]
+async fn test_export_holds_the_avatar_at_its_largest() {
+  // Given: A user with an avatar
+  let pool = pool();
+  let store = InMemoryBlobStore::default();
+  let user = existing_user(&pool).await;
+  AvatarService::upload_avatar(&pool, &store, user.id, png(600, 600)).await.unwrap();
+
+  // When: Their data is exported
+  let export = ExportService::export_user(&pool, &store, user.id).await.unwrap();
+
+  // Then: The avatar is in the archive, as its largest rendition, and in the manifest
+  let mut archive = ZipArchive::new(Cursor::new(export)).unwrap();
+  assert_eq!(file_names(&mut archive), vec!["account.json", "avatar.jpg", "manifest.json"]);
+  let largest = AvatarService::get_avatar(&pool, &store, user.id, 512).await.unwrap();
+  assert_eq!(read(&mut archive, "avatar.jpg"), largest.blob.bytes);
+  let manifest: Value = serde_json::from_slice(&read(&mut archive, "manifest.json")).unwrap();
+  assert_eq!(manifest["files"], serde_json::json!(["account.json", "avatar.jpg"]));
+}
+
+#[actix_rt::test]
+#[ignore = "needs a Postgres database at DATABASE_URL"]
+async fn test_export_of_an_unknown_user_is_not_found() {
+  // Given: No such user
+  let pool = pool();
+  let store = InMemoryBlobStore::default();
+
+  // When: Their data is exported
+  let export = ExportService::export_user(&pool, &store, Uuid::new_v4()).await;
+
+  // Then: There is no one to export
+  assert!(matches!(export, Err(UserError::UserNotFound)));
+}
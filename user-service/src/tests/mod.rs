@@ -1,2 +1,7 @@
+mod support;
+mod avatar_image_tests;
+mod avatar_tests;
+mod erasure_tests;
+mod export_tests;
+mod profile_tests;
 mod user_tests;
-mod rate_limiter_tests;
\ No newline at end of file
@@ -0,0 +1,188 @@
+use chrono::{Duration, NaiveDateTime, Timelike, Utc};
+use diesel::prelude::*;
+use serde_json::json;
+use uuid::Uuid;
+use validator::Validate;
+
+use crate::config::database::DbPool;
+use crate::errors::error::UserError;
+use crate::models::audit::LOGIN_SUCCEEDED;
+use crate::models::schema::users;
+use crate::models::user::{NewUser, UpdateProfile, UserResponse};
+use crate::services::user_service::UserService;
+use crate::tests::support::pool;
+
+fn profile(body: serde_json::Value) -> UpdateProfile {
+  serde_json::from_value(body).unwrap()
+}
+
+/// The fields of `changes` that fail validation, once normalized.
+fn invalid_fields(changes: UpdateProfile) -> Vec<String> {
+  match changes.normalized().validate() {
+    Ok(()) => Vec::new(),
+    Err(errors) => {
+      let mut fields: Vec<String> = errors.field_errors().keys().map(|field| field.to_string()).collect();
+      fields.sort();
+      fields
+    }
+  }
+}
+
+fn phone_is_valid(phone: &str) -> bool {
+  invalid_fields(profile(json!({ "phone": phone }))).is_empty()
+}
+
+fn website_is_valid(website: &str) -> bool {
+  invalid_fields(profile(json!({ "website": website }))).is_empty()
+}
+
+async fn existing_user(pool: &DbPool) -> UserResponse {
+  let tag = &Uuid::new_v4().simple().to_string()[..12];
+  UserService::create_user(pool, NewUser {
+    username: format!("profile-{}", tag),
+    email: format!("profile-{}@example.com", tag),
+    password: "Password123!".to_string(),
+  }).await.unwrap()
+}
+
+fn login_succeeded(email: &str, at: NaiveDateTime) -> String {
+  json!({ "event_type": LOGIN_SUCCEEDED, "occurred_at": at, "metadata": { "email": email } }).to_string()
+}
+
+fn last_login_at(pool: &DbPool, user_id: Uuid) -> Option<NaiveDateTime> {
+  let conn = &mut pool.get().expect("Failed to get DB connection from pool");
+  users::table.find(user_id).select(users::last_login_at).first(conn).unwrap()
+}
+
+#[test]
+fn test_absent_and_null_fields_are_told_apart() {
+  // Given / When: A patch that sets one field, clears another and leaves the rest out
+  let changes = profile(json!({ "headline": "Rust developer", "bio": null }));
+
+  // Then: The set field has its value, the cleared one is null, and the rest are absent
+  assert_eq!(changes.headline, Some(Some("Rust developer".to_string())));
+  assert_eq!(changes.bio, Some(None));
+  assert_eq!(changes.display_name, None);
+  assert_eq!(changes.phone, None);
+  assert!(!changes.is_empty());
+
+  // And: A patch without fields is empty
+  assert!(profile(json!({})).is_empty());
+}
+
+#[test]
+fn test_normalized_trims_values_and_clears_blank_ones() {
+  // Given: A patch with padded, blank and absent fields
+  let changes = profile(json!({ "display_name": "  Ada Lovelace ", "location": "   ", "bio": null }));
+
+  // When: It is normalized
+  let normalized = changes.normalized();
+
+  // Then: Values are trimmed, blank ones are cleared and absent ones stay absent
+  assert_eq!(normalized.display_name, Some(Some("Ada Lovelace".to_string())));
+  assert_eq!(normalized.location, Some(None));
+  assert_eq!(normalized.bio, Some(None));
+  assert_eq!(normalized.headline, None);
+}
+
+#[test]
+fn test_profile_lengths_are_validated() {
+  // Given / When / Then: Values within their limits pass
+  assert!(invalid_fields(profile(json!({
+    "display_name": "A",
+    "headline": "h".repeat(150),
+    "location": "l".repeat(100),
+    "bio": "b".repeat(2000),
+  }))).is_empty());
+
+  // And: Values past them are refused, field by field
+  assert_eq!(invalid_fields(profile(json!({
+    "display_name": "d".repeat(101),
+    "headline": "h".repeat(151),
+    "location": "l".repeat(101),
+    "bio": "b".repeat(2001),
+  }))), vec!["bio", "display_name", "headline", "location"]);
+
+  // And: Clearing a field is never refused
+  assert!(invalid_fields(profile(json!({ "display_name": null, "website": "", "phone": " " }))).is_empty());
+}
+
+#[test]
+fn test_phone_numbers_are_validated() {
+  // Given / When / Then: 7 to 15 digits, with an optional leading + and separators, pass
+  assert!(phone_is_valid("+44 20 7946 0958"));
+  assert!(phone_is_valid("(555) 123-4567"));
+  assert!(phone_is_valid("555.1234"));
+  assert!(phone_is_valid("123456789012345"));
+
+  // And: Too few or too many digits, letters and a + elsewhere are refused
+  assert!(!phone_is_valid("123456"));
+  assert!(!phone_is_valid("1234567890123456"));
+  assert!(!phone_is_valid("555-CALL-NOW"));
+  assert!(!phone_is_valid("555+1234567"));
+  assert!(!phone_is_valid("++15551234567"));
+}
+
+#[test]
+fn test_websites_are_validated() {
+  // Given / When / Then: http and https URLs pass
+  assert!(website_is_valid("https://example.com"));
+  assert!(website_is_valid("http://example.com/about?lang=en"));
+
+  // And: Other schemes, and what is not a URL, are refused
+  assert!(!website_is_valid("javascript:alert(1)"));
+  assert!(!website_is_valid("ftp://example.com"));
+  assert!(!website_is_valid("example.com"));
+  assert!(!website_is_valid("https://"));
+
+  // And: So is a URL past the length limit
+  assert!(!website_is_valid(&format!("https://example.com/{}", "a".repeat(2048))));
+}
+
+#[actix_rt::test]
+#[ignore = "needs a Postgres database at DATABASE_URL"]
+async fn test_update_profile_changes_only_the_fields_present() {
+  // Given: A user with a headline and a location
+  let pool = pool();
+  let user = existing_user(&pool).await;
+  UserService::update_profile(&pool, user.id, profile(json!({ "headline": "Engineer", "location": "Lisbon" }))).await.unwrap();
+
+  // When: The headline is changed, padded, and the location cleared with a blank value
+  let updated = UserService::update_profile(&pool, user.id, profile(json!({ "headline": " Staff engineer ", "location": "" }))).await.unwrap();
+
+  // Then: The headline is trimmed, the location cleared and the rest untouched
+  assert_eq!(updated.headline.as_deref(), Some("Staff engineer"));
+  assert_eq!(updated.location, None);
+  assert_eq!(updated.username, user.username);
+
+  // And: An empty patch changes nothing
+  let unchanged = UserService::update_profile(&pool, user.id, profile(json!({}))).await.unwrap();
+  assert_eq!(unchanged.headline.as_deref(), Some("Staff engineer"));
+
+  // And: An invalid patch is refused as a whole
+  let refused = UserService::update_profile(&pool, user.id, profile(json!({ "headline": "Lead", "phone": "call me" }))).await;
+  assert!(matches!(refused, Err(UserError::ValidationError(_))));
+  assert_eq!(UserService::get_profile(&pool, user.id).await.unwrap().headline.as_deref(), Some("Staff engineer"));
+}
+
+#[actix_rt::test]
+#[ignore = "needs a Postgres database at DATABASE_URL"]
+async fn test_out_of_order_sign_ins_do_not_move_last_login_back() {
+  // Given: A user whose latest sign-in is recorded
+  let pool = pool();
+  let user = existing_user(&pool).await;
+  let latest = Utc::now().naive_utc().with_nanosecond(0).unwrap();
+  UserService::handle_audit_event(&pool, &login_succeeded(&user.email, latest)).await.unwrap();
+  assert_eq!(last_login_at(&pool, user.id), Some(latest));
+
+  // When: An earlier sign-in arrives late
+  UserService::handle_audit_event(&pool, &login_succeeded(&user.email, latest - Duration::hours(1))).await.unwrap();
+
+  // Then: The latest one stays recorded
+  assert_eq!(last_login_at(&pool, user.id), Some(latest));
+
+  // And: A later one replaces it
+  let later = latest + Duration::minutes(5);
+  UserService::handle_audit_event(&pool, &login_succeeded(&user.email, later)).await.unwrap();
+  assert_eq!(last_login_at(&pool, user.id), Some(later));
+}
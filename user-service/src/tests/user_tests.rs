@@ -1,385 +1,341 @@
-use actix_web::{App, test, web};
+use std::sync::Arc;
+use actix_web::http::StatusCode;
+use actix_web::{test, web, App};
 use diesel::prelude::*;
-use log::info;
-use lazy_static::lazy_static;
-use std::sync::Once;
-use serial_test::serial;
+use serde_json::{json, Value};
 use uuid::Uuid;
 
-use crate::config::database::establish_connection;
-use crate::models::user::{NewUser, UpdateUser};
+use crate::config::database::DbPool;
+use crate::models::schema::users;
+use crate::models::user::{NewUser, UserResponse};
 use crate::routes::user_routes;
-
-lazy_static! {
-    static ref INIT: Once = Once::new();
+use crate::services::user_service::UserService;
+use crate::storage::in_memory::InMemoryBlobStore;
+use crate::storage::BlobStore;
+use crate::tests::support::{bearer, pool, token, validator};
+
+/// A user that is not there yet, with a name and email no other test uses.
+fn new_user() -> NewUser {
+  let tag = &Uuid::new_v4().simple().to_string()[..12];
+  NewUser {
+    username: format!("user-{}", tag),
+    email: format!("user-{}@example.com", tag),
+    password: "Password123!".to_string(),
+  }
 }
 
-fn init_logger() {
-  INIT.call_once(|| {
-    env_logger::init();
-  });
+async fn existing_user(pool: &DbPool) -> UserResponse {
+  UserService::create_user(pool, new_user()).await.unwrap()
 }
 
-fn clear_users_table() {
-  let pool = establish_connection();
+fn stored_password(pool: &DbPool, user_id: Uuid) -> String {
   let conn = &mut pool.get().expect("Failed to get DB connection from pool");
+  users::table.find(user_id).select(users::password).first(conn).unwrap()
+}
 
-  // Log antes de limpar a tabela
-  let user_count: i64 = crate::models::schema::users::dsl::users
-    .count()
-    .get_result(conn)
-    .expect("Failed to count users before clearing");
-  info!("Number of users before clearing: {}", user_count);
-
-  diesel::delete(crate::models::schema::users::dsl::users)
-    .execute(conn)
-    .expect("Error clearing users table");
-
-  // Log depois de limpar a tabela
-  let user_count_after: i64 = crate::models::schema::users::dsl::users
-    .count()
-    .get_result(conn)
-    .expect("Failed to count users after clearing");
-  info!("Number of users after clearing: {}", user_count_after);
+fn store() -> web::Data<dyn BlobStore> {
+  web::Data::from(Arc::new(InMemoryBlobStore::default()) as Arc<dyn BlobStore>)
+}
+
+fn assert_no_password(body: &Value) {
+  assert!(body.get("password").is_none(), "password leaked in {}", body);
 }
 
 #[actix_rt::test]
-#[serial]
+#[ignore = "needs a Postgres database at DATABASE_URL"]
 async fn test_create_user() {
-  init_logger();
-  info!("Teste de criação de usuário");
-  info!("Limpando a tabela de usuários");
-  clear_users_table();
-  info!("Tabela de usuários limpa");
-
-  // Given: A new user
-  let pool = establish_connection();
-  let new_user = NewUser {
-    username: "testuser".to_string(),
-    email: "testuser@example.com".to_string(),
-    password: "Password123!".to_string(),
-  };
-
-  info!("Vai consumir o serviço");
-  // When: The user is created
-  let mut app = test::init_service(App::new()
+  // Given: An admin token and a new user
+  let pool = pool();
+  let app = test::init_service(App::new()
     .app_data(web::Data::new(pool.clone()))
+    .app_data(validator())
     .service(user_routes())).await;
-  info!("Vai consumir, agora");
+  let new_user = new_user();
 
+  // When: The user is created
   let req = test::TestRequest::post()
     .uri("/users")
+    .insert_header(bearer(&token(Uuid::new_v4(), &["users:write"])))
     .set_json(&new_user)
     .to_request();
-  info!("Serviço consumido");
-
-  let resp = test::call_service(&mut app, req).await;
+  let resp = test::call_service(&app, req).await;
+
+  // Then: The account comes back without its password, which is stored hashed
+  assert_eq!(resp.status(), StatusCode::OK);
+  let created: Value = test::read_body_json(resp).await;
+  assert_eq!(created["username"], new_user.username.as_str());
+  assert_eq!(created["email"], new_user.email.as_str());
+  assert_no_password(&created);
+  let user_id = Uuid::parse_str(created["id"].as_str().unwrap()).unwrap();
+  assert_ne!(stored_password(&pool, user_id), new_user.password);
+}
 
-  // Log response status and body for debugging
-  let status = resp.status();
-  let body = test::read_body(resp).await;
-  info!("Create User Response Status: {:?}", status);
-  info!("Create User Response Body: {:?}", body);
+#[actix_rt::test]
+#[ignore = "needs a Postgres database at DATABASE_URL"]
+async fn test_create_user_needs_a_token_with_users_write() {
+  // Given: The user routes
+  let pool = pool();
+  let app = test::init_service(App::new()
+    .app_data(web::Data::new(pool.clone()))
+    .app_data(validator())
+    .service(user_routes())).await;
 
-  // Then: The response status should be 200 OK
-  assert_eq!(status, actix_web::http::StatusCode::OK);
+  // When / Then: Without a token the request is unauthorized
+  let req = test::TestRequest::post().uri("/users").set_json(new_user()).to_request();
+  let err = test::try_call_service(&app, req).await.unwrap_err();
+  assert_eq!(err.error_response().status(), StatusCode::UNAUTHORIZED);
 
-  // Additional check to see the user is actually inserted
-  let conn = &mut pool.get().expect("Failed to get DB connection from pool");
-  let inserted_user: Option<(String, String)> = crate::models::schema::users::table
-    .select((crate::models::schema::users::dsl::username, crate::models::schema::users::dsl::email))
-    .first(conn)
-    .optional()
-    .expect("Failed to fetch inserted user");
-
-  assert!(inserted_user.is_some());
-  assert_eq!(inserted_user.unwrap(), ("testuser".to_string(), "testuser@example.com".to_string()));
+  // And: A token that can only read is forbidden
+  let req = test::TestRequest::post()
+    .uri("/users")
+    .insert_header(bearer(&token(Uuid::new_v4(), &["users:read"])))
+    .set_json(new_user())
+    .to_request();
+  let err = test::try_call_service(&app, req).await.unwrap_err();
+  assert_eq!(err.error_response().status(), StatusCode::FORBIDDEN);
 }
 
 #[actix_rt::test]
-#[serial]
+#[ignore = "needs a Postgres database at DATABASE_URL"]
 async fn test_create_user_with_invalid_email() {
-  init_logger();
-  clear_users_table();
-
   // Given: A new user with an invalid email
-  let pool = establish_connection();
-  let new_user = NewUser {
-    username: "testuser".to_string(),
-    email: "invalid_email".to_string(),
-    password: "Password123!".to_string(),
-  };
-
-  // When: The user is created
-  let mut app = test::init_service(App::new()
+  let pool = pool();
+  let app = test::init_service(App::new()
     .app_data(web::Data::new(pool.clone()))
+    .app_data(validator())
     .service(user_routes())).await;
+  let new_user = NewUser { email: "invalid_email".to_string(), ..new_user() };
 
+  // When: The user is created
   let req = test::TestRequest::post()
     .uri("/users")
+    .insert_header(bearer(&token(Uuid::new_v4(), &["users:write"])))
     .set_json(&new_user)
     .to_request();
+  let resp = test::call_service(&app, req).await;
 
-  let resp = test::call_service(&mut app, req).await;
-
-  // Log response status and body for debugging
-  let status = resp.status();
-  let body = test::read_body(resp).await;
-
-  info!("Create User with Invalid Email Response Status: {:?}", status);
-  info!("Create User with Invalid Email Response Body: {:?}", body);
-
-  // Then: The response status should be 204 No Content
-  assert_eq!(status, actix_web::http::StatusCode::BAD_REQUEST);
+  // Then: The email is refused
+  assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+  let body: Value = test::read_body_json(resp).await;
+  assert!(body["fields"].get("email").is_some());
 }
 
 #[actix_rt::test]
-#[serial]
+#[ignore = "needs a Postgres database at DATABASE_URL"]
 async fn test_create_user_with_weak_password() {
-  init_logger();
-  clear_users_table();
-
   // Given: A new user with a weak password
-  let pool = establish_connection();
-  let new_user = NewUser {
-    username: "testuser".to_string(),
-    email: "testuser@example.com".to_string(),
-    password: "weakpass".to_string(),
-  };
-
-  // When: The user is created
-  let mut app = test::init_service(App::new()
+  let pool = pool();
+  let app = test::init_service(App::new()
     .app_data(web::Data::new(pool.clone()))
+    .app_data(validator())
     .service(user_routes())).await;
+  let new_user = NewUser { password: "weakpass".to_string(), ..new_user() };
 
+  // When: The user is created
   let req = test::TestRequest::post()
     .uri("/users")
+    .insert_header(bearer(&token(Uuid::new_v4(), &["users:write"])))
     .set_json(&new_user)
     .to_request();
+  let resp = test::call_service(&app, req).await;
 
-  let resp = test::call_service(&mut app, req).await;
-
-  // Log response status and body for debugging
-  let status = resp.status();
-  let body = test::read_body(resp).await;
-
-  info!("Create User with Weak Password Response Status: {:?}", status);
-  info!("Create User with Weak Password Response Body: {:?}", body);
-
-  // Then: The response status should be 204 No Content
-  assert_eq!(status, actix_web::http::StatusCode::BAD_REQUEST);
+  // Then: The password is refused
+  assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+  let body: Value = test::read_body_json(resp).await;
+  assert!(body["fields"].get("password").is_some());
 }
 
 #[actix_rt::test]
-#[serial]
+#[ignore = "needs a Postgres database at DATABASE_URL"]
 async fn test_create_user_with_short_username() {
-  init_logger();
-  clear_users_table();
-
   // Given: A new user with a short username
-  let pool = establish_connection();
-  let new_user = NewUser {
-    username: "tu".to_string(),
-    email: "testuser@example.com".to_string(),
-    password: "Password123!".to_string(),
-  };
-
-  // When: The user is created
-  let mut app = test::init_service(App::new()
+  let pool = pool();
+  let app = test::init_service(App::new()
     .app_data(web::Data::new(pool.clone()))
+    .app_data(validator())
     .service(user_routes())).await;
+  let new_user = NewUser { username: "tu".to_string(), ..new_user() };
 
+  // When: The user is created
   let req = test::TestRequest::post()
     .uri("/users")
+    .insert_header(bearer(&token(Uuid::new_v4(), &["users:write"])))
     .set_json(&new_user)
     .to_request();
+  let resp = test::call_service(&app, req).await;
 
-  let resp = test::call_service(&mut app, req).await;
-
-  // Log response status and body for debugging
-  let status = resp.status();
-  let body = test::read_body(resp).await;
-
-  info!("Create User with Short Username Response Status: {:?}", status);
-  info!("Create User with Short Username Response Body: {:?}", body);
-
-  // Then: The response status should be 204 No Content
-  assert_eq!(status, actix_web::http::StatusCode::BAD_REQUEST);
+  // Then: The username is refused
+  assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+  let body: Value = test::read_body_json(resp).await;
+  assert!(body["fields"].get("username").is_some());
 }
 
 #[actix_rt::test]
-#[serial]
+#[ignore = "needs a Postgres database at DATABASE_URL"]
 async fn test_list_users() {
-  init_logger();
-  clear_users_table();
-
-  // Given: Users exist in the database
-  let pool = establish_connection();
-  let mut app = test::init_service(App::new()
+  // Given: A user exists
+  let pool = pool();
+  let app = test::init_service(App::new()
     .app_data(web::Data::new(pool.clone()))
+    .app_data(validator())
     .service(user_routes())).await;
+  let user = existing_user(&pool).await;
 
+  // When: Users are listed, filtered by that user's email
   let req = test::TestRequest::get()
-    .uri("/users")
+    .uri(&format!("/users?email={}", user.email))
+    .insert_header(bearer(&token(Uuid::new_v4(), &["users:read"])))
     .to_request();
+  let page: Value = test::call_and_read_body_json(&app, req).await;
 
-  let resp = test::call_service(&mut app, req).await;
-
-  // Log response status and body for debugging
-  let status = resp.status();
-  let body = test::read_body(resp).await;
-
-  info!("List Users Response Status: {:?}", status);
-  info!("List Users Response Body: {:?}", body);
-
-  // Then: The response status should be 204 No Content
-  assert_eq!(status, actix_web::http::StatusCode::OK);
+  // Then: Only that user is listed, without a password
+  let items = page["items"].as_array().unwrap();
+  assert_eq!(items.len(), 1);
+  assert_eq!(items[0]["id"], json!(user.id));
+  items.iter().for_each(assert_no_password);
 }
 
 #[actix_rt::test]
-#[serial]
-async fn test_get_user() {
-  init_logger();
-  clear_users_table();
+#[ignore = "needs a Postgres database at DATABASE_URL"]
+async fn test_list_users_matches_prefixes_ignoring_case() {
+  // Given: A user exists
+  let pool = pool();
+  let app = test::init_service(App::new()
+    .app_data(web::Data::new(pool.clone()))
+    .app_data(validator())
+    .service(user_routes())).await;
+  let user = existing_user(&pool).await;
+  let list = |query: String| test::TestRequest::get()
+    .uri(&format!("/users?{}", query))
+    .insert_header(bearer(&token(Uuid::new_v4(), &["users:read"])))
+    .to_request();
 
-  // Given: A user exists in the database
-  let pool = establish_connection();
-  let conn = &mut pool.get().expect("Failed to get DB connection from pool");
+  // When: Users are listed by an uppercased prefix of their username, and of their email
+  let by_username: Value = test::call_and_read_body_json(&app, list(format!("username={}", user.username[..14].to_uppercase()))).await;
+  let by_email: Value = test::call_and_read_body_json(&app, list(format!("email={}", user.email[..14].to_uppercase()))).await;
 
-  let new_user = NewUser {
-    username: "testuser".to_string(),
-    email: "testuser@example.com".to_string(),
-    password: "Password123!".to_string(),
-  };
-  diesel::insert_into(crate::models::schema::users::table)
-    .values(&new_user)
-    .execute(conn)
-    .expect("Error inserting user");
+  // Then: Both find them
+  for page in [by_username, by_email] {
+    let items = page["items"].as_array().unwrap();
+    assert_eq!(items.len(), 1);
+    assert_eq!(items[0]["id"], json!(user.id));
+  }
 
-  let user_id: Uuid = crate::models::schema::users::table
-    .select(crate::models::schema::users::dsl::id)
-    .first(conn)
-    .expect("Error fetching user ID");
+  // And: A wildcard in the prefix is taken literally
+  let page: Value = test::call_and_read_body_json(&app, list("username=user_%25".to_string())).await;
+  assert!(page["items"].as_array().unwrap().is_empty());
+}
 
-  // When: The user is requested by ID
-  let mut app = test::init_service(App::new()
+#[actix_rt::test]
+#[ignore = "needs a Postgres database at DATABASE_URL"]
+async fn test_get_user() {
+  // Given: A user exists
+  let pool = pool();
+  let app = test::init_service(App::new()
     .app_data(web::Data::new(pool.clone()))
+    .app_data(validator())
     .service(user_routes())).await;
+  let user = existing_user(&pool).await;
+  let reader = token(Uuid::new_v4(), &["users:read"]);
 
+  // When: The user is requested by ID
   let req = test::TestRequest::get()
-    .uri(&format!("/users/{}", user_id))
+    .uri(&format!("/users/{}", user.id))
+    .insert_header(bearer(&reader))
     .to_request();
+  let resp = test::call_service(&app, req).await;
 
-  let resp = test::call_service(&mut app, req).await;
-
-  // Log response status and body for debugging
-  let status = resp.status();
-  let body = test::read_body(resp).await;
+  // Then: The account comes back without its password
+  assert_eq!(resp.status(), StatusCode::OK);
+  let found: Value = test::read_body_json(resp).await;
+  assert_eq!(found["email"], user.email.as_str());
+  assert_no_password(&found);
 
-  info!("Get User Response Status: {:?}", status);
-  info!("Get User Response Body: {:?}", body);
-
-  // Then: The response status should be 200 OK
-  assert_eq!(status, actix_web::http::StatusCode::OK);
+  // And: An unknown ID is not found
+  let req = test::TestRequest::get()
+    .uri(&format!("/users/{}", Uuid::new_v4()))
+    .insert_header(bearer(&reader))
+    .to_request();
+  assert_eq!(test::call_service(&app, req).await.status(), StatusCode::NOT_FOUND);
 }
 
 #[actix_rt::test]
-#[serial]
+#[ignore = "needs a Postgres database at DATABASE_URL"]
 async fn test_update_user() {
-  init_logger();
-  clear_users_table();
-
-  // Given: A user exists in the database
-  let pool = establish_connection();
-  let conn = &mut pool.get().expect("Failed to get DB connection from pool");
-
-  let new_user = NewUser {
-    username: "testuser".to_string(),
-    email: "testuser@example.com".to_string(),
-    password: "Password123!".to_string(),
-  };
-  diesel::insert_into(crate::models::schema::users::table)
-    .values(&new_user)
-    .execute(conn)
-    .expect("Error inserting user");
-
-  let user_id: Uuid = crate::models::schema::users::table
-    .select(crate::models::schema::users::dsl::id)
-    .first(conn)
-    .expect("Error fetching user ID");
-
-  let updated_user = UpdateUser {
-    username: Some("updateduser".to_string()),
-    email: None,
-    password: Some("NewPassword123!".to_string()),
-  };
-
-  // When: The user is updated by ID
-  let mut app = test::init_service(App::new()
+  // Given: A user exists
+  let pool = pool();
+  let app = test::init_service(App::new()
     .app_data(web::Data::new(pool.clone()))
+    .app_data(validator())
     .service(user_routes())).await;
+  let user = existing_user(&pool).await;
+  let writer = token(Uuid::new_v4(), &["users:write"]);
+  let username = format!("renamed-{}", &Uuid::new_v4().simple().to_string()[..8]);
 
+  // When: Only the username is changed
   let req = test::TestRequest::put()
-    .uri(&format!("/users/{}", user_id))
-    .set_json(&updated_user)
+    .uri(&format!("/users/{}", user.id))
+    .insert_header(bearer(&writer))
+    .set_json(json!({ "username": username }))
     .to_request();
+  let resp = test::call_service(&app, req).await;
 
-  let resp = test::call_service(&mut app, req).await;
-
-  // Log response status and body for debugging
-  let status = resp.status();
-  let body = test::read_body(resp).await;
+  // Then: The username changes and the email stays
+  assert_eq!(resp.status(), StatusCode::OK);
+  let updated: Value = test::read_body_json(resp).await;
+  assert_eq!(updated["username"], username.as_str());
+  assert_eq!(updated["email"], user.email.as_str());
+  assert_no_password(&updated);
 
-  info!("Update User Response Status: {:?}", status);
-  info!("Update User Response Body: {:?}", body);
-
-  // Then: The response status should be 200 OK
-  assert_eq!(status, actix_web::http::StatusCode::OK);
+  // When: Only the password is changed
+  let before = stored_password(&pool, user.id);
+  let req = test::TestRequest::put()
+    .uri(&format!("/users/{}", user.id))
+    .insert_header(bearer(&writer))
+    .set_json(json!({ "password": "NewPassword123!" }))
+    .to_request();
+  let updated: Value = test::call_and_read_body_json(&app, req).await;
+
+  // Then: A new hash is stored, and neither shows in the response
+  let after = stored_password(&pool, user.id);
+  assert_ne!(after, before);
+  assert_ne!(after, "NewPassword123!");
+  assert_eq!(updated["username"], username.as_str());
+  assert_no_password(&updated);
 }
 
 #[actix_rt::test]
-#[serial]
+#[ignore = "needs a Postgres database at DATABASE_URL"]
 async fn test_delete_user() {
-  init_logger();
-  clear_users_table();
-
-  // Given: A user exists in the database
-  let pool = establish_connection();
-  let conn = &mut pool.get().expect("Failed to get DB connection from pool");
-
-  let new_user = NewUser {
-    username: "testuser".to_string(),
-    email: "testuser@example.com".to_string(),
-    password: "Password123!".to_string(),
-  };
-  diesel::insert_into(crate::models::schema::users::table)
-    .values(&new_user)
-    .execute(conn)
-    .expect("Error inserting user");
-
-  let user_id: Uuid = crate::models::schema::users::table
-    .select(crate::models::schema::users::dsl::id)
-    .first(conn)
-    .expect("Error fetching user ID");
-
-  // When: The user is deleted by ID
-  let mut app = test::init_service(App::new()
+  // Given: A user exists
+  let pool = pool();
+  let app = test::init_service(App::new()
     .app_data(web::Data::new(pool.clone()))
+    .app_data(store())
+    .app_data(validator())
     .service(user_routes())).await;
+  let user = existing_user(&pool).await;
+  let admin = token(Uuid::new_v4(), &["users:read", "users:delete"]);
 
+  // When: The user is deleted by ID
   let req = test::TestRequest::delete()
-    .uri(&format!("/users/{}", user_id))
+    .uri(&format!("/users/{}", user.id))
+    .insert_header(bearer(&admin))
     .to_request();
+  let resp = test::call_service(&app, req).await;
 
-  let resp = test::call_service(&mut app, req).await;
-
-  // Log response status and body for debugging
-  let status = resp.status();
-  let body = test::read_body(resp).await;
-
-  info!("Delete User Response Status: {:?}", status);
-  info!("Delete User Response Body: {:?}", body);
+  // Then: The account is gone
+  assert_eq!(resp.status(), StatusCode::NO_CONTENT);
+  let req = test::TestRequest::get()
+    .uri(&format!("/users/{}", user.id))
+    .insert_header(bearer(&admin))
+    .to_request();
+  assert_eq!(test::call_service(&app, req).await.status(), StatusCode::NOT_FOUND);
 
-  // Then: The response status should be 204 No Content
-  assert_eq!(status, actix_web::http::StatusCode::NO_CONTENT);
-}
\ No newline at end of file
+  // And: Deleting it again finds nothing
+  let req = test::TestRequest::delete()
+    .uri(&format!("/users/{}", user.id))
+    .insert_header(bearer(&admin))
+    .to_request();
+  assert_eq!(test::call_service(&app, req).await.status(), StatusCode::NOT_FOUND);
+}
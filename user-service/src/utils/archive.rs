@@ -0,0 +1,39 @@
+use std::io::{Cursor, Write};
+use serde::Serialize;
+use zip::result::ZipResult;
+use zip::write::SimpleFileOptions;
+use zip::{CompressionMethod, ZipWriter};
+
+/// A zip archive built in memory, for downloads.
+pub struct Archive {
+    writer: ZipWriter<Cursor<Vec<u8>>>,
+}
+
+impl Archive {
+    pub fn new() -> Archive {
+        Archive { writer: ZipWriter::new(Cursor::new(Vec::new())) }
+    }
+
+    pub fn add_file(&mut self, name: &str, contents: &[u8]) -> ZipResult<()> {
+        let options = SimpleFileOptions::default().compression_method(CompressionMethod::Deflated);
+        self.writer.start_file(name, options)?;
+        self.writer.write_all(contents)?;
+        Ok(())
+    }
+
+    /// Adds `value` as pretty-printed JSON.
+    pub fn add_json<T: Serialize>(&mut self, name: &str, value: &T) -> ZipResult<()> {
+        let contents = serde_json::to_vec_pretty(value).map_err(std::io::Error::from)?;
+        self.add_file(name, &contents)
+    }
+
+    pub fn finish(self) -> ZipResult<Vec<u8>> {
+        Ok(self.writer.finish()?.into_inner())
+    }
+}
+
+impl Default for Archive {
+    fn default() -> Self {
+        Archive::new()
+    }
+}
@@ -0,0 +1,130 @@
+use std::io::Cursor;
+use image::codecs::jpeg::JpegEncoder;
+use image::codecs::png::PngEncoder;
+use image::error::ImageError;
+use image::imageops::FilterType;
+use image::{DynamicImage, ImageDecoder, ImageFormat, ImageReader, Limits};
+use uuid::Uuid;
+use crate::errors::error::UserError;
+
+/// The square renditions kept of every avatar, in pixels, largest first.
+pub const AVATAR_SIZES: [u32; 4] = [512, 256, 128, 64];
+
+/// Uploads wider or taller than this are refused before they are decoded.
+const MAX_SIDE: u32 = 8000;
+/// What decoding an upload may allocate at most.
+const MAX_DECODED_BYTES: u64 = 512 * 1024 * 1024;
+const JPEG_QUALITY: u8 = 85;
+
+/// What the renditions are encoded as: PNG when the upload has
+/// transparency, JPEG otherwise.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum AvatarFormat {
+    Jpeg,
+    Png,
+}
+
+impl AvatarFormat {
+    /// The extension of the renditions' keys, also stored as `avatar_format`.
+    pub fn extension(&self) -> &'static str {
+        match self {
+            AvatarFormat::Jpeg => "jpg",
+            AvatarFormat::Png => "png",
+        }
+    }
+
+    pub fn content_type(&self) -> &'static str {
+        match self {
+            AvatarFormat::Jpeg => "image/jpeg",
+            AvatarFormat::Png => "image/png",
+        }
+    }
+
+    pub fn from_extension(extension: &str) -> Option<AvatarFormat> {
+        match extension {
+            "jpg" => Some(AvatarFormat::Jpeg),
+            "png" => Some(AvatarFormat::Png),
+            _ => None,
+        }
+    }
+}
+
+pub struct Rendition {
+    pub size: u32,
+    pub bytes: Vec<u8>,
+}
+
+pub struct ProcessedAvatar {
+    pub format: AvatarFormat,
+    /// One per `AVATAR_SIZES`, in that order.
+    pub renditions: Vec<Rendition>,
+}
+
+/// Where a rendition is kept in the blob store.
+pub fn avatar_key(user_id: Uuid, avatar_id: Uuid, size: u32, format: AvatarFormat) -> String {
+    format!("avatars/{}/{}/{}.{}", user_id, avatar_id, size, format.extension())
+}
+
+/// The rendition to serve for a requested size: the smallest one at least
+/// that large, or the largest there is.
+pub fn rendition_size(requested: u32) -> u32 {
+    AVATAR_SIZES.iter().rev().copied().find(|&size| size >= requested).unwrap_or(AVATAR_SIZES[0])
+}
+
+/// Decodes an upload and renders it at every size in `AVATAR_SIZES`,
+/// cropped to a square around its center.
+///
+/// The format is told from the bytes, whatever the upload claims to be; only
+/// JPEG, PNG and WebP are accepted. The EXIF orientation is applied before
+/// the image is re-encoded, and nothing else of the upload's metadata, such
+/// as the location a photo was taken at, is carried over to the renditions.
+///
+/// CPU-bound: run it off the async executor.
+pub fn process_avatar(upload: &[u8]) -> Result<ProcessedAvatar, UserError> {
+    let format = image::guess_format(upload).map_err(|_| UserError::UnsupportedMediaType)?;
+    if !matches!(format, ImageFormat::Jpeg | ImageFormat::Png | ImageFormat::WebP) {
+        return Err(UserError::UnsupportedMediaType);
+    }
+
+    let mut limits = Limits::default();
+    limits.max_image_width = Some(MAX_SIDE);
+    limits.max_image_height = Some(MAX_SIDE);
+    limits.max_alloc = Some(MAX_DECODED_BYTES);
+    let mut reader = ImageReader::with_format(Cursor::new(upload), format);
+    reader.limits(limits);
+
+    let mut decoder = reader.into_decoder().map_err(decode_error)?;
+    let orientation = decoder.orientation().map_err(decode_error)?;
+    let mut image = DynamicImage::from_decoder(decoder).map_err(decode_error)?;
+    image.apply_orientation(orientation);
+
+    let format = if image.color().has_alpha() { AvatarFormat::Png } else { AvatarFormat::Jpeg };
+    let renditions = AVATAR_SIZES.iter()
+        .map(|&size| {
+            let square = image.resize_to_fill(size, size, FilterType::Lanczos3);
+            encode(&square, format).map(|bytes| Rendition { size, bytes })
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    Ok(ProcessedAvatar { format, renditions })
+}
+
+fn encode(image: &DynamicImage, format: AvatarFormat) -> Result<Vec<u8>, UserError> {
+    let mut bytes = Vec::new();
+    let encoded = match format {
+        AvatarFormat::Jpeg => image.to_rgb8().write_with_encoder(JpegEncoder::new_with_quality(&mut bytes, JPEG_QUALITY)),
+        AvatarFormat::Png => image.to_rgba8().write_with_encoder(PngEncoder::new(&mut bytes)),
+    };
+    encoded.map_err(|e| {
+        log::error!("Failed to encode avatar: {}", e);
+        UserError::InternalServerError
+    })?;
+    Ok(bytes)
+}
+
+fn decode_error(e: ImageError) -> UserError {
+    match e {
+        ImageError::Limits(_) => UserError::AvatarTooLarge,
+        e => UserError::InvalidUpload(format!("the image could not be read: {}", e)),
+    }
+}
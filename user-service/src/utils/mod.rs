@@ -1,2 +1,3 @@
+pub mod archive;
+pub mod avatar_image;
 pub mod hash_password;
-mod validate_token;
\ No newline at end of file